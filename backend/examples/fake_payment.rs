@@ -0,0 +1,57 @@
+// Completes a payment taken with PAYMENT_PROVIDER=fake by posting a signed
+// webhook event, as a real provider would once the customer pays:
+//
+//   cargo run --example fake_payment -- fake_pi_... [succeeded|failed]
+//
+// API_URL (default http://localhost:8000) and FAKE_PAYMENT_WEBHOOK_SECRET
+// must match the running API.
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let payment_id = args
+        .next()
+        .expect("Usage: fake_payment <payment id> [succeeded|failed]");
+    let outcome = args.next().unwrap_or_else(|| "succeeded".to_string());
+
+    let mut event = json!({
+        "id": format!("evt_{}", uuid::Uuid::new_v4().simple()),
+        "type": match outcome.as_str() {
+            "succeeded" => "payment.succeeded",
+            "failed" => "payment.failed",
+            other => panic!("Unknown outcome '{}', expected succeeded or failed", other),
+        },
+        "payment_id": payment_id,
+    });
+    if outcome == "failed" {
+        event["message"] = json!("Your card was declined.");
+    }
+    let body = serde_json::to_vec(&event).unwrap();
+
+    let secret = std::env::var("FAKE_PAYMENT_WEBHOOK_SECRET")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "fake-payment-webhook-secret".to_string());
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(&body);
+    let signature = hex::encode(mac.finalize().into_bytes());
+
+    let api_url = std::env::var("API_URL").unwrap_or_else(|_| "http://localhost:8000".to_string());
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/payments/webhook", api_url))
+        .header("Content-Type", "application/json")
+        .header("X-Fake-Signature", signature)
+        .body(body)
+        .send()
+        .await
+        .expect("API is not reachable");
+
+    println!(
+        "{} {}",
+        response.status(),
+        response.text().await.unwrap_or_default()
+    );
+}
@@ -0,0 +1,241 @@
+// Stand-in for the Econt and Speedy APIs, for trying waybills and tracking
+// without courier accounts:
+//
+//   cargo run --example mock_courier
+//   ECONT_API_URL=http://localhost:8090/econt ECONT_USERNAME=demo
+//   SPEEDY_API_URL=http://localhost:8090/speedy SPEEDY_USERNAME=demo
+//
+// Every waybill moves one step per tracking request: created, in transit,
+// delivered.
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// Smallest PDF viewers open, one empty A6 page
+const LABEL_PDF: &[u8] = b"%PDF-1.4
+1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj
+2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj
+3 0 obj << /Type /Page /Parent 2 0 R /MediaBox [0 0 298 420] >> endobj
+trailer << /Root 1 0 R >>
+%%EOF
+";
+
+#[derive(Default)]
+struct Shipments {
+    next_number: u64,
+    // Tracking requests seen per waybill
+    polls: HashMap<String, usize>,
+}
+
+#[derive(Clone)]
+struct Mock {
+    base_url: String,
+    shipments: Arc<Mutex<Shipments>>,
+}
+
+impl Mock {
+    fn create(&self, prefix: &str) -> String {
+        let mut shipments = self.shipments.lock().unwrap();
+        shipments.next_number += 1;
+        let number = format!("{}{:08}", prefix, shipments.next_number);
+        shipments.polls.insert(number.clone(), 0);
+        number
+    }
+
+    // 0 created, 1 in transit, 2 delivered; None for unknown waybills
+    fn poll(&self, number: &str) -> Option<usize> {
+        let mut shipments = self.shipments.lock().unwrap();
+        let polls = shipments.polls.get_mut(number)?;
+        *polls += 1;
+        Some((*polls - 1).min(2))
+    }
+}
+
+const OFFICES: [(&str, &str, &str, &str); 3] = [
+    (
+        "1127",
+        "София - Изток",
+        "София",
+        "бул. Цариградско шосе 115",
+    ),
+    ("1032", "София - Лозенец", "София", "ул. Златовръх 30"),
+    ("4001", "Пловдив - Център", "Пловдив", "ул. Иван Вазов 12"),
+];
+
+#[tokio::main]
+async fn main() {
+    let port: u16 = std::env::var("MOCK_COURIER_PORT")
+        .unwrap_or_else(|_| "8090".to_string())
+        .parse()
+        .expect("MOCK_COURIER_PORT must be a valid number");
+
+    let mock = Mock {
+        base_url: format!("http://localhost:{}", port),
+        shipments: Arc::default(),
+    };
+
+    let app = Router::new()
+        .route(
+            "/econt/Nomenclatures/NomenclaturesService.getOffices.json",
+            post(econt_offices),
+        )
+        .route(
+            "/econt/Shipments/LabelService.createLabel.json",
+            post(econt_create_label),
+        )
+        .route(
+            "/econt/Shipments/ShipmentService.getShipmentStatuses.json",
+            post(econt_statuses),
+        )
+        .route("/econt/labels/:number", get(label))
+        .route("/speedy/location/office", post(speedy_offices))
+        .route("/speedy/shipment", post(speedy_shipment))
+        .route("/speedy/print", post(speedy_print))
+        .route("/speedy/track", post(speedy_track))
+        .with_state(mock);
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
+        .await
+        .expect("Failed to bind to address");
+    println!(
+        "Mock couriers on http://localhost:{}/econt and /speedy",
+        port
+    );
+
+    axum::serve(listener, app).await.expect("Server error");
+}
+
+async fn label(Path(_number): Path<String>) -> Response {
+    pdf()
+}
+
+fn pdf() -> Response {
+    ([(header::CONTENT_TYPE, "application/pdf")], LABEL_PDF).into_response()
+}
+
+// Econt
+
+const ECONT_EVENTS: [&str; 3] = ["Създадена", "Приета в офис", "Доставена"];
+
+async fn econt_offices() -> Json<Value> {
+    let offices: Vec<Value> = OFFICES
+        .iter()
+        .map(|(code, name, city, address)| {
+            json!({
+                "code": code,
+                "name": name,
+                "address": { "city": { "name": city }, "fullAddress": address },
+            })
+        })
+        .collect();
+
+    Json(json!({ "offices": offices }))
+}
+
+async fn econt_create_label(State(mock): State<Mock>, Json(body): Json<Value>) -> Response {
+    let label = &body["label"];
+    let has_destination =
+        label["receiverOfficeCode"].is_string() || label["receiverAddress"].is_object();
+    if !has_destination {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "message": "Липсва адрес или офис на получателя" })),
+        )
+            .into_response();
+    }
+
+    let number = mock.create("10");
+    Json(json!({
+        "label": {
+            "shipmentNumber": number,
+            "pdfURL": format!("{}/econt/labels/{}", mock.base_url, number),
+        },
+    }))
+    .into_response()
+}
+
+async fn econt_statuses(State(mock): State<Mock>, Json(body): Json<Value>) -> Json<Value> {
+    let statuses: Vec<Value> = body["shipmentNumbers"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|number| {
+            let number = number.as_str().unwrap_or_default();
+            match mock.poll(number) {
+                Some(step) => json!({
+                    "status": {
+                        "shipmentNumber": number,
+                        "sendTime": (step >= 1).then_some(1_700_000_000_000_i64),
+                        "deliveryTime": (step >= 2).then_some(1_700_100_000_000_i64),
+                        "shortDeliveryStatus": ECONT_EVENTS[step],
+                    },
+                }),
+                None => json!({ "error": { "message": "Няма такава пратка" } }),
+            }
+        })
+        .collect();
+
+    Json(json!({ "shipmentStatuses": statuses }))
+}
+
+// Speedy
+
+async fn speedy_offices(Json(body): Json<Value>) -> Json<Value> {
+    let site = body["siteName"].as_str().unwrap_or_default().to_lowercase();
+    let offices: Vec<Value> = OFFICES
+        .iter()
+        .filter(|(_, _, city, _)| city.to_lowercase() == site)
+        .map(|(code, name, city, address)| {
+            json!({
+                "id": code.parse::<i64>().unwrap(),
+                "name": name,
+                "address": { "siteName": city, "fullAddressString": address },
+            })
+        })
+        .collect();
+
+    Json(json!({ "offices": offices }))
+}
+
+async fn speedy_shipment(State(mock): State<Mock>, Json(body): Json<Value>) -> Json<Value> {
+    let recipient = &body["recipient"];
+    if !recipient["pickupOfficeId"].is_number() && !recipient["address"].is_object() {
+        return Json(json!({ "error": { "message": "Invalid recipient address" } }));
+    }
+
+    Json(json!({ "id": mock.create("3") }))
+}
+
+async fn speedy_print() -> Response {
+    pdf()
+}
+
+async fn speedy_track(State(mock): State<Mock>, Json(body): Json<Value>) -> Json<Value> {
+    let parcels: Vec<Value> = body["parcels"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|parcel| {
+            let id = parcel["id"].as_str().unwrap_or_default();
+            match mock.poll(id) {
+                Some(step) => {
+                    let operations = [
+                        json!({ "operationCode": 1, "description": "Приета в офис" }),
+                        json!({ "operationCode": -14, "description": "Доставена" }),
+                    ];
+                    json!({ "parcelId": id, "operations": &operations[..step] })
+                }
+                None => json!({ "parcelId": id, "error": { "message": "Unknown parcel" } }),
+            }
+        })
+        .collect();
+
+    Json(json!({ "parcels": parcels }))
+}
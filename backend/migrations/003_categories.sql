@@ -0,0 +1,64 @@
+-- Product categories with parent/child nesting
+
+CREATE TABLE IF NOT EXISTS categories (
+    id SERIAL PRIMARY KEY,
+    parent_id INTEGER REFERENCES categories(id) ON DELETE SET NULL,
+    name VARCHAR(255) NOT NULL,
+    slug VARCHAR(255) UNIQUE NOT NULL,
+    description TEXT,
+    position INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK (parent_id IS NULL OR parent_id <> id)
+);
+
+-- Many-to-many link between products and categories
+CREATE TABLE IF NOT EXISTS product_categories (
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    category_id INTEGER NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+    PRIMARY KEY (product_id, category_id)
+);
+
+CREATE INDEX idx_categories_parent_id ON categories(parent_id);
+CREATE INDEX idx_product_categories_category_id ON product_categories(category_id);
+
+CREATE TRIGGER update_categories_updated_at BEFORE UPDATE ON categories
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Initial category tree
+INSERT INTO categories (name, slug, description, position) VALUES
+('Фигурки', 'figurki', 'Ръчно изработени фигурки и скулптури от полимерна глина', 1),
+('Бижута', 'bizhuta', 'Обеци, пръстени, брошки и медальони', 2),
+('Декорация', 'dekoratsiya', 'Декоративни предмети за дома и градината', 3);
+
+INSERT INTO categories (parent_id, name, slug, position)
+SELECT c.id, v.name, v.slug, v.position
+FROM categories c
+JOIN (VALUES
+    ('Обеци', 'obetsi', 1),
+    ('Пръстени', 'prasteni', 2),
+    ('Брошки', 'broshki', 3),
+    ('Медальони', 'medalyoni', 4)
+) AS v(name, slug, position) ON true
+WHERE c.slug = 'bizhuta';
+
+-- Assign seeded products to their categories
+INSERT INTO product_categories (product_id, category_id)
+SELECT p.id, c.id
+FROM products p
+JOIN (VALUES
+    ('Миниатюрна горска фея', 'figurki'),
+    ('Ръчно изработени обеци — Есенни листа', 'obetsi'),
+    ('Декоративна горска гъбка', 'dekoratsiya'),
+    ('Дракон миниатюра — Пазител на кристала', 'figurki'),
+    ('Пръстен — Цветна поляна', 'prasteni'),
+    ('Градински дух — Пазител', 'figurki'),
+    ('Градински дух — Пазител', 'dekoratsiya'),
+    ('Сет медальони — Четирите сезона', 'medalyoni'),
+    ('Фентъзи същество — Лунен заек', 'figurki'),
+    ('Цветна брошка — Дива роза', 'broshki'),
+    ('Абстрактна скулптура — Вълни', 'dekoratsiya')
+) AS v(product_name, category_slug) ON p.name = v.product_name
+JOIN categories c ON c.slug = v.category_slug
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,50 @@
+-- SEO-friendly product slugs with redirect history
+
+ALTER TABLE products ADD COLUMN IF NOT EXISTS slug VARCHAR(255);
+
+-- One-off transliteration for existing rows; new slugs are generated by the API
+CREATE OR REPLACE FUNCTION bg_slugify_backfill(input TEXT)
+RETURNS TEXT AS $$
+DECLARE
+    result TEXT := input;
+BEGIN
+    result := regexp_replace(result, '([иИ])[яЯ]\M', '\1a', 'g');
+    result := replace(replace(result, 'Щ', 'sht'), 'щ', 'sht');
+    result := replace(replace(result, 'Ж', 'zh'), 'ж', 'zh');
+    result := replace(replace(result, 'Ц', 'ts'), 'ц', 'ts');
+    result := replace(replace(result, 'Ч', 'ch'), 'ч', 'ch');
+    result := replace(replace(result, 'Ш', 'sh'), 'ш', 'sh');
+    result := replace(replace(result, 'Ю', 'yu'), 'ю', 'yu');
+    result := replace(replace(result, 'Я', 'ya'), 'я', 'ya');
+    result := translate(
+        result,
+        'АБВГДЕЗИЙКЛМНОПРСТУФХЪЬабвгдезийклмнопрстуфхъь',
+        'abvgdeziyklmnoprstufhayabvgdeziyklmnoprstufhay'
+    );
+    result := lower(result);
+    result := regexp_replace(result, '[^a-z0-9]+', '-', 'g');
+    result := trim(both '-' from result);
+    RETURN result;
+END;
+$$ LANGUAGE plpgsql;
+
+UPDATE products SET slug = bg_slugify_backfill(name) WHERE slug IS NULL;
+UPDATE products SET slug = 'product' WHERE slug = '';
+
+-- Disambiguate duplicates by appending the product id
+UPDATE products p SET slug = p.slug || '-' || p.id
+WHERE EXISTS (SELECT 1 FROM products q WHERE q.slug = p.slug AND q.id < p.id);
+
+DROP FUNCTION bg_slugify_backfill(TEXT);
+
+ALTER TABLE products ALTER COLUMN slug SET NOT NULL;
+ALTER TABLE products ADD CONSTRAINT products_slug_key UNIQUE (slug);
+
+-- Previous slugs of renamed products keep resolving to the product
+CREATE TABLE IF NOT EXISTS product_slug_history (
+    slug VARCHAR(255) PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_slug_history_product_id ON product_slug_history(product_id);
@@ -0,0 +1,30 @@
+-- Full-text product search
+-- PostgreSQL ships no Bulgarian stemmer, so the "bulgarian" configuration is
+-- the simple dictionary with accents stripped.
+
+CREATE EXTENSION IF NOT EXISTS unaccent;
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_ts_config WHERE cfgname = 'bulgarian') THEN
+        CREATE TEXT SEARCH CONFIGURATION bulgarian (COPY = simple);
+        ALTER TEXT SEARCH CONFIGURATION bulgarian
+            ALTER MAPPING FOR hword, hword_part, word WITH unaccent, simple;
+    END IF;
+END
+$$;
+
+-- The transliterated slug is indexed too, so Latin-typed queries match Cyrillic names
+ALTER TABLE products ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('bulgarian'::regconfig, coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('bulgarian'::regconfig, replace(slug, '-', ' ')), 'A') ||
+        setweight(to_tsvector('bulgarian'::regconfig, coalesce(description, '')), 'B')
+    ) STORED;
+
+CREATE INDEX idx_products_search_vector ON products USING GIN (search_vector);
+
+-- Trigram indexes for typo-tolerant fallback matching
+CREATE INDEX idx_products_name_trgm ON products USING GIN (name gin_trgm_ops);
+CREATE INDEX idx_products_slug_trgm ON products USING GIN (slug gin_trgm_ops);
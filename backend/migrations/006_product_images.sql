@@ -0,0 +1,23 @@
+-- Product image galleries
+-- products.image_url is kept as a copy of the primary image URL for listings
+
+CREATE TABLE IF NOT EXISTS product_images (
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    url VARCHAR(500) NOT NULL,
+    alt_text VARCHAR(255),
+    position INTEGER NOT NULL DEFAULT 0,
+    is_primary BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_images_product_id ON product_images(product_id, position);
+
+-- At most one primary image per product
+CREATE UNIQUE INDEX idx_product_images_primary ON product_images(product_id) WHERE is_primary;
+
+-- Existing single images become the primary gallery image
+INSERT INTO product_images (product_id, url, alt_text, position, is_primary)
+SELECT id, image_url, name, 0, true
+FROM products
+WHERE image_url IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Resized WebP/AVIF renditions of each product image for responsive srcset
+-- Each entry: {"size", "format", "width", "height", "url"}
+
+ALTER TABLE product_images ADD COLUMN IF NOT EXISTS variants JSONB NOT NULL DEFAULT '[]';
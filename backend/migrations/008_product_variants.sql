@@ -0,0 +1,75 @@
+-- Product variants (colour, size, finish...)
+-- A product with variants is sold per variant: each variant has its own SKU,
+-- stock and optional price override. products.stock_quantity then holds the
+-- total stock of the active variants so listings and filters keep working.
+
+CREATE TABLE IF NOT EXISTS product_options (
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (product_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS product_option_values (
+    id SERIAL PRIMARY KEY,
+    option_id INTEGER NOT NULL REFERENCES product_options(id) ON DELETE CASCADE,
+    value VARCHAR(100) NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    UNIQUE (option_id, value)
+);
+
+CREATE TABLE IF NOT EXISTS product_variants (
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    sku VARCHAR(100) NOT NULL UNIQUE,
+    -- NULL means the product price applies
+    price DECIMAL(10,2) CHECK (price >= 0),
+    stock_quantity INTEGER NOT NULL DEFAULT 0 CHECK (stock_quantity >= 0),
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- One value per option for every variant
+CREATE TABLE IF NOT EXISTS product_variant_values (
+    variant_id INTEGER NOT NULL REFERENCES product_variants(id) ON DELETE CASCADE,
+    option_value_id INTEGER NOT NULL REFERENCES product_option_values(id) ON DELETE CASCADE,
+    PRIMARY KEY (variant_id, option_value_id)
+);
+
+CREATE INDEX idx_product_options_product_id ON product_options(product_id, position);
+CREATE INDEX idx_product_option_values_option_id ON product_option_values(option_id, position);
+CREATE INDEX idx_product_variants_product_id ON product_variants(product_id, position);
+CREATE INDEX idx_product_variant_values_value_id ON product_variant_values(option_value_id);
+
+CREATE TRIGGER update_product_variants_updated_at BEFORE UPDATE ON product_variants
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Keep products.stock_quantity equal to the stock of its active variants
+CREATE OR REPLACE FUNCTION sync_product_variant_stock()
+RETURNS TRIGGER AS $$
+DECLARE
+    affected_product INTEGER := COALESCE(NEW.product_id, OLD.product_id);
+BEGIN
+    UPDATE products SET stock_quantity = (
+        SELECT COALESCE(SUM(stock_quantity), 0)
+        FROM product_variants
+        WHERE product_id = affected_product AND is_active
+    )
+    WHERE id = affected_product;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER sync_product_variant_stock
+    AFTER INSERT OR DELETE OR UPDATE OF stock_quantity, is_active ON product_variants
+    FOR EACH ROW EXECUTE FUNCTION sync_product_variant_stock();
+
+-- Variant snapshot on order lines
+ALTER TABLE order_items
+    ADD COLUMN IF NOT EXISTS variant_id INTEGER REFERENCES product_variants(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS variant_label VARCHAR(255),
+    ADD COLUMN IF NOT EXISTS sku VARCHAR(100);
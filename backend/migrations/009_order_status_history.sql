@@ -0,0 +1,42 @@
+-- Order status state machine
+-- Every status change is recorded with the admin who made it; the orders table
+-- keeps the time each milestone was first reached.
+
+UPDATE orders SET status = 'pending'
+WHERE status NOT IN ('pending', 'processing', 'shipped', 'delivered', 'cancelled', 'returned');
+
+ALTER TABLE orders
+    ADD CONSTRAINT orders_status_check
+        CHECK (status IN ('pending', 'processing', 'shipped', 'delivered', 'cancelled', 'returned')),
+    ADD COLUMN IF NOT EXISTS processing_at TIMESTAMP,
+    ADD COLUMN IF NOT EXISTS shipped_at TIMESTAMP,
+    ADD COLUMN IF NOT EXISTS delivered_at TIMESTAMP,
+    ADD COLUMN IF NOT EXISTS cancelled_at TIMESTAMP,
+    ADD COLUMN IF NOT EXISTS returned_at TIMESTAMP;
+
+CREATE TABLE IF NOT EXISTS order_status_history (
+    id SERIAL PRIMARY KEY,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    -- NULL for the initial status of a new order
+    from_status VARCHAR(50),
+    to_status VARCHAR(50) NOT NULL,
+    -- NULL for changes made by the customer or the system
+    admin_id INTEGER REFERENCES admins(id) ON DELETE SET NULL,
+    comment TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_order_status_history_order_id ON order_status_history(order_id, created_at);
+
+-- Existing orders: initial entry plus the change to their current status
+INSERT INTO order_status_history (order_id, from_status, to_status, created_at)
+SELECT id, NULL, 'pending', created_at FROM orders;
+
+INSERT INTO order_status_history (order_id, from_status, to_status, created_at)
+SELECT id, 'pending', status, updated_at FROM orders WHERE status <> 'pending';
+
+UPDATE orders SET processing_at = updated_at WHERE status = 'processing';
+UPDATE orders SET shipped_at = updated_at WHERE status = 'shipped';
+UPDATE orders SET delivered_at = updated_at WHERE status = 'delivered';
+UPDATE orders SET cancelled_at = updated_at WHERE status = 'cancelled';
+UPDATE orders SET returned_at = updated_at WHERE status = 'returned';
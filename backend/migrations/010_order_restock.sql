@@ -0,0 +1,26 @@
+-- Inventory movements
+-- Append-only record of stock changes. Cancelled and returned orders put
+-- their items back in stock exactly once per order line.
+
+CREATE TABLE IF NOT EXISTS inventory_movements (
+    id BIGSERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    variant_id INTEGER REFERENCES product_variants(id) ON DELETE SET NULL,
+    quantity_change INTEGER NOT NULL CHECK (quantity_change <> 0),
+    -- Stock of the product (or variant) right after the movement
+    stock_after INTEGER NOT NULL,
+    reason VARCHAR(50) NOT NULL CHECK (reason IN ('cancellation_restock', 'return_restock')),
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    order_item_id INTEGER REFERENCES order_items(id) ON DELETE SET NULL,
+    admin_id INTEGER REFERENCES admins(id) ON DELETE SET NULL,
+    note TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_inventory_movements_product_id ON inventory_movements(product_id, created_at DESC);
+CREATE INDEX idx_inventory_movements_order_id ON inventory_movements(order_id);
+
+-- An order line is put back in stock at most once
+CREATE UNIQUE INDEX idx_inventory_movements_restock
+    ON inventory_movements(order_item_id)
+    WHERE reason IN ('cancellation_restock', 'return_restock');
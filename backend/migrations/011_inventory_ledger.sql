@@ -0,0 +1,41 @@
+-- Inventory ledger
+-- Every stock change is recorded in inventory_movements with its reason.
+
+ALTER TABLE inventory_movements DROP CONSTRAINT IF EXISTS inventory_movements_reason_check;
+ALTER TABLE inventory_movements ADD CONSTRAINT inventory_movements_reason_check
+    CHECK (reason IN (
+        'sale', 'cancellation_restock', 'return_restock', 'manual_adjustment',
+        'production_batch', 'damage', 'stock_count'
+    ));
+
+-- The ledger is append-only; only references cleared by ON DELETE SET NULL may change
+CREATE OR REPLACE FUNCTION reject_inventory_movement_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.product_id <> OLD.product_id
+        OR NEW.quantity_change <> OLD.quantity_change
+        OR NEW.stock_after <> OLD.stock_after
+        OR NEW.reason <> OLD.reason
+        OR NEW.note IS DISTINCT FROM OLD.note
+        OR NEW.created_at <> OLD.created_at
+    THEN
+        RAISE EXCEPTION 'inventory_movements is append-only';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER inventory_movements_append_only BEFORE UPDATE ON inventory_movements
+    FOR EACH ROW EXECUTE FUNCTION reject_inventory_movement_change();
+
+-- Opening balances so current stock levels are explained by the ledger
+INSERT INTO inventory_movements (product_id, quantity_change, stock_after, reason, note)
+SELECT p.id, p.stock_quantity, p.stock_quantity, 'stock_count', 'Opening balance'
+FROM products p
+WHERE p.stock_quantity > 0
+  AND NOT EXISTS (SELECT 1 FROM product_variants v WHERE v.product_id = p.id);
+
+INSERT INTO inventory_movements (product_id, variant_id, quantity_change, stock_after, reason, note)
+SELECT v.product_id, v.id, v.stock_quantity, v.stock_quantity, 'stock_count', 'Opening balance'
+FROM product_variants v
+WHERE v.stock_quantity > 0;
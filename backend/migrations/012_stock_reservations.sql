@@ -0,0 +1,36 @@
+-- Time-limited stock reservations
+-- A reservation holds stock for a checkout until it expires or becomes an
+-- order. Expired holds stop counting immediately; the sweeper only deletes them.
+
+CREATE TABLE IF NOT EXISTS stock_reservations (
+    id UUID PRIMARY KEY,
+    expires_at TIMESTAMP NOT NULL,
+    -- Set once the reservation has been turned into an order
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    converted_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS stock_reservation_items (
+    id SERIAL PRIMARY KEY,
+    reservation_id UUID NOT NULL REFERENCES stock_reservations(id) ON DELETE CASCADE,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    variant_id INTEGER REFERENCES product_variants(id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL CHECK (quantity > 0)
+);
+
+CREATE INDEX idx_stock_reservations_expires_at ON stock_reservations(expires_at)
+    WHERE converted_at IS NULL;
+CREATE INDEX idx_stock_reservation_items_reservation_id ON stock_reservation_items(reservation_id);
+CREATE INDEX idx_stock_reservation_items_product_id ON stock_reservation_items(product_id, variant_id);
+
+CREATE TRIGGER update_stock_reservations_updated_at BEFORE UPDATE ON stock_reservations
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Quantities currently held by unexpired, unconverted reservations
+CREATE OR REPLACE VIEW active_stock_holds AS
+SELECT ri.reservation_id, ri.product_id, ri.variant_id, ri.quantity
+FROM stock_reservation_items ri
+JOIN stock_reservations r ON r.id = ri.reservation_id
+WHERE r.converted_at IS NULL AND r.expires_at > NOW();
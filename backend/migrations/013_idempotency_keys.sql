@@ -0,0 +1,20 @@
+-- Idempotency keys for retried requests
+-- A key is stored in the same transaction as the work it protects, together
+-- with the response to replay. Requests that fail store nothing and can be
+-- retried with the same key.
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    -- Endpoint the key was used on, e.g. 'POST /api/orders'
+    scope VARCHAR(100) NOT NULL,
+    key VARCHAR(255) NOT NULL,
+    -- SHA-256 of the request body
+    request_hash CHAR(64) NOT NULL,
+    response_status SMALLINT NOT NULL,
+    -- Serialized exactly as first sent
+    response_body TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (scope, key)
+);
+
+CREATE INDEX idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
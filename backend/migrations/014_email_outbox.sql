@@ -0,0 +1,28 @@
+-- Transactional email
+-- Emails are rendered and queued in the transaction of the change that
+-- triggers them and delivered by a background worker, so a mail outage delays
+-- notifications but never fails an order.
+
+CREATE TABLE IF NOT EXISTS email_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    -- Template the email was rendered from, e.g. 'order_confirmation'
+    template VARCHAR(50) NOT NULL,
+    to_address VARCHAR(255) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    text_body TEXT NOT NULL,
+    html_body TEXT NOT NULL,
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sent', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    sent_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_outbox_pending ON email_outbox(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX idx_email_outbox_order_id ON email_outbox(order_id);
+
+-- Shown in the shipping notification
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS tracking_number VARCHAR(100);
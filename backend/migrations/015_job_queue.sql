@@ -0,0 +1,44 @@
+-- Background job queue
+-- Workers in the API process claim due jobs with FOR UPDATE SKIP LOCKED, so
+-- any number of instances can share the queue. Failed jobs are retried with an
+-- exponential backoff and end up 'dead' once they run out of attempts.
+
+CREATE TABLE IF NOT EXISTS jobs (
+    id BIGSERIAL PRIMARY KEY,
+    kind VARCHAR(100) NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}',
+    status VARCHAR(20) NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'succeeded', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL CHECK (max_attempts > 0),
+    run_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    -- When a worker claimed the job; stale claims are taken over
+    locked_at TIMESTAMP,
+    last_error TEXT,
+    finished_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_jobs_due ON jobs(run_at) WHERE status IN ('queued', 'running');
+CREATE INDEX idx_jobs_status ON jobs(status, created_at DESC);
+
+CREATE TRIGGER update_jobs_updated_at BEFORE UPDATE ON jobs
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Next run of each cron schedule, shared by all instances. Times are UTC.
+CREATE TABLE IF NOT EXISTS job_schedules (
+    name VARCHAR(100) PRIMARY KEY,
+    schedule VARCHAR(100) NOT NULL,
+    next_run_at TIMESTAMP NOT NULL,
+    last_run_at TIMESTAMP
+);
+
+-- Email delivery moves onto the job queue, which owns the retry timing
+DROP INDEX IF EXISTS idx_email_outbox_pending;
+ALTER TABLE email_outbox DROP COLUMN IF EXISTS next_attempt_at;
+
+INSERT INTO jobs (kind, payload, max_attempts)
+SELECT 'send_email', jsonb_build_object('email_id', id), 8
+FROM email_outbox
+WHERE status = 'pending';
@@ -0,0 +1,61 @@
+-- Discount codes
+-- A promotion takes a percentage or a fixed amount off the items it applies
+-- to, or waives shipping. It may be limited to a time window, a minimum order,
+-- a number of uses overall and per customer, and to some products or
+-- categories (including their subcategories).
+
+CREATE TABLE IF NOT EXISTS promotions (
+    id SERIAL PRIMARY KEY,
+    -- Stored upper-case, matched case-insensitively
+    code VARCHAR(50) NOT NULL,
+    description TEXT,
+    kind VARCHAR(20) NOT NULL
+        CHECK (kind IN ('percentage', 'fixed_amount', 'free_shipping')),
+    -- Percent off for 'percentage', amount off for 'fixed_amount', 0 otherwise
+    value DECIMAL(10, 2) NOT NULL DEFAULT 0 CHECK (value >= 0),
+    starts_at TIMESTAMP,
+    ends_at TIMESTAMP,
+    min_order_amount DECIMAL(10, 2) CHECK (min_order_amount >= 0),
+    usage_limit INTEGER CHECK (usage_limit > 0),
+    usage_limit_per_customer INTEGER CHECK (usage_limit_per_customer > 0),
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK (kind <> 'percentage' OR value <= 100),
+    CHECK (starts_at IS NULL OR ends_at IS NULL OR starts_at < ends_at)
+);
+
+CREATE UNIQUE INDEX idx_promotions_code ON promotions(UPPER(code));
+
+CREATE TRIGGER update_promotions_updated_at BEFORE UPDATE ON promotions
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Without rows in either table a promotion applies to every product
+CREATE TABLE IF NOT EXISTS promotion_products (
+    promotion_id INTEGER NOT NULL REFERENCES promotions(id) ON DELETE CASCADE,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    PRIMARY KEY (promotion_id, product_id)
+);
+
+CREATE TABLE IF NOT EXISTS promotion_categories (
+    promotion_id INTEGER NOT NULL REFERENCES promotions(id) ON DELETE CASCADE,
+    category_id INTEGER NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+    PRIMARY KEY (promotion_id, category_id)
+);
+
+-- Orders keep the item total before the discount and the code they used;
+-- total_amount is what the customer pays. Uses of a promotion are the orders
+-- that carry it, except cancelled ones.
+ALTER TABLE orders ADD COLUMN subtotal_amount DECIMAL(10, 2);
+UPDATE orders SET subtotal_amount = total_amount;
+ALTER TABLE orders ALTER COLUMN subtotal_amount SET NOT NULL;
+
+ALTER TABLE orders
+    ADD COLUMN discount_amount DECIMAL(10, 2) NOT NULL DEFAULT 0,
+    ADD COLUMN promotion_id INTEGER REFERENCES promotions(id) ON DELETE SET NULL,
+    ADD COLUMN promotion_code VARCHAR(50);
+
+CREATE INDEX idx_orders_promotion ON orders(promotion_id) WHERE promotion_id IS NOT NULL;
+
+-- Share of the order discount taken off each line
+ALTER TABLE order_items ADD COLUMN discount_amount DECIMAL(10, 2) NOT NULL DEFAULT 0;
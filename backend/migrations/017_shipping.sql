@@ -0,0 +1,96 @@
+-- Shipping methods and delivery prices
+-- Each method has a price table by parcel weight and delivery zone. Zones are
+-- ranges of Bulgarian postal codes; rates without a zone apply where the
+-- method has no fitting rate for the zone. The lightest band that fits wins.
+
+ALTER TABLE products
+    ADD COLUMN weight_grams INTEGER NOT NULL DEFAULT 0 CHECK (weight_grams >= 0);
+
+CREATE TABLE IF NOT EXISTS shipping_zones (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    postal_code_from INTEGER NOT NULL CHECK (postal_code_from BETWEEN 1000 AND 9999),
+    postal_code_to INTEGER NOT NULL CHECK (postal_code_to BETWEEN 1000 AND 9999),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK (postal_code_from <= postal_code_to)
+);
+
+CREATE TRIGGER update_shipping_zones_updated_at BEFORE UPDATE ON shipping_zones
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS shipping_methods (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    kind VARCHAR(20) NOT NULL
+        CHECK (kind IN ('courier_office', 'courier_address', 'pickup')),
+    -- Courier delivering the parcel, none for pickup
+    carrier VARCHAR(20) CHECK (carrier IN ('econt', 'speedy')),
+    -- Orders of at least this much (after discounts) ship for free
+    free_shipping_threshold DECIMAL(10, 2) CHECK (free_shipping_threshold >= 0),
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK ((kind = 'pickup') = (carrier IS NULL))
+);
+
+CREATE TRIGGER update_shipping_methods_updated_at BEFORE UPDATE ON shipping_methods
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS shipping_rates (
+    id SERIAL PRIMARY KEY,
+    method_id INTEGER NOT NULL REFERENCES shipping_methods(id) ON DELETE CASCADE,
+    -- NULL applies outside the method's zone-specific rates
+    zone_id INTEGER REFERENCES shipping_zones(id) ON DELETE CASCADE,
+    -- Heaviest parcel of the band, NULL for no limit
+    max_weight_grams INTEGER CHECK (max_weight_grams > 0),
+    price DECIMAL(10, 2) NOT NULL CHECK (price >= 0)
+);
+
+CREATE UNIQUE INDEX idx_shipping_rates_band
+    ON shipping_rates(method_id, COALESCE(zone_id, 0), COALESCE(max_weight_grams, 0));
+
+-- Orders keep the method name and price they were placed with
+ALTER TABLE orders
+    ADD COLUMN shipping_method_id INTEGER REFERENCES shipping_methods(id) ON DELETE SET NULL,
+    ADD COLUMN shipping_method_name VARCHAR(255),
+    ADD COLUMN shipping_amount DECIMAL(10, 2) NOT NULL DEFAULT 0;
+
+-- Starting price list
+INSERT INTO shipping_zones (name, postal_code_from, postal_code_to) VALUES
+    ('София', 1000, 1999);
+
+INSERT INTO shipping_methods (name, description, kind, carrier, free_shipping_threshold, position) VALUES
+    ('До офис на Еконт', 'Вземане от избран офис на Еконт', 'courier_office', 'econt', 100.00, 1),
+    ('До офис на Спиди', 'Вземане от избран офис на Спиди', 'courier_office', 'speedy', 100.00, 2),
+    ('До адрес', 'Доставка с куриер до посочения адрес', 'courier_address', 'econt', 150.00, 3),
+    ('Вземане от ателието', 'Лично вземане от ателието в София', 'pickup', NULL, NULL, 4);
+
+INSERT INTO shipping_rates (method_id, zone_id, max_weight_grams, price)
+SELECT m.id, NULL, r.max_weight_grams, r.price
+FROM shipping_methods m
+JOIN (VALUES
+    ('econt', 'courier_office', 1000, 5.50),
+    ('econt', 'courier_office', 2000, 6.50),
+    ('econt', 'courier_office', 5000, 8.90),
+    ('econt', 'courier_office', 10000, 11.90),
+    ('speedy', 'courier_office', 1000, 5.90),
+    ('speedy', 'courier_office', 2000, 6.90),
+    ('speedy', 'courier_office', 5000, 9.50),
+    ('speedy', 'courier_office', 10000, 12.50),
+    ('econt', 'courier_address', 1000, 7.90),
+    ('econt', 'courier_address', 2000, 8.90),
+    ('econt', 'courier_address', 5000, 11.50),
+    ('econt', 'courier_address', 10000, 14.90)
+) AS r(carrier, kind, max_weight_grams, price)
+    ON m.carrier = r.carrier AND m.kind = r.kind;
+
+INSERT INTO shipping_rates (method_id, zone_id, max_weight_grams, price)
+SELECT m.id, z.id, 5000, 5.90
+FROM shipping_methods m, shipping_zones z
+WHERE m.kind = 'courier_address' AND z.name = 'София';
+
+INSERT INTO shipping_rates (method_id, zone_id, max_weight_grams, price)
+SELECT id, NULL, NULL, 0 FROM shipping_methods WHERE kind = 'pickup';
@@ -0,0 +1,38 @@
+-- Courier waybills
+-- A waybill is created with the order's carrier once the parcel is ready. The
+-- label PDF is kept in media storage and the courier's tracking is polled to
+-- move the order to shipped and delivered.
+
+-- Carrier office chosen at checkout for office delivery, and the parcel weight
+-- the shipping price was worked out for
+ALTER TABLE orders
+    ADD COLUMN courier_office_code VARCHAR(50),
+    ADD COLUMN shipping_weight_grams INTEGER NOT NULL DEFAULT 0;
+
+UPDATE orders o SET shipping_weight_grams = COALESCE((
+    SELECT SUM(p.weight_grams * i.quantity)
+    FROM order_items i
+    JOIN products p ON p.id = i.product_id
+    WHERE i.order_id = o.id
+), 0);
+
+CREATE TABLE IF NOT EXISTS shipments (
+    id SERIAL PRIMARY KEY,
+    order_id UUID NOT NULL UNIQUE REFERENCES orders(id) ON DELETE CASCADE,
+    carrier VARCHAR(20) NOT NULL CHECK (carrier IN ('econt', 'speedy')),
+    tracking_number VARCHAR(100) NOT NULL,
+    -- Storage key of the label PDF
+    label_key VARCHAR(255) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'created'
+        CHECK (status IN ('created', 'in_transit', 'delivered')),
+    -- Latest tracking event as reported by the courier
+    last_event TEXT,
+    last_checked_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_shipments_undelivered ON shipments(id) WHERE status <> 'delivered';
+
+CREATE TRIGGER update_shipments_updated_at BEFORE UPDATE ON shipments
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
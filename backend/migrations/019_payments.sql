@@ -0,0 +1,61 @@
+-- Payments
+-- Orders are paid in cash on delivery or by card through a payment provider.
+-- payment_status is kept apart from the fulfilment status: a card order can
+-- be paid while still pending, and a delivered order can be refunded.
+
+ALTER TABLE orders
+    ADD COLUMN payment_method VARCHAR(20) NOT NULL DEFAULT 'cash_on_delivery'
+        CHECK (payment_method IN ('cash_on_delivery', 'card')),
+    ADD COLUMN payment_status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (payment_status IN ('pending', 'paid', 'failed', 'partially_refunded', 'refunded')),
+    ADD COLUMN paid_at TIMESTAMP,
+    ADD COLUMN refunded_amount DECIMAL(10, 2) NOT NULL DEFAULT 0;
+
+-- Cash was collected for orders that were handed over
+UPDATE orders SET payment_status = 'paid', paid_at = delivered_at
+WHERE delivered_at IS NOT NULL;
+
+CREATE INDEX idx_orders_payment_status ON orders(payment_status);
+
+-- One attempt to take a card payment, e.g. a Stripe PaymentIntent
+CREATE TABLE IF NOT EXISTS payments (
+    id SERIAL PRIMARY KEY,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    provider VARCHAR(20) NOT NULL,
+    -- The provider's id, which its webhooks refer to
+    provider_payment_id VARCHAR(255) NOT NULL,
+    amount DECIMAL(10, 2) NOT NULL CHECK (amount > 0),
+    currency CHAR(3) NOT NULL DEFAULT 'BGN',
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'succeeded', 'failed')),
+    -- Why the last attempt was declined, as reported by the provider
+    failure_message TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (provider, provider_payment_id)
+);
+
+CREATE INDEX idx_payments_order ON payments(order_id);
+
+CREATE TRIGGER update_payments_updated_at BEFORE UPDATE ON payments
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS refunds (
+    id SERIAL PRIMARY KEY,
+    payment_id INTEGER NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+    provider_refund_id VARCHAR(255) NOT NULL,
+    amount DECIMAL(10, 2) NOT NULL CHECK (amount > 0),
+    reason TEXT,
+    admin_id INTEGER REFERENCES admins(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_refunds_payment ON refunds(payment_id);
+
+-- Webhook events already handled; providers deliver them at least once
+CREATE TABLE IF NOT EXISTS payment_events (
+    provider VARCHAR(20) NOT NULL,
+    event_id VARCHAR(255) NOT NULL,
+    received_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (provider, event_id)
+);
@@ -0,0 +1,51 @@
+-- Cash on delivery reconciliation
+-- Couriers collect the order total on delivery and pay it out later with a
+-- report of the parcels it covers. Importing the report marks the matching
+-- orders paid and keeps every row that did not match for a closer look.
+
+ALTER TABLE orders DROP CONSTRAINT orders_payment_method_check;
+ALTER TABLE orders ADD CONSTRAINT orders_payment_method_check
+    CHECK (payment_method IN ('cash_on_delivery', 'bank_transfer', 'card'));
+
+-- Fee the courier charges for collecting cash, passed on to the customer
+ALTER TABLE shipping_methods
+    ADD COLUMN cod_fee DECIMAL(10, 2) NOT NULL DEFAULT 0 CHECK (cod_fee >= 0);
+
+ALTER TABLE orders
+    ADD COLUMN cod_fee DECIMAL(10, 2) NOT NULL DEFAULT 0,
+    -- What was actually received for the order
+    ADD COLUMN collected_amount DECIMAL(10, 2);
+
+-- One imported payout report
+CREATE TABLE IF NOT EXISTS cod_remittances (
+    id SERIAL PRIMARY KEY,
+    -- Courier that paid out, when known
+    carrier VARCHAR(20) CHECK (carrier IN ('econt', 'speedy')),
+    file_name VARCHAR(255),
+    total_amount DECIMAL(10, 2) NOT NULL,
+    line_count INTEGER NOT NULL,
+    matched_count INTEGER NOT NULL,
+    admin_id INTEGER REFERENCES admins(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- One parcel of the report and what it was matched to
+CREATE TABLE IF NOT EXISTS cod_remittance_lines (
+    id SERIAL PRIMARY KEY,
+    remittance_id INTEGER NOT NULL REFERENCES cod_remittances(id) ON DELETE CASCADE,
+    -- Row of the file, counting the header as 1
+    line_number INTEGER NOT NULL,
+    tracking_number VARCHAR(100) NOT NULL,
+    amount DECIMAL(10, 2) NOT NULL,
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    -- What the order expected to collect
+    expected_amount DECIMAL(10, 2),
+    status VARCHAR(30) NOT NULL
+        CHECK (status IN (
+            'matched', 'amount_mismatch', 'unknown_tracking_number',
+            'not_cash_on_delivery', 'already_collected', 'duplicate'
+        ))
+);
+
+CREATE INDEX idx_cod_remittance_lines_remittance ON cod_remittance_lines(remittance_id);
+CREATE INDEX idx_cod_remittance_lines_order ON cod_remittance_lines(order_id);
@@ -0,0 +1,47 @@
+-- Bank transfers
+-- Transfer orders get a payment reference the customer quotes with the
+-- transfer. Imported bank statements are matched to orders by it, and orders
+-- still unpaid at their deadline are cancelled.
+
+ALTER TABLE orders
+    ADD COLUMN payment_reference VARCHAR(20) UNIQUE,
+    -- Cancelled when not paid by then
+    ADD COLUMN payment_due_at TIMESTAMP;
+
+CREATE INDEX idx_orders_payment_due ON orders(payment_due_at)
+    WHERE payment_method = 'bank_transfer' AND payment_status = 'pending';
+
+-- One imported bank statement
+CREATE TABLE IF NOT EXISTS bank_statements (
+    id SERIAL PRIMARY KEY,
+    file_name VARCHAR(255),
+    format VARCHAR(10) NOT NULL CHECK (format IN ('camt053', 'csv')),
+    total_amount DECIMAL(10, 2) NOT NULL,
+    entry_count INTEGER NOT NULL,
+    matched_count INTEGER NOT NULL,
+    admin_id INTEGER REFERENCES admins(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- One incoming payment of the statement and what it was matched to
+CREATE TABLE IF NOT EXISTS bank_statement_entries (
+    id SERIAL PRIMARY KEY,
+    statement_id INTEGER NOT NULL REFERENCES bank_statements(id) ON DELETE CASCADE,
+    -- The bank's id for the payment, for recognising it in a later statement
+    bank_reference VARCHAR(255) NOT NULL,
+    booking_date DATE,
+    amount DECIMAL(10, 2) NOT NULL,
+    currency CHAR(3) NOT NULL,
+    payer_name VARCHAR(255),
+    -- Payment details as written by the payer
+    remittance_info TEXT,
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    status VARCHAR(30) NOT NULL
+        CHECK (status IN (
+            'matched', 'amount_mismatch', 'no_reference', 'unknown_reference',
+            'already_paid', 'order_cancelled', 'duplicate'
+        ))
+);
+
+CREATE INDEX idx_bank_statement_entries_statement ON bank_statement_entries(statement_id);
+CREATE INDEX idx_bank_statement_entries_reference ON bank_statement_entries(bank_reference);
@@ -0,0 +1,46 @@
+-- Euro
+-- Prices and order amounts were kept in leva. They are converted to euro at
+-- the fixed rate of 1.95583 leva per euro, rounded to the cent, and the lev
+-- amount is derived from the euro one from now on. Payments, refunds and
+-- bank statement entries keep the currency they were made in.
+
+UPDATE products SET price = ROUND(price / 1.95583, 2);
+UPDATE product_variants SET price = ROUND(price / 1.95583, 2) WHERE price IS NOT NULL;
+
+UPDATE promotions SET value = ROUND(value / 1.95583, 2) WHERE kind = 'fixed_amount';
+UPDATE promotions SET min_order_amount = ROUND(min_order_amount / 1.95583, 2)
+    WHERE min_order_amount IS NOT NULL;
+
+UPDATE shipping_methods SET
+    free_shipping_threshold = ROUND(free_shipping_threshold / 1.95583, 2),
+    cod_fee = ROUND(cod_fee / 1.95583, 2);
+UPDATE shipping_rates SET price = ROUND(price / 1.95583, 2);
+
+-- Totals are converted on their own rather than summed again, so an old
+-- order may be off by a cent against its converted lines
+UPDATE orders SET
+    total_amount = ROUND(total_amount / 1.95583, 2),
+    subtotal_amount = ROUND(subtotal_amount / 1.95583, 2),
+    discount_amount = ROUND(discount_amount / 1.95583, 2),
+    shipping_amount = ROUND(shipping_amount / 1.95583, 2),
+    refunded_amount = ROUND(refunded_amount / 1.95583, 2),
+    cod_fee = ROUND(cod_fee / 1.95583, 2),
+    collected_amount = ROUND(collected_amount / 1.95583, 2);
+
+UPDATE order_items SET
+    product_price = ROUND(product_price / 1.95583, 2),
+    subtotal = ROUND(subtotal / 1.95583, 2),
+    discount_amount = ROUND(discount_amount / 1.95583, 2);
+
+UPDATE cod_remittances SET total_amount = ROUND(total_amount / 1.95583, 2);
+UPDATE cod_remittance_lines SET
+    amount = ROUND(amount / 1.95583, 2),
+    expected_amount = ROUND(expected_amount / 1.95583, 2);
+
+UPDATE bank_statements SET total_amount = ROUND(total_amount / 1.95583, 2);
+
+-- Existing refunds were made in leva
+ALTER TABLE refunds ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'BGN';
+ALTER TABLE refunds ALTER COLUMN currency SET DEFAULT 'EUR';
+
+ALTER TABLE payments ALTER COLUMN currency SET DEFAULT 'EUR';
@@ -0,0 +1,47 @@
+-- VAT
+-- Prices include VAT at the rate of the product's tax class. Orders record
+-- the net and VAT parts of every line and a breakdown by rate; orders placed
+-- while the studio is not VAT-registered carry no VAT.
+
+CREATE TABLE IF NOT EXISTS tax_classes (
+    code VARCHAR(20) PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    -- Percent
+    rate DECIMAL(5, 2) NOT NULL CHECK (rate >= 0 AND rate < 100),
+    -- Legal ground printed on invoices for lines charged no VAT
+    exemption_reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER update_tax_classes_updated_at BEFORE UPDATE ON tax_classes
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO tax_classes (code, name, rate, exemption_reason) VALUES
+    ('standard', 'Стандартна ставка', 20, NULL),
+    ('reduced', 'Намалена ставка', 9, NULL),
+    ('exempt', 'Освободена доставка', 0, 'чл. 39 от ЗДДС');
+
+ALTER TABLE products
+    ADD COLUMN tax_class VARCHAR(20) NOT NULL DEFAULT 'standard' REFERENCES tax_classes(code);
+
+-- Parts of what was paid for the line, after its discount. tax_rate is NULL
+-- when no VAT was charged.
+ALTER TABLE order_items
+    ADD COLUMN tax_rate DECIMAL(5, 2),
+    ADD COLUMN net_amount DECIMAL(10, 2),
+    ADD COLUMN vat_amount DECIMAL(10, 2) NOT NULL DEFAULT 0;
+
+UPDATE order_items SET net_amount = subtotal - discount_amount;
+ALTER TABLE order_items ALTER COLUMN net_amount SET NOT NULL;
+
+ALTER TABLE orders
+    ADD COLUMN net_amount DECIMAL(10, 2),
+    ADD COLUMN vat_amount DECIMAL(10, 2) NOT NULL DEFAULT 0,
+    -- Rate charged on shipping and the cash on delivery fee
+    ADD COLUMN shipping_tax_rate DECIMAL(5, 2),
+    -- Net, VAT and gross amounts by rate
+    ADD COLUMN tax_breakdown JSONB NOT NULL DEFAULT '[]';
+
+UPDATE orders SET net_amount = total_amount;
+ALTER TABLE orders ALTER COLUMN net_amount SET NOT NULL;
@@ -0,0 +1,79 @@
+-- Invoices
+-- Invoices and credit notes are issued from orders and share one gap-free
+-- sequence of 10-digit numbers. Both keep a copy of the seller, buyer and
+-- lines as issued, since they may not change afterwards.
+
+-- Company details a business customer gave at checkout
+ALTER TABLE orders
+    ADD COLUMN invoice_company VARCHAR(255),
+    -- ЕИК (BULSTAT) of the company
+    ADD COLUMN invoice_eik VARCHAR(13),
+    ADD COLUMN invoice_vat_number VARCHAR(20),
+    -- МОЛ, the person legally representing the company
+    ADD COLUMN invoice_mol VARCHAR(255),
+    ADD COLUMN invoice_address TEXT;
+
+-- Last number given out. Taking the next one locks the row until the
+-- transaction ends, so a failed issue leaves no gap.
+CREATE TABLE IF NOT EXISTS invoice_numbers (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    last_number BIGINT NOT NULL DEFAULT 0
+);
+
+INSERT INTO invoice_numbers (id, last_number) VALUES (TRUE, 0);
+
+CREATE TABLE IF NOT EXISTS invoices (
+    id SERIAL PRIMARY KEY,
+    number BIGINT NOT NULL UNIQUE CHECK (number BETWEEN 1 AND 9999999999),
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('invoice', 'credit_note')),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE RESTRICT,
+    -- Invoice a credit note corrects
+    invoice_id INTEGER REFERENCES invoices(id) ON DELETE RESTRICT,
+    refund_id INTEGER UNIQUE REFERENCES refunds(id) ON DELETE SET NULL,
+    issue_date DATE NOT NULL,
+    -- Date of the supply (данъчно събитие)
+    tax_event_date DATE NOT NULL,
+    seller_name VARCHAR(255) NOT NULL,
+    seller_eik VARCHAR(13) NOT NULL,
+    seller_vat_number VARCHAR(20),
+    seller_mol VARCHAR(255),
+    seller_address TEXT NOT NULL,
+    buyer_name VARCHAR(255) NOT NULL,
+    buyer_eik VARCHAR(13),
+    buyer_vat_number VARCHAR(20),
+    buyer_mol VARCHAR(255),
+    buyer_address TEXT NOT NULL,
+    payment_method VARCHAR(20) NOT NULL,
+    net_amount DECIMAL(10, 2) NOT NULL,
+    vat_amount DECIMAL(10, 2) NOT NULL,
+    total_amount DECIMAL(10, 2) NOT NULL,
+    -- Net, VAT and gross amounts by rate
+    tax_breakdown JSONB NOT NULL DEFAULT '[]',
+    -- Legal ground for lines charged no VAT
+    vat_exemption_reason TEXT,
+    -- Why a credit note was issued
+    reason TEXT,
+    admin_id INTEGER REFERENCES admins(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK ((kind = 'credit_note') = (invoice_id IS NOT NULL))
+);
+
+-- One invoice per order; credit notes go against it
+CREATE UNIQUE INDEX idx_invoices_order ON invoices(order_id) WHERE kind = 'invoice';
+CREATE INDEX idx_invoices_invoice ON invoices(invoice_id);
+
+CREATE TABLE IF NOT EXISTS invoice_lines (
+    id SERIAL PRIMARY KEY,
+    invoice_id INTEGER NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    -- Without VAT, after any discount
+    unit_price DECIMAL(10, 2) NOT NULL,
+    -- NULL when no VAT is charged
+    tax_rate DECIMAL(5, 2),
+    net_amount DECIMAL(10, 2) NOT NULL,
+    vat_amount DECIMAL(10, 2) NOT NULL,
+    total_amount DECIMAL(10, 2) NOT NULL,
+    UNIQUE (invoice_id, position)
+);
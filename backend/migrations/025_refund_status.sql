@@ -0,0 +1,14 @@
+-- Refund status
+-- A refund is recorded as pending before it is sent to the provider, so a
+-- refund whose answer was lost can be sent again under the same idempotency
+-- key instead of paying out twice. It gets the provider's id once settled.
+
+ALTER TABLE refunds
+    ALTER COLUMN provider_refund_id DROP NOT NULL,
+    ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'succeeded'
+        CHECK (status IN ('pending', 'succeeded', 'failed')),
+    ADD COLUMN failure_message TEXT;
+
+ALTER TABLE refunds ALTER COLUMN status SET DEFAULT 'pending';
+
+CREATE INDEX idx_refunds_pending ON refunds(payment_id) WHERE status = 'pending';
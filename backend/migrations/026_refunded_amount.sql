@@ -0,0 +1,19 @@
+-- Refunded amount in euro
+-- orders.refunded_amount was converted to euro as a total, while the refunds
+-- behind it keep the leva they were made in, so a total converted at once
+-- could differ by a cent from its refunds converted one by one. It is worked
+-- out again from its refunds, each converted at the fixed rate and rounded
+-- to the cent, the way amounts in leva are counted from now on.
+
+UPDATE orders o SET refunded_amount = refunded.total
+FROM (
+    SELECT p.order_id,
+           SUM(CASE WHEN r.currency = 'BGN' THEN ROUND(r.amount / 1.95583, 2) ELSE r.amount END)
+               AS total
+    FROM refunds r
+    JOIN payments p ON p.id = r.payment_id
+    WHERE r.status = 'succeeded'
+    GROUP BY p.order_id
+) refunded
+WHERE refunded.order_id = o.id;
+
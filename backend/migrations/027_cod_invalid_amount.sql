@@ -0,0 +1,16 @@
+-- Unreadable amounts in payout reports
+-- Couriers write amounts with a decimal point. A row whose amount reads
+-- otherwise, like "1,250", is kept as it was written instead of failing the
+-- whole import, and matches no order.
+
+ALTER TABLE cod_remittance_lines
+    ALTER COLUMN amount DROP NOT NULL,
+    -- The amount as written, when it could not be read
+    ADD COLUMN raw_amount VARCHAR(100);
+
+ALTER TABLE cod_remittance_lines DROP CONSTRAINT cod_remittance_lines_status_check;
+ALTER TABLE cod_remittance_lines ADD CONSTRAINT cod_remittance_lines_status_check
+    CHECK (status IN (
+        'matched', 'amount_mismatch', 'unknown_tracking_number',
+        'not_cash_on_delivery', 'already_collected', 'duplicate', 'invalid_amount'
+    ));
@@ -0,0 +1,8 @@
+-- Short order number
+-- Customers quote the first 8 characters of the order id from their emails.
+-- Stored and indexed so looking an order up by it does not scan every order.
+
+ALTER TABLE orders ADD COLUMN order_reference CHAR(8)
+    GENERATED ALWAYS AS (UPPER(LEFT(id::text, 8))) STORED;
+
+CREATE INDEX idx_orders_order_reference ON orders (order_reference);
@@ -0,0 +1,15 @@
+-- Bank statement totals in euro
+-- bank_statements.total_amount was converted to euro as a total, while its
+-- entries keep the currency they were paid in, so the total and its entries
+-- disagreed. It is worked out again from its entries the way imports count
+-- it: euro as it is, leva converted at the fixed rate and rounded to the
+-- cent, other currencies left out.
+
+UPDATE bank_statements s SET total_amount = COALESCE((
+    SELECT SUM(CASE e.currency
+                   WHEN 'EUR' THEN e.amount
+                   WHEN 'BGN' THEN ROUND(e.amount / 1.95583, 2)
+               END)
+    FROM bank_statement_entries e
+    WHERE e.statement_id = s.id
+), 0);
@@ -1,8 +1,4 @@
-use axum::{
-    extract::Request,
-    middleware::Next,
-    response::Response,
-};
+use axum::{extract::Request, middleware::Next, response::Response};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -11,9 +7,9 @@ use crate::error::AppError;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
-    pub sub: String,     // admin email
-    pub admin_id: i32,   // admin id
-    pub exp: u64,        // expiry timestamp
+    pub sub: String,   // admin email
+    pub admin_id: i32, // admin id
+    pub exp: u64,      // expiry timestamp
 }
 
 pub fn create_jwt(admin_id: i32, email: &str) -> Result<String, AppError> {
@@ -0,0 +1,287 @@
+use async_trait::async_trait;
+use rust_decimal::prelude::ToPrimitive;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{
+    Courier, CourierOffice, Destination, ShipmentRequest, ShipmentStatus, TrackingUpdate, Waybill,
+};
+use crate::{error::AppError, models::Currency};
+
+pub struct EcontConfig {
+    // e.g. https://ee.econt.com/services, or https://demo.econt.com/ee/services
+    pub api_url: String,
+    pub username: String,
+    pub password: String,
+    pub sender_name: String,
+    pub sender_phone: String,
+    // Office the studio hands parcels in at; the profile address otherwise
+    pub sender_office_code: Option<String>,
+}
+
+// Econt Express JSON API, authenticated with the e-Econt account
+pub struct EcontCourier {
+    config: EcontConfig,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct OfficesResponse {
+    offices: Vec<Office>,
+}
+
+#[derive(Deserialize)]
+struct Office {
+    code: String,
+    name: String,
+    address: OfficeAddress,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OfficeAddress {
+    city: City,
+    full_address: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct City {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct LabelResponse {
+    label: Label,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Label {
+    shipment_number: String,
+    #[serde(rename = "pdfURL")]
+    pdf_url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusesResponse {
+    shipment_statuses: Vec<StatusEntry>,
+}
+
+#[derive(Deserialize)]
+struct StatusEntry {
+    status: Option<ShipmentStatusInfo>,
+    error: Option<EcontError>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ShipmentStatusInfo {
+    send_time: Option<Value>,
+    delivery_time: Option<Value>,
+    short_delivery_status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EcontError {
+    message: Option<String>,
+}
+
+impl EcontCourier {
+    pub fn new(mut config: EcontConfig) -> Self {
+        config.api_url = config.api_url.trim_end_matches('/').to_string();
+
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    // Calls one service method, e.g. "Shipments/LabelService.createLabel"
+    async fn call<T: DeserializeOwned>(&self, method: &str, body: Value) -> Result<T, AppError> {
+        let response = self
+            .client
+            .post(format!("{}/{}.json", self.config.api_url, method))
+            .basic_auth(&self.config.username, Some(&self.config.password))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Econt request {} failed: {:?}", method, e);
+                AppError::InternalServerError("Econt is unavailable".to_string())
+            })?;
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            // Validation errors come back as an error object with a message,
+            // outages without one
+            let message = serde_json::from_str::<EcontError>(&body)
+                .ok()
+                .and_then(|error| error.message);
+            return match message {
+                Some(message) => Err(AppError::BadRequest(format!("Econt: {}", message))),
+                None => {
+                    tracing::error!("Econt rejected {}: {} {}", method, status, body);
+                    Err(AppError::InternalServerError(
+                        "Econt is unavailable".to_string(),
+                    ))
+                }
+            };
+        }
+
+        serde_json::from_str(&body).map_err(|e| {
+            tracing::error!("Unexpected Econt response to {}: {:?} {}", method, e, body);
+            AppError::InternalServerError("Unexpected response from Econt".to_string())
+        })
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>, AppError> {
+        let response = self
+            .client
+            .get(url)
+            .basic_auth(&self.config.username, Some(&self.config.password))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                tracing::error!("Failed to download Econt label {}: {:?}", url, e);
+                AppError::InternalServerError("Failed to download the waybill label".to_string())
+            })?;
+
+        let pdf = response.bytes().await.map_err(|e| {
+            tracing::error!("Failed to read Econt label {}: {:?}", url, e);
+            AppError::InternalServerError("Failed to download the waybill label".to_string())
+        })?;
+
+        Ok(pdf.to_vec())
+    }
+}
+
+#[async_trait]
+impl Courier for EcontCourier {
+    // Econt filters offices by its own city ids, so they are matched by name
+    async fn list_offices(&self, city: &str) -> Result<Vec<CourierOffice>, AppError> {
+        let response: OfficesResponse = self
+            .call(
+                "Nomenclatures/NomenclaturesService.getOffices",
+                json!({ "countryCode": "BGR" }),
+            )
+            .await?;
+
+        let city = city.trim().to_lowercase();
+        Ok(response
+            .offices
+            .into_iter()
+            .filter(|office| office.address.city.name.to_lowercase() == city)
+            .map(|office| CourierOffice {
+                code: office.code,
+                name: office.name,
+                address: office.address.full_address.unwrap_or_default(),
+                city: office.address.city.name,
+            })
+            .collect())
+    }
+
+    async fn create_waybill(&self, shipment: &ShipmentRequest) -> Result<Waybill, AppError> {
+        let mut label = json!({
+            "senderClient": {
+                "name": self.config.sender_name,
+                "phones": [self.config.sender_phone],
+            },
+            "receiverClient": {
+                "name": shipment.recipient_name,
+                "phones": [shipment.recipient_phone],
+                "email": shipment.recipient_email,
+            },
+            "packCount": 1,
+            "shipmentType": "PACK",
+            "weight": shipment.weight_kg(),
+            "shipmentDescription": shipment.description,
+            "orderNumber": shipment.reference,
+        });
+
+        if let Some(code) = &self.config.sender_office_code {
+            label["senderOfficeCode"] = json!(code);
+        }
+        match &shipment.destination {
+            Destination::Office { code } => label["receiverOfficeCode"] = json!(code),
+            Destination::Address {
+                city,
+                postal_code,
+                address,
+            } => {
+                label["receiverAddress"] = json!({
+                    "city": {
+                        "country": { "code3": "BGR" },
+                        "name": city,
+                        "postCode": postal_code,
+                    },
+                    "other": address,
+                });
+            }
+        }
+        if let Some(amount) = shipment.cash_on_delivery {
+            label["services"] = json!({
+                "cdAmount": amount.amount().to_f64(),
+                "cdType": "get",
+                "cdCurrency": Currency::Eur.as_str(),
+            });
+        }
+
+        let response: LabelResponse = self
+            .call(
+                "Shipments/LabelService.createLabel",
+                json!({ "label": label, "mode": "create" }),
+            )
+            .await?;
+
+        Ok(Waybill {
+            label_pdf: self.download(&response.label.pdf_url).await?,
+            tracking_number: response.label.shipment_number,
+        })
+    }
+
+    async fn track(&self, tracking_number: &str) -> Result<TrackingUpdate, AppError> {
+        let response: StatusesResponse = self
+            .call(
+                "Shipments/ShipmentService.getShipmentStatuses",
+                json!({ "shipmentNumbers": [tracking_number] }),
+            )
+            .await?;
+
+        let entry = response
+            .shipment_statuses
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                AppError::InternalServerError("Unexpected response from Econt".to_string())
+            })?;
+        if let Some(error) = entry.error {
+            return Err(AppError::BadRequest(format!(
+                "Econt: {}",
+                error
+                    .message
+                    .unwrap_or_else(|| "unknown shipment".to_string())
+            )));
+        }
+        let info = entry.status.ok_or_else(|| {
+            AppError::InternalServerError("Unexpected response from Econt".to_string())
+        })?;
+
+        let is_set = |time: &Option<Value>| time.as_ref().is_some_and(|t| !t.is_null());
+        let status = if is_set(&info.delivery_time) {
+            ShipmentStatus::Delivered
+        } else if is_set(&info.send_time) {
+            ShipmentStatus::InTransit
+        } else {
+            ShipmentStatus::Created
+        };
+
+        Ok(TrackingUpdate {
+            status,
+            last_event: info.short_delivery_status,
+        })
+    }
+}
@@ -0,0 +1,322 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    handlers::orders::transition_order,
+    models::{Money, Shipment},
+    order_status::OrderStatus,
+    shipping::Carrier,
+    storage::{SharedStorage, PRIVATE_PREFIX},
+};
+
+mod econt;
+mod speedy;
+
+pub use econt::{EcontConfig, EcontCourier};
+pub use speedy::{SpeedyConfig, SpeedyCourier};
+
+// Couriers weigh every parcel; unweighed products still come in a box
+const MIN_PARCEL_GRAMS: i32 = 100;
+
+// Speedy's standard delivery service
+const DEFAULT_SPEEDY_SERVICE_ID: i32 = 505;
+
+// API of a courier company: offices, waybills and tracking
+#[async_trait]
+pub trait Courier: Send + Sync {
+    async fn list_offices(&self, city: &str) -> Result<Vec<CourierOffice>, AppError>;
+
+    async fn create_waybill(&self, shipment: &ShipmentRequest) -> Result<Waybill, AppError>;
+
+    async fn track(&self, tracking_number: &str) -> Result<TrackingUpdate, AppError>;
+}
+
+pub type SharedCourier = Arc<dyn Courier>;
+
+// Office where customers collect parcels
+#[derive(Debug, Clone, Serialize)]
+pub struct CourierOffice {
+    pub code: String,
+    pub name: String,
+    pub city: String,
+    pub address: String,
+}
+
+// Where the courier takes the parcel
+pub enum Destination {
+    Office {
+        code: String,
+    },
+    Address {
+        city: String,
+        postal_code: Option<String>,
+        address: String,
+    },
+}
+
+// Parcel handed to a courier, built from an order
+pub struct ShipmentRequest {
+    // Order number printed on the label
+    pub reference: String,
+    pub recipient_name: String,
+    pub recipient_phone: String,
+    pub recipient_email: String,
+    pub destination: Destination,
+    pub weight_grams: i32,
+    // Collected from the recipient on delivery
+    pub cash_on_delivery: Option<Money>,
+    pub description: String,
+}
+
+impl ShipmentRequest {
+    pub fn weight_kg(&self) -> f64 {
+        f64::from(self.weight_grams.max(MIN_PARCEL_GRAMS)) / 1000.0
+    }
+}
+
+// Waybill registered with the courier
+pub struct Waybill {
+    pub tracking_number: String,
+    pub label_pdf: Vec<u8>,
+}
+
+// Where a parcel is; stored in `shipments.status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShipmentStatus {
+    // Waybill created, parcel not yet with the courier
+    Created,
+    InTransit,
+    Delivered,
+}
+
+impl ShipmentStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ShipmentStatus::Created => "created",
+            ShipmentStatus::InTransit => "in_transit",
+            ShipmentStatus::Delivered => "delivered",
+        }
+    }
+}
+
+pub struct TrackingUpdate {
+    pub status: ShipmentStatus,
+    // Latest event, e.g. "Приета в офис София - Изток"
+    pub last_event: Option<String>,
+}
+
+// The configured couriers; a carrier without credentials is unavailable
+#[derive(Clone, Default)]
+pub struct Couriers {
+    econt: Option<SharedCourier>,
+    speedy: Option<SharedCourier>,
+}
+
+impl Couriers {
+    pub fn get(&self, carrier: Carrier) -> Result<&SharedCourier, AppError> {
+        let courier = match carrier {
+            Carrier::Econt => &self.econt,
+            Carrier::Speedy => &self.speedy,
+        };
+
+        courier.as_ref().ok_or_else(|| {
+            AppError::BadRequest(format!("Courier '{}' is not configured", carrier.as_str()))
+        })
+    }
+}
+
+// Builds the couriers that have credentials in ECONT_* and SPEEDY_*
+pub fn from_env() -> Couriers {
+    // docker-compose passes unset variables as empty strings
+    let optional = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+    let econt = optional("ECONT_USERNAME").map(|username| {
+        Arc::new(EcontCourier::new(EcontConfig {
+            api_url: optional("ECONT_API_URL")
+                .unwrap_or_else(|| "https://ee.econt.com/services".to_string()),
+            username,
+            password: optional("ECONT_PASSWORD").unwrap_or_default(),
+            sender_name: optional("COURIER_SENDER_NAME")
+                .unwrap_or_else(|| "Studio Zemya".to_string()),
+            sender_phone: optional("COURIER_SENDER_PHONE").unwrap_or_default(),
+            sender_office_code: optional("ECONT_SENDER_OFFICE_CODE"),
+        })) as SharedCourier
+    });
+
+    let speedy = optional("SPEEDY_USERNAME").map(|username| {
+        Arc::new(SpeedyCourier::new(SpeedyConfig {
+            api_url: optional("SPEEDY_API_URL")
+                .unwrap_or_else(|| "https://api.speedy.bg/v1".to_string()),
+            username,
+            password: optional("SPEEDY_PASSWORD").unwrap_or_default(),
+            service_id: optional("SPEEDY_SERVICE_ID")
+                .and_then(|id| {
+                    let parsed = id.parse().ok();
+                    if parsed.is_none() {
+                        tracing::warn!(
+                            "SPEEDY_SERVICE_ID '{}' is not a number, using {}",
+                            id,
+                            DEFAULT_SPEEDY_SERVICE_ID
+                        );
+                    }
+                    parsed
+                })
+                .unwrap_or(DEFAULT_SPEEDY_SERVICE_ID),
+        })) as SharedCourier
+    });
+
+    Couriers { econt, speedy }
+}
+
+// Asks the couriers where undelivered parcels are and moves their orders
+// along: to shipped once the courier has the parcel, to delivered once the
+// customer has it. One failing shipment does not hold up the others.
+pub async fn poll_shipments(pool: &PgPool, couriers: &Couriers) -> Result<usize, AppError> {
+    let shipments = sqlx::query_as::<_, Shipment>(
+        r#"
+        SELECT s.* FROM shipments s
+        JOIN orders o ON o.id = s.order_id
+        WHERE s.status <> 'delivered'
+          AND o.status IN ('pending', 'processing', 'shipped')
+        ORDER BY s.last_checked_at NULLS FIRST, s.id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut advanced = 0;
+    for shipment in shipments {
+        match poll_shipment(pool, couriers, &shipment).await {
+            Ok(true) => advanced += 1,
+            Ok(false) => {}
+            Err(err) => tracing::warn!(
+                "Failed to track shipment {} ({}): {:?}",
+                shipment.tracking_number,
+                shipment.carrier,
+                err
+            ),
+        }
+    }
+
+    Ok(advanced)
+}
+
+// Returns whether the shipment moved on
+async fn poll_shipment(
+    pool: &PgPool,
+    couriers: &Couriers,
+    shipment: &Shipment,
+) -> Result<bool, AppError> {
+    let courier = couriers.get(Carrier::parse(&shipment.carrier)?)?;
+    let update = courier.track(&shipment.tracking_number).await?;
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE shipments
+        SET status = $1, last_event = COALESCE($2, last_event), last_checked_at = NOW()
+        WHERE id = $3
+        "#,
+    )
+    .bind(update.status.as_str())
+    .bind(&update.last_event)
+    .bind(shipment.id)
+    .execute(&mut *tx)
+    .await?;
+
+    let target = match update.status {
+        ShipmentStatus::Created => None,
+        ShipmentStatus::InTransit => Some(OrderStatus::Shipped),
+        ShipmentStatus::Delivered => Some(OrderStatus::Delivered),
+    };
+    if let Some(target) = target {
+        advance_order(
+            &mut tx,
+            shipment.order_id,
+            target,
+            &shipment.tracking_number,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(update.status.as_str() != shipment.status)
+}
+
+// Moves labels stored under public keys, as they were before labels were
+// kept private, to private keys; returns how many were moved
+pub async fn make_labels_private(
+    pool: &PgPool,
+    storage: &SharedStorage,
+) -> Result<usize, AppError> {
+    let shipments = sqlx::query_as::<_, (i32, String)>(
+        "SELECT id, label_key FROM shipments WHERE label_key NOT LIKE 'private/%' ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for (id, key) in &shipments {
+        let private_key = format!("{}{}", PRIVATE_PREFIX, key);
+        let label = storage.get(key).await?;
+        storage.put(&private_key, label).await?;
+        sqlx::query("UPDATE shipments SET label_key = $1 WHERE id = $2")
+            .bind(&private_key)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        storage.delete(key).await?;
+    }
+
+    if !shipments.is_empty() {
+        tracing::info!(
+            "Moved {} waybill labels to private storage",
+            shipments.len()
+        );
+    }
+
+    Ok(shipments.len())
+}
+
+// Walks the order forward through the usual statuses up to `target`, so a
+// parcel picked up before anyone marked the order as processing still ends
+// up shipped
+async fn advance_order(
+    conn: &mut sqlx::PgConnection,
+    order_id: Uuid,
+    target: OrderStatus,
+    tracking_number: &str,
+) -> Result<(), AppError> {
+    const PATH: [OrderStatus; 4] = [
+        OrderStatus::Pending,
+        OrderStatus::Processing,
+        OrderStatus::Shipped,
+        OrderStatus::Delivered,
+    ];
+
+    let current = sqlx::query_scalar::<_, String>("SELECT status FROM orders WHERE id = $1")
+        .bind(order_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let current = OrderStatus::parse(&current)?;
+
+    let (Some(from), Some(to)) = (
+        PATH.iter().position(|status| *status == current),
+        PATH.iter().position(|status| *status == target),
+    ) else {
+        return Ok(());
+    };
+
+    let comment = format!("Tracking update for waybill {}", tracking_number);
+    for next in PATH.iter().take(to + 1).skip(from + 1) {
+        transition_order(conn, order_id, *next, None, Some(&comment)).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use async_trait::async_trait;
+use rust_decimal::prelude::ToPrimitive;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{
+    Courier, CourierOffice, Destination, ShipmentRequest, ShipmentStatus, TrackingUpdate, Waybill,
+};
+use crate::{error::AppError, models::Currency};
+
+// Bulgaria in Speedy's country list
+const COUNTRY_ID: i32 = 100;
+
+// Tracking operation for a parcel handed to the recipient
+const OPERATION_DELIVERED: i32 = -14;
+
+pub struct SpeedyConfig {
+    // e.g. https://api.speedy.bg/v1
+    pub api_url: String,
+    pub username: String,
+    pub password: String,
+    // Service the waybills are booked with, 505 is standard delivery
+    pub service_id: i32,
+}
+
+// Speedy REST API. Credentials travel in every request body; the sender is
+// the client the account belongs to.
+pub struct SpeedyCourier {
+    config: SpeedyConfig,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct SpeedyError {
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OfficesResponse {
+    offices: Vec<Office>,
+}
+
+#[derive(Deserialize)]
+struct Office {
+    id: i64,
+    name: String,
+    address: OfficeAddress,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OfficeAddress {
+    site_name: String,
+    full_address_string: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ShipmentResponse {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct TrackResponse {
+    parcels: Vec<TrackedParcel>,
+}
+
+#[derive(Deserialize)]
+struct TrackedParcel {
+    #[serde(default)]
+    operations: Vec<Operation>,
+    error: Option<SpeedyError>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Operation {
+    operation_code: i32,
+    description: Option<String>,
+}
+
+impl SpeedyCourier {
+    pub fn new(mut config: SpeedyConfig) -> Self {
+        config.api_url = config.api_url.trim_end_matches('/').to_string();
+
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    // Posts to one endpoint with the credentials added to the body. Speedy
+    // reports failures as an `error` object, usually with status 200.
+    async fn send(&self, path: &str, mut body: Value) -> Result<reqwest::Response, AppError> {
+        body["userName"] = json!(self.config.username);
+        body["password"] = json!(self.config.password);
+        body["language"] = json!("BG");
+
+        let response = self
+            .client
+            .post(format!("{}/{}", self.config.api_url, path))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Speedy request {} failed: {:?}", path, e);
+                AppError::InternalServerError("Speedy is unavailable".to_string())
+            })?;
+
+        if !response.status().is_success() {
+            tracing::error!(
+                "Speedy rejected {}: {} {}",
+                path,
+                response.status(),
+                response.text().await.unwrap_or_default()
+            );
+            return Err(AppError::InternalServerError(
+                "Speedy is unavailable".to_string(),
+            ));
+        }
+
+        Ok(response)
+    }
+
+    async fn call<T: DeserializeOwned>(&self, path: &str, body: Value) -> Result<T, AppError> {
+        let body = self
+            .send(path, body)
+            .await?
+            .text()
+            .await
+            .unwrap_or_default();
+        parse(path, &body)
+    }
+}
+
+fn parse<T: DeserializeOwned>(path: &str, body: &str) -> Result<T, AppError> {
+    #[derive(Deserialize)]
+    struct ErrorResponse {
+        error: SpeedyError,
+    }
+
+    if let Ok(response) = serde_json::from_str::<ErrorResponse>(body) {
+        return Err(AppError::BadRequest(format!(
+            "Speedy: {}",
+            response
+                .error
+                .message
+                .unwrap_or_else(|| "request rejected".to_string())
+        )));
+    }
+
+    serde_json::from_str(body).map_err(|e| {
+        tracing::error!("Unexpected Speedy response to {}: {:?} {}", path, e, body);
+        AppError::InternalServerError("Unexpected response from Speedy".to_string())
+    })
+}
+
+#[async_trait]
+impl Courier for SpeedyCourier {
+    async fn list_offices(&self, city: &str) -> Result<Vec<CourierOffice>, AppError> {
+        let response: OfficesResponse = self
+            .call(
+                "location/office",
+                json!({ "countryId": COUNTRY_ID, "siteName": city.trim() }),
+            )
+            .await?;
+
+        Ok(response
+            .offices
+            .into_iter()
+            .map(|office| CourierOffice {
+                code: office.id.to_string(),
+                name: office.name,
+                address: office.address.full_address_string.unwrap_or_default(),
+                city: office.address.site_name,
+            })
+            .collect())
+    }
+
+    async fn create_waybill(&self, shipment: &ShipmentRequest) -> Result<Waybill, AppError> {
+        let mut recipient = json!({
+            "clientName": shipment.recipient_name,
+            "phone1": { "number": shipment.recipient_phone },
+            "email": shipment.recipient_email,
+            "privatePerson": true,
+        });
+        match &shipment.destination {
+            Destination::Office { code } => {
+                let office_id: i64 = code.parse().map_err(|_| {
+                    AppError::BadRequest(format!("Invalid Speedy office '{}'", code))
+                })?;
+                recipient["pickupOfficeId"] = json!(office_id);
+            }
+            Destination::Address {
+                city,
+                postal_code,
+                address,
+            } => {
+                recipient["address"] = json!({
+                    "countryId": COUNTRY_ID,
+                    "siteName": city,
+                    "postCode": postal_code,
+                    "addressNote": address,
+                });
+            }
+        }
+
+        let mut service = json!({ "serviceId": self.config.service_id });
+        if let Some(amount) = shipment.cash_on_delivery {
+            service["additionalServices"] = json!({
+                "cod": {
+                    "amount": amount.amount().to_f64(),
+                    "currencyCode": Currency::Eur.as_str(),
+                    "processingType": "CASH",
+                },
+            });
+        }
+
+        let created: ShipmentResponse = self
+            .call(
+                "shipment",
+                json!({
+                    "recipient": recipient,
+                    "service": service,
+                    "content": {
+                        "parcelsCount": 1,
+                        "totalWeight": shipment.weight_kg(),
+                        "contents": shipment.description,
+                        "package": "BOX",
+                    },
+                    "payment": { "courierServicePayer": "SENDER" },
+                    "ref1": shipment.reference,
+                }),
+            )
+            .await?;
+
+        // The label comes back as the PDF itself, or as an error object
+        let response = self
+            .send(
+                "print",
+                json!({
+                    "paperSize": "A6",
+                    "parcels": [{ "parcel": { "id": created.id } }],
+                }),
+            )
+            .await?;
+        let is_pdf = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/pdf"));
+        let label = response.bytes().await.map_err(|e| {
+            tracing::error!("Failed to read Speedy label {}: {:?}", created.id, e);
+            AppError::InternalServerError("Failed to download the waybill label".to_string())
+        })?;
+        if !is_pdf {
+            parse::<Value>("print", &String::from_utf8_lossy(&label))?;
+            return Err(AppError::InternalServerError(
+                "Failed to download the waybill label".to_string(),
+            ));
+        }
+
+        Ok(Waybill {
+            tracking_number: created.id,
+            label_pdf: label.to_vec(),
+        })
+    }
+
+    async fn track(&self, tracking_number: &str) -> Result<TrackingUpdate, AppError> {
+        let response: TrackResponse = self
+            .call("track", json!({ "parcels": [{ "id": tracking_number }] }))
+            .await?;
+
+        let parcel = response.parcels.into_iter().next().ok_or_else(|| {
+            AppError::InternalServerError("Unexpected response from Speedy".to_string())
+        })?;
+        if let Some(error) = parcel.error {
+            return Err(AppError::BadRequest(format!(
+                "Speedy: {}",
+                error
+                    .message
+                    .unwrap_or_else(|| "unknown shipment".to_string())
+            )));
+        }
+
+        let status = if parcel
+            .operations
+            .iter()
+            .any(|operation| operation.operation_code == OPERATION_DELIVERED)
+        {
+            ShipmentStatus::Delivered
+        } else if parcel.operations.is_empty() {
+            ShipmentStatus::Created
+        } else {
+            ShipmentStatus::InTransit
+        };
+
+        Ok(TrackingUpdate {
+            status,
+            last_event: parcel
+                .operations
+                .into_iter()
+                .last()
+                .and_then(|operation| operation.description),
+        })
+    }
+}
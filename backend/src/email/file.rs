@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::Message;
+use std::path::PathBuf;
+use tokio::fs;
+use uuid::Uuid;
+
+use super::Mailer;
+
+// Development and test stand-in for SMTP: every message is written to
+// EMAIL_OUTBOX_DIR as an .eml file that any mail client can open
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, message: Message) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| format!("Failed to create outbox directory: {}", e))?;
+
+        // Sortable by time, unique within the same second
+        let file_name = format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S"),
+            Uuid::new_v4()
+        );
+
+        fs::write(self.dir.join(file_name), message.formatted())
+            .await
+            .map_err(|e| format!("Failed to write email file: {}", e))
+    }
+}
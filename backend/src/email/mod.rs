@@ -0,0 +1,213 @@
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox, MultiPart, SinglePart},
+    Message,
+};
+use sqlx::{PgConnection, PgPool};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    jobs::{self, Job},
+};
+
+mod file;
+mod smtp;
+pub mod templates;
+
+pub use file::FileMailer;
+pub use smtp::{SmtpConfig, SmtpMailer, SmtpSecurity};
+
+const DEFAULT_FROM: &str = "Studio Zemya <noreply@studiozemya.com>";
+
+// Delivers a finished message. Errors are kept with the queued email, so they
+// are plain messages rather than API errors.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, message: Message) -> Result<(), String>;
+}
+
+pub type SharedMailer = Arc<dyn Mailer>;
+
+// Rendered email, before it is addressed and queued
+pub struct EmailContent {
+    pub template: &'static str,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: String,
+}
+
+// Builds the transport selected by EMAIL_BACKEND ("file" or "smtp")
+pub fn from_env() -> SharedMailer {
+    let backend = std::env::var("EMAIL_BACKEND").unwrap_or_else(|_| "file".to_string());
+
+    match backend.as_str() {
+        "file" => Arc::new(FileMailer::new(
+            std::env::var("EMAIL_OUTBOX_DIR").unwrap_or_else(|_| "./outbox".to_string()),
+        )),
+        "smtp" => {
+            // docker-compose passes unset variables as empty strings
+            let optional = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+            let security = match optional("SMTP_SECURITY").as_deref() {
+                Some("tls") => SmtpSecurity::Tls,
+                Some("none") => SmtpSecurity::None,
+                Some("starttls") | None => SmtpSecurity::StartTls,
+                Some(other) => panic!(
+                    "Unknown SMTP_SECURITY '{}', expected 'starttls', 'tls' or 'none'",
+                    other
+                ),
+            };
+
+            Arc::new(SmtpMailer::new(SmtpConfig {
+                host: optional("SMTP_HOST").expect("SMTP_HOST must be set for SMTP email"),
+                port: optional("SMTP_PORT")
+                    .map(|port| port.parse().expect("SMTP_PORT must be a valid number")),
+                username: optional("SMTP_USERNAME"),
+                password: optional("SMTP_PASSWORD").unwrap_or_default(),
+                security,
+            }))
+        }
+        other => panic!(
+            "Unknown EMAIL_BACKEND '{}', expected 'file' or 'smtp'",
+            other
+        ),
+    }
+}
+
+// Sender of all outgoing email, from EMAIL_FROM
+pub fn sender_from_env() -> Mailbox {
+    std::env::var("EMAIL_FROM")
+        .unwrap_or_else(|_| DEFAULT_FROM.to_string())
+        .parse()
+        .expect("EMAIL_FROM must be a valid address, e.g. \"Studio Zemya <shop@example.com>\"")
+}
+
+// Queues an email for delivery as part of the caller's transaction. A
+// `send_email` job delivers it once the transaction commits.
+pub async fn enqueue(
+    conn: &mut PgConnection,
+    to: &str,
+    content: EmailContent,
+    order_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let email_id = sqlx::query_scalar::<_, i64>(
+        r#"
+        INSERT INTO email_outbox (template, to_address, subject, text_body, html_body, order_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
+        "#,
+    )
+    .bind(content.template)
+    .bind(to)
+    .bind(&content.subject)
+    .bind(&content.text_body)
+    .bind(&content.html_body)
+    .bind(order_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    jobs::enqueue(conn, &Job::SendEmail { email_id }).await?;
+
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct QueuedEmail {
+    to_address: String,
+    subject: String,
+    text_body: String,
+    html_body: String,
+    status: String,
+}
+
+// Sends one queued email, run by its `send_email` job. The outcome is recorded
+// on the email; when `last_attempt` fails too it is marked failed.
+pub async fn deliver(
+    pool: &PgPool,
+    mailer: &dyn Mailer,
+    from: &Mailbox,
+    email_id: i64,
+    last_attempt: bool,
+) -> Result<(), String> {
+    let email = sqlx::query_as::<_, QueuedEmail>(
+        "SELECT to_address, subject, text_body, html_body, status FROM email_outbox WHERE id = $1",
+    )
+    .bind(email_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load email {}: {}", email_id, e))?;
+
+    // Deleted, or sent by an earlier run that failed to finish its job
+    let Some(email) = email.filter(|email| email.status != "sent") else {
+        return Ok(());
+    };
+
+    let result = match build_message(from, &email) {
+        Ok(message) => mailer.send(message).await,
+        Err(e) => Err(e),
+    };
+
+    let recorded = match &result {
+        Ok(()) => {
+            sqlx::query(
+                r#"
+                UPDATE email_outbox
+                SET status = 'sent', sent_at = NOW(), attempts = attempts + 1, last_error = NULL
+                WHERE id = $1
+                "#,
+            )
+            .bind(email_id)
+            .execute(pool)
+            .await
+        }
+        Err(error) => {
+            sqlx::query(
+                r#"
+                UPDATE email_outbox
+                SET status = CASE WHEN $3 THEN 'failed' ELSE 'pending' END,
+                    attempts = attempts + 1, last_error = $2
+                WHERE id = $1
+                "#,
+            )
+            .bind(email_id)
+            .bind(error)
+            .bind(last_attempt)
+            .execute(pool)
+            .await
+        }
+    };
+
+    if let Err(e) = recorded {
+        tracing::error!("Failed to record delivery of email {}: {:?}", email_id, e);
+    }
+
+    result
+}
+
+fn build_message(from: &Mailbox, email: &QueuedEmail) -> Result<Message, String> {
+    let to: Mailbox = email
+        .to_address
+        .parse()
+        .map_err(|e| format!("Invalid recipient address: {}", e))?;
+
+    Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(&email.subject)
+        .multipart(
+            MultiPart::alternative()
+                .singlepart(
+                    SinglePart::builder()
+                        .header(ContentType::TEXT_PLAIN)
+                        .body(email.text_body.clone()),
+                )
+                .singlepart(
+                    SinglePart::builder()
+                        .header(ContentType::TEXT_HTML)
+                        .body(email.html_body.clone()),
+                ),
+        )
+        .map_err(|e| format!("Failed to build message: {}", e))
+}
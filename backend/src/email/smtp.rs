@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use lettre::{
+    transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Message,
+    Tokio1Executor,
+};
+
+use super::Mailer;
+
+pub enum SmtpSecurity {
+    // Plain connection upgraded with STARTTLS, usually port 587
+    StartTls,
+    // TLS from the start, usually port 465
+    Tls,
+    // Unencrypted, for local relays such as Mailpit
+    None,
+}
+
+pub struct SmtpConfig {
+    pub host: String,
+    // Defaults to the usual port for the security mode
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: String,
+    pub security: SmtpSecurity,
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(config: SmtpConfig) -> Self {
+        let mut builder = match config.security {
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                    .expect("SMTP_HOST must be a valid host name")
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+                .expect("SMTP_HOST must be a valid host name"),
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host).port(25)
+            }
+        };
+
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let Some(username) = config.username {
+            builder = builder.credentials(Credentials::new(username, config.password));
+        }
+
+        Self {
+            transport: builder.build(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: Message) -> Result<(), String> {
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("SMTP delivery failed: {}", e))
+    }
+}
@@ -0,0 +1,289 @@
+use super::EmailContent;
+use crate::models::{BankTransferInstructions, Order, OrderItem};
+
+const SHOP_NAME: &str = "Studio Zemya";
+
+// Part of an email body, rendered to both plain text and HTML
+enum Block {
+    Paragraph(String),
+    // Consecutive lines such as an address or the order items
+    Lines(Vec<String>),
+}
+
+pub fn order_confirmation(
+    order: &Order,
+    items: &[OrderItem],
+    bank_transfer: Option<&BankTransferInstructions>,
+    tracking_url: &str,
+    site_url: &str,
+) -> EmailContent {
+    let mut blocks = vec![
+        greeting(order),
+        Block::Paragraph(format!(
+            "Благодарим ви за поръчката в {}! Получихме я и ще я обработим възможно най-скоро.",
+            SHOP_NAME
+        )),
+        Block::Paragraph(format!("Поръчка № {}", order_reference(order))),
+        item_lines(order, items),
+        delivery_lines(order),
+    ];
+    if let Some(transfer) = bank_transfer {
+        blocks.push(Block::Paragraph(format!(
+            "Моля, преведете сумата до {} с основание {}, за да я свържем с поръчката. \
+             Неплатените навреме поръчки се отменят.",
+            transfer.due_at.format("%d.%m.%Y"),
+            transfer.reference
+        )));
+        blocks.push(bank_transfer_lines(transfer));
+    }
+    blocks.push(Block::Paragraph(
+        "Ще ви уведомим, когато изпратим пратката.".to_string(),
+    ));
+    blocks.push(tracking_link(tracking_url));
+    blocks.push(signature(site_url));
+
+    render(
+        "order_confirmation",
+        format!("Потвърждение на поръчка № {}", order_reference(order)),
+        blocks,
+    )
+}
+
+pub fn order_shipped(order: &Order, tracking_url: &str, site_url: &str) -> EmailContent {
+    let mut blocks = vec![
+        greeting(order),
+        Block::Paragraph(format!(
+            "Поръчка № {} е изпратена и скоро ще бъде при вас.",
+            order_reference(order)
+        )),
+    ];
+    if let Some(tracking_number) = &order.tracking_number {
+        blocks.push(Block::Paragraph(format!(
+            "Номер за проследяване на пратката: {}",
+            tracking_number
+        )));
+    }
+    blocks.push(delivery_lines(order));
+    blocks.push(tracking_link(tracking_url));
+    blocks.push(signature(site_url));
+
+    render(
+        "order_shipped",
+        format!("Поръчка № {} е изпратена", order_reference(order)),
+        blocks,
+    )
+}
+
+pub fn order_delivered(order: &Order, site_url: &str) -> EmailContent {
+    render(
+        "order_delivered",
+        format!("Поръчка № {} е доставена", order_reference(order)),
+        vec![
+            greeting(order),
+            Block::Paragraph(format!(
+                "Поръчка № {} е доставена. Надяваме се да ви хареса!",
+                order_reference(order)
+            )),
+            Block::Paragraph(
+                "Ако нещо не е наред с пратката, просто отговорете на този имейл.".to_string(),
+            ),
+            signature(site_url),
+        ],
+    )
+}
+
+pub fn order_cancelled(order: &Order, site_url: &str) -> EmailContent {
+    render(
+        "order_cancelled",
+        format!("Поръчка № {} е отменена", order_reference(order)),
+        vec![
+            greeting(order),
+            Block::Paragraph(format!(
+                "Поръчка № {} беше отменена. Ако вече сте платили, ще възстановим сумата.",
+                order_reference(order)
+            )),
+            Block::Paragraph("Ако имате въпроси, просто отговорете на този имейл.".to_string()),
+            signature(site_url),
+        ],
+    )
+}
+
+// Sent to the studio for every new order
+pub fn new_order_alert(order: &Order, items: &[OrderItem], site_url: &str) -> EmailContent {
+    let mut blocks = vec![
+        Block::Paragraph(format!(
+            "Нова поръчка № {} от {} за {}",
+            order_reference(order),
+            order.customer_name,
+            order.total_amount
+        )),
+        item_lines(order, items),
+        Block::Lines(vec![
+            format!("Имейл: {}", order.customer_email),
+            format!("Телефон: {}", order.customer_phone),
+        ]),
+        delivery_lines(order),
+    ];
+    if let Some(notes) = order.notes.as_deref().filter(|notes| !notes.is_empty()) {
+        blocks.push(Block::Paragraph(format!("Бележка от клиента: {}", notes)));
+    }
+    blocks.push(Block::Paragraph(format!(
+        "{}/admin/orders",
+        site_url.trim_end_matches('/')
+    )));
+
+    render(
+        "new_order_alert",
+        format!(
+            "Нова поръчка № {} - {}",
+            order_reference(order),
+            order.total_amount
+        ),
+        blocks,
+    )
+}
+
+// Short order number shown to people, the start of the order id
+pub fn order_reference(order: &Order) -> String {
+    order.id.simple().to_string()[..8].to_uppercase()
+}
+
+fn greeting(order: &Order) -> Block {
+    Block::Paragraph(format!("Здравейте, {},", order.customer_name))
+}
+
+fn item_lines(order: &Order, items: &[OrderItem]) -> Block {
+    let mut lines: Vec<String> = items
+        .iter()
+        .map(|item| {
+            let name = match &item.variant_label {
+                Some(label) => format!("{} ({})", item.product_name, label),
+                None => item.product_name.clone(),
+            };
+            format!("{} × {} - {}", item.quantity, name, item.subtotal)
+        })
+        .collect();
+    if !order.discount_amount.is_zero() {
+        lines.push(format!("Междинна сума: {}", order.subtotal_amount));
+        let label = match &order.promotion_code {
+            Some(code) => format!("Отстъпка ({})", code),
+            None => "Отстъпка".to_string(),
+        };
+        lines.push(format!("{}: -{}", label, order.discount_amount));
+    }
+    if let Some(method) = &order.shipping_method_name {
+        lines.push(format!("Доставка ({}): {}", method, order.shipping_amount));
+    }
+    if !order.cod_fee.is_zero() {
+        lines.push(format!("Такса за наложен платеж: {}", order.cod_fee));
+    }
+    lines.push(format!("Общо: {}", order.total_amount));
+    for tax in order.tax_breakdown.iter().filter(|tax| !tax.rate.is_zero()) {
+        lines.push(format!(
+            "в т.ч. ДДС {}%: {}",
+            tax.rate.normalize(),
+            tax.vat_amount
+        ));
+    }
+
+    Block::Lines(lines)
+}
+
+fn delivery_lines(order: &Order) -> Block {
+    let city = match &order.delivery_postal_code {
+        Some(postal_code) if !postal_code.is_empty() => {
+            format!("{} {}", postal_code, order.delivery_city)
+        }
+        _ => order.delivery_city.clone(),
+    };
+
+    Block::Lines(vec![
+        "Адрес за доставка:".to_string(),
+        order.delivery_address.clone(),
+        city,
+    ])
+}
+
+fn bank_transfer_lines(transfer: &BankTransferInstructions) -> Block {
+    let mut lines = vec![
+        format!("Получател: {}", transfer.beneficiary),
+        format!("IBAN: {}", transfer.iban),
+    ];
+    if let Some(bic) = &transfer.bic {
+        lines.push(format!("BIC: {}", bic));
+    }
+    lines.push(format!("Сума: {}", transfer.amount));
+    lines.push(format!("Основание: {}", transfer.reference));
+
+    Block::Lines(lines)
+}
+
+fn tracking_link(tracking_url: &str) -> Block {
+    Block::Lines(vec![
+        "Състоянието на поръчката можете да проверите тук:".to_string(),
+        tracking_url.to_string(),
+    ])
+}
+
+fn signature(site_url: &str) -> Block {
+    Block::Lines(vec![
+        "Поздрави,".to_string(),
+        format!("екипът на {}", SHOP_NAME),
+        site_url.to_string(),
+    ])
+}
+
+fn render(template: &'static str, subject: String, blocks: Vec<Block>) -> EmailContent {
+    let text_body = blocks
+        .iter()
+        .map(|block| match block {
+            Block::Paragraph(text) => text.clone(),
+            Block::Lines(lines) => lines.join("\n"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let html_blocks: String = blocks
+        .iter()
+        .map(|block| match block {
+            Block::Paragraph(text) => format!("<p>{}</p>\n", escape_html(text)),
+            Block::Lines(lines) => format!(
+                "<p>{}</p>\n",
+                lines
+                    .iter()
+                    .map(|line| escape_html(line))
+                    .collect::<Vec<_>>()
+                    .join("<br>\n")
+            ),
+        })
+        .collect();
+
+    let html_body = format!(
+        "<!DOCTYPE html>\n<html lang=\"bg\">\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body style=\"font-family: Georgia, serif; color: #3b2f2a; line-height: 1.5;\">\n{}</body>\n</html>\n",
+        escape_html(&subject),
+        html_blocks
+    );
+
+    EmailContent {
+        template,
+        subject,
+        text_body,
+        html_body,
+    }
+}
+
+// Customer input ends up in the HTML part
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -19,11 +19,10 @@ pub async fn login(
         .ok_or_else(|| AppError::Unauthorized("Invalid credentials".to_string()))?;
 
     // Verify password
-    let valid = bcrypt::verify(&payload.password, &admin.password_hash)
-        .map_err(|e| {
-            tracing::error!("Bcrypt error: {:?}", e);
-            AppError::InternalServerError("Authentication error".to_string())
-        })?;
+    let valid = bcrypt::verify(&payload.password, &admin.password_hash).map_err(|e| {
+        tracing::error!("Bcrypt error: {:?}", e);
+        AppError::InternalServerError("Authentication error".to_string())
+    })?;
 
     if !valid {
         return Err(AppError::Unauthorized("Invalid credentials".to_string()));
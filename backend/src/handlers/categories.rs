@@ -0,0 +1,308 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    models::{
+        Category, CategoryTree, CreateCategoryRequest, SetProductCategoriesRequest,
+        UpdateCategoryRequest,
+    },
+    slug,
+};
+
+// Public endpoints
+
+pub async fn list_categories(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<CategoryTree>>, AppError> {
+    let categories = sqlx::query_as::<_, Category>(
+        "SELECT * FROM categories WHERE is_active = true ORDER BY position, name",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(build_tree(categories)))
+}
+
+// Admin endpoints (require authentication)
+
+pub async fn list_all_categories(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<Category>>, AppError> {
+    let categories =
+        sqlx::query_as::<_, Category>("SELECT * FROM categories ORDER BY position, name")
+            .fetch_all(&pool)
+            .await?;
+
+    Ok(Json(categories))
+}
+
+pub async fn create_category(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<CreateCategoryRequest>,
+) -> Result<(StatusCode, Json<Category>), AppError> {
+    let category_slug = match &payload.slug {
+        Some(requested) => requested.clone(),
+        None => slug::slugify(&payload.name),
+    };
+    slug::validate(&category_slug)?;
+
+    if let Some(parent_id) = payload.parent_id {
+        ensure_category_exists(&pool, parent_id).await?;
+    }
+
+    let category = sqlx::query_as::<_, Category>(
+        r#"
+        INSERT INTO categories (parent_id, name, slug, description, position)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(payload.parent_id)
+    .bind(&payload.name)
+    .bind(&category_slug)
+    .bind(&payload.description)
+    .bind(payload.position.unwrap_or(0))
+    .fetch_one(&pool)
+    .await
+    .map_err(map_slug_conflict)?;
+
+    Ok((StatusCode::CREATED, Json(category)))
+}
+
+pub async fn update_category(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<UpdateCategoryRequest>,
+) -> Result<Json<Category>, AppError> {
+    ensure_category_exists(&pool, id).await?;
+
+    if let Some(requested) = &payload.slug {
+        slug::validate(requested)?;
+    }
+
+    // Reject moves that would make the category its own ancestor
+    if let Some(Some(parent_id)) = payload.parent_id {
+        ensure_category_exists(&pool, parent_id).await?;
+
+        let creates_cycle = sqlx::query_scalar::<_, bool>(
+            r#"
+            WITH RECURSIVE subtree AS (
+                SELECT id FROM categories WHERE id = $1
+                UNION ALL
+                SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+            )
+            SELECT EXISTS (SELECT 1 FROM subtree WHERE id = $2)
+            "#,
+        )
+        .bind(id)
+        .bind(parent_id)
+        .fetch_one(&pool)
+        .await?;
+
+        if creates_cycle {
+            return Err(AppError::BadRequest(
+                "A category cannot be moved under itself or one of its subcategories".to_string(),
+            ));
+        }
+    }
+
+    let category = sqlx::query_as::<_, Category>(
+        r#"
+        UPDATE categories SET
+            name = COALESCE($1, name),
+            slug = COALESCE($2, slug),
+            description = COALESCE($3, description),
+            parent_id = CASE WHEN $4 THEN $5 ELSE parent_id END,
+            position = COALESCE($6, position),
+            is_active = COALESCE($7, is_active)
+        WHERE id = $8
+        RETURNING *
+        "#,
+    )
+    .bind(&payload.name)
+    .bind(&payload.slug)
+    .bind(&payload.description)
+    .bind(payload.parent_id.is_some())
+    .bind(payload.parent_id.flatten())
+    .bind(payload.position)
+    .bind(payload.is_active)
+    .bind(id)
+    .fetch_one(&pool)
+    .await
+    .map_err(map_slug_conflict)?;
+
+    Ok(Json(category))
+}
+
+pub async fn delete_category(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let category =
+        sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Category not found".to_string()))?;
+
+    // Subcategories move up one level instead of becoming top-level
+    sqlx::query("UPDATE categories SET parent_id = $1 WHERE parent_id = $2")
+        .bind(category.parent_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_product_categories(
+    State(pool): State<PgPool>,
+    Path(product_id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<Category>>, AppError> {
+    ensure_product_exists(&pool, product_id).await?;
+
+    let categories = sqlx::query_as::<_, Category>(
+        r#"
+        SELECT c.* FROM categories c
+        JOIN product_categories pc ON pc.category_id = c.id
+        WHERE pc.product_id = $1
+        ORDER BY c.position, c.name
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(categories))
+}
+
+pub async fn set_product_categories(
+    State(pool): State<PgPool>,
+    Path(product_id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<SetProductCategoriesRequest>,
+) -> Result<Json<Vec<Category>>, AppError> {
+    ensure_product_exists(&pool, product_id).await?;
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM product_categories WHERE product_id = $1")
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO product_categories (product_id, category_id)
+        SELECT $1, id FROM categories WHERE id = ANY($2)
+        "#,
+    )
+    .bind(product_id)
+    .bind(&payload.category_ids)
+    .execute(&mut *tx)
+    .await?;
+
+    let mut requested = payload.category_ids.clone();
+    requested.sort_unstable();
+    requested.dedup();
+    if inserted.rows_affected() != requested.len() as u64 {
+        return Err(AppError::BadRequest("Unknown category id".to_string()));
+    }
+
+    let categories = sqlx::query_as::<_, Category>(
+        r#"
+        SELECT c.* FROM categories c
+        JOIN product_categories pc ON pc.category_id = c.id
+        WHERE pc.product_id = $1
+        ORDER BY c.position, c.name
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(categories))
+}
+
+// Helpers
+
+fn build_tree(categories: Vec<Category>) -> Vec<CategoryTree> {
+    // Categories arrive sorted, so grouping by parent keeps sibling order
+    let mut by_parent: HashMap<Option<i32>, Vec<Category>> = HashMap::new();
+    for category in categories {
+        by_parent
+            .entry(category.parent_id)
+            .or_default()
+            .push(category);
+    }
+
+    fn attach(
+        parent_id: Option<i32>,
+        by_parent: &mut HashMap<Option<i32>, Vec<Category>>,
+    ) -> Vec<CategoryTree> {
+        by_parent
+            .remove(&parent_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|category| {
+                let children = attach(Some(category.id), by_parent);
+                CategoryTree { category, children }
+            })
+            .collect()
+    }
+
+    // Subcategories of an inactive parent are hidden along with it
+    attach(None, &mut by_parent)
+}
+
+fn map_slug_conflict(err: sqlx::Error) -> AppError {
+    match err.as_database_error().and_then(|e| e.code()) {
+        Some(code) if code == "23505" => {
+            AppError::BadRequest("A category with this slug already exists".to_string())
+        }
+        _ => AppError::DatabaseError(err),
+    }
+}
+
+async fn ensure_category_exists(pool: &PgPool, id: i32) -> Result<(), AppError> {
+    sqlx::query_scalar::<_, i32>("SELECT id FROM categories WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Category {} not found", id)))?;
+
+    Ok(())
+}
+
+async fn ensure_product_exists(pool: &PgPool, id: i32) -> Result<(), AppError> {
+    sqlx::query_scalar::<_, i32>("SELECT id FROM products WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+    Ok(())
+}
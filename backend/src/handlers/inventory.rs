@@ -0,0 +1,172 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    inventory::{self, MovementReason, StockMovement, StockUpdate},
+    models::{
+        CreateInventoryAdjustmentRequest, InventoryMovement, InventoryMovementQuery,
+        PaginatedResponse, StockLevel,
+    },
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+// Admin endpoints (require authentication)
+
+pub async fn list_inventory_movements(
+    State(pool): State<PgPool>,
+    Path(product_id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+    Query(query): Query<InventoryMovementQuery>,
+) -> Result<Json<PaginatedResponse<InventoryMovement>>, AppError> {
+    ensure_product_exists(&pool, product_id).await?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM inventory_movements m");
+    push_movement_filters(&mut count_query, product_id, &query);
+    let total: i64 = count_query.build_query_scalar().fetch_one(&pool).await?;
+
+    let mut items_query = QueryBuilder::new(
+        "SELECT m.*, a.email AS admin_email FROM inventory_movements m LEFT JOIN admins a ON a.id = m.admin_id",
+    );
+    push_movement_filters(&mut items_query, product_id, &query);
+    items_query
+        .push(" ORDER BY m.created_at DESC, m.id DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind((page - 1) * limit);
+
+    let items = items_query
+        .build_query_as::<InventoryMovement>()
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(PaginatedResponse {
+        items,
+        total,
+        page,
+        limit,
+        total_pages: (total + limit - 1) / limit,
+    }))
+}
+
+// Records stock that arrived, broke or was counted outside of orders
+pub async fn create_inventory_adjustment(
+    State(pool): State<PgPool>,
+    Path(product_id): Path<i32>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateInventoryAdjustmentRequest>,
+) -> Result<(StatusCode, Json<StockLevel>), AppError> {
+    let update = match payload.reason {
+        MovementReason::ManualAdjustment if payload.quantity != 0 => {
+            StockUpdate::By(payload.quantity)
+        }
+        MovementReason::ProductionBatch if payload.quantity > 0 => {
+            StockUpdate::By(payload.quantity)
+        }
+        MovementReason::Damage if payload.quantity < 0 => StockUpdate::By(payload.quantity),
+        MovementReason::StockCount if payload.quantity >= 0 => StockUpdate::To(payload.quantity),
+        MovementReason::ManualAdjustment => {
+            return Err(AppError::BadRequest("Quantity cannot be zero".to_string()))
+        }
+        MovementReason::ProductionBatch => {
+            return Err(AppError::BadRequest(
+                "A production batch must add stock".to_string(),
+            ))
+        }
+        MovementReason::Damage => {
+            return Err(AppError::BadRequest(
+                "Damage must remove stock (negative quantity)".to_string(),
+            ))
+        }
+        MovementReason::StockCount => {
+            return Err(AppError::BadRequest(
+                "Counted stock cannot be negative".to_string(),
+            ))
+        }
+        MovementReason::Sale
+        | MovementReason::CancellationRestock
+        | MovementReason::ReturnRestock => {
+            return Err(AppError::BadRequest(
+                "Sales and restocks are recorded by orders".to_string(),
+            ))
+        }
+    };
+
+    let note = payload
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty());
+
+    let mut tx = pool.begin().await?;
+
+    let stock_quantity = inventory::move_stock(
+        &mut tx,
+        StockMovement {
+            product_id,
+            variant_id: payload.variant_id,
+            update,
+            reason: payload.reason,
+            order_id: None,
+            order_item_id: None,
+            admin_id: Some(claims.admin_id),
+            note,
+        },
+    )
+    .await?
+    .ok_or_else(|| match payload.variant_id {
+        Some(_) => AppError::NotFound("Variant not found".to_string()),
+        None => AppError::NotFound("Product not found".to_string()),
+    })?;
+
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(StockLevel {
+            product_id,
+            variant_id: payload.variant_id,
+            stock_quantity,
+        }),
+    ))
+}
+
+// Helpers
+
+fn push_movement_filters(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    product_id: i32,
+    query: &InventoryMovementQuery,
+) {
+    builder.push(" WHERE m.product_id = ").push_bind(product_id);
+
+    if let Some(variant_id) = query.variant_id {
+        builder.push(" AND m.variant_id = ").push_bind(variant_id);
+    }
+    if let Some(reason) = query.reason {
+        builder.push(" AND m.reason = ").push_bind(reason.as_str());
+    }
+}
+
+async fn ensure_product_exists(pool: &PgPool, id: i32) -> Result<(), AppError> {
+    sqlx::query_scalar::<_, i32>("SELECT id FROM products WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    invoices::{self, InvoiceKind, Invoicing},
+    models::{
+        CreateCreditNoteRequest, Invoice, InvoiceDetail, InvoiceListQuery, IssueInvoiceRequest,
+        PaginatedResponse,
+    },
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+// Admin endpoints (require authentication)
+
+// Issues the invoice of an order; `{}` takes everything from the order
+pub async fn issue_invoice(
+    State(pool): State<PgPool>,
+    State(invoicing): State<Invoicing>,
+    Path(order_id): Path<Uuid>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<IssueInvoiceRequest>,
+) -> Result<(StatusCode, Json<InvoiceDetail>), AppError> {
+    let mut tx = pool.begin().await?;
+    let invoice =
+        invoices::issue_invoice(&mut tx, &invoicing, order_id, &payload, claims.admin_id).await?;
+    let detail = invoices::fetch_detail(&mut tx, invoice.id).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Issued invoice {} for order {}",
+        detail.formatted_number,
+        order_id
+    );
+
+    Ok((StatusCode::CREATED, Json(detail)))
+}
+
+pub async fn list_invoices(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+    Query(query): Query<InvoiceListQuery>,
+) -> Result<Json<PaginatedResponse<Invoice>>, AppError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let kind = match query.kind.as_deref().filter(|k| !k.is_empty()) {
+        Some(kind) => Some(InvoiceKind::parse(kind)?),
+        None => None,
+    };
+
+    let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM invoices");
+    push_invoice_filters(&mut count_query, kind, &query);
+    let total: i64 = count_query.build_query_scalar().fetch_one(&pool).await?;
+
+    let mut items_query = QueryBuilder::new("SELECT * FROM invoices");
+    push_invoice_filters(&mut items_query, kind, &query);
+    items_query
+        .push(" ORDER BY number DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind((page - 1) * limit);
+
+    let items = items_query
+        .build_query_as::<Invoice>()
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(PaginatedResponse {
+        items,
+        total,
+        page,
+        limit,
+        total_pages: (total + limit - 1) / limit,
+    }))
+}
+
+pub async fn get_invoice(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<InvoiceDetail>, AppError> {
+    let mut conn = pool.acquire().await?;
+    let detail = invoices::fetch_detail(&mut conn, id).await?;
+
+    Ok(Json(detail))
+}
+
+// Rendered from what was stored when the document was issued, so it reads
+// the same every time
+pub async fn get_invoice_pdf(
+    State(pool): State<PgPool>,
+    State(invoicing): State<Invoicing>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.acquire().await?;
+    let detail = invoices::fetch_detail(&mut conn, id).await?;
+    drop(conn);
+
+    let file_name = format!(
+        "{}-{}.pdf",
+        InvoiceKind::parse(&detail.invoice.kind)?.file_prefix(),
+        detail.formatted_number
+    );
+    let pdf = invoices::render_pdf(&invoicing, detail).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}\"", file_name),
+            ),
+        ],
+        pdf,
+    )
+        .into_response())
+}
+
+// Credits a refund, an amount, or what is left of the invoice
+pub async fn create_credit_note(
+    State(pool): State<PgPool>,
+    State(invoicing): State<Invoicing>,
+    Path(id): Path<i32>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateCreditNoteRequest>,
+) -> Result<(StatusCode, Json<InvoiceDetail>), AppError> {
+    let mut tx = pool.begin().await?;
+    let credit_note =
+        invoices::issue_credit_note(&mut tx, &invoicing, id, &payload, claims.admin_id).await?;
+    let detail = invoices::fetch_detail(&mut tx, credit_note.id).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Issued credit note {} for invoice {}",
+        detail.formatted_number,
+        id
+    );
+
+    Ok((StatusCode::CREATED, Json(detail)))
+}
+
+// Helpers
+
+fn push_invoice_filters(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    kind: Option<InvoiceKind>,
+    query: &InvoiceListQuery,
+) {
+    builder.push(" WHERE TRUE");
+
+    if let Some(kind) = kind {
+        builder.push(" AND kind = ").push_bind(kind.as_str());
+    }
+    if let Some(order_id) = query.order_id {
+        builder.push(" AND order_id = ").push_bind(order_id);
+    }
+}
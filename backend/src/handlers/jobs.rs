@@ -0,0 +1,136 @@
+use axum::{
+    extract::{Path, Query, State},
+    Extension, Json,
+};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    models::{BackgroundJob, JobListQuery, JobSchedule, PaginatedResponse},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+// Admin endpoints (require authentication)
+
+pub async fn list_jobs(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+    Query(query): Query<JobListQuery>,
+) -> Result<Json<PaginatedResponse<BackgroundJob>>, AppError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM jobs");
+    push_job_filters(&mut count_query, &query);
+    let total: i64 = count_query.build_query_scalar().fetch_one(&pool).await?;
+
+    let mut items_query = QueryBuilder::new("SELECT * FROM jobs");
+    push_job_filters(&mut items_query, &query);
+    items_query
+        .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind((page - 1) * limit);
+
+    let items = items_query
+        .build_query_as::<BackgroundJob>()
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(PaginatedResponse {
+        items,
+        total,
+        page,
+        limit,
+        total_pages: (total + limit - 1) / limit,
+    }))
+}
+
+pub async fn get_job(
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<BackgroundJob>, AppError> {
+    let job = sqlx::query_as::<_, BackgroundJob>("SELECT * FROM jobs WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Job not found".to_string()))?;
+
+    Ok(Json(job))
+}
+
+// Gives a dead job a fresh set of attempts, starting now
+pub async fn retry_job(
+    State(pool): State<PgPool>,
+    Path(id): Path<i64>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<BackgroundJob>, AppError> {
+    let job = sqlx::query_as::<_, BackgroundJob>(
+        r#"
+        UPDATE jobs
+        SET status = 'queued', attempts = 0, run_at = NOW(), finished_at = NULL
+        WHERE id = $1 AND status = 'dead'
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await?;
+
+    if let Some(job) = job {
+        // A retried email is pending again until its job finishes
+        if job.kind == "send_email" {
+            sqlx::query(
+                "UPDATE email_outbox SET status = 'pending' WHERE id = ($1->>'email_id')::BIGINT AND status = 'failed'",
+            )
+            .bind(&job.payload)
+            .execute(&pool)
+            .await?;
+        }
+
+        return Ok(Json(job));
+    }
+
+    let exists = sqlx::query_scalar::<_, i64>("SELECT id FROM jobs WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?;
+
+    match exists {
+        Some(_) => Err(AppError::BadRequest(
+            "Only dead jobs can be retried".to_string(),
+        )),
+        None => Err(AppError::NotFound("Job not found".to_string())),
+    }
+}
+
+pub async fn list_job_schedules(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<JobSchedule>>, AppError> {
+    let schedules = sqlx::query_as::<_, JobSchedule>("SELECT * FROM job_schedules ORDER BY name")
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(schedules))
+}
+
+// Helpers
+
+fn push_job_filters(builder: &mut QueryBuilder<'_, Postgres>, query: &JobListQuery) {
+    builder.push(" WHERE TRUE");
+
+    if let Some(status) = query.status {
+        builder.push(" AND status = ").push_bind(status.as_str());
+    }
+    if let Some(kind) = query.kind.as_deref().filter(|k| !k.is_empty()) {
+        builder.push(" AND kind = ").push_bind(kind.to_string());
+    }
+}
@@ -1,4 +1,16 @@
 pub mod admin;
+pub mod categories;
 pub mod health;
+pub mod inventory;
+pub mod invoices;
+pub mod jobs;
 pub mod orders;
+pub mod payments;
+pub mod product_images;
+pub mod product_variants;
 pub mod products;
+pub mod promotions;
+pub mod reservations;
+pub mod shipments;
+pub mod shipping;
+pub mod tax_classes;
@@ -1,68 +1,188 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{ConnectInfo, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Extension, Json,
 };
-use rust_decimal::Decimal;
-use sqlx::PgPool;
+use sqlx::{types::Json as SqlJson, PgConnection, PgPool, Postgres, QueryBuilder};
+use std::{collections::HashMap, net::SocketAddr};
 use uuid::Uuid;
 
 use crate::{
     auth::Claims,
+    email::templates,
     error::AppError,
-    models::{CreateOrderRequest, Order, OrderItem, Product, UpdateOrderStatusRequest},
+    handlers::{
+        product_variants::{has_active_variants, lock_variant_for_order},
+        reservations::lock_open_reservation,
+    },
+    idempotency::IdempotencyKey,
+    inventory::{self, MovementReason, StockMovement, StockUpdate},
+    invoices,
+    models::{
+        CreateOrderRequest, CreatedOrder, Money, Order, OrderDetail, OrderItem, OrderItemRequest,
+        OrderLookupRequest, OrderStatusChange, OrderTracking, OrderTrackingEvent,
+        OrderTrackingItem, Product, ProductVariantDetail, UpdateOrderStatusRequest,
+    },
+    notifications,
+    order_status::OrderStatus,
+    order_tracking::{self, OrderLookupLimiter},
+    payments::{self, bank_transfer, PaymentMethod, Payments},
+    promotions::{self, CartLine},
+    rate_limit, reservations,
+    shipping::{self, Parcel, ShippingKind},
+    tax,
 };
 
+// Validated order line with the price charged per unit
+struct OrderLine {
+    product: Product,
+    variant: Option<ProductVariantDetail>,
+    unit_price: Money,
+    quantity: i32,
+    subtotal: Money,
+}
+
 // Public endpoint - create order
+// Retries carrying the same Idempotency-Key get the original response back
+// instead of creating a second order.
 
 pub async fn create_order(
     State(pool): State<PgPool>,
-    Json(payload): Json<CreateOrderRequest>,
-) -> Result<(StatusCode, Json<Order>), AppError> {
+    State(payments): State<Payments>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, AppError> {
+    let idempotency_key = IdempotencyKey::from_request("POST /api/orders", &headers, &body)?;
+    let Json(payload) = Json::<CreateOrderRequest>::from_bytes(&body)
+        .map_err(|rejection| AppError::BadRequest(rejection.body_text()))?;
+
     if payload.items.is_empty() {
-        return Err(AppError::BadRequest("Order must contain at least one item".to_string()));
+        return Err(AppError::BadRequest(
+            "Order must contain at least one item".to_string(),
+        ));
     }
 
+    let (card_provider, bank_account) = match payload.payment_method {
+        PaymentMethod::Card => (Some(payments.provider()?.clone()), None),
+        PaymentMethod::BankTransfer => (None, Some(payments.bank_account()?)),
+        PaymentMethod::CashOnDelivery => (None, None),
+    };
+
+    // Company details are checked now, so the invoice can be issued later
+    let invoice_buyer = payload
+        .invoice
+        .as_ref()
+        .map(invoices::validate_buyer)
+        .transpose()?;
+
     // Start transaction
     let mut tx = pool.begin().await?;
 
+    if let Some(key) = &idempotency_key {
+        if let Some(response) = key.lock(&mut tx).await? {
+            return Ok(response);
+        }
+    }
+
     // Calculate total and validate products
-    let mut total_amount = Decimal::ZERO;
+    let mut total_amount = Money::ZERO;
+    let mut weight_grams = 0;
     let mut order_items = Vec::new();
+    let mut taken_by_earlier_lines: HashMap<(i32, Option<i32>), i32> = HashMap::new();
+
+    // Stock held for this checkout is the customer's own
+    let reservation_id = payload.reservation_id;
+    if let Some(reservation_id) = reservation_id {
+        lock_open_reservation(&mut tx, reservation_id).await?;
+    }
 
     for item in &payload.items {
-        if item.quantity <= 0 {
-            return Err(AppError::BadRequest("Quantity must be positive".to_string()));
-        }
+        let line = lock_order_line(&mut tx, item, reservation_id).await?;
+        claim_stock(&mut taken_by_earlier_lines, &line, item.quantity)?;
 
-        // Get product and check availability
-        let product = sqlx::query_as::<_, Product>(
-            "SELECT * FROM products WHERE id = $1 AND is_active = true FOR UPDATE",
-        )
-        .bind(item.product_id)
-        .fetch_optional(&mut *tx)
-        .await?
-        .ok_or_else(|| AppError::NotFound(format!("Product {} not found", item.product_id)))?;
+        let unit_price = line.unit_price();
+        let subtotal = unit_price * item.quantity;
+        total_amount += subtotal;
+        weight_grams += line.product.weight_grams * item.quantity;
 
-        if product.stock_quantity < item.quantity {
-            return Err(AppError::BadRequest(format!(
-                "Insufficient stock for product '{}'. Available: {}, Requested: {}",
-                product.name, product.stock_quantity, item.quantity
-            )));
+        order_items.push(OrderLine {
+            product: line.product,
+            variant: line.variant,
+            unit_price,
+            quantity: item.quantity,
+            subtotal,
+        });
+    }
+
+    // Discount code, worked out on the locked prices
+    let subtotal_amount = total_amount;
+    let promotion_code = payload
+        .promotion_code
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    let promotion = match promotion_code {
+        Some(code) => {
+            let lines: Vec<CartLine> = order_items
+                .iter()
+                .map(|line| CartLine {
+                    product_id: line.product.id,
+                    subtotal: line.subtotal,
+                })
+                .collect();
+            Some(promotions::apply(&mut tx, code, Some(&payload.customer_email), &lines).await?)
         }
+        None => None,
+    };
+    let discount_amount = promotion
+        .as_ref()
+        .map_or(Money::ZERO, |applied| applied.discount_amount);
+    total_amount -= discount_amount;
 
-        let subtotal = product.price * Decimal::from(item.quantity);
-        total_amount += subtotal;
+    // Shipping, priced on what is left to pay for the items
+    let parcel = Parcel {
+        weight_grams,
+        zone_id: shipping::zone_for_postal_code(&mut tx, payload.delivery_postal_code.as_deref())
+            .await?,
+        order_amount: total_amount,
+        free_shipping: promotion
+            .as_ref()
+            .is_some_and(|applied| applied.free_shipping),
+    };
+    let (shipping_method, shipping_amount) =
+        shipping::charge(&mut tx, payload.shipping_method_id, &parcel).await?;
+    total_amount += shipping_amount;
 
-        order_items.push((product, item.quantity, subtotal));
+    // The courier's fee for collecting the cash
+    let cod_fee = if payload.payment_method == PaymentMethod::CashOnDelivery {
+        shipping_method.cod_fee
+    } else {
+        Money::ZERO
+    };
+    total_amount += cod_fee;
 
-        // Update stock
-        sqlx::query("UPDATE products SET stock_quantity = stock_quantity - $1 WHERE id = $2")
-            .bind(item.quantity)
-            .bind(item.product_id)
-            .execute(&mut *tx)
-            .await?;
-    }
+    // VAT in what is paid for each line and for shipping
+    let line_discounts = match &promotion {
+        Some(applied) => applied.line_discounts.clone(),
+        None => vec![Money::ZERO; order_items.len()],
+    };
+    let taxed_lines: Vec<(&str, Money)> = order_items
+        .iter()
+        .zip(&line_discounts)
+        .map(|(line, discount)| (line.product.tax_class.as_str(), line.subtotal - *discount))
+        .collect();
+    let rates = tax::current_rates(&mut tx).await?;
+    let order_tax = tax::order_tax(rates.as_ref(), &taxed_lines, shipping_amount + cod_fee)?;
+
+    // Office picked from the carrier's list, kept for the waybill
+    let to_office = shipping_method.kind == ShippingKind::CourierOffice.as_str();
+    let courier_office_code = payload
+        .courier_office_code
+        .as_deref()
+        .map(str::trim)
+        .filter(|code| to_office && !code.is_empty());
 
     // Create order
     let order_id = Uuid::new_v4();
@@ -71,8 +191,17 @@ pub async fn create_order(
         INSERT INTO orders (
             id, customer_name, customer_email, customer_phone,
             delivery_address, delivery_city, delivery_postal_code,
-            total_amount, status, notes
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'pending', $9)
+            total_amount, status, notes,
+            subtotal_amount, discount_amount, promotion_id, promotion_code,
+            shipping_method_id, shipping_method_name, shipping_amount,
+            courier_office_code, shipping_weight_grams, payment_method, cod_fee,
+            net_amount, vat_amount, shipping_tax_rate, tax_breakdown,
+            invoice_company, invoice_eik, invoice_vat_number, invoice_mol, invoice_address
+        ) VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10,
+            $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21,
+            $22, $23, $24, $25, $26, $27, $28, $29, $30
+        )
         RETURNING *
         "#,
     )
@@ -84,33 +213,224 @@ pub async fn create_order(
     .bind(&payload.delivery_city)
     .bind(&payload.delivery_postal_code)
     .bind(total_amount)
+    .bind(OrderStatus::Pending.as_str())
     .bind(&payload.notes)
+    .bind(subtotal_amount)
+    .bind(discount_amount)
+    .bind(promotion.as_ref().map(|applied| applied.promotion.id))
+    .bind(promotion.as_ref().map(|applied| &applied.promotion.code))
+    .bind(shipping_method.id)
+    .bind(&shipping_method.name)
+    .bind(shipping_amount)
+    .bind(courier_office_code)
+    .bind(weight_grams)
+    .bind(payload.payment_method.as_str())
+    .bind(cod_fee)
+    .bind(order_tax.net_amount)
+    .bind(order_tax.vat_amount)
+    .bind(order_tax.shipping_rate)
+    .bind(SqlJson(&order_tax.breakdown))
+    .bind(invoice_buyer.as_ref().map(|buyer| &buyer.company))
+    .bind(invoice_buyer.as_ref().map(|buyer| &buyer.eik))
+    .bind(
+        invoice_buyer
+            .as_ref()
+            .and_then(|buyer| buyer.vat_number.as_ref()),
+    )
+    .bind(invoice_buyer.as_ref().and_then(|buyer| buyer.mol.as_ref()))
+    .bind(invoice_buyer.as_ref().map(|buyer| &buyer.address))
     .fetch_one(&mut *tx)
     .await?;
 
-    // Create order items
-    for (product, quantity, subtotal) in order_items {
-        sqlx::query(
+    // Create order items and take them out of stock
+    let lines = order_items
+        .into_iter()
+        .zip(line_discounts)
+        .zip(order_tax.lines);
+    for ((line, line_discount), line_tax) in lines {
+        let order_item_id = sqlx::query_scalar::<_, i32>(
             r#"
             INSERT INTO order_items (
-                order_id, product_id, product_name, product_price, quantity, subtotal
-            ) VALUES ($1, $2, $3, $4, $5, $6)
+                order_id, product_id, product_name, product_price, quantity, subtotal,
+                variant_id, variant_label, sku, discount_amount,
+                tax_rate, net_amount, vat_amount
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            RETURNING id
             "#,
         )
         .bind(order_id)
-        .bind(product.id)
-        .bind(&product.name)
-        .bind(product.price)
-        .bind(quantity)
-        .bind(subtotal)
+        .bind(line.product.id)
+        .bind(&line.product.name)
+        .bind(line.unit_price)
+        .bind(line.quantity)
+        .bind(line.subtotal)
+        .bind(line.variant.as_ref().map(|v| v.variant.id))
+        .bind(line.variant.as_ref().map(|v| &v.label))
+        .bind(line.variant.as_ref().map(|v| &v.variant.sku))
+        .bind(line_discount)
+        .bind(line_tax.rate)
+        .bind(line_tax.net_amount)
+        .bind(line_tax.vat_amount)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        inventory::move_stock(
+            &mut tx,
+            StockMovement {
+                product_id: line.product.id,
+                variant_id: line.variant.as_ref().map(|v| v.variant.id),
+                update: StockUpdate::By(-line.quantity),
+                reason: MovementReason::Sale,
+                order_id: Some(order_id),
+                order_item_id: Some(order_item_id),
+                admin_id: None,
+                note: None,
+            },
+        )
+        .await?;
+    }
+
+    record_status_change(&mut tx, order_id, None, OrderStatus::Pending, None, None).await?;
+
+    // The held stock has now been sold
+    if let Some(reservation_id) = reservation_id {
+        sqlx::query(
+            "UPDATE stock_reservations SET order_id = $1, converted_at = NOW() WHERE id = $2",
+        )
+        .bind(order_id)
+        .bind(reservation_id)
         .execute(&mut *tx)
         .await?;
     }
 
+    // Transfer orders get a reference to quote with the payment, matched
+    // when the bank statement is imported
+    let (order, bank_transfer) = match bank_account {
+        Some(account) => bank_transfer::start_transfer(&mut tx, account, order).await?,
+        None => (order, None),
+    };
+
+    notifications::order_placed(&mut tx, &order, bank_transfer.as_ref()).await?;
+
+    let mut created = CreatedOrder {
+        order,
+        payment: None,
+        bank_transfer,
+    };
+
+    if let Some(key) = &idempotency_key {
+        if card_provider.is_some() {
+            // Answered once the payment has been started below, until then
+            // a retry is asked to wait
+            let pending = serde_json::json!({ "error": "The order is still being placed" });
+            key.save(&mut tx, StatusCode::CONFLICT, &pending).await?;
+        } else {
+            key.save(&mut tx, StatusCode::CREATED, &created).await?;
+        }
+    }
+
     // Commit transaction
     tx.commit().await?;
 
-    Ok((StatusCode::CREATED, Json(order)))
+    // Card orders get a payment for the browser to confirm; the webhook
+    // marks them paid. The provider is called after the commit, so the
+    // order's stock is not locked while it answers.
+    if let Some(provider) = card_provider {
+        match payments::start_payment(&pool, provider.as_ref(), &created.order).await {
+            Ok(payment) => created.payment = payment,
+            Err(err) => {
+                abandon_order(&pool, created.order.id, idempotency_key.as_ref()).await;
+                return Err(err);
+            }
+        }
+
+        if let Some(key) = &idempotency_key {
+            let mut conn = pool.acquire().await?;
+            key.save(&mut conn, StatusCode::CREATED, &created).await?;
+        }
+    }
+
+    Ok((StatusCode::CREATED, Json(created)).into_response())
+}
+
+// Cancels a card order whose payment could not be started, putting its items
+// back, and frees its idempotency key so a retry can place it again. Failing
+// that, the order is cancelled with the other unpaid card orders.
+async fn abandon_order(pool: &PgPool, id: Uuid, idempotency_key: Option<&IdempotencyKey>) {
+    let abandoned = async {
+        let mut tx = pool.begin().await?;
+        transition_order(
+            &mut tx,
+            id,
+            OrderStatus::Cancelled,
+            None,
+            Some("Card payment could not be started"),
+        )
+        .await?;
+        if let Some(key) = idempotency_key {
+            key.forget(&mut tx).await?;
+        }
+        tx.commit().await?;
+        Ok::<_, AppError>(())
+    };
+
+    if let Err(err) = abandoned.await {
+        tracing::error!("Failed to cancel order {} without a payment: {:?}", id, err);
+    }
+}
+
+// Public endpoint - find an order by its number and the customer's email.
+// A wrong email is reported like an unknown order. Rate limited per client,
+// so order numbers cannot be guessed for a known email.
+pub async fn lookup_order(
+    State(pool): State<PgPool>,
+    State(limiter): State<OrderLookupLimiter>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<OrderLookupRequest>,
+) -> Result<Json<OrderTracking>, AppError> {
+    limiter.0.check(rate_limit::client_ip(peer, &headers))?;
+
+    let number = payload.order_number.trim().trim_start_matches('#');
+    let email = payload.email.trim();
+
+    // The short number is the start of the id, stored as `order_reference`
+    let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM orders WHERE ");
+    match Uuid::try_parse(number) {
+        Ok(id) => query.push("id = ").push_bind(id),
+        Err(_) if number.len() == 8 && number.chars().all(|c| c.is_ascii_hexdigit()) => query
+            .push("order_reference = ")
+            .push_bind(number.to_uppercase()),
+        Err(_) => return Err(AppError::NotFound("Order not found".to_string())),
+    };
+    query
+        .push(" AND LOWER(customer_email) = LOWER(")
+        .push_bind(email)
+        .push(") ORDER BY created_at DESC LIMIT 1");
+
+    let order = query
+        .build_query_as::<Order>()
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+
+    Ok(Json(tracking_view(&pool, order).await?))
+}
+
+// Public endpoint - order behind a tracking link from the emails
+pub async fn track_order(
+    State(pool): State<PgPool>,
+    Path(token): Path<String>,
+) -> Result<Json<OrderTracking>, AppError> {
+    let id = order_tracking::verify(&token)?;
+
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+
+    Ok(Json(tracking_view(&pool, order).await?))
 }
 
 // Admin endpoints (require authentication)
@@ -130,7 +450,7 @@ pub async fn get_order(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
     Extension(_claims): Extension<Claims>,
-) -> Result<Json<(Order, Vec<OrderItem>)>, AppError> {
+) -> Result<Json<OrderDetail>, AppError> {
     let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1")
         .bind(id)
         .fetch_optional(&pool)
@@ -142,32 +462,298 @@ pub async fn get_order(
         .fetch_all(&pool)
         .await?;
 
-    Ok(Json((order, items)))
+    let history = sqlx::query_as::<_, OrderStatusChange>(
+        r#"
+        SELECT h.*, a.email AS admin_email
+        FROM order_status_history h
+        LEFT JOIN admins a ON a.id = h.admin_id
+        WHERE h.order_id = $1
+        ORDER BY h.created_at, h.id
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&pool)
+    .await?;
+
+    let allowed_transitions = OrderStatus::parse(&order.status)?
+        .allowed_transitions()
+        .to_vec();
+
+    Ok(Json(OrderDetail {
+        order,
+        items,
+        history,
+        allowed_transitions,
+    }))
 }
 
 pub async fn update_order_status(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
     Json(payload): Json<UpdateOrderStatusRequest>,
 ) -> Result<Json<Order>, AppError> {
-    // Validate status
-    let valid_statuses = ["pending", "processing", "shipped", "delivered", "cancelled"];
-    if !valid_statuses.contains(&payload.status.as_str()) {
+    let next = OrderStatus::parse(&payload.status)?;
+    let comment = payload
+        .comment
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+
+    let tracking_number = payload
+        .tracking_number
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty());
+
+    let mut tx = pool.begin().await?;
+
+    if let Some(tracking_number) = tracking_number {
+        let updated = sqlx::query("UPDATE orders SET tracking_number = $1 WHERE id = $2")
+            .bind(tracking_number)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        if updated.rows_affected() == 0 {
+            return Err(AppError::NotFound("Order not found".to_string()));
+        }
+    }
+
+    let order = transition_order(&mut tx, id, next, Some(claims.admin_id), comment).await?;
+    tx.commit().await?;
+
+    Ok(Json(order))
+}
+
+// Helpers
+
+async fn tracking_view(pool: &PgPool, order: Order) -> Result<OrderTracking, AppError> {
+    let items = sqlx::query_as::<_, OrderTrackingItem>(
+        r#"
+        SELECT product_name, variant_label, product_price, quantity, subtotal, discount_amount
+        FROM order_items
+        WHERE order_id = $1
+        ORDER BY id
+        "#,
+    )
+    .bind(order.id)
+    .fetch_all(pool)
+    .await?;
+
+    let history = sqlx::query_as::<_, OrderTrackingEvent>(
+        r#"
+        SELECT to_status AS status, created_at
+        FROM order_status_history
+        WHERE order_id = $1
+        ORDER BY created_at, id
+        "#,
+    )
+    .bind(order.id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(OrderTracking {
+        reference: templates::order_reference(&order),
+        tracking_token: order_tracking::token(order.id)?,
+        id: order.id,
+        status: order.status,
+        customer_name: order.customer_name,
+        delivery_city: order.delivery_city,
+        subtotal_amount: order.subtotal_amount,
+        discount_amount: order.discount_amount,
+        promotion_code: order.promotion_code,
+        shipping_method_name: order.shipping_method_name,
+        shipping_amount: order.shipping_amount,
+        cod_fee: order.cod_fee,
+        payment_method: order.payment_method.clone(),
+        payment_status: order.payment_status.clone(),
+        payment_reference: order.payment_reference,
+        payment_due_at: order.payment_due_at,
+        total_amount: order.total_amount,
+        vat_amount: order.vat_amount,
+        tax_breakdown: order.tax_breakdown.0,
+        tracking_number: order.tracking_number,
+        created_at: order.created_at,
+        items,
+        history,
+    })
+}
+
+// Requested line locked for sale, with the units still free for it
+pub(crate) struct LockedLine {
+    pub product: Product,
+    pub variant: Option<ProductVariantDetail>,
+    pub available: i32,
+}
+
+impl LockedLine {
+    pub fn unit_price(&self) -> Money {
+        match &self.variant {
+            Some(v) => v.variant.price.unwrap_or(self.product.price),
+            None => self.product.price,
+        }
+    }
+
+    fn display_name(&self) -> String {
+        match &self.variant {
+            Some(v) => format!("{} ({})", self.product.name, v.label),
+            None => self.product.name.clone(),
+        }
+    }
+}
+
+// Locks the product, and variant, of a requested order or reservation line.
+// Stock held by reservations other than `reservation_id` is not available.
+pub(crate) async fn lock_order_line(
+    conn: &mut PgConnection,
+    item: &OrderItemRequest,
+    reservation_id: Option<Uuid>,
+) -> Result<LockedLine, AppError> {
+    if item.quantity <= 0 {
+        return Err(AppError::BadRequest(
+            "Quantity must be positive".to_string(),
+        ));
+    }
+
+    let product = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE id = $1 AND is_active = true FOR UPDATE",
+    )
+    .bind(item.product_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Product {} not found", item.product_id)))?;
+
+    // Products with variants are priced and stocked per variant
+    let variant = match item.variant_id {
+        Some(variant_id) => Some(
+            lock_variant_for_order(conn, product.id, variant_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Variant {} not found", variant_id)))?,
+        ),
+        None if has_active_variants(conn, product.id).await? => {
+            return Err(AppError::BadRequest(format!(
+                "Please choose a variant of product '{}'",
+                product.name
+            )));
+        }
+        None => None,
+    };
+
+    let in_stock = match &variant {
+        Some(v) => v.variant.stock_quantity,
+        None => product.stock_quantity,
+    };
+    let held =
+        reservations::held_quantity(conn, product.id, item.variant_id, reservation_id).await?;
+
+    Ok(LockedLine {
+        product,
+        variant,
+        available: in_stock - held,
+    })
+}
+
+// Takes a line's quantity out of what is available, keeping count per product
+// and variant because the same one may appear on several lines
+pub(crate) fn claim_stock(
+    claimed: &mut HashMap<(i32, Option<i32>), i32>,
+    line: &LockedLine,
+    quantity: i32,
+) -> Result<(), AppError> {
+    let key = (line.product.id, line.variant.as_ref().map(|v| v.variant.id));
+    let taken = claimed.entry(key).or_insert(0);
+    let available = (line.available - *taken).max(0);
+    *taken += quantity;
+
+    if available < quantity {
         return Err(AppError::BadRequest(format!(
-            "Invalid status. Must be one of: {}",
-            valid_statuses.join(", ")
+            "Insufficient stock for product '{}'. Available: {}, Requested: {}",
+            line.display_name(),
+            available,
+            quantity
         )));
     }
 
-    let order = sqlx::query_as::<_, Order>(
-        "UPDATE orders SET status = $1 WHERE id = $2 RETURNING *",
+    Ok(())
+}
+
+// Moves an order to a new status if the state machine allows it, stamping the
+// milestone time and recording the change. `admin_id` is None for changes
+// made by the system. Cancelled and returned orders are restocked in the
+// same transaction, and the customer email for the new status is queued.
+pub(crate) async fn transition_order(
+    conn: &mut PgConnection,
+    id: Uuid,
+    next: OrderStatus,
+    admin_id: Option<i32>,
+    comment: Option<&str>,
+) -> Result<Order, AppError> {
+    let current =
+        sqlx::query_scalar::<_, String>("SELECT status FROM orders WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+    let current = OrderStatus::parse(&current)?;
+
+    if !current.can_transition_to(next) {
+        return Err(AppError::BadRequest(format!(
+            "Cannot change order status from {} to {}",
+            current, next
+        )));
+    }
+
+    let mut query = String::from("UPDATE orders SET status = $1");
+    if let Some(column) = next.timestamp_column() {
+        query.push_str(&format!(", {} = COALESCE({}, NOW())", column, column));
+    }
+    query.push_str(" WHERE id = $2 RETURNING *");
+
+    let order = sqlx::query_as::<_, Order>(&query)
+        .bind(next.as_str())
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    record_status_change(conn, id, Some(current), next, admin_id, comment).await?;
+
+    // Items of orders that will not be fulfilled go back on the shelf
+    let restock_reason = match next {
+        OrderStatus::Cancelled => Some(MovementReason::CancellationRestock),
+        OrderStatus::Returned => Some(MovementReason::ReturnRestock),
+        _ => None,
+    };
+    if let Some(reason) = restock_reason {
+        inventory::restock_order(conn, id, reason, admin_id).await?;
+    }
+
+    notifications::order_status_changed(conn, &order, next).await?;
+
+    Ok(order)
+}
+
+async fn record_status_change(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+    from: Option<OrderStatus>,
+    to: OrderStatus,
+    admin_id: Option<i32>,
+    comment: Option<&str>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO order_status_history (order_id, from_status, to_status, admin_id, comment)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
     )
-    .bind(&payload.status)
-    .bind(id)
-    .fetch_optional(&pool)
-    .await?
-    .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+    .bind(order_id)
+    .bind(from.map(OrderStatus::as_str))
+    .bind(to.as_str())
+    .bind(admin_id)
+    .bind(comment)
+    .execute(&mut *conn)
+    .await?;
 
-    Ok(Json(order))
+    Ok(())
 }
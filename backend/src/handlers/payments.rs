@@ -0,0 +1,553 @@
+use axum::{
+    body::Bytes,
+    extract::{Multipart, Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    models::{
+        BankStatement, BankStatementDetail, BankStatementEntry, CodRemittance, CodRemittanceDetail,
+        CodRemittanceLine, CreateRefundRequest, Money, Order, OrderPayments, Payment,
+        PaymentConfig, Refund,
+    },
+    payments::{self, bank_transfer, cod, PaymentMethod, PaymentProvider, PaymentStatus, Payments},
+    shipping::Carrier,
+};
+
+// Public endpoints
+
+pub async fn get_payment_config(State(payments): State<Payments>) -> Json<PaymentConfig> {
+    let mut methods = vec![PaymentMethod::CashOnDelivery];
+    if payments.takes_bank_transfers() {
+        methods.push(PaymentMethod::BankTransfer);
+    }
+    if payments.name().is_some() {
+        methods.push(PaymentMethod::Card);
+    }
+
+    Json(PaymentConfig {
+        methods,
+        provider: payments.name(),
+        publishable_key: payments.publishable_key().map(str::to_string),
+    })
+}
+
+// Called by the payment provider. Events are handled once: a redelivered
+// event is acknowledged without being applied again.
+pub async fn payment_webhook(
+    State(pool): State<PgPool>,
+    State(payments): State<Payments>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, AppError> {
+    let provider = payments.provider()?;
+    let event = provider.parse_webhook(&headers, &body)?;
+
+    let mut tx = pool.begin().await?;
+
+    let first_delivery = sqlx::query(
+        "INSERT INTO payment_events (provider, event_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+    )
+    .bind(provider.name())
+    .bind(&event.id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if first_delivery {
+        if let Some(outcome) = event.outcome {
+            payments::record_outcome(&mut tx, provider.name(), outcome).await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(StatusCode::OK)
+}
+
+// Admin endpoints (require authentication)
+
+pub async fn list_order_payments(
+    State(pool): State<PgPool>,
+    Path(order_id): Path<Uuid>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<OrderPayments>, AppError> {
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1")
+        .bind(order_id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+
+    let payments =
+        sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE order_id = $1 ORDER BY id")
+            .bind(order_id)
+            .fetch_all(&pool)
+            .await?;
+
+    let refunds = sqlx::query_as::<_, Refund>(
+        r#"
+        SELECT r.* FROM refunds r
+        JOIN payments p ON p.id = r.payment_id
+        WHERE p.order_id = $1
+        ORDER BY r.id
+        "#,
+    )
+    .bind(order_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(OrderPayments {
+        payment_method: order.payment_method,
+        payment_status: order.payment_status,
+        refunded_amount: order.refunded_amount,
+        payments,
+        refunds,
+    }))
+}
+
+// Refunds part or all of the card payment through the provider. The refund
+// is recorded as pending and committed before it is sent, under a key
+// derived from its id, and settled with the provider's answer. A refund whose
+// answer was lost stays pending, counts against what can still be refunded,
+// and is sent again before the order's next refund.
+pub async fn create_refund(
+    State(pool): State<PgPool>,
+    State(payments): State<Payments>,
+    Path(order_id): Path<Uuid>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateRefundRequest>,
+) -> Result<(StatusCode, Json<Refund>), AppError> {
+    let provider = payments.provider()?;
+
+    let unsettled = sqlx::query_as::<_, Refund>(
+        r#"
+        SELECT r.* FROM refunds r
+        JOIN payments p ON p.id = r.payment_id
+        WHERE p.order_id = $1 AND r.status = 'pending'
+        ORDER BY r.id
+        "#,
+    )
+    .bind(order_id)
+    .fetch_all(&pool)
+    .await?;
+    for refund in unsettled {
+        send_refund(&pool, provider.as_ref(), refund).await?;
+    }
+
+    // The order is locked meanwhile, so two refunds cannot both take the
+    // same remainder
+    let mut tx = pool.begin().await?;
+
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+
+    let payment = sqlx::query_as::<_, Payment>(
+        r#"
+        SELECT * FROM payments
+        WHERE order_id = $1 AND status = 'succeeded'
+        ORDER BY id DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(order_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Order has no card payment to refund".to_string()))?;
+
+    // Refunds go back in the currency the payment was taken in
+    if payment.currency != payments::CURRENCY {
+        return Err(AppError::Conflict(format!(
+            "Payment was taken in {}; refund it from the provider's dashboard",
+            payment.currency
+        )));
+    }
+
+    if provider.name() != payment.provider {
+        return Err(AppError::BadRequest(format!(
+            "Payment was taken through '{}', which is not configured",
+            payment.provider
+        )));
+    }
+
+    let pending = sqlx::query_scalar::<_, Option<Money>>(
+        "SELECT SUM(amount) FROM refunds WHERE payment_id = $1 AND status = 'pending'",
+    )
+    .bind(payment.id)
+    .fetch_one(&mut *tx)
+    .await?
+    .unwrap_or(Money::ZERO);
+    let refundable = Money::eur(payment.amount) - order.refunded_amount - pending;
+    if refundable <= Money::ZERO {
+        return Err(AppError::Conflict(
+            "Order has already been refunded in full".to_string(),
+        ));
+    }
+    let amount = payload.amount.unwrap_or(refundable);
+    if amount <= Money::ZERO {
+        return Err(AppError::BadRequest(
+            "Refund amount must be positive".to_string(),
+        ));
+    }
+    if amount > refundable {
+        return Err(AppError::BadRequest(format!(
+            "Only {} can still be refunded",
+            refundable
+        )));
+    }
+
+    let reason = payload
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+    let refund = sqlx::query_as::<_, Refund>(
+        r#"
+        INSERT INTO refunds (payment_id, amount, currency, reason, admin_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(payment.id)
+    .bind(amount)
+    .bind(&payment.currency)
+    .bind(reason)
+    .bind(claims.admin_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let refund = send_refund(&pool, provider.as_ref(), refund).await?;
+
+    Ok((StatusCode::CREATED, Json(refund)))
+}
+
+// Imports a courier's cash on delivery payout report (multipart: `file`,
+// optional `carrier`, `tracking_column` and `amount_column`)
+pub async fn import_cod_remittance(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<CodRemittanceDetail>), AppError> {
+    let mut file = None;
+    let mut file_name = None;
+    let mut carrier = None;
+    let mut tracking_column = None;
+    let mut amount_column = None;
+
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        tracing::error!("Multipart error: {:?}", e);
+        AppError::BadRequest("Invalid file upload".to_string())
+    })? {
+        let name = field.name().unwrap_or("").to_string();
+
+        if name == "file" {
+            file_name = field.file_name().map(str::to_string);
+            file = Some(field.bytes().await.map_err(|e| {
+                tracing::error!("Failed to read file data: {:?}", e);
+                AppError::BadRequest("Failed to read file".to_string())
+            })?);
+            continue;
+        }
+
+        let value = field
+            .text()
+            .await
+            .map_err(|_| AppError::BadRequest(format!("Invalid {}", name)))?;
+        let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        match name.as_str() {
+            "carrier" => carrier = value,
+            "tracking_column" => tracking_column = value,
+            "amount_column" => amount_column = value,
+            _ => {}
+        }
+    }
+
+    let file = file.ok_or_else(|| AppError::BadRequest("No report uploaded".to_string()))?;
+    let carrier = carrier
+        .map(|value| {
+            Carrier::ALL
+                .into_iter()
+                .find(|carrier| carrier.as_str() == value)
+                .ok_or_else(|| AppError::BadRequest(format!("Unknown carrier '{}'", value)))
+        })
+        .transpose()?;
+
+    let rows =
+        cod::parse_payout_report(&file, tracking_column.as_deref(), amount_column.as_deref())?;
+
+    let mut tx = pool.begin().await?;
+    let remittance = cod::reconcile(
+        &mut tx,
+        carrier,
+        file_name.as_deref(),
+        &rows,
+        claims.admin_id,
+    )
+    .await?;
+    let lines = fetch_remittance_lines(&mut tx, remittance.id).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "COD report {} matched {} of {} parcels",
+        remittance.id,
+        remittance.matched_count,
+        remittance.line_count
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CodRemittanceDetail { remittance, lines }),
+    ))
+}
+
+pub async fn list_cod_remittances(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<CodRemittance>>, AppError> {
+    let remittances =
+        sqlx::query_as::<_, CodRemittance>("SELECT * FROM cod_remittances ORDER BY id DESC")
+            .fetch_all(&pool)
+            .await?;
+
+    Ok(Json(remittances))
+}
+
+pub async fn get_cod_remittance(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<CodRemittanceDetail>, AppError> {
+    let mut conn = pool.acquire().await?;
+
+    let remittance =
+        sqlx::query_as::<_, CodRemittance>("SELECT * FROM cod_remittances WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::NotFound("COD report not found".to_string()))?;
+    let lines = fetch_remittance_lines(&mut conn, id).await?;
+
+    Ok(Json(CodRemittanceDetail { remittance, lines }))
+}
+
+// Imports a statement of the studio's bank account (multipart: `file`, a
+// CAMT.053 XML or CSV export) and matches its payments to transfer orders
+pub async fn import_bank_statement(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<BankStatementDetail>), AppError> {
+    let mut file = None;
+    let mut file_name = None;
+
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        tracing::error!("Multipart error: {:?}", e);
+        AppError::BadRequest("Invalid file upload".to_string())
+    })? {
+        if field.name() == Some("file") {
+            file_name = field.file_name().map(str::to_string);
+            file = Some(field.bytes().await.map_err(|e| {
+                tracing::error!("Failed to read file data: {:?}", e);
+                AppError::BadRequest("Failed to read file".to_string())
+            })?);
+        }
+    }
+
+    let file = file.ok_or_else(|| AppError::BadRequest("No statement uploaded".to_string()))?;
+    let (format, entries) = bank_transfer::parse_statement(&file)?;
+
+    let mut tx = pool.begin().await?;
+    let statement = bank_transfer::reconcile(
+        &mut tx,
+        format,
+        file_name.as_deref(),
+        &entries,
+        claims.admin_id,
+    )
+    .await?;
+    let entries = fetch_statement_entries(&mut tx, statement.id).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Bank statement {} matched {} of {} payments",
+        statement.id,
+        statement.matched_count,
+        statement.entry_count
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(BankStatementDetail { statement, entries }),
+    ))
+}
+
+pub async fn list_bank_statements(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<BankStatement>>, AppError> {
+    let statements =
+        sqlx::query_as::<_, BankStatement>("SELECT * FROM bank_statements ORDER BY id DESC")
+            .fetch_all(&pool)
+            .await?;
+
+    Ok(Json(statements))
+}
+
+pub async fn get_bank_statement(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<BankStatementDetail>, AppError> {
+    let mut conn = pool.acquire().await?;
+
+    let statement =
+        sqlx::query_as::<_, BankStatement>("SELECT * FROM bank_statements WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Bank statement not found".to_string()))?;
+    let entries = fetch_statement_entries(&mut conn, id).await?;
+
+    Ok(Json(BankStatementDetail { statement, entries }))
+}
+
+// Helpers
+
+// Lines of a report, discrepancies first
+async fn fetch_remittance_lines(
+    conn: &mut PgConnection,
+    remittance_id: i32,
+) -> Result<Vec<CodRemittanceLine>, AppError> {
+    let lines = sqlx::query_as::<_, CodRemittanceLine>(
+        r#"
+        SELECT * FROM cod_remittance_lines
+        WHERE remittance_id = $1
+        ORDER BY status = 'matched', line_number
+        "#,
+    )
+    .bind(remittance_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(lines)
+}
+
+// Payments of a statement, discrepancies first
+async fn fetch_statement_entries(
+    conn: &mut PgConnection,
+    statement_id: i32,
+) -> Result<Vec<BankStatementEntry>, AppError> {
+    let entries = sqlx::query_as::<_, BankStatementEntry>(
+        r#"
+        SELECT * FROM bank_statement_entries
+        WHERE statement_id = $1
+        ORDER BY status = 'matched', id
+        "#,
+    )
+    .bind(statement_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(entries)
+}
+
+// Sends a pending refund to the provider, keyed by its id so sending it again
+// cannot pay out twice, and settles it with the answer. The order's refunded
+// amount only grows when the refund succeeds, once however many requests
+// settle it. An error other than the provider's refusal leaves it pending.
+async fn send_refund(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    refund: Refund,
+) -> Result<Refund, AppError> {
+    let payment = sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE id = $1")
+        .bind(refund.payment_id)
+        .fetch_one(pool)
+        .await?;
+
+    let sent = provider
+        .refund(
+            &payment.provider_payment_id,
+            Money::eur(refund.amount),
+            &format!("refund-{}", refund.id),
+        )
+        .await;
+
+    let provider_refund_id = match sent {
+        Ok(provider_refund_id) => provider_refund_id,
+        Err(AppError::BadRequest(message)) => {
+            sqlx::query(
+                r#"
+                UPDATE refunds SET status = 'failed', failure_message = $1
+                WHERE id = $2 AND status = 'pending'
+                "#,
+            )
+            .bind(&message)
+            .bind(refund.id)
+            .execute(pool)
+            .await?;
+            return Err(AppError::BadRequest(message));
+        }
+        Err(err) => return Err(err),
+    };
+
+    let mut tx = pool.begin().await?;
+
+    let settled = sqlx::query_as::<_, Refund>(
+        r#"
+        UPDATE refunds SET status = 'succeeded', provider_refund_id = $1
+        WHERE id = $2 AND status = 'pending'
+        RETURNING *
+        "#,
+    )
+    .bind(&provider_refund_id)
+    .bind(refund.id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(settled) = settled else {
+        // Settled by another request in the meantime
+        let refund = sqlx::query_as::<_, Refund>("SELECT * FROM refunds WHERE id = $1")
+            .bind(refund.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        return Ok(refund);
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE orders SET
+            refunded_amount = refunded_amount + $1,
+            payment_status = CASE WHEN refunded_amount + $1 >= $2 THEN $3 ELSE $4 END
+        WHERE id = $5
+        "#,
+    )
+    .bind(settled.amount)
+    .bind(payment.amount)
+    .bind(PaymentStatus::Refunded.as_str())
+    .bind(PaymentStatus::PartiallyRefunded.as_str())
+    .bind(payment.order_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "Refunded {} of order {} ({})",
+        Money::eur(settled.amount),
+        payment.order_id,
+        provider_refund_id
+    );
+
+    Ok(settled)
+}
@@ -0,0 +1,440 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use bytes::Bytes;
+use sqlx::{types::Json as SqlJson, PgConnection, PgPool};
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    media,
+    models::{
+        ImageVariant, Product, ProductImage, ReorderProductImagesRequest, UpdateProductImageRequest,
+    },
+    storage::{SharedStorage, Storage},
+    tax,
+};
+
+// Processed upload written to storage
+struct StoredImage {
+    // Largest WebP rendition, used where a single URL is needed
+    url: String,
+    variants: Vec<ImageVariant>,
+}
+
+// Admin endpoints (require authentication)
+
+pub async fn list_product_images(
+    State(pool): State<PgPool>,
+    Path(product_id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<ProductImage>>, AppError> {
+    ensure_product_exists(&pool, product_id).await?;
+
+    let images = fetch_images(&pool, product_id).await?;
+
+    Ok(Json(images))
+}
+
+// Accepts one or more `images` file fields and an optional `alt_text` field
+// applied to every uploaded image (defaults to the product name)
+pub async fn upload_product_images(
+    State(pool): State<PgPool>,
+    State(storage): State<SharedStorage>,
+    Path(product_id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+    multipart: Multipart,
+) -> Result<(StatusCode, Json<Vec<ProductImage>>), AppError> {
+    let product = fetch_product(&pool, product_id).await?;
+
+    let (uploads, alt_text) = read_image_fields(multipart, "images").await?;
+    if uploads.is_empty() {
+        return Err(AppError::BadRequest("No image file provided".to_string()));
+    }
+    let alt_text = alt_text.unwrap_or_else(|| product.name.clone());
+
+    let mut stored = Vec::with_capacity(uploads.len());
+    for upload in uploads {
+        stored.push(store_image(storage.as_ref(), product_id, upload).await?);
+    }
+
+    let mut tx = pool.begin().await?;
+    for image in stored {
+        insert_image(&mut tx, product_id, image, &alt_text).await?;
+    }
+    ensure_primary_image(&mut tx, product_id).await?;
+    tx.commit().await?;
+
+    let images = fetch_images(&pool, product_id).await?;
+
+    Ok((StatusCode::CREATED, Json(images)))
+}
+
+// Single-image upload kept for existing clients; the image is added to the
+// gallery and becomes the primary image
+pub async fn upload_product_image(
+    State(pool): State<PgPool>,
+    State(storage): State<SharedStorage>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+    multipart: Multipart,
+) -> Result<Json<Product>, AppError> {
+    let product = fetch_product(&pool, id).await?;
+
+    let (uploads, alt_text) = read_image_fields(multipart, "image").await?;
+    let upload = uploads
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::BadRequest("No image file provided".to_string()))?;
+    let alt_text = alt_text.unwrap_or_else(|| product.name.clone());
+
+    let stored = store_image(storage.as_ref(), id, upload).await?;
+
+    let mut tx = pool.begin().await?;
+    let image = insert_image(&mut tx, id, stored, &alt_text).await?;
+    set_primary(&mut tx, id, image.id).await?;
+    let mut product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    tax::fill_product_tax(&mut tx, [&mut product]).await?;
+    tx.commit().await?;
+
+    Ok(Json(product))
+}
+
+pub async fn update_product_image(
+    State(pool): State<PgPool>,
+    Path((product_id, image_id)): Path<(i32, i32)>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<UpdateProductImageRequest>,
+) -> Result<Json<ProductImage>, AppError> {
+    let image = sqlx::query_as::<_, ProductImage>(
+        r#"
+        UPDATE product_images SET alt_text = $1
+        WHERE id = $2 AND product_id = $3
+        RETURNING *
+        "#,
+    )
+    .bind(&payload.alt_text)
+    .bind(image_id)
+    .bind(product_id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Image not found".to_string()))?;
+
+    Ok(Json(image))
+}
+
+pub async fn reorder_product_images(
+    State(pool): State<PgPool>,
+    Path(product_id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<ReorderProductImagesRequest>,
+) -> Result<Json<Vec<ProductImage>>, AppError> {
+    ensure_product_exists(&pool, product_id).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let mut current = sqlx::query_scalar::<_, i32>(
+        "SELECT id FROM product_images WHERE product_id = $1 FOR UPDATE",
+    )
+    .bind(product_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut requested = payload.image_ids.clone();
+    current.sort_unstable();
+    requested.sort_unstable();
+    if current != requested {
+        return Err(AppError::BadRequest(
+            "image_ids must list every image of the product exactly once".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE product_images i SET position = (o.position - 1)::INTEGER
+        FROM UNNEST($1::INTEGER[]) WITH ORDINALITY AS o(id, position)
+        WHERE i.id = o.id AND i.product_id = $2
+        "#,
+    )
+    .bind(&payload.image_ids)
+    .bind(product_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let images = fetch_images(&pool, product_id).await?;
+
+    Ok(Json(images))
+}
+
+pub async fn set_primary_product_image(
+    State(pool): State<PgPool>,
+    Path((product_id, image_id)): Path<(i32, i32)>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<ProductImage>>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let exists = sqlx::query_scalar::<_, i32>(
+        "SELECT id FROM product_images WHERE id = $1 AND product_id = $2",
+    )
+    .bind(image_id)
+    .bind(product_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if exists.is_none() {
+        return Err(AppError::NotFound("Image not found".to_string()));
+    }
+
+    set_primary(&mut tx, product_id, image_id).await?;
+    tx.commit().await?;
+
+    let images = fetch_images(&pool, product_id).await?;
+
+    Ok(Json(images))
+}
+
+pub async fn delete_product_image(
+    State(pool): State<PgPool>,
+    State(storage): State<SharedStorage>,
+    Path((product_id, image_id)): Path<(i32, i32)>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let image = sqlx::query_as::<_, ProductImage>(
+        "DELETE FROM product_images WHERE id = $1 AND product_id = $2 RETURNING *",
+    )
+    .bind(image_id)
+    .bind(product_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Image not found".to_string()))?;
+
+    // Promote the next image when the primary one is removed
+    ensure_primary_image(&mut tx, product_id).await?;
+    tx.commit().await?;
+
+    remove_image_files(storage.as_ref(), &image).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Helpers
+
+// Loads the galleries of several products in display order
+pub(crate) async fn fetch_images_for(
+    pool: &PgPool,
+    product_ids: &[i32],
+) -> Result<Vec<ProductImage>, AppError> {
+    let images = sqlx::query_as::<_, ProductImage>(
+        "SELECT * FROM product_images WHERE product_id = ANY($1) ORDER BY product_id, position, id",
+    )
+    .bind(product_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(images)
+}
+
+// Deletes the image and all of its variants from storage
+pub(crate) async fn remove_image_files(storage: &dyn Storage, image: &ProductImage) {
+    remove_image_file(storage, &image.url).await;
+    for variant in image.variants.iter() {
+        if variant.url != image.url {
+            remove_image_file(storage, &variant.url).await;
+        }
+    }
+}
+
+async fn remove_image_file(storage: &dyn Storage, url: &str) {
+    // URLs issued by another backend (e.g. before a migration) are left alone
+    let Some(key) = storage.key_from_url(url) else {
+        return;
+    };
+
+    // A missing file must not block deleting the record
+    if let Err(e) = storage.delete(&key).await {
+        tracing::warn!("Failed to remove image file {}: {:?}", key, e);
+    }
+}
+
+async fn fetch_images(pool: &PgPool, product_id: i32) -> Result<Vec<ProductImage>, AppError> {
+    fetch_images_for(pool, &[product_id]).await
+}
+
+async fn fetch_product(pool: &PgPool, id: i32) -> Result<Product, AppError> {
+    sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Product not found".to_string()))
+}
+
+async fn ensure_product_exists(pool: &PgPool, id: i32) -> Result<(), AppError> {
+    fetch_product(pool, id).await.map(|_| ())
+}
+
+async fn read_image_fields(
+    mut multipart: Multipart,
+    file_field: &str,
+) -> Result<(Vec<Bytes>, Option<String>), AppError> {
+    let mut uploads = Vec::new();
+    let mut alt_text = None;
+
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        tracing::error!("Multipart error: {:?}", e);
+        AppError::BadRequest("Invalid file upload".to_string())
+    })? {
+        let name = field.name().unwrap_or("").to_string();
+
+        if name == "alt_text" {
+            let text = field.text().await.map_err(|e| {
+                tracing::error!("Failed to read alt text: {:?}", e);
+                AppError::BadRequest("Invalid alt text".to_string())
+            })?;
+            alt_text = Some(text.trim().to_string()).filter(|t| !t.is_empty());
+            continue;
+        }
+
+        if name != file_field {
+            continue;
+        }
+
+        // The format is verified from the file content during processing
+        let data = field.bytes().await.map_err(|e| {
+            tracing::error!("Failed to read file data: {:?}", e);
+            AppError::BadRequest("Failed to read file".to_string())
+        })?;
+
+        uploads.push(data);
+    }
+
+    Ok((uploads, alt_text))
+}
+
+// Decodes, sanitises and resizes an upload, then writes every variant to storage
+async fn store_image(
+    storage: &dyn Storage,
+    product_id: i32,
+    data: Bytes,
+) -> Result<StoredImage, AppError> {
+    let encoded = media::process_image(data).await?;
+
+    let base_name = format!("product-{}-{}", product_id, uuid::Uuid::new_v4());
+    let mut variants = Vec::with_capacity(encoded.len());
+
+    for variant in encoded {
+        let key = format!("{}-{}.{}", base_name, variant.size, variant.format);
+        storage.put(&key, variant.data).await?;
+
+        variants.push(ImageVariant {
+            size: variant.size.to_string(),
+            format: variant.format.to_string(),
+            width: variant.width,
+            height: variant.height,
+            url: storage.public_url(&key),
+        });
+    }
+
+    let url = variants
+        .iter()
+        .rev()
+        .find(|v| v.format == "webp")
+        .map(|v| v.url.clone())
+        .ok_or_else(|| AppError::InternalServerError("Failed to process image".to_string()))?;
+
+    Ok(StoredImage { url, variants })
+}
+
+async fn insert_image(
+    conn: &mut PgConnection,
+    product_id: i32,
+    stored: StoredImage,
+    alt_text: &str,
+) -> Result<ProductImage, AppError> {
+    let image = sqlx::query_as::<_, ProductImage>(
+        r#"
+        INSERT INTO product_images (product_id, url, alt_text, position, variants)
+        SELECT $1, $2, $3, COALESCE(MAX(position) + 1, 0), $4
+        FROM product_images WHERE product_id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(product_id)
+    .bind(&stored.url)
+    .bind(alt_text)
+    .bind(SqlJson(&stored.variants))
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(image)
+}
+
+// Marks the image as primary and mirrors its URL into products.image_url
+async fn set_primary(
+    conn: &mut PgConnection,
+    product_id: i32,
+    image_id: i32,
+) -> Result<(), AppError> {
+    // Cleared first: the partial unique index is checked row by row
+    sqlx::query(
+        "UPDATE product_images SET is_primary = false WHERE product_id = $1 AND is_primary",
+    )
+    .bind(product_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE product_images SET is_primary = true WHERE id = $1")
+        .bind(image_id)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        "UPDATE products SET image_url = (SELECT url FROM product_images WHERE id = $1) WHERE id = $2",
+    )
+    .bind(image_id)
+    .bind(product_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// Picks the first image by position as primary if none is set, and clears
+// products.image_url once the gallery is empty
+async fn ensure_primary_image(conn: &mut PgConnection, product_id: i32) -> Result<(), AppError> {
+    let has_primary = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM product_images WHERE product_id = $1 AND is_primary)",
+    )
+    .bind(product_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if has_primary {
+        return Ok(());
+    }
+
+    let first = sqlx::query_scalar::<_, i32>(
+        "SELECT id FROM product_images WHERE product_id = $1 ORDER BY position, id LIMIT 1",
+    )
+    .bind(product_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match first {
+        Some(image_id) => set_primary(conn, product_id, image_id).await,
+        None => {
+            sqlx::query("UPDATE products SET image_url = NULL WHERE id = $1")
+                .bind(product_id)
+                .execute(&mut *conn)
+                .await?;
+            Ok(())
+        }
+    }
+}
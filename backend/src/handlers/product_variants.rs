@@ -0,0 +1,710 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::{PgConnection, PgPool};
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    inventory::{self, MovementReason, StockMovement, StockUpdate},
+    models::{
+        CreateProductOptionRequest, CreateProductVariantRequest, Money, ProductOption,
+        ProductOptionDetail, ProductOptionValue, ProductVariantDetail, UpdateProductOptionRequest,
+        UpdateProductVariantRequest,
+    },
+};
+
+// Variants with their option value ids and a "Червен / M" label built in
+// option order; variants without options are labelled with their SKU
+const VARIANT_DETAIL_SQL: &str = r#"
+    SELECT v.*,
+        COALESCE(
+            array_agg(ov.id ORDER BY o.position, o.id) FILTER (WHERE ov.id IS NOT NULL),
+            '{}'
+        ) AS option_value_ids,
+        COALESCE(string_agg(ov.value, ' / ' ORDER BY o.position, o.id), v.sku) AS label,
+        GREATEST(
+            v.stock_quantity
+                - (SELECT COALESCE(SUM(h.quantity), 0) FROM active_stock_holds h WHERE h.variant_id = v.id),
+            0
+        )::INTEGER AS available_quantity
+    FROM product_variants v
+    LEFT JOIN product_variant_values vv ON vv.variant_id = v.id
+    LEFT JOIN product_option_values ov ON ov.id = vv.option_value_id
+    LEFT JOIN product_options o ON o.id = ov.option_id
+"#;
+
+// Admin endpoints (require authentication)
+
+pub async fn list_product_options(
+    State(pool): State<PgPool>,
+    Path(product_id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<ProductOptionDetail>>, AppError> {
+    ensure_product_exists(&pool, product_id).await?;
+
+    let options = fetch_options_for(&pool, &[product_id]).await?;
+
+    Ok(Json(options))
+}
+
+pub async fn create_product_option(
+    State(pool): State<PgPool>,
+    Path(product_id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<CreateProductOptionRequest>,
+) -> Result<(StatusCode, Json<ProductOptionDetail>), AppError> {
+    ensure_product_exists(&pool, product_id).await?;
+
+    let name = required_text(&payload.name, "Option name")?;
+    let values = normalize_values(&payload.values)?;
+
+    let mut tx = pool.begin().await?;
+
+    // Existing variants would be left without a value for the new option
+    let has_variants = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM product_variants WHERE product_id = $1)",
+    )
+    .bind(product_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if has_variants {
+        return Err(AppError::BadRequest(
+            "Options cannot be added to a product that already has variants".to_string(),
+        ));
+    }
+
+    let option = sqlx::query_as::<_, ProductOption>(
+        r#"
+        INSERT INTO product_options (product_id, name, position)
+        VALUES ($1, $2, COALESCE($3, (SELECT COUNT(*) FROM product_options WHERE product_id = $1)::INTEGER))
+        RETURNING *
+        "#,
+    )
+    .bind(product_id)
+    .bind(&name)
+    .bind(payload.position)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_option_conflict)?;
+
+    sync_option_values(&mut tx, option.id, &values).await?;
+    tx.commit().await?;
+
+    let option = fetch_option(&pool, product_id, option.id).await?;
+
+    Ok((StatusCode::CREATED, Json(option)))
+}
+
+pub async fn update_product_option(
+    State(pool): State<PgPool>,
+    Path((product_id, option_id)): Path<(i32, i32)>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<UpdateProductOptionRequest>,
+) -> Result<Json<ProductOptionDetail>, AppError> {
+    let name = payload
+        .name
+        .as_deref()
+        .map(|name| required_text(name, "Option name"))
+        .transpose()?;
+    let values = payload
+        .values
+        .as_deref()
+        .map(normalize_values)
+        .transpose()?;
+
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query(
+        r#"
+        UPDATE product_options
+        SET name = COALESCE($1, name), position = COALESCE($2, position)
+        WHERE id = $3 AND product_id = $4
+        "#,
+    )
+    .bind(&name)
+    .bind(payload.position)
+    .bind(option_id)
+    .bind(product_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(map_option_conflict)?;
+
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Option not found".to_string()));
+    }
+
+    if let Some(values) = values {
+        sync_option_values(&mut tx, option_id, &values).await?;
+    }
+
+    tx.commit().await?;
+
+    let option = fetch_option(&pool, product_id, option_id).await?;
+
+    Ok(Json(option))
+}
+
+pub async fn delete_product_option(
+    State(pool): State<PgPool>,
+    Path((product_id, option_id)): Path<(i32, i32)>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    let in_use = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM product_variant_values vv
+            JOIN product_option_values ov ON ov.id = vv.option_value_id
+            WHERE ov.option_id = $1
+        )
+        "#,
+    )
+    .bind(option_id)
+    .fetch_one(&pool)
+    .await?;
+
+    if in_use {
+        return Err(AppError::BadRequest(
+            "Option is used by variants; delete those variants first".to_string(),
+        ));
+    }
+
+    let result = sqlx::query("DELETE FROM product_options WHERE id = $1 AND product_id = $2")
+        .bind(option_id)
+        .bind(product_id)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Option not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_product_variants(
+    State(pool): State<PgPool>,
+    Path(product_id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<ProductVariantDetail>>, AppError> {
+    ensure_product_exists(&pool, product_id).await?;
+
+    let variants = fetch_variants_for(&pool, &[product_id], false).await?;
+
+    Ok(Json(variants))
+}
+
+pub async fn create_product_variant(
+    State(pool): State<PgPool>,
+    Path(product_id): Path<i32>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateProductVariantRequest>,
+) -> Result<(StatusCode, Json<ProductVariantDetail>), AppError> {
+    ensure_product_exists(&pool, product_id).await?;
+
+    let sku = required_text(&payload.sku, "SKU")?;
+    validate_price(payload.price)?;
+    validate_stock(payload.stock_quantity)?;
+
+    let mut tx = pool.begin().await?;
+
+    let option_value_ids =
+        validate_option_values(&mut tx, product_id, &payload.option_value_ids, None).await?;
+
+    let variant_id = sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO product_variants (product_id, sku, price, stock_quantity, is_active, position)
+        VALUES ($1, $2, $3, 0, COALESCE($4, true),
+            COALESCE($5, (SELECT COUNT(*) FROM product_variants WHERE product_id = $1)::INTEGER))
+        RETURNING id
+        "#,
+    )
+    .bind(product_id)
+    .bind(&sku)
+    .bind(payload.price)
+    .bind(payload.is_active)
+    .bind(payload.position)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_sku_conflict)?;
+
+    set_variant_values(&mut tx, variant_id, &option_value_ids).await?;
+    set_variant_stock(
+        &mut tx,
+        product_id,
+        variant_id,
+        payload.stock_quantity,
+        claims.admin_id,
+    )
+    .await?;
+    tx.commit().await?;
+
+    let variant = fetch_variant(&pool, product_id, variant_id).await?;
+
+    Ok((StatusCode::CREATED, Json(variant)))
+}
+
+pub async fn update_product_variant(
+    State(pool): State<PgPool>,
+    Path((product_id, variant_id)): Path<(i32, i32)>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<UpdateProductVariantRequest>,
+) -> Result<Json<ProductVariantDetail>, AppError> {
+    let sku = payload
+        .sku
+        .as_deref()
+        .map(|sku| required_text(sku, "SKU"))
+        .transpose()?;
+    if let Some(price) = payload.price {
+        validate_price(price)?;
+    }
+    if let Some(stock_quantity) = payload.stock_quantity {
+        validate_stock(stock_quantity)?;
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query(
+        r#"
+        UPDATE product_variants SET
+            sku = COALESCE($1, sku),
+            price = CASE WHEN $2 THEN $3 ELSE price END,
+            is_active = COALESCE($4, is_active),
+            position = COALESCE($5, position)
+        WHERE id = $6 AND product_id = $7
+        "#,
+    )
+    .bind(&sku)
+    .bind(payload.price.is_some())
+    .bind(payload.price.flatten())
+    .bind(payload.is_active)
+    .bind(payload.position)
+    .bind(variant_id)
+    .bind(product_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(map_sku_conflict)?;
+
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Variant not found".to_string()));
+    }
+
+    if let Some(requested) = &payload.option_value_ids {
+        let option_value_ids =
+            validate_option_values(&mut tx, product_id, requested, Some(variant_id)).await?;
+
+        sqlx::query("DELETE FROM product_variant_values WHERE variant_id = $1")
+            .bind(variant_id)
+            .execute(&mut *tx)
+            .await?;
+        set_variant_values(&mut tx, variant_id, &option_value_ids).await?;
+    }
+
+    if let Some(stock_quantity) = payload.stock_quantity {
+        set_variant_stock(
+            &mut tx,
+            product_id,
+            variant_id,
+            stock_quantity,
+            claims.admin_id,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    let variant = fetch_variant(&pool, product_id, variant_id).await?;
+
+    Ok(Json(variant))
+}
+
+// Past order lines keep their snapshot; only the link to the variant is cleared
+pub async fn delete_product_variant(
+    State(pool): State<PgPool>,
+    Path((product_id, variant_id)): Path<(i32, i32)>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query("DELETE FROM product_variants WHERE id = $1 AND product_id = $2")
+        .bind(variant_id)
+        .bind(product_id)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Variant not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Helpers
+
+// Loads the options of several products with their values in display order
+pub(crate) async fn fetch_options_for(
+    pool: &PgPool,
+    product_ids: &[i32],
+) -> Result<Vec<ProductOptionDetail>, AppError> {
+    let options = sqlx::query_as::<_, ProductOption>(
+        "SELECT * FROM product_options WHERE product_id = ANY($1) ORDER BY product_id, position, id",
+    )
+    .bind(product_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let option_ids: Vec<i32> = options.iter().map(|o| o.id).collect();
+    let mut values_by_option: HashMap<i32, Vec<ProductOptionValue>> = HashMap::new();
+    for value in sqlx::query_as::<_, ProductOptionValue>(
+        "SELECT * FROM product_option_values WHERE option_id = ANY($1) ORDER BY position, id",
+    )
+    .bind(&option_ids)
+    .fetch_all(pool)
+    .await?
+    {
+        values_by_option
+            .entry(value.option_id)
+            .or_default()
+            .push(value);
+    }
+
+    Ok(options
+        .into_iter()
+        .map(|option| ProductOptionDetail {
+            values: values_by_option.remove(&option.id).unwrap_or_default(),
+            option,
+        })
+        .collect())
+}
+
+// Loads the variants of several products in display order
+pub(crate) async fn fetch_variants_for(
+    pool: &PgPool,
+    product_ids: &[i32],
+    active_only: bool,
+) -> Result<Vec<ProductVariantDetail>, AppError> {
+    let variants = sqlx::query_as::<_, ProductVariantDetail>(&format!(
+        "{} WHERE v.product_id = ANY($1) AND (v.is_active OR NOT $2) GROUP BY v.id ORDER BY v.product_id, v.position, v.id",
+        VARIANT_DETAIL_SQL
+    ))
+    .bind(product_ids)
+    .bind(active_only)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(variants)
+}
+
+// Locks a variant of the product for an order line, returning None when it
+// does not exist or is not for sale
+pub(crate) async fn lock_variant_for_order(
+    conn: &mut PgConnection,
+    product_id: i32,
+    variant_id: i32,
+) -> Result<Option<ProductVariantDetail>, AppError> {
+    // Row lock first: FOR UPDATE is not allowed together with GROUP BY
+    let locked = sqlx::query_scalar::<_, i32>(
+        "SELECT id FROM product_variants WHERE id = $1 AND product_id = $2 AND is_active FOR UPDATE",
+    )
+    .bind(variant_id)
+    .bind(product_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if locked.is_none() {
+        return Ok(None);
+    }
+
+    let variant = sqlx::query_as::<_, ProductVariantDetail>(&format!(
+        "{} WHERE v.id = $1 GROUP BY v.id",
+        VARIANT_DETAIL_SQL
+    ))
+    .bind(variant_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(Some(variant))
+}
+
+// Whether the product is sold per variant
+pub(crate) async fn has_active_variants(
+    conn: &mut PgConnection,
+    product_id: i32,
+) -> Result<bool, AppError> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM product_variants WHERE product_id = $1 AND is_active)",
+    )
+    .bind(product_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(exists)
+}
+
+async fn fetch_option(
+    pool: &PgPool,
+    product_id: i32,
+    option_id: i32,
+) -> Result<ProductOptionDetail, AppError> {
+    fetch_options_for(pool, &[product_id])
+        .await?
+        .into_iter()
+        .find(|o| o.option.id == option_id)
+        .ok_or_else(|| AppError::NotFound("Option not found".to_string()))
+}
+
+async fn fetch_variant(
+    pool: &PgPool,
+    product_id: i32,
+    variant_id: i32,
+) -> Result<ProductVariantDetail, AppError> {
+    sqlx::query_as::<_, ProductVariantDetail>(&format!(
+        "{} WHERE v.id = $1 AND v.product_id = $2 GROUP BY v.id",
+        VARIANT_DETAIL_SQL
+    ))
+    .bind(variant_id)
+    .bind(product_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Variant not found".to_string()))
+}
+
+async fn ensure_product_exists(pool: &PgPool, id: i32) -> Result<(), AppError> {
+    sqlx::query_scalar::<_, i32>("SELECT id FROM products WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+    Ok(())
+}
+
+// Replaces the value list of an option, keeping ids of values that remain so
+// variants referencing them are unaffected
+async fn sync_option_values(
+    conn: &mut PgConnection,
+    option_id: i32,
+    values: &[String],
+) -> Result<(), AppError> {
+    let removed_in_use = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT ov.value FROM product_option_values ov
+        WHERE ov.option_id = $1 AND NOT (ov.value = ANY($2))
+          AND EXISTS (SELECT 1 FROM product_variant_values vv WHERE vv.option_value_id = ov.id)
+        LIMIT 1
+        "#,
+    )
+    .bind(option_id)
+    .bind(values)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some(value) = removed_in_use {
+        return Err(AppError::BadRequest(format!(
+            "Value '{}' is used by variants and cannot be removed",
+            value
+        )));
+    }
+
+    sqlx::query("DELETE FROM product_option_values WHERE option_id = $1 AND NOT (value = ANY($2))")
+        .bind(option_id)
+        .bind(values)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO product_option_values (option_id, value, position)
+        SELECT $1, v.value, (v.position - 1)::INTEGER
+        FROM UNNEST($2::VARCHAR[]) WITH ORDINALITY AS v(value, position)
+        ON CONFLICT (option_id, value) DO UPDATE SET position = EXCLUDED.position
+        "#,
+    )
+    .bind(option_id)
+    .bind(values)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// Checks that the ids pick exactly one value of every product option and that
+// no other variant already has the same combination; returns them sorted
+async fn validate_option_values(
+    conn: &mut PgConnection,
+    product_id: i32,
+    option_value_ids: &[i32],
+    variant_id: Option<i32>,
+) -> Result<Vec<i32>, AppError> {
+    let mut ids = option_value_ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+
+    let option_count =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM product_options WHERE product_id = $1")
+            .bind(product_id)
+            .fetch_one(&mut *conn)
+            .await?;
+
+    let picked_options = sqlx::query_scalar::<_, i32>(
+        r#"
+        SELECT ov.option_id FROM product_option_values ov
+        JOIN product_options o ON o.id = ov.option_id
+        WHERE o.product_id = $1 AND ov.id = ANY($2)
+        "#,
+    )
+    .bind(product_id)
+    .bind(&ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let distinct_options: HashSet<i32> = picked_options.iter().copied().collect();
+    if picked_options.len() != ids.len()
+        || distinct_options.len() != ids.len()
+        || ids.len() as i64 != option_count
+    {
+        return Err(AppError::BadRequest(
+            "A variant needs exactly one value of every product option".to_string(),
+        ));
+    }
+
+    if ids.is_empty() {
+        return Ok(ids);
+    }
+
+    let duplicate = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT v.sku FROM product_variants v
+        WHERE v.product_id = $1 AND v.id <> COALESCE($3, 0)
+          AND ARRAY(
+              SELECT option_value_id FROM product_variant_values
+              WHERE variant_id = v.id ORDER BY option_value_id
+          ) = $2
+        LIMIT 1
+        "#,
+    )
+    .bind(product_id)
+    .bind(&ids)
+    .bind(variant_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some(sku) = duplicate {
+        return Err(AppError::BadRequest(format!(
+            "Variant {} already has this combination of options",
+            sku
+        )));
+    }
+
+    Ok(ids)
+}
+
+async fn set_variant_values(
+    conn: &mut PgConnection,
+    variant_id: i32,
+    option_value_ids: &[i32],
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO product_variant_values (variant_id, option_value_id)
+        SELECT $1, UNNEST($2::INTEGER[])
+        "#,
+    )
+    .bind(variant_id)
+    .bind(option_value_ids)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// Stock entered on the variant form is recorded as a stock count
+async fn set_variant_stock(
+    conn: &mut PgConnection,
+    product_id: i32,
+    variant_id: i32,
+    stock_quantity: i32,
+    admin_id: i32,
+) -> Result<(), AppError> {
+    inventory::move_stock(
+        conn,
+        StockMovement {
+            product_id,
+            variant_id: Some(variant_id),
+            update: StockUpdate::To(stock_quantity),
+            reason: MovementReason::StockCount,
+            order_id: None,
+            order_item_id: None,
+            admin_id: Some(admin_id),
+            note: None,
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn required_text(value: &str, field: &str) -> Result<String, AppError> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Err(AppError::BadRequest(format!("{} is required", field)));
+    }
+
+    Ok(trimmed.to_string())
+}
+
+// Trims values and drops duplicates, keeping the first occurrence's position
+fn normalize_values(values: &[String]) -> Result<Vec<String>, AppError> {
+    let mut seen = HashSet::new();
+    let normalized: Vec<String> = values
+        .iter()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty() && seen.insert(v.clone()))
+        .collect();
+
+    if normalized.is_empty() {
+        return Err(AppError::BadRequest(
+            "An option needs at least one value".to_string(),
+        ));
+    }
+
+    Ok(normalized)
+}
+
+fn validate_price(price: Option<Money>) -> Result<(), AppError> {
+    if price.is_some_and(|p| p < Money::ZERO) {
+        return Err(AppError::BadRequest("Price cannot be negative".to_string()));
+    }
+
+    Ok(())
+}
+
+fn validate_stock(stock_quantity: i32) -> Result<(), AppError> {
+    if stock_quantity < 0 {
+        return Err(AppError::BadRequest(
+            "Stock quantity cannot be negative".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn map_option_conflict(err: sqlx::Error) -> AppError {
+    match err.as_database_error().and_then(|e| e.code()) {
+        Some(code) if code == "23505" => {
+            AppError::BadRequest("The product already has an option with this name".to_string())
+        }
+        _ => AppError::DatabaseError(err),
+    }
+}
+
+fn map_sku_conflict(err: sqlx::Error) -> AppError {
+    match err.as_database_error().and_then(|e| e.code()) {
+        Some(code) if code == "23505" => {
+            AppError::BadRequest("A variant with this SKU already exists".to_string())
+        }
+        _ => AppError::DatabaseError(err),
+    }
+}
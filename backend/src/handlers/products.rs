@@ -1,44 +1,204 @@
 use axum::{
-    extract::{Multipart, Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Extension, Json,
 };
-use sqlx::PgPool;
-use std::path::PathBuf;
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder};
+use std::collections::HashMap;
 
 use crate::{
     auth::Claims,
     error::AppError,
-    models::{CreateProductRequest, Product, UpdateProductRequest},
+    handlers::{
+        product_images::{fetch_images_for, remove_image_files},
+        product_variants::{fetch_options_for, fetch_variants_for},
+    },
+    inventory::{self, MovementReason, StockMovement, StockUpdate},
+    models::{
+        CreateProductRequest, PaginatedResponse, Product, ProductDetail, ProductListQuery,
+        ProductLookupResponse, ProductSearchQuery, ProductSearchResult, ProductSort,
+        UpdateProductRequest,
+    },
+    reservations, slug,
+    storage::SharedStorage,
+    tax,
 };
 
-// Public endpoints
+const DEFAULT_PAGE_SIZE: i64 = 24;
+const MAX_PAGE_SIZE: i64 = 100;
+
+// Minimum trigram word similarity for the typo-tolerant fallback, set as
+// pg_trgm.word_similarity_threshold for the `<%` operator
+const FUZZY_MATCH_THRESHOLD: &str = "0.4";
 
-pub async fn list_products(State(pool): State<PgPool>) -> Result<Json<Vec<Product>>, AppError> {
-    let products = sqlx::query_as::<_, Product>(
-        "SELECT * FROM products WHERE is_active = true ORDER BY created_at DESC",
+// Shared by the search and count queries. $1/$2 are prefix tsqueries of the
+// raw and transliterated text, $3/$4 the plain text for trigram matching.
+// The `<%` operator form is what the trigram indexes on name and slug serve.
+const SEARCH_MATCH_SQL: &str = r#"
+    WITH query AS (
+        SELECT to_tsquery('bulgarian', $1) || to_tsquery('bulgarian', $2) AS tsq
     )
-    .fetch_all(&pool)
-    .await?;
+    SELECT {columns}
+    FROM products p, query
+    WHERE p.is_active = true
+      AND (
+          p.search_vector @@ query.tsq
+          OR $3 <% p.name
+          OR $4 <% p.slug
+      )
+"#;
+
+// Public endpoints
+
+pub async fn list_products(
+    State(pool): State<PgPool>,
+    Query(query): Query<ProductListQuery>,
+) -> Result<Json<PaginatedResponse<ProductDetail>>, AppError> {
+    let page = fetch_product_page(&pool, &query, true, None).await?;
+
+    Ok(Json(page))
+}
+
+pub async fn search_products(
+    State(pool): State<PgPool>,
+    Query(query): Query<ProductSearchQuery>,
+) -> Result<Json<PaginatedResponse<ProductSearchResult>>, AppError> {
+    let text = query.q.trim();
+    if text.chars().count() < 2 {
+        return Err(AppError::BadRequest(
+            "Search query must be at least 2 characters".to_string(),
+        ));
+    }
+
+    let latin = slug::slugify(text).replace('-', " ");
+    let tsquery = prefix_tsquery(text)
+        .ok_or_else(|| AppError::BadRequest("Search query has no searchable words".to_string()))?;
+    // Transliteration can come out empty (e.g. punctuation only); reuse the original
+    let latin_tsquery = prefix_tsquery(&latin).unwrap_or_else(|| tsquery.clone());
+
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT set_config('pg_trgm.word_similarity_threshold', $1, true)")
+        .bind(FUZZY_MATCH_THRESHOLD)
+        .execute(&mut *tx)
+        .await?;
+
+    let total = sqlx::query_scalar::<_, i64>(&SEARCH_MATCH_SQL.replace("{columns}", "COUNT(*)"))
+        .bind(&tsquery)
+        .bind(&latin_tsquery)
+        .bind(text)
+        .bind(&latin)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let columns = r#"
+        p.*,
+        (
+            ts_rank(p.search_vector, query.tsq)
+            + 0.5 * GREATEST(word_similarity($3, p.name), word_similarity($4, p.slug))
+        )::REAL AS rank,
+        ts_headline('bulgarian', p.name, query.tsq,
+            'StartSel=<mark>, StopSel=</mark>, HighlightAll=true') AS name_highlight,
+        ts_headline('bulgarian', p.description, query.tsq,
+            'StartSel=<mark>, StopSel=</mark>, MaxWords=30, MinWords=12, MaxFragments=2, FragmentDelimiter=" … "') AS snippet
+    "#;
+    let sql = format!(
+        "{} ORDER BY rank DESC, p.id LIMIT $5 OFFSET $6",
+        SEARCH_MATCH_SQL.replace("{columns}", columns)
+    );
+
+    let mut items = sqlx::query_as::<_, ProductSearchResult>(&sql)
+        .bind(&tsquery)
+        .bind(&latin_tsquery)
+        .bind(text)
+        .bind(&latin)
+        .bind(limit)
+        .bind((page - 1).saturating_mul(limit))
+        .fetch_all(&mut *tx)
+        .await?;
+
+    tax::fill_product_tax(&mut tx, items.iter_mut().map(|item| &mut item.product)).await?;
+    tx.commit().await?;
 
-    Ok(Json(products))
+    Ok(Json(PaginatedResponse {
+        items,
+        total,
+        page,
+        limit,
+        total_pages: (total + limit - 1) / limit,
+    }))
 }
 
+// Accepts the current slug, a previous slug or the numeric id
 pub async fn get_product(
     State(pool): State<PgPool>,
-    Path(id): Path<i32>,
-) -> Result<Json<Product>, AppError> {
-    let product = sqlx::query_as::<_, Product>(
-        "SELECT * FROM products WHERE id = $1 AND is_active = true",
+    Path(key): Path<String>,
+) -> Result<Json<ProductLookupResponse>, AppError> {
+    let by_slug =
+        sqlx::query_as::<_, Product>("SELECT * FROM products WHERE slug = $1 AND is_active = true")
+            .bind(&key)
+            .fetch_optional(&pool)
+            .await?;
+
+    let (product, moved) = match (by_slug, key.parse::<i32>()) {
+        (Some(product), _) => (product, false),
+        (None, Ok(id)) => {
+            let product = sqlx::query_as::<_, Product>(
+                "SELECT * FROM products WHERE id = $1 AND is_active = true",
+            )
+            .bind(id)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+            (product, false)
+        }
+        (None, Err(_)) => {
+            let product = sqlx::query_as::<_, Product>(
+                r#"
+                SELECT p.* FROM products p
+                JOIN product_slug_history h ON h.product_id = p.id
+                WHERE h.slug = $1 AND p.is_active = true
+                "#,
+            )
+            .bind(&key)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+            (product, true)
+        }
+    };
+
+    let product = with_details(&pool, vec![product], true)
+        .await?
+        .pop()
+        .expect("one product in, one product out");
+
+    Ok(Json(ProductLookupResponse { product, moved }))
+}
+
+pub async fn list_category_products(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+    Query(query): Query<ProductListQuery>,
+) -> Result<Json<PaginatedResponse<ProductDetail>>, AppError> {
+    let category_id = sqlx::query_scalar::<_, i32>(
+        "SELECT id FROM categories WHERE slug = $1 AND is_active = true",
     )
-    .bind(id)
+    .bind(&slug)
     .fetch_optional(&pool)
     .await?
-    .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+    .ok_or_else(|| AppError::NotFound("Category not found".to_string()))?;
 
-    Ok(Json(product))
+    let page = fetch_product_page(&pool, &query, true, Some(category_id)).await?;
+
+    Ok(Json(page))
 }
 
 // Admin endpoints (require authentication)
@@ -46,42 +206,80 @@ pub async fn get_product(
 pub async fn list_all_products(
     State(pool): State<PgPool>,
     Extension(_claims): Extension<Claims>,
-) -> Result<Json<Vec<Product>>, AppError> {
-    let products = sqlx::query_as::<_, Product>("SELECT * FROM products ORDER BY created_at DESC")
-        .fetch_all(&pool)
-        .await?;
+    Query(query): Query<ProductListQuery>,
+) -> Result<Json<PaginatedResponse<ProductDetail>>, AppError> {
+    let page = fetch_product_page(&pool, &query, false, None).await?;
 
-    Ok(Json(products))
+    Ok(Json(page))
 }
 
 pub async fn create_product(
     State(pool): State<PgPool>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateProductRequest>,
 ) -> Result<(StatusCode, Json<Product>), AppError> {
-    let product = sqlx::query_as::<_, Product>(
+    validate_weight(payload.weight_grams)?;
+
+    let mut tx = pool.begin().await?;
+
+    let product_slug = match &payload.slug {
+        Some(requested) => claim_slug(&mut tx, requested, None).await?,
+        None => unique_slug(&mut tx, &slug::slugify(&payload.name), None).await?,
+    };
+
+    let product_id = sqlx::query_scalar::<_, i32>(
         r#"
-        INSERT INTO products (name, description, price, stock_quantity)
-        VALUES ($1, $2, $3, $4)
-        RETURNING *
+        INSERT INTO products (
+            name, slug, description, price, stock_quantity, weight_grams, tax_class
+        ) VALUES ($1, $2, $3, $4, 0, $5, $6)
+        RETURNING id
         "#,
     )
     .bind(&payload.name)
+    .bind(&product_slug)
     .bind(&payload.description)
     .bind(payload.price)
-    .bind(payload.stock_quantity)
-    .fetch_one(&pool)
+    .bind(payload.weight_grams.unwrap_or(0))
+    .bind(payload.tax_class.as_deref().unwrap_or(tax::STANDARD_CLASS))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_tax_class_error)?;
+
+    // Opening stock is recorded in the inventory ledger
+    inventory::move_stock(
+        &mut tx,
+        StockMovement {
+            product_id,
+            variant_id: None,
+            update: StockUpdate::To(payload.stock_quantity),
+            reason: MovementReason::StockCount,
+            order_id: None,
+            order_item_id: None,
+            admin_id: Some(claims.admin_id),
+            note: None,
+        },
+    )
     .await?;
 
+    let mut product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    tax::fill_product_tax(&mut tx, [&mut product]).await?;
+
+    tx.commit().await?;
+
     Ok((StatusCode::CREATED, Json(product)))
 }
 
 pub async fn update_product(
     State(pool): State<PgPool>,
     Path(id): Path<i32>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
     Json(payload): Json<UpdateProductRequest>,
 ) -> Result<Json<Product>, AppError> {
+    validate_weight(payload.weight_grams)?;
+
     // First check if product exists
     let existing = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
         .bind(id)
@@ -89,34 +287,99 @@ pub async fn update_product(
         .await?
         .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
 
+    let mut tx = pool.begin().await?;
+
+    // An explicit slug wins; otherwise a rename regenerates it from the new name
+    let new_slug = match (&payload.slug, &payload.name) {
+        (Some(requested), _) if *requested != existing.slug => {
+            Some(claim_slug(&mut tx, requested, Some(id)).await?)
+        }
+        (None, Some(name)) if *name != existing.name => {
+            let generated = unique_slug(&mut tx, &slug::slugify(name), Some(id)).await?;
+            (generated != existing.slug).then_some(generated)
+        }
+        _ => None,
+    };
+
+    // Keep the old slug resolving to this product
+    if let Some(new_slug) = &new_slug {
+        sqlx::query("DELETE FROM product_slug_history WHERE slug = $1")
+            .bind(new_slug)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO product_slug_history (slug, product_id) VALUES ($1, $2)
+            ON CONFLICT (slug) DO UPDATE SET product_id = EXCLUDED.product_id, created_at = NOW()
+            "#,
+        )
+        .bind(&existing.slug)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // Stock is set through the inventory ledger as a stock count
+    if let Some(stock_quantity) = payload.stock_quantity {
+        inventory::move_stock(
+            &mut tx,
+            StockMovement {
+                product_id: id,
+                variant_id: None,
+                update: StockUpdate::To(stock_quantity),
+                reason: MovementReason::StockCount,
+                order_id: None,
+                order_item_id: None,
+                admin_id: Some(claims.admin_id),
+                note: None,
+            },
+        )
+        .await?;
+    }
+
     // Build dynamic update query
     let mut query = String::from("UPDATE products SET ");
     let mut updates = Vec::new();
     let mut param_count = 1;
 
-    if let Some(name) = &payload.name {
+    if payload.name.is_some() {
         updates.push(format!("name = ${}", param_count));
         param_count += 1;
     }
-    if let Some(description) = &payload.description {
+    if new_slug.is_some() {
+        updates.push(format!("slug = ${}", param_count));
+        param_count += 1;
+    }
+    if payload.description.is_some() {
         updates.push(format!("description = ${}", param_count));
         param_count += 1;
     }
-    if let Some(price) = &payload.price {
+    if payload.price.is_some() {
         updates.push(format!("price = ${}", param_count));
         param_count += 1;
     }
-    if let Some(stock_quantity) = &payload.stock_quantity {
-        updates.push(format!("stock_quantity = ${}", param_count));
+    if payload.is_active.is_some() {
+        updates.push(format!("is_active = ${}", param_count));
         param_count += 1;
     }
-    if let Some(is_active) = &payload.is_active {
-        updates.push(format!("is_active = ${}", param_count));
+    if payload.weight_grams.is_some() {
+        updates.push(format!("weight_grams = ${}", param_count));
+        param_count += 1;
+    }
+    if payload.tax_class.is_some() {
+        updates.push(format!("tax_class = ${}", param_count));
         param_count += 1;
     }
 
     if updates.is_empty() {
-        return Ok(Json(existing));
+        let mut product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tax::fill_product_tax(&mut tx, [&mut product]).await?;
+        tx.commit().await?;
+        return Ok(Json(product));
     }
 
     query.push_str(&updates.join(", "));
@@ -127,31 +390,46 @@ pub async fn update_product(
     if let Some(name) = &payload.name {
         query_builder = query_builder.bind(name);
     }
+    if let Some(new_slug) = &new_slug {
+        query_builder = query_builder.bind(new_slug);
+    }
     if let Some(description) = &payload.description {
         query_builder = query_builder.bind(description);
     }
     if let Some(price) = &payload.price {
         query_builder = query_builder.bind(price);
     }
-    if let Some(stock_quantity) = &payload.stock_quantity {
-        query_builder = query_builder.bind(stock_quantity);
-    }
     if let Some(is_active) = &payload.is_active {
         query_builder = query_builder.bind(is_active);
     }
+    if let Some(weight_grams) = &payload.weight_grams {
+        query_builder = query_builder.bind(weight_grams);
+    }
+    if let Some(tax_class) = &payload.tax_class {
+        query_builder = query_builder.bind(tax_class);
+    }
 
     query_builder = query_builder.bind(id);
 
-    let product = query_builder.fetch_one(&pool).await?;
+    let mut product = query_builder
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_tax_class_error)?;
+    tax::fill_product_tax(&mut tx, [&mut product]).await?;
+
+    tx.commit().await?;
 
     Ok(Json(product))
 }
 
 pub async fn delete_product(
     State(pool): State<PgPool>,
+    State(storage): State<SharedStorage>,
     Path(id): Path<i32>,
     Extension(_claims): Extension<Claims>,
 ) -> Result<StatusCode, AppError> {
+    let images = fetch_images_for(&pool, &[id]).await?;
+
     let result = sqlx::query("DELETE FROM products WHERE id = $1")
         .bind(id)
         .execute(&pool)
@@ -161,92 +439,290 @@ pub async fn delete_product(
         return Err(AppError::NotFound("Product not found".to_string()));
     }
 
+    for image in &images {
+        remove_image_files(storage.as_ref(), image).await;
+    }
+
     Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn upload_product_image(
-    State(pool): State<PgPool>,
-    Path(id): Path<i32>,
-    Extension(_claims): Extension<Claims>,
-    mut multipart: Multipart,
-) -> Result<Json<Product>, AppError> {
-    // Check if product exists
-    let _product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
-        .bind(id)
-        .fetch_optional(&pool)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+// Helpers
+
+// Attaches galleries, options, variants and the VAT in the price; inactive
+// variants are only included for the admin
+async fn with_details(
+    pool: &PgPool,
+    mut products: Vec<Product>,
+    active_only: bool,
+) -> Result<Vec<ProductDetail>, AppError> {
+    let ids: Vec<i32> = products.iter().map(|p| p.id).collect();
+
+    let mut conn = pool.acquire().await?;
+    tax::fill_product_tax(&mut conn, products.iter_mut()).await?;
+    drop(conn);
+
+    let mut images_by_product: HashMap<i32, Vec<_>> = HashMap::new();
+    for image in fetch_images_for(pool, &ids).await? {
+        images_by_product
+            .entry(image.product_id)
+            .or_default()
+            .push(image);
+    }
 
-    // Get upload directory from env
-    let upload_dir = std::env::var("UPLOAD_DIR").unwrap_or_else(|_| "./uploads".to_string());
+    let mut options_by_product: HashMap<i32, Vec<_>> = HashMap::new();
+    for option in fetch_options_for(pool, &ids).await? {
+        options_by_product
+            .entry(option.option.product_id)
+            .or_default()
+            .push(option);
+    }
 
-    // Ensure upload directory exists
-    fs::create_dir_all(&upload_dir).await.map_err(|e| {
-        tracing::error!("Failed to create upload directory: {:?}", e);
-        AppError::InternalServerError("Failed to create upload directory".to_string())
-    })?;
+    let mut variants_by_product: HashMap<i32, Vec<_>> = HashMap::new();
+    for variant in fetch_variants_for(pool, &ids, active_only).await? {
+        variants_by_product
+            .entry(variant.variant.product_id)
+            .or_default()
+            .push(variant);
+    }
 
-    // Process multipart form
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
-        tracing::error!("Multipart error: {:?}", e);
-        AppError::BadRequest("Invalid file upload".to_string())
-    })? {
-        let name = field.name().unwrap_or("").to_string();
+    let held_by_product = reservations::held_by_product(pool, &ids).await?;
+
+    Ok(products
+        .into_iter()
+        .map(|product| ProductDetail {
+            available_quantity: (product.stock_quantity
+                - held_by_product.get(&product.id).copied().unwrap_or(0))
+            .max(0),
+            images: images_by_product.remove(&product.id).unwrap_or_default(),
+            options: options_by_product.remove(&product.id).unwrap_or_default(),
+            variants: variants_by_product.remove(&product.id).unwrap_or_default(),
+            product,
+        })
+        .collect())
+}
 
-        if name != "image" {
-            continue;
+async fn fetch_product_page(
+    pool: &PgPool,
+    query: &ProductListQuery,
+    active_only: bool,
+    category_id: Option<i32>,
+) -> Result<PaginatedResponse<ProductDetail>, AppError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    if let (Some(min), Some(max)) = (query.min_price, query.max_price) {
+        if min > max {
+            return Err(AppError::BadRequest(
+                "min_price cannot be greater than max_price".to_string(),
+            ));
         }
+    }
 
-        let filename = field
-            .file_name()
-            .ok_or_else(|| AppError::BadRequest("Missing filename".to_string()))?
-            .to_string();
+    let mut count_query =
+        QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM products p WHERE true");
+    push_product_filters(&mut count_query, query, active_only, category_id);
+    let total: i64 = count_query.build_query_scalar().fetch_one(pool).await?;
+
+    let mut items_query = QueryBuilder::<Postgres>::new("SELECT p.* FROM products p WHERE true");
+    push_product_filters(&mut items_query, query, active_only, category_id);
+    items_query.push(match query.sort.unwrap_or_default() {
+        ProductSort::Newest => " ORDER BY p.created_at DESC, p.id DESC",
+        ProductSort::PriceAsc => " ORDER BY p.price ASC, p.id ASC",
+        ProductSort::PriceDesc => " ORDER BY p.price DESC, p.id DESC",
+        ProductSort::Name => " ORDER BY p.name ASC, p.id ASC",
+    });
+    items_query.push(" LIMIT ").push_bind(limit);
+    items_query
+        .push(" OFFSET ")
+        .push_bind((page - 1).saturating_mul(limit));
+
+    let products = items_query
+        .build_query_as::<Product>()
+        .fetch_all(pool)
+        .await?;
+    let items = with_details(pool, products, active_only).await?;
+
+    Ok(PaginatedResponse {
+        items,
+        total,
+        page,
+        limit,
+        total_pages: (total + limit - 1) / limit,
+    })
+}
 
-        // Validate file extension
-        let ext = PathBuf::from(&filename)
-            .extension()
-            .and_then(|e| e.to_str())
-            .map(|e| e.to_lowercase())
-            .ok_or_else(|| AppError::BadRequest("Invalid file extension".to_string()))?;
+// Turns free text into a prefix tsquery: "лунен за" -> "лунен:* & за:*"
+fn prefix_tsquery(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("{}:*", term.to_lowercase()))
+        .collect();
 
-        if !["jpg", "jpeg", "png", "webp"].contains(&ext.as_str()) {
-            return Err(AppError::BadRequest(
-                "Only jpg, jpeg, png, and webp images are allowed".to_string(),
-            ));
-        }
+    (!terms.is_empty()).then(|| terms.join(" & "))
+}
 
-        // Generate unique filename
-        let unique_filename = format!("product-{}-{}.{}", id, uuid::Uuid::new_v4(), ext);
-        let file_path = PathBuf::from(&upload_dir).join(&unique_filename);
-
-        // Save file
-        let data = field.bytes().await.map_err(|e| {
-            tracing::error!("Failed to read file data: {:?}", e);
-            AppError::BadRequest("Failed to read file".to_string())
-        })?;
-
-        let mut file = fs::File::create(&file_path).await.map_err(|e| {
-            tracing::error!("Failed to create file: {:?}", e);
-            AppError::InternalServerError("Failed to save file".to_string())
-        })?;
-
-        file.write_all(&data).await.map_err(|e| {
-            tracing::error!("Failed to write file: {:?}", e);
-            AppError::InternalServerError("Failed to save file".to_string())
-        })?;
-
-        // Update product with image URL
-        let image_url = format!("/uploads/{}", unique_filename);
-        let product = sqlx::query_as::<_, Product>(
-            "UPDATE products SET image_url = $1 WHERE id = $2 RETURNING *",
-        )
-        .bind(&image_url)
-        .bind(id)
-        .fetch_one(&pool)
+fn push_product_filters(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    query: &ProductListQuery,
+    active_only: bool,
+    category_id: Option<i32>,
+) {
+    if active_only {
+        builder.push(" AND p.is_active = true");
+    } else if let Some(is_active) = query.is_active {
+        builder.push(" AND p.is_active = ").push_bind(is_active);
+    }
+
+    if let Some(min_price) = query.min_price {
+        builder.push(" AND p.price >= ").push_bind(min_price);
+    }
+    if let Some(max_price) = query.max_price {
+        builder.push(" AND p.price <= ").push_bind(max_price);
+    }
+    // Stock held by active reservations is not for sale
+    if query.in_stock == Some(true) {
+        builder.push(
+            " AND p.stock_quantity > (SELECT COALESCE(SUM(h.quantity), 0) FROM active_stock_holds h WHERE h.product_id = p.id)",
+        );
+    }
+
+    // Products of the category and all of its active subcategories
+    if let Some(category_id) = category_id {
+        builder
+            .push(
+                r#"
+                AND p.id IN (
+                    WITH RECURSIVE subtree AS (
+                        SELECT id FROM categories WHERE id = "#,
+            )
+            .push_bind(category_id)
+            .push(
+                r#"
+                        UNION ALL
+                        SELECT c.id FROM categories c
+                        JOIN subtree s ON c.parent_id = s.id
+                        WHERE c.is_active = true
+                    )
+                    SELECT pc.product_id FROM product_categories pc
+                    WHERE pc.category_id IN (SELECT id FROM subtree)
+                )
+                "#,
+            );
+    }
+}
+
+// Appends -2, -3, ... to `base` until it collides with neither a live slug nor
+// another product's previous slug
+async fn unique_slug(
+    conn: &mut PgConnection,
+    base: &str,
+    product_id: Option<i32>,
+) -> Result<String, AppError> {
+    let base = if base.is_empty() { "product" } else { base };
+
+    let taken = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT slug FROM products
+        WHERE (slug = $1 OR slug LIKE $1 || '-%') AND ($2::INTEGER IS NULL OR id <> $2)
+        UNION
+        SELECT slug FROM product_slug_history
+        WHERE (slug = $1 OR slug LIKE $1 || '-%') AND ($2::INTEGER IS NULL OR product_id <> $2)
+        "#,
+    )
+    .bind(base)
+    .bind(product_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if !taken.iter().any(|s| s == base) {
+        return Ok(base.to_string());
+    }
+
+    let candidate = (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("unbounded range always yields a free slug");
+
+    Ok(candidate)
+}
+
+// Validates an admin-chosen slug. It may take over another product's previous
+// slug, but never a live one.
+async fn claim_slug(
+    conn: &mut PgConnection,
+    requested: &str,
+    product_id: Option<i32>,
+) -> Result<String, AppError> {
+    slug::validate(requested)?;
+
+    let in_use = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM products WHERE slug = $1 AND ($2::INTEGER IS NULL OR id <> $2))",
+    )
+    .bind(requested)
+    .bind(product_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if in_use {
+        return Err(AppError::BadRequest(
+            "A product with this slug already exists".to_string(),
+        ));
+    }
+
+    sqlx::query("DELETE FROM product_slug_history WHERE slug = $1")
+        .bind(requested)
+        .execute(&mut *conn)
         .await?;
 
-        return Ok(Json(product));
+    Ok(requested.to_string())
+}
+
+fn validate_weight(weight_grams: Option<i32>) -> Result<(), AppError> {
+    if weight_grams.is_some_and(|weight| weight < 0) {
+        return Err(AppError::BadRequest(
+            "Weight cannot be negative".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn map_tax_class_error(err: sqlx::Error) -> AppError {
+    match err.as_database_error().and_then(|e| e.code()) {
+        Some(code) if code == "23503" => AppError::BadRequest("Unknown tax class".to_string()),
+        _ => AppError::DatabaseError(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_tsquery_matches_every_word_by_prefix() {
+        assert_eq!(
+            prefix_tsquery("Лунен заек").as_deref(),
+            Some("лунен:* & заек:*")
+        );
+        assert_eq!(prefix_tsquery("vase").as_deref(), Some("vase:*"));
+    }
+
+    #[test]
+    fn prefix_tsquery_drops_tsquery_operators() {
+        assert_eq!(
+            prefix_tsquery("mug & (blue | !red):*").as_deref(),
+            Some("mug:* & blue:* & red:*")
+        );
+        assert_eq!(prefix_tsquery("it's").as_deref(), Some("it:* & s:*"));
     }
 
-    Err(AppError::BadRequest("No image file provided".to_string()))
+    #[test]
+    fn prefix_tsquery_needs_a_word() {
+        assert_eq!(prefix_tsquery(""), None);
+        assert_eq!(prefix_tsquery(" -&|!' "), None);
+    }
 }
@@ -0,0 +1,464 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    models::{
+        CreatePromotionRequest, Money, OrderItemRequest, Promotion, PromotionDetail,
+        PromotionQuote, PromotionQuoteItem, UpdatePromotionRequest, ValidatePromotionRequest,
+    },
+    promotions::{self, CartLine, PromotionKind},
+};
+
+const PROMOTION_DETAIL_SQL: &str = r#"
+    SELECT p.*,
+        ARRAY(
+            SELECT product_id FROM promotion_products WHERE promotion_id = p.id ORDER BY product_id
+        ) AS product_ids,
+        ARRAY(
+            SELECT category_id FROM promotion_categories WHERE promotion_id = p.id ORDER BY category_id
+        ) AS category_ids,
+        (
+            SELECT COUNT(*) FROM orders o WHERE o.promotion_id = p.id AND o.status <> 'cancelled'
+        ) AS times_used
+    FROM promotions p
+"#;
+
+// Public endpoints
+
+// Checks a discount code against the cart and shows what it would take off.
+// Nothing is held; the order works the discount out again when it is placed.
+pub async fn validate_promotion(
+    State(pool): State<PgPool>,
+    Json(payload): Json<ValidatePromotionRequest>,
+) -> Result<Json<PromotionQuote>, AppError> {
+    if payload.items.is_empty() {
+        return Err(AppError::BadRequest(
+            "Cart must contain at least one item".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let lines: Vec<CartLine> = price_cart(&mut tx, &payload.items)
+        .await?
+        .into_iter()
+        .map(|item| item.line)
+        .collect();
+
+    let customer_email = payload
+        .customer_email
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| !e.is_empty());
+    let applied = promotions::apply(&mut tx, &payload.code, customer_email, &lines).await?;
+
+    let subtotal_amount: Money = lines.iter().map(|line| line.subtotal).sum();
+    let items = payload
+        .items
+        .iter()
+        .zip(&lines)
+        .zip(&applied.line_discounts)
+        .map(|((item, line), discount)| PromotionQuoteItem {
+            product_id: item.product_id,
+            variant_id: item.variant_id,
+            quantity: item.quantity,
+            subtotal: line.subtotal,
+            discount_amount: *discount,
+        })
+        .collect();
+
+    Ok(Json(PromotionQuote {
+        kind: PromotionKind::parse(&applied.promotion.kind)?,
+        code: applied.promotion.code,
+        description: applied.promotion.description,
+        subtotal_amount,
+        discount_amount: applied.discount_amount,
+        total_amount: subtotal_amount - applied.discount_amount,
+        free_shipping: applied.free_shipping,
+        items,
+    }))
+}
+
+// Admin endpoints (require authentication)
+
+pub async fn list_promotions(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<PromotionDetail>>, AppError> {
+    let promotions = sqlx::query_as::<_, PromotionDetail>(&format!(
+        "{} ORDER BY p.created_at DESC, p.id DESC",
+        PROMOTION_DETAIL_SQL
+    ))
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(promotions))
+}
+
+pub async fn get_promotion(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<PromotionDetail>, AppError> {
+    Ok(Json(fetch_promotion(&pool, id).await?))
+}
+
+pub async fn create_promotion(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<CreatePromotionRequest>,
+) -> Result<(StatusCode, Json<PromotionDetail>), AppError> {
+    let fields = PromotionFields {
+        code: payload.code,
+        description: payload.description,
+        kind: payload.kind,
+        value: payload.value.unwrap_or_default(),
+        starts_at: payload.starts_at,
+        ends_at: payload.ends_at,
+        min_order_amount: payload.min_order_amount,
+        usage_limit: payload.usage_limit,
+        usage_limit_per_customer: payload.usage_limit_per_customer,
+        is_active: payload.is_active.unwrap_or(true),
+    }
+    .validated()?;
+
+    let mut tx = pool.begin().await?;
+
+    let id = sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO promotions (
+            code, description, kind, value, starts_at, ends_at, min_order_amount,
+            usage_limit, usage_limit_per_customer, is_active
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING id
+        "#,
+    )
+    .bind(&fields.code)
+    .bind(&fields.description)
+    .bind(fields.kind.as_str())
+    .bind(fields.value)
+    .bind(fields.starts_at)
+    .bind(fields.ends_at)
+    .bind(fields.min_order_amount)
+    .bind(fields.usage_limit)
+    .bind(fields.usage_limit_per_customer)
+    .bind(fields.is_active)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_code_conflict)?;
+
+    set_restrictions(
+        &mut tx,
+        id,
+        payload.product_ids.as_deref(),
+        payload.category_ids.as_deref(),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(fetch_promotion(&pool, id).await?)))
+}
+
+pub async fn update_promotion(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<UpdatePromotionRequest>,
+) -> Result<Json<PromotionDetail>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let current =
+        sqlx::query_as::<_, Promotion>("SELECT * FROM promotions WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Promotion not found".to_string()))?;
+
+    let fields = PromotionFields {
+        code: payload.code.unwrap_or(current.code),
+        description: payload.description.unwrap_or(current.description),
+        kind: match payload.kind {
+            Some(kind) => kind,
+            None => PromotionKind::parse(&current.kind)?,
+        },
+        value: payload.value.unwrap_or(current.value),
+        starts_at: payload.starts_at.unwrap_or(current.starts_at),
+        ends_at: payload.ends_at.unwrap_or(current.ends_at),
+        min_order_amount: payload.min_order_amount.unwrap_or(current.min_order_amount),
+        usage_limit: payload.usage_limit.unwrap_or(current.usage_limit),
+        usage_limit_per_customer: payload
+            .usage_limit_per_customer
+            .unwrap_or(current.usage_limit_per_customer),
+        is_active: payload.is_active.unwrap_or(current.is_active),
+    }
+    .validated()?;
+
+    sqlx::query(
+        r#"
+        UPDATE promotions SET
+            code = $1, description = $2, kind = $3, value = $4, starts_at = $5, ends_at = $6,
+            min_order_amount = $7, usage_limit = $8, usage_limit_per_customer = $9,
+            is_active = $10
+        WHERE id = $11
+        "#,
+    )
+    .bind(&fields.code)
+    .bind(&fields.description)
+    .bind(fields.kind.as_str())
+    .bind(fields.value)
+    .bind(fields.starts_at)
+    .bind(fields.ends_at)
+    .bind(fields.min_order_amount)
+    .bind(fields.usage_limit)
+    .bind(fields.usage_limit_per_customer)
+    .bind(fields.is_active)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(map_code_conflict)?;
+
+    set_restrictions(
+        &mut tx,
+        id,
+        payload.product_ids.as_deref(),
+        payload.category_ids.as_deref(),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(fetch_promotion(&pool, id).await?))
+}
+
+// Orders placed with the promotion keep its code and discount
+pub async fn delete_promotion(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    let deleted = sqlx::query("DELETE FROM promotions WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Promotion not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Helpers
+
+// Cart item priced like the order would, without locking anything
+pub(crate) struct PricedItem {
+    pub line: CartLine,
+    pub weight_grams: i32,
+}
+
+pub(crate) async fn price_cart(
+    conn: &mut PgConnection,
+    items: &[OrderItemRequest],
+) -> Result<Vec<PricedItem>, AppError> {
+    let mut priced = Vec::new();
+    for item in items {
+        if item.quantity <= 0 {
+            return Err(AppError::BadRequest(
+                "Quantity must be positive".to_string(),
+            ));
+        }
+
+        let (unit_price, weight_grams) = sqlx::query_as::<_, (Money, i32)>(
+            r#"
+            SELECT COALESCE(v.price, p.price), p.weight_grams
+            FROM products p
+            LEFT JOIN product_variants v
+                ON v.id = $2 AND v.product_id = p.id AND v.is_active = true
+            WHERE p.id = $1 AND p.is_active = true
+              AND ($2::INTEGER IS NULL OR v.id IS NOT NULL)
+            "#,
+        )
+        .bind(item.product_id)
+        .bind(item.variant_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Product {} not found", item.product_id)))?;
+
+        priced.push(PricedItem {
+            line: CartLine {
+                product_id: item.product_id,
+                subtotal: unit_price * item.quantity,
+            },
+            weight_grams: weight_grams * item.quantity,
+        });
+    }
+
+    Ok(priced)
+}
+
+// Promotion settings, as created or after applying an update
+struct PromotionFields {
+    code: String,
+    description: Option<String>,
+    kind: PromotionKind,
+    value: Decimal,
+    starts_at: Option<NaiveDateTime>,
+    ends_at: Option<NaiveDateTime>,
+    min_order_amount: Option<Money>,
+    usage_limit: Option<i32>,
+    usage_limit_per_customer: Option<i32>,
+    is_active: bool,
+}
+
+impl PromotionFields {
+    fn validated(mut self) -> Result<Self, AppError> {
+        self.code = self.code.trim().to_uppercase();
+        if self.code.is_empty() || self.code.len() > 50 {
+            return Err(AppError::BadRequest(
+                "Code must be between 1 and 50 characters".to_string(),
+            ));
+        }
+        if !self
+            .code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(AppError::BadRequest(
+                "Code may only contain letters, digits, '-' and '_'".to_string(),
+            ));
+        }
+
+        self.description = self
+            .description
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+
+        match self.kind {
+            PromotionKind::Percentage
+                if self.value <= Decimal::ZERO || self.value > Decimal::ONE_HUNDRED =>
+            {
+                return Err(AppError::BadRequest(
+                    "Percentage must be greater than 0 and at most 100".to_string(),
+                ));
+            }
+            PromotionKind::FixedAmount if self.value <= Decimal::ZERO => {
+                return Err(AppError::BadRequest(
+                    "Discount amount must be positive".to_string(),
+                ));
+            }
+            PromotionKind::FreeShipping => self.value = Decimal::ZERO,
+            _ => {}
+        }
+        self.value = self.value.round_dp(2);
+
+        if let (Some(starts_at), Some(ends_at)) = (self.starts_at, self.ends_at) {
+            if starts_at >= ends_at {
+                return Err(AppError::BadRequest(
+                    "Promotion must end after it starts".to_string(),
+                ));
+            }
+        }
+        if self.min_order_amount.is_some_and(|m| m < Money::ZERO) {
+            return Err(AppError::BadRequest(
+                "Minimum order amount cannot be negative".to_string(),
+            ));
+        }
+        if self.usage_limit.is_some_and(|l| l <= 0)
+            || self.usage_limit_per_customer.is_some_and(|l| l <= 0)
+        {
+            return Err(AppError::BadRequest(
+                "Usage limits must be positive".to_string(),
+            ));
+        }
+
+        Ok(self)
+    }
+}
+
+// Replaces the products and categories the promotion is limited to; None
+// leaves that list unchanged
+async fn set_restrictions(
+    conn: &mut PgConnection,
+    promotion_id: i32,
+    product_ids: Option<&[i32]>,
+    category_ids: Option<&[i32]>,
+) -> Result<(), AppError> {
+    if let Some(product_ids) = product_ids {
+        sqlx::query("DELETE FROM promotion_products WHERE promotion_id = $1")
+            .bind(promotion_id)
+            .execute(&mut *conn)
+            .await?;
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO promotion_products (promotion_id, product_id)
+            SELECT $1, id FROM products WHERE id = ANY($2)
+            "#,
+        )
+        .bind(promotion_id)
+        .bind(product_ids)
+        .execute(&mut *conn)
+        .await?;
+
+        if inserted.rows_affected() != distinct_count(product_ids) {
+            return Err(AppError::BadRequest("Unknown product id".to_string()));
+        }
+    }
+
+    if let Some(category_ids) = category_ids {
+        sqlx::query("DELETE FROM promotion_categories WHERE promotion_id = $1")
+            .bind(promotion_id)
+            .execute(&mut *conn)
+            .await?;
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO promotion_categories (promotion_id, category_id)
+            SELECT $1, id FROM categories WHERE id = ANY($2)
+            "#,
+        )
+        .bind(promotion_id)
+        .bind(category_ids)
+        .execute(&mut *conn)
+        .await?;
+
+        if inserted.rows_affected() != distinct_count(category_ids) {
+            return Err(AppError::BadRequest("Unknown category id".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+fn distinct_count(ids: &[i32]) -> u64 {
+    let mut ids = ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+    ids.len() as u64
+}
+
+async fn fetch_promotion(pool: &PgPool, id: i32) -> Result<PromotionDetail, AppError> {
+    sqlx::query_as::<_, PromotionDetail>(&format!("{} WHERE p.id = $1", PROMOTION_DETAIL_SQL))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Promotion not found".to_string()))
+}
+
+fn map_code_conflict(err: sqlx::Error) -> AppError {
+    match err.as_database_error().and_then(|e| e.code()) {
+        Some(code) if code == "23505" => {
+            AppError::BadRequest("A promotion with this code already exists".to_string())
+        }
+        _ => AppError::DatabaseError(err),
+    }
+}
@@ -0,0 +1,217 @@
+use axum::{
+    extract::{ConnectInfo, Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use sqlx::{PgConnection, PgPool};
+use std::{collections::HashMap, net::SocketAddr};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    handlers::orders::{claim_stock, lock_order_line},
+    models::{
+        OrderItemRequest, StockReservation, StockReservationDetail, StockReservationItem,
+        StockReservationRequest,
+    },
+    rate_limit::{self, RateLimiter},
+    reservations,
+};
+
+// Public endpoints
+// The reservation id is only known to the checkout that created it and acts
+// as its key. Creating and changing reservations is rate limited per client,
+// so nobody can hold the shop's stock with a script.
+
+pub async fn create_reservation(
+    State(pool): State<PgPool>,
+    State(limiter): State<RateLimiter>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<StockReservationRequest>,
+) -> Result<(StatusCode, Json<StockReservationDetail>), AppError> {
+    limiter.check(rate_limit::client_ip(peer, &headers))?;
+
+    let mut tx = pool.begin().await?;
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO stock_reservations (id, expires_at)
+        VALUES ($1, NOW() + make_interval(mins => LEAST($2, $3)))
+        "#,
+    )
+    .bind(id)
+    .bind(reservations::ttl_minutes())
+    .bind(reservations::MAX_LIFETIME_MINUTES)
+    .execute(&mut *tx)
+    .await?;
+
+    hold_items(&mut tx, id, &payload.items).await?;
+    let reservation = fetch_reservation(&mut tx, id).await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(reservation)))
+}
+
+pub async fn get_reservation(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<StockReservationDetail>, AppError> {
+    let mut conn = pool.acquire().await?;
+    let reservation = fetch_reservation(&mut conn, id).await?;
+
+    Ok(Json(reservation))
+}
+
+// Replaces the held items with the current cart and restarts the timer, up
+// to the reservation's maximum lifetime. An expired reservation stays
+// expired, the checkout has to reserve again.
+pub async fn update_reservation(
+    State(pool): State<PgPool>,
+    State(limiter): State<RateLimiter>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<StockReservationRequest>,
+) -> Result<Json<StockReservationDetail>, AppError> {
+    limiter.check(rate_limit::client_ip(peer, &headers))?;
+
+    let mut tx = pool.begin().await?;
+
+    lock_open_reservation(&mut tx, id).await?;
+
+    let renewed = sqlx::query(
+        r#"
+        UPDATE stock_reservations
+        SET expires_at = LEAST(
+            NOW() + make_interval(mins => $1),
+            created_at + make_interval(mins => $2)
+        )
+        WHERE id = $3 AND expires_at > NOW()
+        "#,
+    )
+    .bind(reservations::ttl_minutes())
+    .bind(reservations::MAX_LIFETIME_MINUTES)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    if renewed == 0 {
+        return Err(AppError::Conflict("Reservation has expired".to_string()));
+    }
+
+    hold_items(&mut tx, id, &payload.items).await?;
+    let reservation = fetch_reservation(&mut tx, id).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(reservation))
+}
+
+// Gives the held stock back, e.g. when the cart is emptied
+pub async fn delete_reservation(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = pool.begin().await?;
+
+    lock_open_reservation(&mut tx, id).await?;
+
+    sqlx::query("DELETE FROM stock_reservations WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Helpers
+
+// Locks a reservation that has not been turned into an order yet
+pub(crate) async fn lock_open_reservation(
+    conn: &mut PgConnection,
+    id: Uuid,
+) -> Result<StockReservation, AppError> {
+    let reservation = sqlx::query_as::<_, StockReservation>(
+        "SELECT * FROM stock_reservations WHERE id = $1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Reservation not found".to_string()))?;
+
+    if reservation.converted_at.is_some() {
+        return Err(AppError::BadRequest(
+            "Reservation has already been used for an order".to_string(),
+        ));
+    }
+
+    Ok(reservation)
+}
+
+// Holds the requested items for the reservation, replacing what it held before
+async fn hold_items(
+    conn: &mut PgConnection,
+    reservation_id: Uuid,
+    items: &[OrderItemRequest],
+) -> Result<(), AppError> {
+    if items.is_empty() {
+        return Err(AppError::BadRequest(
+            "Reservation must contain at least one item".to_string(),
+        ));
+    }
+    reservations::check_quantities(items)?;
+
+    sqlx::query("DELETE FROM stock_reservation_items WHERE reservation_id = $1")
+        .bind(reservation_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let mut taken_by_earlier_lines: HashMap<(i32, Option<i32>), i32> = HashMap::new();
+
+    for item in items {
+        let line = lock_order_line(conn, item, Some(reservation_id)).await?;
+        claim_stock(&mut taken_by_earlier_lines, &line, item.quantity)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO stock_reservation_items (reservation_id, product_id, variant_id, quantity)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(reservation_id)
+        .bind(line.product.id)
+        .bind(line.variant.as_ref().map(|v| v.variant.id))
+        .bind(item.quantity)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn fetch_reservation(
+    conn: &mut PgConnection,
+    id: Uuid,
+) -> Result<StockReservationDetail, AppError> {
+    let reservation =
+        sqlx::query_as::<_, StockReservation>("SELECT * FROM stock_reservations WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Reservation not found".to_string()))?;
+
+    let items = sqlx::query_as::<_, StockReservationItem>(
+        "SELECT * FROM stock_reservation_items WHERE reservation_id = $1 ORDER BY id",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(StockReservationDetail { reservation, items })
+}
@@ -0,0 +1,253 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Extension, Json,
+};
+use sqlx::{PgConnection, PgPool};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{
+    auth::Claims,
+    couriers::{Couriers, Destination, ShipmentRequest},
+    email::templates,
+    error::AppError,
+    models::{CreateWaybillRequest, Money, Order, Shipment, ShippingMethod},
+    order_status::OrderStatus,
+    payments::PaymentMethod,
+    shipping::{Carrier, ShippingKind},
+    storage::{SharedStorage, PRIVATE_PREFIX},
+};
+
+// How long a signed label link stays valid
+const LABEL_URL_TTL: Duration = Duration::from_secs(300);
+
+// Couriers cap the declared contents, Econt at 100 characters
+const MAX_DESCRIPTION_CHARS: usize = 100;
+
+// Admin endpoints (require authentication)
+
+// Registers the parcel with the carrier of the order's shipping method and
+// keeps the label. The order stays locked while the courier is called, so a
+// double click cannot book two waybills.
+pub async fn create_waybill(
+    State(pool): State<PgPool>,
+    State(storage): State<SharedStorage>,
+    State(couriers): State<Couriers>,
+    Path(order_id): Path<Uuid>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<CreateWaybillRequest>,
+) -> Result<(StatusCode, Json<Shipment>), AppError> {
+    let mut tx = pool.begin().await?;
+
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+
+    let status = OrderStatus::parse(&order.status)?;
+    if !matches!(status, OrderStatus::Pending | OrderStatus::Processing) {
+        return Err(AppError::BadRequest(format!(
+            "Cannot create a waybill for a {} order",
+            status
+        )));
+    }
+
+    let existing = sqlx::query_scalar::<_, String>(
+        "SELECT tracking_number FROM shipments WHERE order_id = $1",
+    )
+    .bind(order_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(tracking_number) = existing {
+        return Err(AppError::Conflict(format!(
+            "Order already has waybill {}",
+            tracking_number
+        )));
+    }
+
+    let method = match order.shipping_method_id {
+        Some(method_id) => {
+            sqlx::query_as::<_, ShippingMethod>("SELECT * FROM shipping_methods WHERE id = $1")
+                .bind(method_id)
+                .fetch_optional(&mut *tx)
+                .await?
+        }
+        None => None,
+    };
+    let not_by_courier = || AppError::BadRequest("Order is not delivered by a courier".to_string());
+    let method = method.ok_or_else(not_by_courier)?;
+    let carrier = Carrier::parse(method.carrier.as_deref().ok_or_else(not_by_courier)?)?;
+
+    let destination = if ShippingKind::parse(&method.kind)? == ShippingKind::CourierOffice {
+        let code = payload
+            .office_code
+            .as_deref()
+            .or(order.courier_office_code.as_deref())
+            .map(str::trim)
+            .filter(|code| !code.is_empty())
+            .ok_or_else(|| {
+                AppError::BadRequest("Choose the carrier office for the waybill".to_string())
+            })?;
+        Destination::Office {
+            code: code.to_string(),
+        }
+    } else {
+        Destination::Address {
+            city: order.delivery_city.clone(),
+            postal_code: order.delivery_postal_code.clone(),
+            address: order.delivery_address.clone(),
+        }
+    };
+    let office_code = match &destination {
+        Destination::Office { code } => Some(code.clone()),
+        Destination::Address { .. } => None,
+    };
+
+    let description = match payload
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+    {
+        Some(description) => description.to_string(),
+        None => contents_description(&mut tx, order_id).await?,
+    };
+
+    let request = ShipmentRequest {
+        reference: templates::order_reference(&order),
+        recipient_name: order.customer_name.clone(),
+        recipient_phone: order.customer_phone.clone(),
+        recipient_email: order.customer_email.clone(),
+        destination,
+        weight_grams: order.shipping_weight_grams,
+        // Card orders are already paid
+        cash_on_delivery: Some(order.total_amount).filter(|amount| {
+            order.payment_method == PaymentMethod::CashOnDelivery.as_str() && *amount > Money::ZERO
+        }),
+        description: description.chars().take(MAX_DESCRIPTION_CHARS).collect(),
+    };
+
+    let waybill = couriers.get(carrier)?.create_waybill(&request).await?;
+
+    // The label names the customer, so it is private and only reached
+    // through get_waybill_label
+    let label_key = format!(
+        "{}waybill-{}-{}.pdf",
+        PRIVATE_PREFIX,
+        carrier.as_str(),
+        Uuid::new_v4().simple()
+    );
+    storage.put(&label_key, waybill.label_pdf).await?;
+
+    let shipment = sqlx::query_as::<_, Shipment>(
+        r#"
+        INSERT INTO shipments (order_id, carrier, tracking_number, label_key)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(order_id)
+    .bind(carrier.as_str())
+    .bind(&waybill.tracking_number)
+    .bind(&label_key)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE orders
+        SET tracking_number = $1, courier_office_code = COALESCE($2, courier_office_code)
+        WHERE id = $3
+        "#,
+    )
+    .bind(&waybill.tracking_number)
+    .bind(office_code)
+    .bind(order_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "Created {} waybill {} for order {}",
+        carrier.as_str(),
+        shipment.tracking_number,
+        order_id
+    );
+
+    Ok((StatusCode::CREATED, Json(shipment)))
+}
+
+pub async fn get_waybill(
+    State(pool): State<PgPool>,
+    Path(order_id): Path<Uuid>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Shipment>, AppError> {
+    let shipment = fetch_shipment(&pool, order_id).await?;
+
+    Ok(Json(shipment))
+}
+
+// Redirects to a short-lived link when the storage can sign one, otherwise
+// streams the PDF
+pub async fn get_waybill_label(
+    State(pool): State<PgPool>,
+    State(storage): State<SharedStorage>,
+    Path(order_id): Path<Uuid>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Response, AppError> {
+    let shipment = fetch_shipment(&pool, order_id).await?;
+
+    if let Some(url) = storage.signed_url(&shipment.label_key, LABEL_URL_TTL) {
+        return Ok(Redirect::temporary(&url).into_response());
+    }
+
+    let pdf = storage.get(&shipment.label_key).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "inline; filename=\"waybill-{}.pdf\"",
+                    shipment.tracking_number
+                ),
+            ),
+        ],
+        pdf,
+    )
+        .into_response())
+}
+
+// Helpers
+
+async fn fetch_shipment(pool: &PgPool, order_id: Uuid) -> Result<Shipment, AppError> {
+    sqlx::query_as::<_, Shipment>("SELECT * FROM shipments WHERE order_id = $1")
+        .bind(order_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Order has no waybill".to_string()))
+}
+
+// "Ваза Луна x2, Чаша Зора" from the order items
+async fn contents_description(conn: &mut PgConnection, order_id: Uuid) -> Result<String, AppError> {
+    let items = sqlx::query_as::<_, (String, i32)>(
+        "SELECT product_name, quantity FROM order_items WHERE order_id = $1 ORDER BY id",
+    )
+    .bind(order_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(items
+        .iter()
+        .map(|(name, quantity)| match quantity {
+            1 => name.clone(),
+            _ => format!("{} x{}", name, quantity),
+        })
+        .collect::<Vec<_>>()
+        .join(", "))
+}
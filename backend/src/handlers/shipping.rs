@@ -0,0 +1,544 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashSet;
+
+use crate::{
+    auth::Claims,
+    couriers::{CourierOffice, Couriers},
+    error::AppError,
+    handlers::promotions::price_cart,
+    models::{
+        CourierOfficeQuery, CreateShippingMethodRequest, Money, ShippingMethod,
+        ShippingMethodDetail, ShippingOption, ShippingQuote, ShippingQuoteRequest, ShippingRate,
+        ShippingRateRequest, ShippingZone, ShippingZoneRequest, UpdateShippingMethodRequest,
+    },
+    promotions::{self, CartLine},
+    shipping::{self, Carrier, Parcel, ShippingKind},
+};
+
+// Public endpoints
+
+pub async fn list_shipping_methods(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<ShippingMethod>>, AppError> {
+    let methods = sqlx::query_as::<_, ShippingMethod>(
+        "SELECT * FROM shipping_methods WHERE is_active = true ORDER BY position, id",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(methods))
+}
+
+// Prices every active method for the cart. Methods whose price table does not
+// cover the parcel are left out. Nothing is held; the order works the price
+// out again when it is placed.
+pub async fn quote_shipping(
+    State(pool): State<PgPool>,
+    Json(payload): Json<ShippingQuoteRequest>,
+) -> Result<Json<ShippingQuote>, AppError> {
+    if payload.items.is_empty() {
+        return Err(AppError::BadRequest(
+            "Cart must contain at least one item".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let priced = price_cart(&mut tx, &payload.items).await?;
+    let weight_grams: i32 = priced.iter().map(|item| item.weight_grams).sum();
+    let lines: Vec<CartLine> = priced.into_iter().map(|item| item.line).collect();
+    let subtotal_amount: Money = lines.iter().map(|line| line.subtotal).sum();
+
+    let promotion_code = payload
+        .promotion_code
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    let promotion = match promotion_code {
+        Some(code) => {
+            let customer_email = payload
+                .customer_email
+                .as_deref()
+                .map(str::trim)
+                .filter(|e| !e.is_empty());
+            Some(promotions::apply(&mut tx, code, customer_email, &lines).await?)
+        }
+        None => None,
+    };
+    let discount_amount = promotion
+        .as_ref()
+        .map_or(Money::ZERO, |applied| applied.discount_amount);
+    let order_amount = subtotal_amount - discount_amount;
+    let free_shipping = promotion
+        .as_ref()
+        .is_some_and(|applied| applied.free_shipping);
+
+    let parcel = Parcel {
+        weight_grams,
+        zone_id: shipping::zone_for_postal_code(&mut tx, payload.postal_code.as_deref()).await?,
+        order_amount,
+        free_shipping,
+    };
+
+    let methods = sqlx::query_as::<_, ShippingMethod>(
+        "SELECT * FROM shipping_methods WHERE is_active = true ORDER BY position, id",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut options = Vec::new();
+    for method in methods {
+        let Some(price) = shipping::price(&mut tx, &method, &parcel).await? else {
+            continue;
+        };
+        let free_shipping_remaining = method
+            .free_shipping_threshold
+            .filter(|threshold| !free_shipping && order_amount < *threshold)
+            .map(|threshold| threshold - order_amount);
+
+        options.push(ShippingOption {
+            method,
+            price,
+            free_shipping_remaining,
+            total_amount: order_amount + price,
+        });
+    }
+
+    Ok(Json(ShippingQuote {
+        weight_grams,
+        subtotal_amount,
+        discount_amount,
+        options,
+    }))
+}
+
+// Offices of a carrier in one city, for office delivery at checkout
+pub async fn list_courier_offices(
+    State(couriers): State<Couriers>,
+    Query(query): Query<CourierOfficeQuery>,
+) -> Result<Json<Vec<CourierOffice>>, AppError> {
+    let city = query.city.trim();
+    if city.is_empty() {
+        return Err(AppError::BadRequest("City is required".to_string()));
+    }
+
+    let offices = couriers.get(query.carrier)?.list_offices(city).await?;
+
+    Ok(Json(offices))
+}
+
+// Admin endpoints (require authentication)
+
+pub async fn list_all_shipping_methods(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<ShippingMethodDetail>>, AppError> {
+    let methods =
+        sqlx::query_as::<_, ShippingMethod>("SELECT * FROM shipping_methods ORDER BY position, id")
+            .fetch_all(&pool)
+            .await?;
+
+    let mut details = Vec::with_capacity(methods.len());
+    for method in methods {
+        let rates = fetch_rates(&pool, method.id).await?;
+        details.push(ShippingMethodDetail { method, rates });
+    }
+
+    Ok(Json(details))
+}
+
+pub async fn get_shipping_method(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<ShippingMethodDetail>, AppError> {
+    Ok(Json(fetch_shipping_method(&pool, id).await?))
+}
+
+pub async fn create_shipping_method(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<CreateShippingMethodRequest>,
+) -> Result<(StatusCode, Json<ShippingMethodDetail>), AppError> {
+    let fields = ShippingMethodFields {
+        name: payload.name,
+        description: payload.description,
+        kind: payload.kind,
+        carrier: payload.carrier,
+        free_shipping_threshold: payload.free_shipping_threshold,
+        cod_fee: payload.cod_fee.unwrap_or(Money::ZERO),
+        is_active: payload.is_active.unwrap_or(true),
+        position: payload.position.unwrap_or(0),
+    }
+    .validated()?;
+
+    let mut tx = pool.begin().await?;
+
+    let id = sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO shipping_methods (
+            name, description, kind, carrier, free_shipping_threshold, cod_fee,
+            is_active, position
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id
+        "#,
+    )
+    .bind(&fields.name)
+    .bind(&fields.description)
+    .bind(fields.kind.as_str())
+    .bind(fields.carrier.map(Carrier::as_str))
+    .bind(fields.free_shipping_threshold)
+    .bind(fields.cod_fee)
+    .bind(fields.is_active)
+    .bind(fields.position)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    set_rates(&mut tx, id, &payload.rates).await?;
+
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(fetch_shipping_method(&pool, id).await?),
+    ))
+}
+
+pub async fn update_shipping_method(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<UpdateShippingMethodRequest>,
+) -> Result<Json<ShippingMethodDetail>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let current = sqlx::query_as::<_, ShippingMethod>(
+        "SELECT * FROM shipping_methods WHERE id = $1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Shipping method not found".to_string()))?;
+
+    let fields = ShippingMethodFields {
+        name: payload.name.unwrap_or(current.name),
+        description: payload.description.unwrap_or(current.description),
+        kind: match payload.kind {
+            Some(kind) => kind,
+            None => ShippingKind::parse(&current.kind)?,
+        },
+        carrier: match payload.carrier {
+            Some(carrier) => carrier,
+            None => current.carrier.as_deref().map(Carrier::parse).transpose()?,
+        },
+        free_shipping_threshold: payload
+            .free_shipping_threshold
+            .unwrap_or(current.free_shipping_threshold),
+        cod_fee: payload.cod_fee.unwrap_or(current.cod_fee),
+        is_active: payload.is_active.unwrap_or(current.is_active),
+        position: payload.position.unwrap_or(current.position),
+    }
+    .validated()?;
+
+    sqlx::query(
+        r#"
+        UPDATE shipping_methods SET
+            name = $1, description = $2, kind = $3, carrier = $4,
+            free_shipping_threshold = $5, cod_fee = $6, is_active = $7, position = $8
+        WHERE id = $9
+        "#,
+    )
+    .bind(&fields.name)
+    .bind(&fields.description)
+    .bind(fields.kind.as_str())
+    .bind(fields.carrier.map(Carrier::as_str))
+    .bind(fields.free_shipping_threshold)
+    .bind(fields.cod_fee)
+    .bind(fields.is_active)
+    .bind(fields.position)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    if let Some(rates) = &payload.rates {
+        set_rates(&mut tx, id, rates).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(fetch_shipping_method(&pool, id).await?))
+}
+
+// Orders shipped with the method keep its name and price
+pub async fn delete_shipping_method(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    let deleted = sqlx::query("DELETE FROM shipping_methods WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Shipping method not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_shipping_zones(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<ShippingZone>>, AppError> {
+    let zones = sqlx::query_as::<_, ShippingZone>(
+        "SELECT * FROM shipping_zones ORDER BY postal_code_from, postal_code_to, id",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(zones))
+}
+
+pub async fn create_shipping_zone(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<ShippingZoneRequest>,
+) -> Result<(StatusCode, Json<ShippingZone>), AppError> {
+    let name = validate_zone(&payload)?;
+
+    let zone = sqlx::query_as::<_, ShippingZone>(
+        r#"
+        INSERT INTO shipping_zones (name, postal_code_from, postal_code_to)
+        VALUES ($1, $2, $3)
+        RETURNING *
+        "#,
+    )
+    .bind(name)
+    .bind(payload.postal_code_from)
+    .bind(payload.postal_code_to)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(zone)))
+}
+
+pub async fn update_shipping_zone(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<ShippingZoneRequest>,
+) -> Result<Json<ShippingZone>, AppError> {
+    let name = validate_zone(&payload)?;
+
+    let zone = sqlx::query_as::<_, ShippingZone>(
+        r#"
+        UPDATE shipping_zones SET name = $1, postal_code_from = $2, postal_code_to = $3
+        WHERE id = $4
+        RETURNING *
+        "#,
+    )
+    .bind(name)
+    .bind(payload.postal_code_from)
+    .bind(payload.postal_code_to)
+    .bind(id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Shipping zone not found".to_string()))?;
+
+    Ok(Json(zone))
+}
+
+// The zone's rates go with it
+pub async fn delete_shipping_zone(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    let deleted = sqlx::query("DELETE FROM shipping_zones WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Shipping zone not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Helpers
+
+// Shipping method settings, as created or after applying an update
+struct ShippingMethodFields {
+    name: String,
+    description: Option<String>,
+    kind: ShippingKind,
+    carrier: Option<Carrier>,
+    free_shipping_threshold: Option<Money>,
+    cod_fee: Money,
+    is_active: bool,
+    position: i32,
+}
+
+impl ShippingMethodFields {
+    fn validated(mut self) -> Result<Self, AppError> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err(AppError::BadRequest("Name is required".to_string()));
+        }
+
+        self.description = self
+            .description
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+
+        match (self.kind, self.carrier) {
+            (ShippingKind::Pickup, Some(_)) => {
+                return Err(AppError::BadRequest(
+                    "Pickup methods have no carrier".to_string(),
+                ));
+            }
+            (ShippingKind::CourierOffice | ShippingKind::CourierAddress, None) => {
+                return Err(AppError::BadRequest(
+                    "Courier methods need a carrier".to_string(),
+                ));
+            }
+            _ => {}
+        }
+
+        if self
+            .free_shipping_threshold
+            .is_some_and(|threshold| threshold < Money::ZERO)
+        {
+            return Err(AppError::BadRequest(
+                "Free shipping threshold cannot be negative".to_string(),
+            ));
+        }
+
+        if self.cod_fee < Money::ZERO {
+            return Err(AppError::BadRequest(
+                "COD fee cannot be negative".to_string(),
+            ));
+        }
+
+        Ok(self)
+    }
+}
+
+// Replaces the method's price table
+async fn set_rates(
+    conn: &mut PgConnection,
+    method_id: i32,
+    rates: &[ShippingRateRequest],
+) -> Result<(), AppError> {
+    if rates.is_empty() {
+        return Err(AppError::BadRequest(
+            "A shipping method needs at least one rate".to_string(),
+        ));
+    }
+
+    let mut bands = HashSet::new();
+    for rate in rates {
+        if rate.price < Money::ZERO {
+            return Err(AppError::BadRequest(
+                "Shipping price cannot be negative".to_string(),
+            ));
+        }
+        if rate.max_weight_grams.is_some_and(|weight| weight <= 0) {
+            return Err(AppError::BadRequest(
+                "Maximum weight must be positive".to_string(),
+            ));
+        }
+        if !bands.insert((rate.zone_id, rate.max_weight_grams)) {
+            return Err(AppError::BadRequest(
+                "Each zone and weight may only have one rate".to_string(),
+            ));
+        }
+    }
+
+    let zone_ids: Vec<i32> = rates.iter().filter_map(|rate| rate.zone_id).collect();
+    let unknown_zone = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM UNNEST($1::INTEGER[]) AS z(id) WHERE z.id NOT IN (SELECT id FROM shipping_zones))",
+    )
+    .bind(&zone_ids)
+    .fetch_one(&mut *conn)
+    .await?;
+    if unknown_zone {
+        return Err(AppError::BadRequest("Unknown shipping zone".to_string()));
+    }
+
+    sqlx::query("DELETE FROM shipping_rates WHERE method_id = $1")
+        .bind(method_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for rate in rates {
+        sqlx::query(
+            r#"
+            INSERT INTO shipping_rates (method_id, zone_id, max_weight_grams, price)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(method_id)
+        .bind(rate.zone_id)
+        .bind(rate.max_weight_grams)
+        .bind(rate.price)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+fn validate_zone(payload: &ShippingZoneRequest) -> Result<&str, AppError> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("Name is required".to_string()));
+    }
+
+    let valid = 1000..=9999;
+    if !valid.contains(&payload.postal_code_from)
+        || !valid.contains(&payload.postal_code_to)
+        || payload.postal_code_from > payload.postal_code_to
+    {
+        return Err(AppError::BadRequest(
+            "Postal codes must form a range between 1000 and 9999".to_string(),
+        ));
+    }
+
+    Ok(name)
+}
+
+async fn fetch_rates(pool: &PgPool, method_id: i32) -> Result<Vec<ShippingRate>, AppError> {
+    let rates = sqlx::query_as::<_, ShippingRate>(
+        r#"
+        SELECT * FROM shipping_rates
+        WHERE method_id = $1
+        ORDER BY zone_id NULLS FIRST, max_weight_grams NULLS LAST
+        "#,
+    )
+    .bind(method_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rates)
+}
+
+async fn fetch_shipping_method(pool: &PgPool, id: i32) -> Result<ShippingMethodDetail, AppError> {
+    let method =
+        sqlx::query_as::<_, ShippingMethod>("SELECT * FROM shipping_methods WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Shipping method not found".to_string()))?;
+
+    let rates = fetch_rates(pool, id).await?;
+
+    Ok(ShippingMethodDetail { method, rates })
+}
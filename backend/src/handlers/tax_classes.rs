@@ -0,0 +1,148 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    models::{CreateTaxClassRequest, TaxClass, UpdateTaxClassRequest},
+    tax,
+};
+
+pub async fn list_tax_classes(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<Json<Vec<TaxClass>>, AppError> {
+    let classes =
+        sqlx::query_as::<_, TaxClass>("SELECT * FROM tax_classes ORDER BY rate DESC, code")
+            .fetch_all(&pool)
+            .await?;
+
+    Ok(Json(classes))
+}
+
+pub async fn create_tax_class(
+    State(pool): State<PgPool>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<CreateTaxClassRequest>,
+) -> Result<(StatusCode, Json<TaxClass>), AppError> {
+    let code = payload.code.trim();
+    if code.is_empty()
+        || code.len() > 20
+        || !code
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(AppError::BadRequest(
+            "Code must be up to 20 lowercase letters, digits or underscores".to_string(),
+        ));
+    }
+    let name = validate_tax_class(&payload.name, payload.rate)?;
+
+    let class = sqlx::query_as::<_, TaxClass>(
+        r#"
+        INSERT INTO tax_classes (code, name, rate, exemption_reason)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(code)
+    .bind(name)
+    .bind(payload.rate)
+    .bind(exemption_reason(&payload.exemption_reason))
+    .fetch_one(&pool)
+    .await
+    .map_err(|err| match err.as_database_error().and_then(|e| e.code()) {
+        Some(code) if code == "23505" => {
+            AppError::BadRequest("A tax class with this code already exists".to_string())
+        }
+        _ => AppError::DatabaseError(err),
+    })?;
+
+    Ok((StatusCode::CREATED, Json(class)))
+}
+
+// Orders already placed keep the rates they were charged
+pub async fn update_tax_class(
+    State(pool): State<PgPool>,
+    Path(code): Path<String>,
+    Extension(_claims): Extension<Claims>,
+    Json(payload): Json<UpdateTaxClassRequest>,
+) -> Result<Json<TaxClass>, AppError> {
+    let name = validate_tax_class(&payload.name, payload.rate)?;
+
+    let class = sqlx::query_as::<_, TaxClass>(
+        r#"
+        UPDATE tax_classes SET name = $1, rate = $2, exemption_reason = $3
+        WHERE code = $4
+        RETURNING *
+        "#,
+    )
+    .bind(name)
+    .bind(payload.rate)
+    .bind(exemption_reason(&payload.exemption_reason))
+    .bind(&code)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Tax class not found".to_string()))?;
+
+    Ok(Json(class))
+}
+
+// Only classes no product is in can go; shipping needs the standard class
+pub async fn delete_tax_class(
+    State(pool): State<PgPool>,
+    Path(code): Path<String>,
+    Extension(_claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    if code == tax::STANDARD_CLASS {
+        return Err(AppError::Conflict(
+            "The standard tax class cannot be deleted".to_string(),
+        ));
+    }
+
+    let deleted = sqlx::query("DELETE FROM tax_classes WHERE code = $1")
+        .bind(&code)
+        .execute(&pool)
+        .await
+        .map_err(|err| match err.as_database_error().and_then(|e| e.code()) {
+            Some(code) if code == "23503" => {
+                AppError::Conflict("Products are still in this tax class".to_string())
+            }
+            _ => AppError::DatabaseError(err),
+        })?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Tax class not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Helpers
+
+fn validate_tax_class(name: &str, rate: Decimal) -> Result<&str, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("Name is required".to_string()));
+    }
+
+    if rate < Decimal::ZERO || rate >= Decimal::ONE_HUNDRED || rate.scale() > 2 {
+        return Err(AppError::BadRequest(
+            "Rate must be a percent from 0 to below 100, with up to two decimals".to_string(),
+        ));
+    }
+
+    Ok(name)
+}
+
+fn exemption_reason(reason: &Option<String>) -> Option<&str> {
+    reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty())
+}
@@ -0,0 +1,169 @@
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool};
+
+use crate::error::AppError;
+
+pub const HEADER: &str = "Idempotency-Key";
+
+// Set on responses replayed from an earlier request
+const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+const MAX_KEY_LENGTH: usize = 255;
+
+// How long keys are kept unless IDEMPOTENCY_KEY_RETENTION_HOURS says otherwise
+const DEFAULT_RETENTION_HOURS: i32 = 24;
+
+// Idempotency key sent with a request, and what it must match on reuse
+pub struct IdempotencyKey {
+    scope: &'static str,
+    key: String,
+    request_hash: String,
+}
+
+impl IdempotencyKey {
+    // Reads the key from the request headers, None when the client sent none
+    pub fn from_request(
+        scope: &'static str,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<Option<Self>, AppError> {
+        let Some(value) = headers.get(HEADER) else {
+            return Ok(None);
+        };
+
+        let key = value
+            .to_str()
+            .map(str::trim)
+            .ok()
+            .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LENGTH)
+            .ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "{} must be 1 to {} visible characters",
+                    HEADER, MAX_KEY_LENGTH
+                ))
+            })?;
+
+        Ok(Some(Self {
+            scope,
+            key: key.to_string(),
+            request_hash: hex::encode(Sha256::digest(body)),
+        }))
+    }
+
+    // Waits for any other request with the same key to finish, then returns
+    // the response stored for it. Hold the lock by calling this inside the
+    // transaction that will `save` the response.
+    pub async fn lock(&self, conn: &mut PgConnection) -> Result<Option<Response>, AppError> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1 || ' ' || $2, 0))")
+            .bind(self.scope)
+            .bind(&self.key)
+            .execute(&mut *conn)
+            .await?;
+
+        let stored = sqlx::query_as::<_, (String, i16, String)>(
+            r#"
+            SELECT request_hash, response_status, response_body
+            FROM idempotency_keys
+            WHERE scope = $1 AND key = $2 AND expires_at > NOW()
+            "#,
+        )
+        .bind(self.scope)
+        .bind(&self.key)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let Some((request_hash, status, body)) = stored else {
+            return Ok(None);
+        };
+
+        if request_hash != self.request_hash {
+            return Err(AppError::Conflict(format!(
+                "{} has already been used for a different request",
+                HEADER
+            )));
+        }
+
+        let status = u16::try_from(status)
+            .ok()
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .unwrap_or(StatusCode::OK);
+
+        let headers = [
+            (header::CONTENT_TYPE.as_str(), "application/json"),
+            (REPLAYED_HEADER, "true"),
+        ];
+
+        Ok(Some((status, headers, body).into_response()))
+    }
+
+    // Stores the response to replay for later requests with this key
+    pub async fn save<T: Serialize>(
+        &self,
+        conn: &mut PgConnection,
+        status: StatusCode,
+        body: &T,
+    ) -> Result<(), AppError> {
+        let body = serde_json::to_string(body).map_err(|e| {
+            AppError::InternalServerError(format!("Failed to store response: {}", e))
+        })?;
+
+        // An expired key may still be there until the next purge
+        sqlx::query(
+            r#"
+            INSERT INTO idempotency_keys (
+                scope, key, request_hash, response_status, response_body, expires_at
+            ) VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(hours => $6))
+            ON CONFLICT (scope, key) DO UPDATE SET
+                request_hash = EXCLUDED.request_hash,
+                response_status = EXCLUDED.response_status,
+                response_body = EXCLUDED.response_body,
+                created_at = NOW(),
+                expires_at = EXCLUDED.expires_at
+            "#,
+        )
+        .bind(self.scope)
+        .bind(&self.key)
+        .bind(&self.request_hash)
+        .bind(status.as_u16() as i16)
+        .bind(body)
+        .bind(retention_hours())
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    // Lets the key be used again, for a request that was undone
+    pub async fn forget(&self, conn: &mut PgConnection) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE scope = $1 AND key = $2")
+            .bind(self.scope)
+            .bind(&self.key)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn retention_hours() -> i32 {
+    std::env::var("IDEMPOTENCY_KEY_RETENTION_HOURS")
+        .ok()
+        .and_then(|value| value.parse::<i32>().ok())
+        .filter(|hours| *hours > 0)
+        .unwrap_or(DEFAULT_RETENTION_HOURS)
+}
+
+// Deletes keys past their retention window, run hourly by the job scheduler
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, AppError> {
+    let purged = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= NOW()")
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+    Ok(purged)
+}
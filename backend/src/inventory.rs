@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+// Why stock changed; stored in `inventory_movements.reason`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementReason {
+    Sale,
+    CancellationRestock,
+    ReturnRestock,
+    ManualAdjustment,
+    ProductionBatch,
+    Damage,
+    // Stock set to a counted level, including the stock entered on creation
+    StockCount,
+}
+
+impl MovementReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MovementReason::Sale => "sale",
+            MovementReason::CancellationRestock => "cancellation_restock",
+            MovementReason::ReturnRestock => "return_restock",
+            MovementReason::ManualAdjustment => "manual_adjustment",
+            MovementReason::ProductionBatch => "production_batch",
+            MovementReason::Damage => "damage",
+            MovementReason::StockCount => "stock_count",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum StockUpdate {
+    // Add (or with a negative value remove) units
+    By(i32),
+    // Set the stock to a counted level
+    To(i32),
+}
+
+// A stock change of a product, or of one of its variants, and its cause
+pub struct StockMovement<'a> {
+    pub product_id: i32,
+    pub variant_id: Option<i32>,
+    pub update: StockUpdate,
+    pub reason: MovementReason,
+    pub order_id: Option<Uuid>,
+    pub order_item_id: Option<i32>,
+    pub admin_id: Option<i32>,
+    pub note: Option<&'a str>,
+}
+
+// Applies a stock change and appends it to the ledger; every write to
+// `stock_quantity` goes through here. Returns the new stock level, or None when
+// the product or variant does not exist. Products with variants are stocked
+// per variant only.
+pub async fn move_stock(
+    conn: &mut PgConnection,
+    movement: StockMovement<'_>,
+) -> Result<Option<i32>, AppError> {
+    let current = match movement.variant_id {
+        Some(variant_id) => {
+            sqlx::query_scalar::<_, i32>(
+                "SELECT stock_quantity FROM product_variants WHERE id = $1 AND product_id = $2 FOR UPDATE",
+            )
+            .bind(variant_id)
+            .bind(movement.product_id)
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+        None => {
+            let product = sqlx::query_as::<_, (i32, bool)>(
+                r#"
+                SELECT stock_quantity,
+                    EXISTS (SELECT 1 FROM product_variants WHERE product_id = $1)
+                FROM products WHERE id = $1 FOR UPDATE
+                "#,
+            )
+            .bind(movement.product_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+            if product.is_some_and(|(_, has_variants)| has_variants) {
+                return Err(AppError::BadRequest(
+                    "Stock of a product with variants is managed per variant".to_string(),
+                ));
+            }
+
+            product.map(|(stock_quantity, _)| stock_quantity)
+        }
+    };
+
+    let Some(current) = current else {
+        return Ok(None);
+    };
+
+    let stock_after = match movement.update {
+        StockUpdate::By(change) => current + change,
+        StockUpdate::To(level) => level,
+    };
+
+    if stock_after < 0 {
+        return Err(AppError::BadRequest(format!(
+            "Stock cannot go below zero (currently {})",
+            current
+        )));
+    }
+
+    // Nothing moved, nothing to record
+    if stock_after == current {
+        return Ok(Some(current));
+    }
+
+    match movement.variant_id {
+        Some(variant_id) => {
+            sqlx::query("UPDATE product_variants SET stock_quantity = $1 WHERE id = $2")
+                .bind(stock_after)
+                .bind(variant_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        None => {
+            sqlx::query("UPDATE products SET stock_quantity = $1 WHERE id = $2")
+                .bind(stock_after)
+                .bind(movement.product_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO inventory_movements (
+            product_id, variant_id, quantity_change, stock_after, reason,
+            order_id, order_item_id, admin_id, note
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+    )
+    .bind(movement.product_id)
+    .bind(movement.variant_id)
+    .bind(stock_after - current)
+    .bind(stock_after)
+    .bind(movement.reason.as_str())
+    .bind(movement.order_id)
+    .bind(movement.order_item_id)
+    .bind(movement.admin_id)
+    .bind(movement.note)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(Some(stock_after))
+}
+
+// Order line still waiting to be put back in stock
+#[derive(sqlx::FromRow)]
+struct RestockLine {
+    id: i32,
+    product_id: i32,
+    variant_id: Option<i32>,
+    variant_label: Option<String>,
+    quantity: i32,
+    sold_per_variant: bool,
+}
+
+// Puts the items of a cancelled or returned order back in stock. Lines that
+// were already restocked, or whose product or variant no longer exists, are
+// skipped, so calling this again for the same order changes nothing.
+pub async fn restock_order(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+    reason: MovementReason,
+    admin_id: Option<i32>,
+) -> Result<(), AppError> {
+    // Ordered by product so concurrent restocks lock rows in the same order
+    let lines = sqlx::query_as::<_, RestockLine>(
+        r#"
+        SELECT oi.id, oi.product_id, oi.variant_id, oi.variant_label, oi.quantity,
+            EXISTS (SELECT 1 FROM product_variants v WHERE v.product_id = oi.product_id)
+                AS sold_per_variant
+        FROM order_items oi
+        JOIN products p ON p.id = oi.product_id
+        WHERE oi.order_id = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM inventory_movements m
+              WHERE m.order_item_id = oi.id
+                AND m.reason IN ('cancellation_restock', 'return_restock')
+          )
+        ORDER BY oi.product_id, oi.variant_id, oi.id
+        "#,
+    )
+    .bind(order_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for line in lines {
+        // A deleted variant leaves nothing to put the units back on, and a
+        // product that gained variants since is no longer stocked as a whole
+        let matches_current_stock = match line.variant_id {
+            Some(_) => true,
+            None => line.variant_label.is_none() && !line.sold_per_variant,
+        };
+
+        let restocked = if matches_current_stock {
+            move_stock(
+                conn,
+                StockMovement {
+                    product_id: line.product_id,
+                    variant_id: line.variant_id,
+                    update: StockUpdate::By(line.quantity),
+                    reason,
+                    order_id: Some(order_id),
+                    order_item_id: Some(line.id),
+                    admin_id,
+                    note: None,
+                },
+            )
+            .await?
+        } else {
+            None
+        };
+
+        if restocked.is_none() {
+            tracing::warn!(
+                "Order {} line {} not restocked: product {} is no longer stocked as sold",
+                order_id,
+                line.id,
+                line.product_id
+            );
+        }
+    }
+
+    Ok(())
+}
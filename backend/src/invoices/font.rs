@@ -0,0 +1,742 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
+
+// More mappings than Unicode has characters only come from overlapping
+// ranges in a broken or hostile character map
+const MAX_MAPPINGS: usize = 0x11_0000;
+
+// Tables kept in a subset: metrics, outlines and the hinting programs. The
+// PDF addresses glyphs by id, so the character map is left out.
+const SUBSET_TABLES: [&str; 9] = [
+    "cvt ", "fpgm", "glyf", "head", "hhea", "hmtx", "loca", "maxp", "prep",
+];
+
+// Just enough of a TrueType font to embed it in a PDF: the glyph and width
+// of each character, the metrics the font descriptor asks for, and the
+// outlines to subset
+pub struct Font {
+    data: Vec<u8>,
+    units_per_em: u16,
+    // xMin, yMin, xMax, yMax in font units
+    pub bbox: [i16; 4],
+    pub ascent: i16,
+    pub descent: i16,
+    pub cap_height: i16,
+    advances: Vec<u16>,
+    glyphs: HashMap<char, u16>,
+    // Where each table lies in `data`
+    tables: HashMap<String, Range<usize>>,
+    // Where the outline of each glyph lies in the glyf table
+    outlines: Vec<Range<usize>>,
+}
+
+impl Font {
+    // Reads a TrueType font. Anything out of place is an error rather than
+    // a panic, as the file comes from configuration.
+    pub fn parse(data: Vec<u8>) -> Result<Self, String> {
+        let tables = table_directory(&data)?;
+        let table = |tag: &str| {
+            tables
+                .get(tag)
+                .map(|range| &data[range.clone()])
+                .ok_or_else(|| format!("the font has no '{}' table", tag))
+        };
+
+        let head = table("head")?;
+        let units_per_em = read_u16(head, 18)?;
+        let bbox = [
+            read_i16(head, 36)?,
+            read_i16(head, 38)?,
+            read_i16(head, 40)?,
+            read_i16(head, 42)?,
+        ];
+        let long_offsets = read_i16(head, 50)? != 0;
+
+        let hhea = table("hhea")?;
+        let ascent = read_i16(hhea, 4)?;
+        let descent = read_i16(hhea, 6)?;
+        let metric_count = read_u16(hhea, 34)? as usize;
+
+        let hmtx = table("hmtx")?;
+        let advances = (0..metric_count)
+            .map(|index| read_u16(hmtx, index * 4))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Version 2 of the OS/2 table onwards knows the cap height
+        let cap_height = match tables.get("OS/2") {
+            Some(os2) => {
+                let os2 = &data[os2.clone()];
+                match read_u16(os2, 0)? {
+                    version if version >= 2 => read_i16(os2, 88)?,
+                    _ => ascent,
+                }
+            }
+            None => ascent,
+        };
+
+        let glyph_count = read_u16(table("maxp")?, 4)?;
+        let outlines = glyph_locations(
+            table("loca")?,
+            table("glyf")?.len(),
+            glyph_count,
+            long_offsets,
+        )?;
+        let glyphs = character_map(table("cmap")?, glyph_count)?;
+
+        Ok(Self {
+            data,
+            units_per_em,
+            bbox,
+            ascent,
+            descent,
+            cap_height,
+            advances,
+            glyphs,
+            tables,
+            outlines,
+        })
+    }
+
+    // Glyph of the character; 0, the missing glyph box, when the font has
+    // none
+    pub fn glyph(&self, c: char) -> u16 {
+        self.glyphs.get(&c).copied().unwrap_or(0)
+    }
+
+    // Advance width of the glyph in PDF text space, thousandths of the size
+    pub fn width(&self, glyph: u16) -> u32 {
+        // Glyphs past the last metric share its width
+        let advance = self
+            .advances
+            .get(glyph as usize)
+            .or(self.advances.last())
+            .copied()
+            .unwrap_or(0);
+        self.scale(advance as i32) as u32
+    }
+
+    // Font units to thousandths of the size
+    pub fn scale(&self, units: i32) -> i32 {
+        units * 1000 / self.units_per_em.max(1) as i32
+    }
+
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        let units: u32 = text.chars().map(|c| self.width(self.glyph(c))).sum();
+        units as f32 * size / 1000.0
+    }
+
+    // The font with only the outlines of the given glyphs, the missing glyph
+    // box and the glyphs they are built from. Glyph ids stay the same, so
+    // text drawn with the full font reads the same with the subset.
+    pub fn subset(&self, glyphs: impl IntoIterator<Item = u16>) -> Vec<u8> {
+        let glyf = self.table("glyf");
+
+        let mut kept = BTreeSet::new();
+        let mut pending: Vec<u16> = glyphs.into_iter().chain([0]).collect();
+        while let Some(glyph) = pending.pop() {
+            let Some(outline) = self.outlines.get(glyph as usize) else {
+                continue;
+            };
+            if kept.insert(glyph) {
+                pending.extend(components(&glyf[outline.clone()]));
+            }
+        }
+
+        // Offsets are written as 32 bits whatever the font used
+        let mut outlines = Vec::new();
+        let mut locations = Vec::with_capacity((self.outlines.len() + 1) * 4);
+        for (glyph, outline) in self.outlines.iter().enumerate() {
+            locations.extend_from_slice(&(outlines.len() as u32).to_be_bytes());
+            if kept.contains(&(glyph as u16)) {
+                outlines.extend_from_slice(&glyf[outline.clone()]);
+                outlines.resize(outlines.len().next_multiple_of(4), 0);
+            }
+        }
+        locations.extend_from_slice(&(outlines.len() as u32).to_be_bytes());
+
+        let mut head = self.table("head").to_vec();
+        head[8..12].fill(0);
+        head[50..52].copy_from_slice(&1u16.to_be_bytes());
+
+        let mut tables = BTreeMap::new();
+        for tag in SUBSET_TABLES {
+            let data = match tag {
+                "glyf" => outlines.clone(),
+                "head" => head.clone(),
+                "loca" => locations.clone(),
+                _ => match self.tables.get(tag) {
+                    Some(range) => self.data[range.clone()].to_vec(),
+                    None => continue,
+                },
+            };
+            tables.insert(tag, data);
+        }
+
+        write_font(&tables)
+    }
+
+    // A table `parse` found
+    fn table(&self, tag: &str) -> &[u8] {
+        self.tables
+            .get(tag)
+            .map_or(&[], |range| &self.data[range.clone()])
+    }
+}
+
+// Where each table lies, by its tag; every table is within the file
+fn table_directory(data: &[u8]) -> Result<HashMap<String, Range<usize>>, String> {
+    let table_count = read_u16(data, 4)? as usize;
+
+    (0..table_count)
+        .map(|index| {
+            let record = 12 + index * 16;
+            let tag = data
+                .get(record..record + 4)
+                .ok_or("the table directory is cut short")?;
+            let tag = String::from_utf8_lossy(tag).into_owned();
+            let offset = read_u32(data, record + 8)? as usize;
+            let length = read_u32(data, record + 12)? as usize;
+            match offset.checked_add(length) {
+                Some(end) if end <= data.len() => Ok((tag, offset..end)),
+                _ => Err(format!("the '{}' table runs past the end of the font", tag)),
+            }
+        })
+        .collect()
+}
+
+// Where the outline of each glyph lies in the glyf table, from the loca
+// table of offsets, short ones halved
+fn glyph_locations(
+    loca: &[u8],
+    glyf_length: usize,
+    glyph_count: u16,
+    long_offsets: bool,
+) -> Result<Vec<Range<usize>>, String> {
+    let offsets = (0..=glyph_count as usize)
+        .map(|index| match long_offsets {
+            true => read_u32(loca, index * 4).map(|offset| offset as usize),
+            false => read_u16(loca, index * 2).map(|offset| offset as usize * 2),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    offsets
+        .windows(2)
+        .enumerate()
+        .map(|(glyph, pair)| match (pair[0], pair[1]) {
+            (start, end) if start <= end && end <= glyf_length => Ok(start..end),
+            _ => Err(format!(
+                "the outline of glyph {} lies outside the 'glyf' table",
+                glyph
+            )),
+        })
+        .collect()
+}
+
+// Glyphs a composite glyph is built from; none for a simple glyph. A
+// description cut short ends the list.
+fn components(outline: &[u8]) -> Vec<u16> {
+    const ARGS_ARE_WORDS: u16 = 0x0001;
+    const HAS_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const HAS_X_AND_Y_SCALE: u16 = 0x0040;
+    const HAS_TWO_BY_TWO: u16 = 0x0080;
+
+    let mut glyphs = Vec::new();
+    if !matches!(read_i16(outline, 0), Ok(contours) if contours < 0) {
+        return glyphs;
+    }
+
+    let mut at = 10;
+    while let (Ok(flags), Ok(glyph)) = (read_u16(outline, at), read_u16(outline, at + 2)) {
+        glyphs.push(glyph);
+        at += 4 + if flags & ARGS_ARE_WORDS != 0 { 4 } else { 2 };
+        at += if flags & HAS_SCALE != 0 {
+            2
+        } else if flags & HAS_X_AND_Y_SCALE != 0 {
+            4
+        } else if flags & HAS_TWO_BY_TWO != 0 {
+            8
+        } else {
+            0
+        };
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+    glyphs
+}
+
+// Glyph of every character, from the Unicode subtable of the cmap table:
+// format 12 for the full range if there is one, otherwise format 4. Glyphs
+// the font does not have map to the missing glyph.
+fn character_map(cmap: &[u8], glyph_count: u16) -> Result<HashMap<char, u16>, String> {
+    let subtable_count = read_u16(cmap, 2)? as usize;
+    let mut full_range = None;
+    let mut basic = None;
+    for index in 0..subtable_count {
+        let record = 4 + index * 8;
+        let platform = read_u16(cmap, record)?;
+        let encoding = read_u16(cmap, record + 2)?;
+        let offset = read_u32(cmap, record + 4)? as usize;
+        let subtable = cmap.get(offset..).ok_or("the character map is cut short")?;
+        match (platform, encoding, read_u16(subtable, 0)?) {
+            (0, _, 12) | (3, 10, 12) => full_range = Some(subtable),
+            (0, _, 4) | (3, 1, 4) => basic = Some(subtable),
+            _ => {}
+        }
+    }
+
+    let mut glyphs = HashMap::new();
+    let mut mappings = 0;
+    let mut map = |code: u32, glyph: u32| {
+        mappings += 1;
+        if mappings > MAX_MAPPINGS {
+            return Err("the character map maps more characters than Unicode has".to_string());
+        }
+        if let Some(c) = char::from_u32(code) {
+            let glyph = if glyph < glyph_count as u32 { glyph } else { 0 };
+            glyphs.insert(c, glyph as u16);
+        }
+        Ok(())
+    };
+
+    if let Some(subtable) = full_range {
+        let group_count = read_u32(subtable, 12)? as usize;
+        for index in 0..group_count {
+            let group = 16 + index * 12;
+            let first = read_u32(subtable, group)?;
+            let last = read_u32(subtable, group + 4)?;
+            let first_glyph = read_u32(subtable, group + 8)?;
+            if first_glyph >= glyph_count as u32 {
+                continue;
+            }
+            // Only as far as there are glyphs, so a group cannot run on
+            // over the whole 32 bit range
+            let glyphs_left = glyph_count as u32 - first_glyph;
+            let last = last.min(first.saturating_add(glyphs_left - 1));
+            for code in first..=last {
+                map(code, first_glyph + (code - first))?;
+            }
+        }
+    } else if let Some(subtable) = basic {
+        let segment_count = read_u16(subtable, 6)? as usize / 2;
+        let ends = 14;
+        let starts = ends + segment_count * 2 + 2;
+        let deltas = starts + segment_count * 2;
+        let range_offsets = deltas + segment_count * 2;
+        for segment in 0..segment_count {
+            let end = read_u16(subtable, ends + segment * 2)?;
+            let start = read_u16(subtable, starts + segment * 2)?;
+            let delta = read_u16(subtable, deltas + segment * 2)?;
+            let range_offset_at = range_offsets + segment * 2;
+            let range_offset = read_u16(subtable, range_offset_at)? as usize;
+            for code in start..=end {
+                if code == 0xFFFF {
+                    break;
+                }
+                let glyph = if range_offset == 0 {
+                    code.wrapping_add(delta)
+                } else {
+                    let at = range_offset_at + range_offset + (code - start) as usize * 2;
+                    match read_u16(subtable, at)? {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(delta),
+                    }
+                };
+                map(code as u32, glyph as u32)?;
+            }
+        }
+    } else {
+        return Err("the font has no Unicode character map".to_string());
+    }
+
+    Ok(glyphs)
+}
+
+// Font file of the tables, sorted by tag, each with its checksum, and the
+// checksum adjustment of the head table set for the whole file
+fn write_font(tables: &BTreeMap<&str, Vec<u8>>) -> Vec<u8> {
+    let count = tables.len() as u16;
+    let entry_selector = count.max(1).ilog2() as u16;
+    let search_range = (1u16 << entry_selector) * 16;
+
+    let mut font = Vec::new();
+    font.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    for value in [
+        count,
+        search_range,
+        entry_selector,
+        count * 16 - search_range,
+    ] {
+        font.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let mut offset = 12 + tables.len() * 16;
+    let mut head_at = None;
+    for (tag, data) in tables {
+        if *tag == "head" {
+            head_at = Some(offset);
+        }
+        font.extend_from_slice(tag.as_bytes());
+        font.extend_from_slice(&checksum(data).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += data.len().next_multiple_of(4);
+    }
+    for data in tables.values() {
+        font.extend_from_slice(data);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+
+    if let Some(head) = head_at {
+        let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+        font[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    font
+}
+
+// Sum of the big-endian 32 bit words, the last one padded with zeros
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn read_u16(data: &[u8], at: usize) -> Result<u16, String> {
+    at.checked_add(2)
+        .and_then(|end| data.get(at..end))
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| "the font file is cut short".to_string())
+}
+
+fn read_i16(data: &[u8], at: usize) -> Result<i16, String> {
+    read_u16(data, at).map(|value| value as i16)
+}
+
+fn read_u32(data: &[u8], at: usize) -> Result<u32, String> {
+    at.checked_add(4)
+        .and_then(|end| data.get(at..end))
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| "the font file is cut short".to_string())
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    // Advances of the sample glyphs at 2000 units to the em
+    const ADVANCES: [u16; 6] = [500, 1200, 1400, 1300, 600, 1100];
+
+    // Simple glyph, a single point placed by the glyph id so outlines
+    // differ; padded to 4 bytes as subsets pad them
+    fn simple_glyph(glyph: u8) -> Vec<u8> {
+        let mut outline = vec![0, 1, 0, 0, 0, 0, 0, glyph, 0, glyph];
+        outline.extend_from_slice(&[0, 0, 0, 0, 0x37, glyph, glyph, 0, 0, 0]);
+        outline
+    }
+
+    // Glyph 3: glyphs 1 and 4 drawn together
+    fn composite_glyph() -> Vec<u8> {
+        let mut outline = vec![0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0];
+        outline.extend_from_slice(&[0x00, 0x22, 0, 1, 0, 0]);
+        outline.extend_from_slice(&[0x00, 0x02, 0, 4, 0, 0, 0, 0]);
+        outline
+    }
+
+    fn outlines() -> Vec<Vec<u8>> {
+        vec![
+            simple_glyph(0),
+            simple_glyph(1),
+            simple_glyph(2),
+            composite_glyph(),
+            simple_glyph(4),
+            simple_glyph(5),
+        ]
+    }
+
+    // Format 4 subtable: 'A' and 'Z' by delta, 'Ф' and 'Х' from the glyph
+    // id array
+    fn basic_subtable() -> Vec<u8> {
+        let segments: [(u16, u16, u16, u16); 4] = [
+            (0x41, 0x41, 1u16.wrapping_sub(0x41), 0),
+            (0x5A, 0x5A, 5u16.wrapping_sub(0x5A), 0),
+            (0x424, 0x425, 0, 4),
+            (0xFFFF, 0xFFFF, 1, 0),
+        ];
+        let mut subtable = Vec::new();
+        for value in [4u16, 0, 0, segments.len() as u16 * 2, 0, 0, 0] {
+            subtable.extend_from_slice(&value.to_be_bytes());
+        }
+        let column = |subtable: &mut Vec<u8>, value: fn(&(u16, u16, u16, u16)) -> u16| {
+            for segment in &segments {
+                subtable.extend_from_slice(&value(segment).to_be_bytes());
+            }
+        };
+        column(&mut subtable, |segment| segment.1);
+        subtable.extend_from_slice(&[0, 0]);
+        column(&mut subtable, |segment| segment.0);
+        column(&mut subtable, |segment| segment.2);
+        column(&mut subtable, |segment| segment.3);
+        for glyph in [2u16, 3] {
+            subtable.extend_from_slice(&glyph.to_be_bytes());
+        }
+        subtable
+    }
+
+    // Format 12 subtable of (first, last, first glyph) groups
+    fn full_range_subtable(groups: &[(u32, u32, u32)]) -> Vec<u8> {
+        let mut subtable = vec![0, 12, 0, 0];
+        subtable.extend_from_slice(&(16 + groups.len() as u32 * 12).to_be_bytes());
+        subtable.extend_from_slice(&0u32.to_be_bytes());
+        subtable.extend_from_slice(&(groups.len() as u32).to_be_bytes());
+        for (first, last, glyph) in groups {
+            for value in [first, last, glyph] {
+                subtable.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        subtable
+    }
+
+    // cmap table of (platform, encoding, subtable) entries
+    fn character_map_table(subtables: &[(u16, u16, Vec<u8>)]) -> Vec<u8> {
+        let mut cmap = vec![0, 0];
+        cmap.extend_from_slice(&(subtables.len() as u16).to_be_bytes());
+        let mut offset = 4 + subtables.len() * 8;
+        for (platform, encoding, subtable) in subtables {
+            cmap.extend_from_slice(&platform.to_be_bytes());
+            cmap.extend_from_slice(&encoding.to_be_bytes());
+            cmap.extend_from_slice(&(offset as u32).to_be_bytes());
+            offset += subtable.len();
+        }
+        for (_, _, subtable) in subtables {
+            cmap.extend_from_slice(subtable);
+        }
+        cmap
+    }
+
+    fn font_file(cmap: Vec<u8>) -> Vec<u8> {
+        let mut head = vec![0u8; 54];
+        head[0..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+        head[12..16].copy_from_slice(&0x5F0F_3CF5u32.to_be_bytes());
+        head[18..20].copy_from_slice(&2000u16.to_be_bytes());
+        for (index, value) in [-100i16, -400, 1800, 1600].iter().enumerate() {
+            head[36 + index * 2..38 + index * 2].copy_from_slice(&value.to_be_bytes());
+        }
+
+        let mut hhea = vec![0u8; 36];
+        hhea[0..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+        hhea[4..6].copy_from_slice(&1600i16.to_be_bytes());
+        hhea[6..8].copy_from_slice(&(-400i16).to_be_bytes());
+        hhea[34..36].copy_from_slice(&(ADVANCES.len() as u16).to_be_bytes());
+
+        let mut maxp = 0x0000_5000u32.to_be_bytes().to_vec();
+        maxp.extend_from_slice(&(ADVANCES.len() as u16).to_be_bytes());
+
+        let hmtx: Vec<u8> = ADVANCES
+            .iter()
+            .flat_map(|advance| [advance.to_be_bytes(), [0, 0]].concat())
+            .collect();
+
+        let mut glyf = Vec::new();
+        let mut loca = Vec::new();
+        for outline in outlines() {
+            loca.extend_from_slice(&((glyf.len() / 2) as u16).to_be_bytes());
+            glyf.extend_from_slice(&outline);
+        }
+        loca.extend_from_slice(&((glyf.len() / 2) as u16).to_be_bytes());
+
+        let tables = BTreeMap::from([
+            ("cmap", cmap),
+            ("glyf", glyf),
+            ("head", head),
+            ("hhea", hhea),
+            ("hmtx", hmtx),
+            ("loca", loca),
+            ("maxp", maxp),
+        ]);
+        write_font(&tables)
+    }
+
+    // Six glyphs: the missing glyph box, 'A', 'Ф', 'Х' built from glyphs 1
+    // and 4, and 'Z'
+    pub(in crate::invoices) fn sample_font() -> Vec<u8> {
+        font_file(character_map_table(&[(3, 1, basic_subtable())]))
+    }
+
+    // Outline of each glyph of a font file written by `subset`
+    fn subset_outlines(file: &[u8]) -> Vec<Vec<u8>> {
+        let tables = table_directory(file).unwrap();
+        let glyf = &file[tables["glyf"].clone()];
+        let loca = &file[tables["loca"].clone()];
+        glyph_locations(loca, glyf.len(), ADVANCES.len() as u16, true)
+            .unwrap()
+            .into_iter()
+            .map(|range| glyf[range].to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn maps_latin_and_cyrillic_characters_to_their_glyphs() {
+        let font = Font::parse(sample_font()).unwrap();
+
+        assert_eq!(font.glyph('A'), 1);
+        assert_eq!(font.glyph('Ф'), 2);
+        assert_eq!(font.glyph('Х'), 3);
+        assert_eq!(font.glyph('Z'), 5);
+        assert_eq!(font.glyph('Ж'), 0);
+        assert_eq!(font.ascent, 1600);
+        assert_eq!(font.cap_height, 1600);
+    }
+
+    #[test]
+    fn measures_in_thousandths_of_the_size() {
+        let font = Font::parse(sample_font()).unwrap();
+
+        assert_eq!(font.width(1), 600);
+        assert_eq!(font.width(2), 700);
+        // Past the last metric
+        assert_eq!(font.width(40), 550);
+        assert_eq!(font.scale(font.bbox[1] as i32), -200);
+        assert_eq!(font.text_width("AФ", 10.0), 13.0);
+    }
+
+    #[test]
+    fn prefers_the_full_range_character_map() {
+        let cmap = character_map_table(&[
+            (3, 1, basic_subtable()),
+            (
+                3,
+                10,
+                full_range_subtable(&[(0x424, 0x424, 5), (0x1D400, 0x1D400, 1)]),
+            ),
+        ]);
+        let font = Font::parse(font_file(cmap)).unwrap();
+
+        assert_eq!(font.glyph('Ф'), 5);
+        assert_eq!(font.glyph('𝐀'), 1);
+        assert_eq!(font.glyph('A'), 0);
+    }
+
+    #[test]
+    fn maps_glyphs_the_font_lacks_to_the_missing_glyph() {
+        // Groups running over the whole 32 bit range stop at the last glyph
+        let cmap = character_map_table(&[(
+            0,
+            4,
+            full_range_subtable(&[(0x41, u32::MAX, 4), (0x61, u32::MAX, 1000)]),
+        )]);
+        let font = Font::parse(font_file(cmap)).unwrap();
+
+        assert_eq!(font.glyph('A'), 4);
+        assert_eq!(font.glyph('B'), 5);
+        assert_eq!(font.glyph('C'), 0);
+        assert_eq!(font.glyph('a'), 0);
+
+        let mut subtable = basic_subtable();
+        // The glyph id array entry of 'Х'
+        let last = subtable.len() - 2;
+        subtable[last..].copy_from_slice(&300u16.to_be_bytes());
+        let font = Font::parse(font_file(character_map_table(&[(3, 1, subtable)]))).unwrap();
+        assert_eq!(font.glyph('Ф'), 2);
+        assert_eq!(font.glyph('Х'), 0);
+    }
+
+    #[test]
+    fn subset_keeps_only_the_outlines_drawn() {
+        let file = sample_font();
+        let font = Font::parse(file.clone()).unwrap();
+        let subset = font.subset([font.glyph('Ф')]);
+
+        let tables = table_directory(&subset).unwrap();
+        let mut tags: Vec<&str> = tables.keys().map(String::as_str).collect();
+        tags.sort();
+        assert_eq!(tags, ["glyf", "head", "hhea", "hmtx", "loca", "maxp"]);
+        assert!(subset.len() < file.len());
+        assert_eq!(checksum(&subset), 0xB1B0_AFBA);
+
+        let kept = subset_outlines(&subset);
+        let original = outlines();
+        assert_eq!(kept[0], original[0]);
+        assert_eq!(kept[2], original[2]);
+        for glyph in [1, 3, 4, 5] {
+            assert!(kept[glyph].is_empty(), "glyph {} was kept", glyph);
+        }
+    }
+
+    #[test]
+    fn subset_keeps_the_glyphs_composites_are_built_from() {
+        let font = Font::parse(sample_font()).unwrap();
+        let subset = font.subset([font.glyph('Х')]);
+
+        let kept = subset_outlines(&subset);
+        let original = outlines();
+        for glyph in [0, 1, 3, 4] {
+            assert_eq!(kept[glyph], original[glyph]);
+        }
+        assert!(kept[2].is_empty());
+        assert!(kept[5].is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_fonts_without_panicking() {
+        assert!(Font::parse(Vec::new()).is_err());
+        assert!(Font::parse(b"not a font at all, just some text".to_vec()).is_err());
+
+        let file = sample_font();
+        for length in 0..file.len() - 3 {
+            assert!(Font::parse(file[..length].to_vec()).is_err());
+        }
+
+        // Any byte broken either parses or is refused, and what parses can
+        // be subset
+        for at in 0..file.len() {
+            for value in [0x00, 0x80, 0xFF] {
+                let mut broken = file.clone();
+                broken[at] = value;
+                if let Ok(font) = Font::parse(broken) {
+                    font.subset(0..=u16::MAX);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_tables_past_the_end_of_the_file() {
+        let mut file = sample_font();
+        // Length of the first table, cmap
+        file[24..28].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        let error = Font::parse(file).err().unwrap();
+        assert_eq!(error, "the 'cmap' table runs past the end of the font");
+    }
+
+    #[test]
+    fn rejects_outlines_outside_the_glyph_table() {
+        let file = sample_font();
+        let tables = table_directory(&file).unwrap();
+        let mut broken = file.clone();
+        // Last loca offset, halved
+        let end = tables["loca"].end;
+        broken[end - 2..end].copy_from_slice(&u16::MAX.to_be_bytes());
+
+        let error = Font::parse(broken).err().unwrap();
+        assert_eq!(
+            error,
+            "the outline of glyph 5 lies outside the 'glyf' table"
+        );
+    }
+
+    #[test]
+    fn refuses_character_maps_that_map_too_much() {
+        let groups = vec![(0, 0x10FFFF, 0); 300];
+        let mut file = font_file(character_map_table(&[(0, 4, full_range_subtable(&groups))]));
+        // Claim enough glyphs for every group to run its full length
+        let tables = table_directory(&file).unwrap();
+        let maxp = tables["maxp"].start;
+        file[maxp + 4..maxp + 6].copy_from_slice(&u16::MAX.to_be_bytes());
+
+        assert!(Font::parse(file).is_err());
+    }
+}
@@ -0,0 +1,1042 @@
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Europe::Sofia;
+use rust_decimal::Decimal;
+use sqlx::{types::Json, PgConnection};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        CreateCreditNoteRequest, Invoice, InvoiceBuyerRequest, InvoiceDetail, InvoiceLine,
+        IssueInvoiceRequest, Money, Order, OrderItem, TaxLine,
+    },
+    order_status::OrderStatus,
+    payments::{PaymentMethod, PaymentStatus},
+    tax,
+};
+
+mod font;
+mod pdf;
+
+pub use font::Font;
+
+// Legal ground printed on invoices of a studio not registered for VAT
+const NOT_VAT_REGISTERED: &str = "чл. 113, ал. 9 от ЗДДС";
+
+const DEFAULT_FONT: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf";
+
+// Largest number the 10 digits hold
+const LAST_NUMBER: i64 = 9_999_999_999;
+
+// Invoices and credit notes share one sequence of numbers; stored in
+// `invoices.kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvoiceKind {
+    Invoice,
+    CreditNote,
+}
+
+impl InvoiceKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InvoiceKind::Invoice => "invoice",
+            InvoiceKind::CreditNote => "credit_note",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value {
+            "invoice" => Ok(InvoiceKind::Invoice),
+            "credit_note" => Ok(InvoiceKind::CreditNote),
+            other => Err(AppError::BadRequest(format!(
+                "Unknown invoice kind '{}', expected 'invoice' or 'credit_note'",
+                other
+            ))),
+        }
+    }
+
+    // Title printed on the document
+    pub fn title(self) -> &'static str {
+        match self {
+            InvoiceKind::Invoice => "ФАКТУРА",
+            InvoiceKind::CreditNote => "КРЕДИТНО ИЗВЕСТИЕ",
+        }
+    }
+
+    // Start of the PDF file name
+    pub fn file_prefix(self) -> &'static str {
+        match self {
+            InvoiceKind::Invoice => "faktura",
+            InvoiceKind::CreditNote => "kreditno-izvestie",
+        }
+    }
+}
+
+// The studio as printed on its invoices
+pub struct Seller {
+    pub name: String,
+    pub eik: String,
+    // Only once the studio is registered for VAT
+    pub vat_number: Option<String>,
+    pub mol: Option<String>,
+    pub address: String,
+}
+
+// The configured seller, font and first number; without a seller no
+// invoices are issued
+#[derive(Clone)]
+pub struct Invoicing {
+    seller: Option<Arc<Seller>>,
+    font_path: String,
+    first_number: i64,
+}
+
+impl Invoicing {
+    pub fn seller(&self) -> Result<&Seller, AppError> {
+        self.seller
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest("Invoicing is not available".to_string()))
+    }
+
+    // Reads the font for every document, so a replaced font file needs no
+    // restart
+    pub async fn font(&self) -> Result<Font, AppError> {
+        let data = tokio::fs::read(&self.font_path).await.map_err(|e| {
+            tracing::error!("Failed to read invoice font {}: {:?}", self.font_path, e);
+            AppError::InternalServerError("Failed to render invoice".to_string())
+        })?;
+
+        Font::parse(data).map_err(|e| {
+            tracing::error!("Failed to read invoice font {}: {}", self.font_path, e);
+            AppError::InternalServerError("Failed to render invoice".to_string())
+        })
+    }
+}
+
+// Reads the seller from INVOICE_SELLER_*. INVOICE_FIRST_NUMBER carries on a
+// sequence started elsewhere, e.g. in invoicing software used before.
+pub fn from_env() -> Invoicing {
+    // docker-compose passes unset variables as empty strings
+    let optional = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let require =
+        |name: &str| optional(name).unwrap_or_else(|| panic!("{} must be set for invoicing", name));
+
+    let seller = optional("INVOICE_SELLER_NAME").map(|name| {
+        let eik = require("INVOICE_SELLER_EIK");
+        if !valid_eik(&eik) {
+            panic!("INVOICE_SELLER_EIK '{}' is not a valid ЕИК", eik);
+        }
+        Arc::new(Seller {
+            name,
+            eik,
+            vat_number: optional("INVOICE_SELLER_VAT_NUMBER").map(|number| {
+                normalize_vat_number(&number).unwrap_or_else(|| {
+                    panic!("INVOICE_SELLER_VAT_NUMBER '{}' is not a VAT number", number)
+                })
+            }),
+            mol: optional("INVOICE_SELLER_MOL"),
+            address: require("INVOICE_SELLER_ADDRESS"),
+        })
+    });
+
+    let first_number = optional("INVOICE_FIRST_NUMBER")
+        .map(|number| {
+            number
+                .parse::<i64>()
+                .ok()
+                .filter(|number| (1..=LAST_NUMBER).contains(number))
+                .unwrap_or_else(|| {
+                    panic!(
+                        "INVOICE_FIRST_NUMBER must be a number from 1 to {}",
+                        LAST_NUMBER
+                    )
+                })
+        })
+        .unwrap_or(1);
+
+    Invoicing {
+        seller,
+        font_path: optional("INVOICE_FONT").unwrap_or_else(|| DEFAULT_FONT.to_string()),
+        first_number,
+    }
+}
+
+// Numbers are printed with all 10 digits, e.g. 0000000042
+pub fn format_number(number: i64) -> String {
+    format!("{:010}", number)
+}
+
+// Company details as printed, checked and tidied up
+pub struct Buyer {
+    pub company: String,
+    pub eik: String,
+    pub vat_number: Option<String>,
+    pub mol: Option<String>,
+    pub address: String,
+}
+
+// Checks the company details given at checkout or when issuing
+pub fn validate_buyer(request: &InvoiceBuyerRequest) -> Result<Buyer, AppError> {
+    let company = request.company.trim();
+    if company.is_empty() {
+        return Err(AppError::BadRequest(
+            "Company name is required for an invoice".to_string(),
+        ));
+    }
+
+    let address = request.address.trim();
+    if address.is_empty() {
+        return Err(AppError::BadRequest(
+            "Company address is required for an invoice".to_string(),
+        ));
+    }
+
+    let eik: String = request.eik.chars().filter(|c| !c.is_whitespace()).collect();
+    if !valid_eik(&eik) {
+        return Err(AppError::BadRequest(
+            "ЕИК must be a valid 9 or 13 digit number".to_string(),
+        ));
+    }
+
+    let vat_number = match request.vat_number.as_deref().map(str::trim) {
+        Some(number) if !number.is_empty() => Some(
+            normalize_vat_number(number)
+                .ok_or_else(|| AppError::BadRequest("VAT number is not valid".to_string()))?,
+        ),
+        _ => None,
+    };
+
+    let mol = request
+        .mol
+        .as_deref()
+        .map(str::trim)
+        .filter(|mol| !mol.is_empty())
+        .map(str::to_string);
+
+    Ok(Buyer {
+        company: company.to_string(),
+        eik,
+        vat_number,
+        mol,
+        address: address.to_string(),
+    })
+}
+
+// ЕИК of a company (9 digits) or of one of its branches (13 digits), with
+// the check digits of the BULSTAT register
+pub fn valid_eik(eik: &str) -> bool {
+    if !eik.chars().all(|c| c.is_ascii_digit()) || !matches!(eik.len(), 9 | 13) {
+        return false;
+    }
+    let digits: Vec<u32> = eik.chars().filter_map(|c| c.to_digit(10)).collect();
+
+    // Weighted sum modulo 11; a remainder of 10 is retried with the second
+    // weights, and a second 10 counts as 0
+    let check = |digits: &[u32], weights: [&[u32]; 2]| {
+        for weights in weights {
+            let sum: u32 = digits.iter().zip(weights).map(|(d, w)| d * w).sum();
+            if sum % 11 != 10 {
+                return sum % 11;
+            }
+        }
+        0
+    };
+
+    let base = check(
+        &digits[..8],
+        [&[1, 2, 3, 4, 5, 6, 7, 8], &[3, 4, 5, 6, 7, 8, 9, 10]],
+    );
+    if digits[8] != base {
+        return false;
+    }
+    if digits.len() == 9 {
+        return true;
+    }
+
+    check(&digits[8..12], [&[2, 7, 3, 5], &[4, 9, 5, 7]]) == digits[12]
+}
+
+// VAT number with its country prefix, e.g. BG123456789; plain digits are
+// taken to be Bulgarian
+pub fn normalize_vat_number(number: &str) -> Option<String> {
+    let number: String = number
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase();
+    let number = if number.chars().all(|c| c.is_ascii_digit()) {
+        format!("BG{}", number)
+    } else {
+        number
+    };
+
+    let (country, rest) = number.split_at_checked(2)?;
+    if !country.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let valid = if country == "BG" {
+        matches!(rest.len(), 9 | 10) && rest.chars().all(|c| c.is_ascii_digit())
+    } else {
+        (2..=12).contains(&rest.len()) && rest.chars().all(|c| c.is_ascii_alphanumeric())
+    };
+
+    valid.then_some(number)
+}
+
+// Takes the next number. The counter row stays locked until the transaction
+// ends and a rollback gives the number back, so numbers have no gaps.
+async fn next_number(conn: &mut PgConnection, first_number: i64) -> Result<i64, AppError> {
+    let number = sqlx::query_scalar::<_, i64>(
+        r#"
+        UPDATE invoice_numbers SET last_number = GREATEST(last_number + 1, $1)
+        RETURNING last_number
+        "#,
+    )
+    .bind(first_number)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if number > LAST_NUMBER {
+        return Err(AppError::Conflict(
+            "Invoice numbers have run out".to_string(),
+        ));
+    }
+
+    Ok(number)
+}
+
+// The date in Bulgaria, which documents are dated by
+fn today() -> NaiveDate {
+    Utc::now().with_timezone(&Sofia).date_naive()
+}
+
+// Documents cannot be dated ahead; one dated in the future would hold back
+// every document after it until that date
+fn check_not_future(issue_date: NaiveDate, today: NaiveDate) -> Result<(), AppError> {
+    if issue_date > today {
+        return Err(AppError::BadRequest(format!(
+            "Issue date cannot be after today, {}",
+            today.format("%d.%m.%Y")
+        )));
+    }
+
+    Ok(())
+}
+
+// Documents follow each other in date order as well as in number
+async fn check_issue_date(conn: &mut PgConnection, issue_date: NaiveDate) -> Result<(), AppError> {
+    check_not_future(issue_date, today())?;
+
+    let last = sqlx::query_scalar::<_, Option<NaiveDate>>("SELECT MAX(issue_date) FROM invoices")
+        .fetch_one(&mut *conn)
+        .await?;
+
+    match last {
+        Some(last) if issue_date < last => Err(AppError::BadRequest(format!(
+            "Issue date cannot be before {}, the date of the last invoice",
+            last.format("%d.%m.%Y")
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// Line as it goes on the document
+struct NewLine {
+    description: String,
+    quantity: i32,
+    rate: Option<Decimal>,
+    net_amount: Money,
+    vat_amount: Money,
+}
+
+// An invoice is issued for a sale that has taken place: an order paid for,
+// or one paid in cash that has been delivered. Cancelled orders get none,
+// even if a payment came in before they were cancelled.
+fn check_invoiceable(order: &Order) -> Result<(), AppError> {
+    if order.status == OrderStatus::Cancelled.as_str() {
+        return Err(AppError::Conflict(
+            "Cancelled orders cannot be invoiced".to_string(),
+        ));
+    }
+
+    let paid = [
+        PaymentStatus::Paid,
+        PaymentStatus::PartiallyRefunded,
+        PaymentStatus::Refunded,
+    ]
+    .iter()
+    .any(|status| order.payment_status == status.as_str());
+    let delivered_cash = order.payment_method == PaymentMethod::CashOnDelivery.as_str()
+        && order.status == OrderStatus::Delivered.as_str();
+    if !paid && !delivered_cash {
+        return Err(AppError::Conflict(
+            "Only paid orders, or cash on delivery orders once delivered, can be invoiced"
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Issues the invoice of an order: to the company given in the request or at
+// checkout, otherwise to the customer. The lines are the order's items,
+// shipping and cash on delivery fee with the VAT the order was charged.
+pub async fn issue_invoice(
+    conn: &mut PgConnection,
+    invoicing: &Invoicing,
+    order_id: Uuid,
+    request: &IssueInvoiceRequest,
+    admin_id: i32,
+) -> Result<Invoice, AppError> {
+    let seller = invoicing.seller()?;
+
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+
+    check_invoiceable(&order)?;
+
+    let buyer = match &request.buyer {
+        Some(buyer) => Some(validate_buyer(buyer)?),
+        None => order.invoice_company.clone().map(|company| Buyer {
+            company,
+            eik: order.invoice_eik.clone().unwrap_or_default(),
+            vat_number: order.invoice_vat_number.clone(),
+            mol: order.invoice_mol.clone(),
+            address: order.invoice_address.clone().unwrap_or_default(),
+        }),
+    };
+
+    let issue_date = request.issue_date.unwrap_or_else(today);
+    let tax_event_date = request.tax_event_date.unwrap_or(issue_date);
+
+    let items =
+        sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id = $1 ORDER BY id")
+            .bind(order_id)
+            .fetch_all(&mut *conn)
+            .await?;
+
+    let mut lines: Vec<NewLine> = items
+        .iter()
+        .map(|item| NewLine {
+            description: match &item.variant_label {
+                Some(label) => format!("{} ({})", item.product_name, label),
+                None => item.product_name.clone(),
+            },
+            quantity: item.quantity,
+            rate: item.tax_rate,
+            net_amount: item.net_amount,
+            vat_amount: item.vat_amount,
+        })
+        .collect();
+
+    // The order split shipping and the fee together; the fee gets its own
+    // share so the VAT adds up to what the order was charged
+    let (_, charges_vat) = match order.shipping_tax_rate {
+        Some(rate) => tax::split(order.shipping_amount + order.cod_fee, rate),
+        None => (Money::ZERO, Money::ZERO),
+    };
+    let (_, cod_fee_vat) = match order.shipping_tax_rate {
+        Some(rate) => tax::split(order.cod_fee, rate),
+        None => (Money::ZERO, Money::ZERO),
+    };
+    if !order.shipping_amount.is_zero() {
+        let vat = charges_vat - cod_fee_vat;
+        lines.push(NewLine {
+            description: match &order.shipping_method_name {
+                Some(method) => format!("Доставка ({})", method),
+                None => "Доставка".to_string(),
+            },
+            quantity: 1,
+            rate: order.shipping_tax_rate,
+            net_amount: order.shipping_amount - vat,
+            vat_amount: vat,
+        });
+    }
+    if !order.cod_fee.is_zero() {
+        lines.push(NewLine {
+            description: "Такса за наложен платеж".to_string(),
+            quantity: 1,
+            rate: order.shipping_tax_rate,
+            net_amount: order.cod_fee - cod_fee_vat,
+            vat_amount: cod_fee_vat,
+        });
+    }
+
+    // Orders placed before the studio charged VAT have no rates at all
+    let vat_exemption_reason = if order.shipping_tax_rate.is_none() {
+        Some(NOT_VAT_REGISTERED.to_string())
+    } else {
+        let reasons = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT tc.exemption_reason
+            FROM order_items oi
+            JOIN products p ON p.id = oi.product_id
+            JOIN tax_classes tc ON tc.code = p.tax_class
+            WHERE oi.order_id = $1 AND oi.tax_rate = 0 AND tc.exemption_reason IS NOT NULL
+            ORDER BY tc.exemption_reason
+            "#,
+        )
+        .bind(order_id)
+        .fetch_all(&mut *conn)
+        .await?;
+        (!reasons.is_empty()).then(|| reasons.join("; "))
+    };
+
+    let number = next_number(conn, invoicing.first_number).await?;
+    check_issue_date(conn, issue_date).await?;
+
+    let invoice = insert_invoice(
+        conn,
+        NewInvoice {
+            number,
+            kind: InvoiceKind::Invoice,
+            order_id,
+            invoice_id: None,
+            refund_id: None,
+            issue_date,
+            tax_event_date,
+            seller_name: &seller.name,
+            seller_eik: &seller.eik,
+            seller_vat_number: seller.vat_number.as_deref(),
+            seller_mol: seller.mol.as_deref(),
+            seller_address: &seller.address,
+            buyer_name: buyer
+                .as_ref()
+                .map_or(&order.customer_name, |buyer| &buyer.company),
+            buyer_eik: buyer.as_ref().map(|buyer| buyer.eik.as_str()),
+            buyer_vat_number: buyer.as_ref().and_then(|buyer| buyer.vat_number.as_deref()),
+            buyer_mol: buyer.as_ref().and_then(|buyer| buyer.mol.as_deref()),
+            buyer_address: &match &buyer {
+                Some(buyer) => buyer.address.clone(),
+                None => customer_address(&order),
+            },
+            payment_method: &order.payment_method,
+            vat_exemption_reason: vat_exemption_reason.as_deref(),
+            reason: None,
+            admin_id,
+        },
+        &lines,
+    )
+    .await
+    .map_err(|err| match err {
+        AppError::Conflict(_) => {
+            AppError::Conflict("The order has already been invoiced".to_string())
+        }
+        err => err,
+    })?;
+
+    Ok(invoice)
+}
+
+// Issues a credit note against an invoice, for a refund of its order or for
+// an amount. The amount is spread over the invoice's VAT rates in proportion
+// to what each rate came to.
+pub async fn issue_credit_note(
+    conn: &mut PgConnection,
+    invoicing: &Invoicing,
+    invoice_id: i32,
+    request: &CreateCreditNoteRequest,
+    admin_id: i32,
+) -> Result<Invoice, AppError> {
+    let invoice = sqlx::query_as::<_, Invoice>("SELECT * FROM invoices WHERE id = $1 FOR UPDATE")
+        .bind(invoice_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+
+    if invoice.kind != InvoiceKind::Invoice.as_str() {
+        return Err(AppError::BadRequest(
+            "Credit notes can only correct invoices".to_string(),
+        ));
+    }
+
+    let credited = sqlx::query_scalar::<_, Option<Money>>(
+        "SELECT SUM(total_amount) FROM invoices WHERE invoice_id = $1",
+    )
+    .bind(invoice.id)
+    .fetch_one(&mut *conn)
+    .await?
+    .unwrap_or(Money::ZERO);
+    let creditable = invoice.total_amount - credited;
+    if creditable <= Money::ZERO {
+        return Err(AppError::Conflict(
+            "The invoice has already been credited in full".to_string(),
+        ));
+    }
+
+    let refund = match request.refund_id {
+        Some(refund_id) => Some(
+            sqlx::query_as::<_, (Decimal, String, Option<String>)>(
+                r#"
+                SELECT r.amount, r.currency, r.reason
+                FROM refunds r
+                JOIN payments p ON p.id = r.payment_id
+                WHERE r.id = $1 AND p.order_id = $2 AND r.status = 'succeeded'
+                "#,
+            )
+            .bind(refund_id)
+            .bind(invoice.order_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Refund not found for this order".to_string()))?,
+        ),
+        None => None,
+    };
+
+    let amount = match &refund {
+        Some((amount, currency, _)) => {
+            if currency != crate::payments::CURRENCY {
+                return Err(AppError::BadRequest(format!(
+                    "The refund was made in {}; give the amount in euro instead",
+                    currency
+                )));
+            }
+            Money::eur(*amount)
+        }
+        None => request.amount.unwrap_or(creditable),
+    };
+    if amount <= Money::ZERO {
+        return Err(AppError::BadRequest(
+            "Credit note amount must be positive".to_string(),
+        ));
+    }
+    if amount > creditable {
+        return Err(AppError::BadRequest(format!(
+            "Only {} of the invoice can still be credited",
+            creditable
+        )));
+    }
+
+    let reason = request
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty())
+        .map(str::to_string)
+        .or_else(|| refund.as_ref().and_then(|(_, _, reason)| reason.clone()))
+        .unwrap_or_else(|| {
+            if refund.is_some() {
+                "Върната сума".to_string()
+            } else {
+                "Намаление на сумата".to_string()
+            }
+        });
+
+    let description = format!(
+        "Корекция по фактура № {} от {}",
+        format_number(invoice.number),
+        invoice.issue_date.format("%d.%m.%Y")
+    );
+    let lines = credit_lines(
+        &invoice.tax_breakdown,
+        invoice.total_amount,
+        amount,
+        &description,
+    );
+
+    let issue_date = request.issue_date.unwrap_or_else(today);
+
+    let number = next_number(conn, invoicing.first_number).await?;
+    check_issue_date(conn, issue_date).await?;
+
+    insert_invoice(
+        conn,
+        NewInvoice {
+            number,
+            kind: InvoiceKind::CreditNote,
+            order_id: invoice.order_id,
+            invoice_id: Some(invoice.id),
+            refund_id: request.refund_id,
+            issue_date,
+            tax_event_date: issue_date,
+            seller_name: &invoice.seller_name,
+            seller_eik: &invoice.seller_eik,
+            seller_vat_number: invoice.seller_vat_number.as_deref(),
+            seller_mol: invoice.seller_mol.as_deref(),
+            seller_address: &invoice.seller_address,
+            buyer_name: &invoice.buyer_name,
+            buyer_eik: invoice.buyer_eik.as_deref(),
+            buyer_vat_number: invoice.buyer_vat_number.as_deref(),
+            buyer_mol: invoice.buyer_mol.as_deref(),
+            buyer_address: &invoice.buyer_address,
+            payment_method: &invoice.payment_method,
+            vat_exemption_reason: invoice.vat_exemption_reason.as_deref(),
+            reason: Some(&reason),
+            admin_id,
+        },
+        &lines,
+    )
+    .await
+    .map_err(|err| match err {
+        AppError::Conflict(_) => {
+            AppError::Conflict("A credit note has already been issued for this refund".to_string())
+        }
+        err => err,
+    })
+}
+
+// One line per rate of the invoice; the last rate takes what rounding left
+fn credit_lines(
+    breakdown: &[TaxLine],
+    invoice_total: Money,
+    amount: Money,
+    description: &str,
+) -> Vec<NewLine> {
+    if breakdown.is_empty() {
+        return vec![NewLine {
+            description: description.to_string(),
+            quantity: 1,
+            rate: None,
+            net_amount: amount,
+            vat_amount: Money::ZERO,
+        }];
+    }
+
+    let mut remaining = amount;
+    breakdown
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let gross = if index + 1 == breakdown.len() {
+                remaining
+            } else {
+                amount.share(line.gross_amount, invoice_total)
+            };
+            remaining -= gross;
+            let (net_amount, vat_amount) = tax::split(gross, line.rate);
+            NewLine {
+                description: if breakdown.len() > 1 {
+                    format!("{} (ДДС {}%)", description, line.rate.normalize())
+                } else {
+                    description.to_string()
+                },
+                quantity: 1,
+                rate: Some(line.rate),
+                net_amount,
+                vat_amount,
+            }
+        })
+        .filter(|line| !(line.net_amount + line.vat_amount).is_zero())
+        .collect()
+}
+
+fn customer_address(order: &Order) -> String {
+    match &order.delivery_postal_code {
+        Some(postal_code) if !postal_code.is_empty() => format!(
+            "{}, {} {}",
+            order.delivery_address, postal_code, order.delivery_city
+        ),
+        _ => format!("{}, {}", order.delivery_address, order.delivery_city),
+    }
+}
+
+struct NewInvoice<'a> {
+    number: i64,
+    kind: InvoiceKind,
+    order_id: Uuid,
+    invoice_id: Option<i32>,
+    refund_id: Option<i32>,
+    issue_date: NaiveDate,
+    tax_event_date: NaiveDate,
+    seller_name: &'a str,
+    seller_eik: &'a str,
+    seller_vat_number: Option<&'a str>,
+    seller_mol: Option<&'a str>,
+    seller_address: &'a str,
+    buyer_name: &'a str,
+    buyer_eik: Option<&'a str>,
+    buyer_vat_number: Option<&'a str>,
+    buyer_mol: Option<&'a str>,
+    buyer_address: &'a str,
+    payment_method: &'a str,
+    vat_exemption_reason: Option<&'a str>,
+    reason: Option<&'a str>,
+    admin_id: i32,
+}
+
+// Stores the document and its lines with totals and a breakdown worked out
+// from the lines. Conflict when a unique constraint is hit, for the caller to
+// explain.
+async fn insert_invoice(
+    conn: &mut PgConnection,
+    invoice: NewInvoice<'_>,
+    lines: &[NewLine],
+) -> Result<Invoice, AppError> {
+    let mut breakdown = tax::Breakdown::default();
+    for line in lines {
+        if let Some(rate) = line.rate {
+            breakdown.add(rate, line.net_amount, line.vat_amount);
+        }
+    }
+    let net_amount: Money = lines.iter().map(|line| line.net_amount).sum();
+    let vat_amount: Money = lines.iter().map(|line| line.vat_amount).sum();
+
+    let stored = sqlx::query_as::<_, Invoice>(
+        r#"
+        INSERT INTO invoices (
+            number, kind, order_id, invoice_id, refund_id, issue_date, tax_event_date,
+            seller_name, seller_eik, seller_vat_number, seller_mol, seller_address,
+            buyer_name, buyer_eik, buyer_vat_number, buyer_mol, buyer_address,
+            payment_method, net_amount, vat_amount, total_amount, tax_breakdown,
+            vat_exemption_reason, reason, admin_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17,
+                $18, $19, $20, $21, $22, $23, $24, $25)
+        RETURNING *
+        "#,
+    )
+    .bind(invoice.number)
+    .bind(invoice.kind.as_str())
+    .bind(invoice.order_id)
+    .bind(invoice.invoice_id)
+    .bind(invoice.refund_id)
+    .bind(invoice.issue_date)
+    .bind(invoice.tax_event_date)
+    .bind(invoice.seller_name)
+    .bind(invoice.seller_eik)
+    .bind(invoice.seller_vat_number)
+    .bind(invoice.seller_mol)
+    .bind(invoice.seller_address)
+    .bind(invoice.buyer_name)
+    .bind(invoice.buyer_eik)
+    .bind(invoice.buyer_vat_number)
+    .bind(invoice.buyer_mol)
+    .bind(invoice.buyer_address)
+    .bind(invoice.payment_method)
+    .bind(net_amount)
+    .bind(vat_amount)
+    .bind(net_amount + vat_amount)
+    .bind(Json(breakdown.into_lines()))
+    .bind(invoice.vat_exemption_reason)
+    .bind(invoice.reason)
+    .bind(invoice.admin_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| match err.as_database_error().and_then(|e| e.code()) {
+        Some(code) if code == "23505" => AppError::Conflict(err.to_string()),
+        _ => AppError::DatabaseError(err),
+    })?;
+
+    for (position, line) in lines.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO invoice_lines (
+                invoice_id, position, description, quantity, unit_price, tax_rate,
+                net_amount, vat_amount, total_amount
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(stored.id)
+        .bind(position as i32 + 1)
+        .bind(&line.description)
+        .bind(line.quantity)
+        .bind(Money::eur(
+            line.net_amount.amount() / Decimal::from(line.quantity),
+        ))
+        .bind(line.rate)
+        .bind(line.net_amount)
+        .bind(line.vat_amount)
+        .bind(line.net_amount + line.vat_amount)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(stored)
+}
+
+// The document with its lines and related documents
+pub async fn fetch_detail(conn: &mut PgConnection, id: i32) -> Result<InvoiceDetail, AppError> {
+    let invoice = sqlx::query_as::<_, Invoice>("SELECT * FROM invoices WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+
+    let lines = sqlx::query_as::<_, InvoiceLine>(
+        "SELECT * FROM invoice_lines WHERE invoice_id = $1 ORDER BY position",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let credit_notes = sqlx::query_as::<_, Invoice>(
+        "SELECT * FROM invoices WHERE invoice_id = $1 ORDER BY number",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let corrected_invoice = match invoice.invoice_id {
+        Some(invoice_id) => {
+            sqlx::query_as::<_, Invoice>("SELECT * FROM invoices WHERE id = $1")
+                .bind(invoice_id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        None => None,
+    };
+
+    Ok(InvoiceDetail {
+        formatted_number: format_number(invoice.number),
+        invoice,
+        lines,
+        credit_notes,
+        corrected_invoice,
+    })
+}
+
+// Renders the document as a PDF. CPU bound, so it runs on the blocking pool.
+pub async fn render_pdf(invoicing: &Invoicing, detail: InvoiceDetail) -> Result<Vec<u8>, AppError> {
+    let font = invoicing.font().await?;
+
+    tokio::task::spawn_blocking(move || pdf::render(&font, &detail))
+        .await
+        .map_err(|e| {
+            tracing::error!("Invoice rendering task failed: {:?}", e);
+            AppError::InternalServerError("Failed to render invoice".to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Only what decides whether an order can be invoiced
+    fn order(status: OrderStatus, method: PaymentMethod, payment: PaymentStatus) -> Order {
+        Order {
+            status: status.as_str().to_string(),
+            payment_method: method.as_str().to_string(),
+            payment_status: payment.as_str().to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn formats_numbers_with_ten_digits() {
+        assert_eq!(format_number(42), "0000000042");
+        assert_eq!(format_number(LAST_NUMBER), "9999999999");
+    }
+
+    #[test]
+    fn refuses_issue_dates_after_today() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+
+        assert!(check_not_future(today, today).is_ok());
+        assert!(check_not_future(today.pred_opt().unwrap(), today).is_ok());
+        assert!(matches!(
+            check_not_future(today.succ_opt().unwrap(), today),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn checks_eik_check_digits() {
+        assert!(valid_eik("123456786"));
+        assert!(valid_eik("1234567860016"));
+        // First weighted sum leaves 10, so the second weights decide
+        assert!(valid_eik("100000086"));
+
+        for eik in [
+            "123456787",
+            "1234567860017",
+            "12345678",
+            "12345678a",
+            "",
+            "１２３４５６７８６",
+        ] {
+            assert!(!valid_eik(eik), "{eik:?}");
+        }
+    }
+
+    #[test]
+    fn normalizes_vat_numbers() {
+        assert_eq!(
+            normalize_vat_number("bg 123 456 786").as_deref(),
+            Some("BG123456786")
+        );
+        assert_eq!(
+            normalize_vat_number("1234567860").as_deref(),
+            Some("BG1234567860")
+        );
+        assert_eq!(
+            normalize_vat_number("de123456789").as_deref(),
+            Some("DE123456789")
+        );
+
+        for number in ["BG12345678", "BG12345678X", "B", "12", "ДЕ123456789", "DE1"] {
+            assert_eq!(normalize_vat_number(number), None, "{number:?}");
+        }
+    }
+
+    #[test]
+    fn validates_the_buyer() {
+        let request = InvoiceBuyerRequest {
+            company: " Земя ООД ".to_string(),
+            eik: "123 456 786".to_string(),
+            vat_number: Some(" ".to_string()),
+            mol: Some("".to_string()),
+            address: "София".to_string(),
+        };
+        let buyer = validate_buyer(&request).unwrap();
+
+        assert_eq!(buyer.company, "Земя ООД");
+        assert_eq!(buyer.eik, "123456786");
+        assert_eq!(buyer.vat_number, None);
+        assert_eq!(buyer.mol, None);
+
+        let bad_eik = InvoiceBuyerRequest {
+            eik: "123456787".to_string(),
+            ..request
+        };
+        assert!(matches!(
+            validate_buyer(&bad_eik),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn invoices_only_completed_sales() {
+        use PaymentMethod::{BankTransfer, Card, CashOnDelivery};
+
+        let invoiceable = [
+            order(OrderStatus::Processing, Card, PaymentStatus::Paid),
+            order(OrderStatus::Returned, Card, PaymentStatus::Refunded),
+            order(
+                OrderStatus::Shipped,
+                BankTransfer,
+                PaymentStatus::PartiallyRefunded,
+            ),
+            order(
+                OrderStatus::Delivered,
+                CashOnDelivery,
+                PaymentStatus::Pending,
+            ),
+        ];
+        for order in &invoiceable {
+            assert!(check_invoiceable(order).is_ok(), "{}", order.status);
+        }
+
+        let not_invoiceable = [
+            order(OrderStatus::Pending, Card, PaymentStatus::Pending),
+            order(OrderStatus::Processing, Card, PaymentStatus::Failed),
+            order(OrderStatus::Shipped, CashOnDelivery, PaymentStatus::Pending),
+            order(OrderStatus::Cancelled, Card, PaymentStatus::Paid),
+            order(
+                OrderStatus::Cancelled,
+                CashOnDelivery,
+                PaymentStatus::Pending,
+            ),
+        ];
+        for order in &not_invoiceable {
+            assert!(matches!(
+                check_invoiceable(order),
+                Err(AppError::Conflict(_))
+            ));
+        }
+    }
+}
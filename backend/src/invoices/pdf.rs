@@ -0,0 +1,678 @@
+use flate2::{write::ZlibEncoder, Compression};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+use super::{format_number, Font, InvoiceKind};
+use crate::models::{InvoiceDetail, Money, BGN_PER_EUR};
+
+// A4 in points
+const PAGE_WIDTH: f32 = 595.28;
+const PAGE_HEIGHT: f32 = 841.89;
+const MARGIN: f32 = 50.0;
+const RIGHT: f32 = PAGE_WIDTH - MARGIN;
+
+const TITLE_SIZE: f32 = 18.0;
+const TEXT_SIZE: f32 = 9.0;
+const LINE_HEIGHT: f32 = 12.0;
+
+// Right edges of the amount columns of the lines table
+const QUANTITY_RIGHT: f32 = 345.0;
+const UNIT_PRICE_RIGHT: f32 = 415.0;
+const RATE_RIGHT: f32 = 465.0;
+const DESCRIPTION_LEFT: f32 = MARGIN + 25.0;
+const DESCRIPTION_WIDTH: f32 = 215.0;
+
+// Renders an invoice or credit note: parties, lines, totals by rate and the
+// amount due in euro with its lev equivalent. Text is drawn with the
+// embedded font, so Cyrillic needs nothing from the viewer.
+pub fn render(font: &Font, detail: &InvoiceDetail) -> Vec<u8> {
+    let invoice = &detail.invoice;
+    let kind = InvoiceKind::parse(&invoice.kind).unwrap_or(InvoiceKind::Invoice);
+    let mut page = Layout::new(font);
+
+    // Heading
+    page.centered(kind.title(), TITLE_SIZE);
+    page.y -= 8.0;
+    page.centered(&format!("№ {}", format_number(invoice.number)), 12.0);
+    page.centered("ОРИГИНАЛ", TEXT_SIZE);
+    page.y -= 6.0;
+    page.text(
+        MARGIN,
+        &format!(
+            "Дата на издаване: {}",
+            invoice.issue_date.format("%d.%m.%Y")
+        ),
+    );
+    page.text(
+        MARGIN,
+        &format!(
+            "Дата на данъчното събитие: {}",
+            invoice.tax_event_date.format("%d.%m.%Y")
+        ),
+    );
+    if let Some(corrected) = &detail.corrected_invoice {
+        page.text(
+            MARGIN,
+            &format!(
+                "Към фактура № {} от {}",
+                format_number(corrected.number),
+                corrected.issue_date.format("%d.%m.%Y")
+            ),
+        );
+    }
+    page.y -= LINE_HEIGHT;
+
+    // Parties side by side
+    let column_width = (RIGHT - MARGIN) / 2.0 - 10.0;
+    let party = |title: &str,
+                 name: &str,
+                 eik: Option<&str>,
+                 vat_number: Option<&str>,
+                 mol: Option<&str>,
+                 address: &str| {
+        let mut lines = vec![title.to_string(), name.to_string()];
+        if let Some(eik) = eik {
+            lines.push(format!("ЕИК: {}", eik));
+        }
+        if let Some(vat_number) = vat_number {
+            lines.push(format!("ИН по ЗДДС: {}", vat_number));
+        }
+        if let Some(mol) = mol {
+            lines.push(format!("МОЛ: {}", mol));
+        }
+        lines.push(format!("Адрес: {}", address));
+        lines
+            .iter()
+            .flat_map(|line| font_wrap(font, line, column_width, TEXT_SIZE))
+            .collect::<Vec<_>>()
+    };
+    let buyer = party(
+        "Получател",
+        &invoice.buyer_name,
+        invoice.buyer_eik.as_deref(),
+        invoice.buyer_vat_number.as_deref(),
+        invoice.buyer_mol.as_deref(),
+        &invoice.buyer_address,
+    );
+    let seller = party(
+        "Доставчик",
+        &invoice.seller_name,
+        Some(&invoice.seller_eik),
+        invoice.seller_vat_number.as_deref(),
+        invoice.seller_mol.as_deref(),
+        &invoice.seller_address,
+    );
+    let top = page.y;
+    for (index, line) in buyer.iter().enumerate() {
+        page.draw(MARGIN, top - index as f32 * LINE_HEIGHT, line, TEXT_SIZE);
+    }
+    let seller_left = MARGIN + column_width + 20.0;
+    for (index, line) in seller.iter().enumerate() {
+        page.draw(
+            seller_left,
+            top - index as f32 * LINE_HEIGHT,
+            line,
+            TEXT_SIZE,
+        );
+    }
+    page.y = top - buyer.len().max(seller.len()) as f32 * LINE_HEIGHT - LINE_HEIGHT;
+
+    // Lines
+    page.table_header();
+    for line in &detail.lines {
+        let description = font_wrap(font, &line.description, DESCRIPTION_WIDTH, TEXT_SIZE);
+        page.ensure(description.len() as f32 * LINE_HEIGHT + 4.0, true);
+        let y = page.y;
+        page.draw(MARGIN, y, &line.position.to_string(), TEXT_SIZE);
+        for (index, part) in description.iter().enumerate() {
+            page.draw(
+                DESCRIPTION_LEFT,
+                y - index as f32 * LINE_HEIGHT,
+                part,
+                TEXT_SIZE,
+            );
+        }
+        page.draw_right(QUANTITY_RIGHT, y, &line.quantity.to_string(), TEXT_SIZE);
+        page.draw_right(UNIT_PRICE_RIGHT, y, &euro(line.unit_price), TEXT_SIZE);
+        let rate = match line.tax_rate {
+            Some(rate) => format!("{}%", rate.normalize()),
+            None => "-".to_string(),
+        };
+        page.draw_right(RATE_RIGHT, y, &rate, TEXT_SIZE);
+        page.draw_right(RIGHT, y, &euro(line.net_amount), TEXT_SIZE);
+        page.y -= description.len() as f32 * LINE_HEIGHT + 4.0;
+    }
+    page.rule();
+    page.y -= 4.0;
+
+    // Totals
+    let mut totals: Vec<(String, String)> = Vec::new();
+    if invoice.tax_breakdown.is_empty() {
+        totals.push(("Данъчна основа".to_string(), euro(invoice.net_amount)));
+        totals.push(("ДДС".to_string(), euro(invoice.vat_amount)));
+    } else {
+        for tax in invoice.tax_breakdown.iter() {
+            let rate = tax.rate.normalize();
+            totals.push((format!("Данъчна основа {}%", rate), euro(tax.net_amount)));
+            totals.push((format!("ДДС {}%", rate), euro(tax.vat_amount)));
+        }
+    }
+    let due_label = match kind {
+        InvoiceKind::Invoice => "Сума за плащане",
+        InvoiceKind::CreditNote => "Сума на кредитното известие",
+    };
+    totals.push((due_label.to_string(), euro(invoice.total_amount)));
+    totals.push((
+        "Равностойност в лева".to_string(),
+        format!("{} лв.", invoice.total_amount.bgn()),
+    ));
+    page.ensure(totals.len() as f32 * LINE_HEIGHT, false);
+    for (label, value) in &totals {
+        page.draw_right(UNIT_PRICE_RIGHT + 50.0, page.y, label, TEXT_SIZE);
+        page.draw_right(RIGHT, page.y, value, TEXT_SIZE);
+        page.y -= LINE_HEIGHT;
+    }
+    page.y -= LINE_HEIGHT;
+
+    // Notes
+    page.paragraph(&format!(
+        "Начин на плащане: {}",
+        payment_method_label(&invoice.payment_method)
+    ));
+    if let Some(reason) = &invoice.vat_exemption_reason {
+        page.paragraph(&format!("Основание за неначисляване на ДДС: {}", reason));
+    }
+    if let Some(reason) = &invoice.reason {
+        page.paragraph(&format!("Основание за издаване: {}", reason));
+    }
+    page.paragraph(&format!(
+        "Сумите са в евро. Левовата равностойност е по фиксирания курс 1 EUR = {} BGN.",
+        BGN_PER_EUR
+    ));
+
+    page.finish()
+}
+
+// Amount with the euro sign, e.g. "12.50 €"
+fn euro(amount: Money) -> String {
+    format!("{} €", amount.amount())
+}
+
+fn payment_method_label(method: &str) -> &str {
+    match method {
+        "cash_on_delivery" => "наложен платеж",
+        "bank_transfer" => "банков превод",
+        "card" => "с карта",
+        other => other,
+    }
+}
+
+// Splits text into lines that fit the width, breaking between words; a word
+// longer than the width gets a line of its own
+fn font_wrap(font: &Font, text: &str, width: f32, size: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", current, word)
+        };
+        if font.text_width(&candidate, size) > width && !current.is_empty() {
+            lines.push(std::mem::replace(&mut current, word.to_string()));
+        } else {
+            current = candidate;
+        }
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+// Pages being drawn top to bottom, and the glyphs they use
+struct Layout<'a> {
+    font: &'a Font,
+    pages: Vec<String>,
+    // Baseline of the next line on the current page
+    y: f32,
+    // Glyph id to the character it was drawn for
+    glyphs: BTreeMap<u16, char>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(font: &'a Font) -> Self {
+        Self {
+            font,
+            pages: vec![String::new()],
+            y: PAGE_HEIGHT - MARGIN - TITLE_SIZE,
+            glyphs: BTreeMap::new(),
+        }
+    }
+
+    fn draw(&mut self, x: f32, y: f32, text: &str, size: f32) {
+        let mut hex = String::with_capacity(text.len() * 4);
+        for c in text.chars() {
+            let glyph = self.font.glyph(c);
+            self.glyphs.entry(glyph).or_insert(c);
+            let _ = write!(hex, "{:04X}", glyph);
+        }
+        let content = self.pages.last_mut().expect("a page is always open");
+        let _ = writeln!(
+            content,
+            "BT /F1 {:.1} Tf {:.2} {:.2} Td <{}> Tj ET",
+            size, x, y, hex
+        );
+    }
+
+    fn draw_right(&mut self, right: f32, y: f32, text: &str, size: f32) {
+        let x = right - self.font.text_width(text, size);
+        self.draw(x, y, text, size);
+    }
+
+    fn centered(&mut self, text: &str, size: f32) {
+        let x = (PAGE_WIDTH - self.font.text_width(text, size)) / 2.0;
+        self.draw(x, self.y, text, size);
+        self.y -= size * 1.3;
+    }
+
+    // One line at the left margin
+    fn text(&mut self, x: f32, text: &str) {
+        self.draw(x, self.y, text, TEXT_SIZE);
+        self.y -= LINE_HEIGHT;
+    }
+
+    // Text wrapped to the page width
+    fn paragraph(&mut self, text: &str) {
+        for line in font_wrap(self.font, text, RIGHT - MARGIN, TEXT_SIZE) {
+            self.ensure(LINE_HEIGHT, false);
+            self.text(MARGIN, &line);
+        }
+    }
+
+    // Horizontal line under the text above
+    fn rule(&mut self) {
+        let y = self.y + LINE_HEIGHT - 3.0;
+        let content = self.pages.last_mut().expect("a page is always open");
+        let _ = writeln!(
+            content,
+            "0.5 w {:.2} {:.2} m {:.2} {:.2} l S",
+            MARGIN, y, RIGHT, y
+        );
+    }
+
+    fn table_header(&mut self) {
+        let y = self.y;
+        self.draw(MARGIN, y, "№", TEXT_SIZE);
+        self.draw(DESCRIPTION_LEFT, y, "Описание", TEXT_SIZE);
+        self.draw_right(QUANTITY_RIGHT, y, "Кол.", TEXT_SIZE);
+        self.draw_right(UNIT_PRICE_RIGHT, y, "Ед. цена", TEXT_SIZE);
+        self.draw_right(RATE_RIGHT, y, "ДДС", TEXT_SIZE);
+        self.draw_right(RIGHT, y, "Стойност", TEXT_SIZE);
+        self.y -= LINE_HEIGHT;
+        self.rule();
+        self.y -= 4.0;
+    }
+
+    // Starts a new page when `height` no longer fits; within the lines
+    // table the header is repeated
+    fn ensure(&mut self, height: f32, in_table: bool) {
+        if self.y - height >= MARGIN {
+            return;
+        }
+        self.pages.push(String::new());
+        self.y = PAGE_HEIGHT - MARGIN - TEXT_SIZE;
+        if in_table {
+            self.table_header();
+        }
+    }
+
+    // Writes out the document: catalog, pages, the font as a Type 0 font
+    // addressing glyphs by id with only the glyphs used embedded, and the
+    // page contents
+    fn finish(self) -> Vec<u8> {
+        let font = self.font;
+        let mut pdf = Pdf::default();
+        let name = format!("{}+InvoiceSans", subset_tag(self.glyphs.keys().copied()));
+
+        // Objects 1 to 7 are fixed; pages and their contents follow
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|i| 8 + i * 2).collect();
+
+        pdf.object(1, "<< /Type /Catalog /Pages 2 0 R >>");
+        pdf.object(
+            2,
+            &format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_ids
+                    .iter()
+                    .map(|id| format!("{} 0 R", id))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                page_ids.len()
+            ),
+        );
+        pdf.object(
+            3,
+            &format!(
+                "<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H \
+                 /DescendantFonts [4 0 R] /ToUnicode 7 0 R >>",
+                name
+            ),
+        );
+
+        let widths: String = self
+            .glyphs
+            .keys()
+            .map(|glyph| format!("{} [{}]", glyph, font.width(*glyph)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        pdf.object(
+            4,
+            &format!(
+                "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{} \
+                 /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+                 /FontDescriptor 5 0 R /CIDToGIDMap /Identity /DW 500 /W [{}] >>",
+                name, widths
+            ),
+        );
+
+        let [x_min, y_min, x_max, y_max] = font.bbox.map(|value| font.scale(value as i32));
+        pdf.object(
+            5,
+            &format!(
+                "<< /Type /FontDescriptor /FontName /{} /Flags 32 \
+                 /FontBBox [{} {} {} {}] /ItalicAngle 0 /Ascent {} /Descent {} \
+                 /CapHeight {} /StemV 80 /FontFile2 6 0 R >>",
+                name,
+                x_min,
+                y_min,
+                x_max,
+                y_max,
+                font.scale(font.ascent as i32),
+                font.scale(font.descent as i32),
+                font.scale(font.cap_height as i32)
+            ),
+        );
+        let subset = font.subset(self.glyphs.keys().copied());
+        pdf.stream(6, &format!("/Length1 {}", subset.len()), &subset);
+
+        let mut cmap = String::from(
+            "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+             /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+             1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+        );
+        // At most 100 entries a block
+        let glyphs: Vec<(&u16, &char)> = self.glyphs.iter().collect();
+        for block in glyphs.chunks(100) {
+            let _ = writeln!(cmap, "{} beginbfchar", block.len());
+            for (glyph, c) in block {
+                let mut units = [0u16; 2];
+                let unicode: String = c
+                    .encode_utf16(&mut units)
+                    .iter()
+                    .map(|unit| format!("{:04X}", unit))
+                    .collect();
+                let _ = writeln!(cmap, "<{:04X}> <{}>", glyph, unicode);
+            }
+            cmap.push_str("endbfchar\n");
+        }
+        cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+        pdf.stream(7, "", cmap.as_bytes());
+
+        for (index, content) in self.pages.iter().enumerate() {
+            let id = page_ids[index];
+            pdf.object(
+                id,
+                &format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                     /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                    PAGE_WIDTH,
+                    PAGE_HEIGHT,
+                    id + 1
+                ),
+            );
+            pdf.stream(id + 1, "", content.as_bytes());
+        }
+
+        pdf.finish()
+    }
+}
+
+// Six capital letters naming a subset, the same for the same glyphs, as
+// viewers take fonts of one name to be the same font
+fn subset_tag(glyphs: impl Iterator<Item = u16>) -> String {
+    // FNV-1a
+    let mut hash = glyphs.fold(0x811C_9DC5u32, |hash, glyph| {
+        (hash ^ glyph as u32).wrapping_mul(0x0100_0193)
+    });
+    (0..6)
+        .map(|_| {
+            let letter = (b'A' + (hash % 26) as u8) as char;
+            hash /= 26;
+            letter
+        })
+        .collect()
+}
+
+// PDF file being written, with where each object starts for the
+// cross-reference table
+#[derive(Default)]
+struct Pdf {
+    data: Vec<u8>,
+    offsets: BTreeMap<usize, usize>,
+}
+
+impl Pdf {
+    fn start_object(&mut self, id: usize) {
+        if self.data.is_empty() {
+            // The binary comment tells transfer tools the file is not text
+            self.data
+                .extend_from_slice(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n");
+        }
+        self.offsets.insert(id, self.data.len());
+        self.data
+            .extend_from_slice(format!("{} 0 obj\n", id).as_bytes());
+    }
+
+    fn object(&mut self, id: usize, body: &str) {
+        self.start_object(id);
+        self.data.extend_from_slice(body.as_bytes());
+        self.data.extend_from_slice(b"\nendobj\n");
+    }
+
+    // Stream compressed with deflate; `entries` go in its dictionary
+    fn stream(&mut self, id: usize, entries: &str, content: &[u8]) {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        let compressed = encoder
+            .write_all(content)
+            .and_then(|_| encoder.finish())
+            .expect("compressing into memory cannot fail");
+
+        self.start_object(id);
+        self.data.extend_from_slice(
+            format!(
+                "<< /Length {} /Filter /FlateDecode {} >>\nstream\n",
+                compressed.len(),
+                entries
+            )
+            .as_bytes(),
+        );
+        self.data.extend_from_slice(&compressed);
+        self.data.extend_from_slice(b"\nendstream\nendobj\n");
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let size = self.offsets.keys().max().copied().unwrap_or(0) + 1;
+        let xref = self.data.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", size);
+        for id in 1..size {
+            match self.offsets.get(&id) {
+                Some(offset) => {
+                    let _ = writeln!(table, "{:010} 00000 n ", offset);
+                }
+                None => table.push_str("0000000000 65535 f \n"),
+            }
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            size, xref
+        );
+        self.data.extend_from_slice(table.as_bytes());
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Invoice, InvoiceLine};
+    use chrono::NaiveDate;
+    use flate2::read::ZlibDecoder;
+    use rust_decimal::Decimal;
+    use sqlx::types::Json;
+    use std::io::Read as _;
+    use uuid::Uuid;
+
+    use super::super::font::tests::sample_font;
+
+    fn euros(amount: &str) -> Money {
+        Money::eur(amount.parse::<Decimal>().unwrap())
+    }
+
+    fn detail() -> InvoiceDetail {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        InvoiceDetail {
+            invoice: Invoice {
+                id: 1,
+                number: 42,
+                kind: "invoice".to_string(),
+                order_id: Uuid::nil(),
+                invoice_id: None,
+                refund_id: None,
+                issue_date: date,
+                tax_event_date: date,
+                seller_name: "Студио Земя ЕООД".to_string(),
+                seller_eik: "812114069".to_string(),
+                seller_vat_number: None,
+                seller_mol: Some("Мария Иванова".to_string()),
+                seller_address: "ул. Цар Симеон 1, София".to_string(),
+                buyer_name: "Фея ООД".to_string(),
+                buyer_eik: Some("131468980".to_string()),
+                buyer_vat_number: None,
+                buyer_mol: None,
+                buyer_address: "бул. Витоша 2, София".to_string(),
+                payment_method: "card".to_string(),
+                net_amount: euros("24.00"),
+                vat_amount: euros("0.00"),
+                total_amount: euros("24.00"),
+                tax_breakdown: Json(Vec::new()),
+                vat_exemption_reason: None,
+                reason: None,
+                admin_id: Some(1),
+                created_at: date.and_hms_opt(10, 0, 0).unwrap(),
+            },
+            formatted_number: format_number(42),
+            lines: vec![InvoiceLine {
+                id: 1,
+                invoice_id: 1,
+                position: 1,
+                description: "Ваза Фея".to_string(),
+                quantity: 2,
+                unit_price: euros("12.00"),
+                tax_rate: None,
+                net_amount: euros("24.00"),
+                vat_amount: euros("0.00"),
+                total_amount: euros("24.00"),
+            }],
+            credit_notes: Vec::new(),
+            corrected_invoice: None,
+        }
+    }
+
+    // Dictionary and inflated content of every stream
+    fn streams(pdf: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut streams = Vec::new();
+        let mut rest = pdf;
+        while let Some(start) = find(rest, b">>\nstream\n") {
+            let dictionary_start = rfind(&rest[..start], b"<<").unwrap();
+            let dictionary = String::from_utf8_lossy(&rest[dictionary_start..start]).into_owned();
+            let content_start = start + b">>\nstream\n".len();
+            let end = content_start + find(&rest[content_start..], b"\nendstream").unwrap();
+            let mut content = Vec::new();
+            ZlibDecoder::new(&rest[content_start..end])
+                .read_to_end(&mut content)
+                .unwrap();
+            streams.push((dictionary, content));
+            rest = &rest[end..];
+        }
+        streams
+    }
+
+    fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+        data.windows(needle.len())
+            .position(|window| window == needle)
+    }
+
+    fn rfind(data: &[u8], needle: &[u8]) -> Option<usize> {
+        data.windows(needle.len())
+            .rposition(|window| window == needle)
+    }
+
+    #[test]
+    fn embeds_a_subset_of_the_glyphs_drawn() {
+        let file = sample_font();
+        let font = Font::parse(file.clone()).unwrap();
+        let pdf = render(&font, &detail());
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        // Of the letters drawn, the sample font only has 'Ф'
+        let tag = subset_tag([0, font.glyph('Ф')].into_iter());
+        let name = format!("/{}+InvoiceSans", tag);
+        assert_eq!(text.matches(&format!("/BaseFont {}", name)).count(), 2);
+        assert!(text.contains(&format!("/FontName {}", name)));
+
+        let streams = streams(&pdf);
+        let (dictionary, embedded) = streams
+            .iter()
+            .find(|(dictionary, _)| dictionary.contains("/Length1"))
+            .unwrap();
+        assert!(dictionary.contains(&format!("/Length1 {}", embedded.len())));
+        assert_eq!(*embedded, font.subset([0, font.glyph('Ф')]));
+        assert!(embedded.len() < file.len());
+
+        let to_unicode = streams
+            .iter()
+            .map(|(_, content)| String::from_utf8_lossy(content).into_owned())
+            .find(|content| content.contains("beginbfchar"))
+            .unwrap();
+        assert!(to_unicode.contains("<0002> <0424>"));
+    }
+
+    #[test]
+    fn draws_cyrillic_text_by_glyph_id() {
+        let font = Font::parse(sample_font()).unwrap();
+        let mut layout = Layout::new(&font);
+        layout.draw(MARGIN, 700.0, "ФХA", TEXT_SIZE);
+
+        assert!(layout.pages[0].contains("<000200030001> Tj"));
+        assert_eq!(
+            layout.glyphs,
+            BTreeMap::from([(1, 'A'), (2, 'Ф'), (3, 'Х')])
+        );
+    }
+
+    #[test]
+    fn names_subsets_by_their_glyphs() {
+        let tag = subset_tag([0u16, 2, 3].into_iter());
+
+        assert_eq!(tag.len(), 6);
+        assert!(tag.chars().all(|c| c.is_ascii_uppercase()));
+        assert_eq!(tag, subset_tag([0u16, 2, 3].into_iter()));
+        assert_ne!(tag, subset_tag([0u16, 2].into_iter()));
+    }
+}
@@ -0,0 +1,215 @@
+use lettre::message::Mailbox;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use std::sync::Arc;
+
+use crate::{
+    couriers::{self, Couriers},
+    email::{self, SharedMailer},
+    error::AppError,
+    idempotency,
+    payments::{self, bank_transfer, Payments},
+    reservations,
+};
+
+mod schedule;
+mod worker;
+
+// Work done outside of requests. Stored as `kind` plus the remaining fields as
+// the JSON `payload`, so renaming a variant or field needs a migration for the
+// jobs still queued.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Job {
+    // Delivers one email from `email_outbox`
+    SendEmail { email_id: i64 },
+    ReleaseExpiredReservations,
+    PurgeIdempotencyKeys,
+    PurgeFinishedJobs,
+    // Advances orders from the tracking of their waybills
+    PollShipments,
+    // Cancels bank transfer orders not paid by their deadline
+    CancelUnpaidTransfers,
+    // Cancels card orders whose payment was never completed
+    CancelAbandonedCardPayments,
+}
+
+impl Job {
+    pub fn max_attempts(&self) -> i32 {
+        match self {
+            Job::SendEmail { .. } => 8,
+            // Cron jobs run again on their next tick anyway
+            Job::ReleaseExpiredReservations
+            | Job::PurgeIdempotencyKeys
+            | Job::PurgeFinishedJobs
+            | Job::PollShipments
+            | Job::CancelUnpaidTransfers
+            | Job::CancelAbandonedCardPayments => 3,
+        }
+    }
+
+    // Splits the job into its `kind` and `payload` columns
+    fn to_columns(&self) -> (String, serde_json::Value) {
+        let mut value = serde_json::to_value(self).expect("jobs serialize to JSON objects");
+        let kind = value
+            .as_object_mut()
+            .and_then(|fields| fields.remove("kind"))
+            .and_then(|kind| kind.as_str().map(str::to_string))
+            .expect("jobs are tagged with their kind");
+
+        (kind, value)
+    }
+
+    fn from_columns(kind: &str, mut payload: serde_json::Value) -> Result<Self, String> {
+        match payload.as_object_mut() {
+            Some(fields) => {
+                fields.insert("kind".to_string(), kind.into());
+            }
+            None => return Err(format!("Payload of {} job is not an object", kind)),
+        }
+
+        serde_json::from_value(payload).map_err(|e| format!("Invalid {} job: {}", kind, e))
+    }
+
+    async fn run(self, ctx: &JobContext, attempt: Attempt) -> Result<(), JobError> {
+        match self {
+            Job::SendEmail { email_id } => {
+                email::deliver(
+                    &ctx.pool,
+                    ctx.mailer.as_ref(),
+                    &ctx.sender,
+                    email_id,
+                    attempt.is_last(),
+                )
+                .await?;
+            }
+            Job::ReleaseExpiredReservations => {
+                let released = reservations::release_expired(&ctx.pool).await?;
+                if released > 0 {
+                    tracing::info!("Released {} expired stock reservations", released);
+                }
+            }
+            Job::PurgeIdempotencyKeys => {
+                let purged = idempotency::purge_expired(&ctx.pool).await?;
+                if purged > 0 {
+                    tracing::info!("Purged {} expired idempotency keys", purged);
+                }
+            }
+            Job::PurgeFinishedJobs => {
+                let purged = worker::purge_finished(&ctx.pool).await?;
+                if purged > 0 {
+                    tracing::info!("Purged {} finished jobs", purged);
+                }
+            }
+            Job::PollShipments => {
+                let advanced = couriers::poll_shipments(&ctx.pool, &ctx.couriers).await?;
+                if advanced > 0 {
+                    tracing::info!("Tracking moved {} shipments along", advanced);
+                }
+            }
+            Job::CancelUnpaidTransfers => {
+                let cancelled = bank_transfer::cancel_overdue(&ctx.pool).await?;
+                if cancelled > 0 {
+                    tracing::info!("Cancelled {} unpaid bank transfer orders", cancelled);
+                }
+            }
+            Job::CancelAbandonedCardPayments => {
+                let cancelled = payments::cancel_abandoned(&ctx.pool, &ctx.payments).await?;
+                if cancelled > 0 {
+                    tracing::info!("Cancelled {} unpaid card orders", cancelled);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Lifecycle of a job; stored in `jobs.status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    // Out of attempts, waiting for an admin to retry it
+    Dead,
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Dead => "dead",
+        }
+    }
+}
+
+// Everything jobs need besides their payload
+pub struct JobContext {
+    pub pool: PgPool,
+    pub mailer: SharedMailer,
+    pub sender: Mailbox,
+    pub couriers: Couriers,
+    pub payments: Payments,
+}
+
+// Which run of a job this is
+#[derive(Debug, Clone, Copy)]
+pub struct Attempt {
+    pub number: i32,
+    pub max: i32,
+}
+
+impl Attempt {
+    // A failure now leaves the job dead
+    pub fn is_last(self) -> bool {
+        self.number >= self.max
+    }
+}
+
+// Why a job run failed, kept in `jobs.last_error`
+#[derive(Debug)]
+pub struct JobError(pub String);
+
+impl From<AppError> for JobError {
+    fn from(err: AppError) -> Self {
+        JobError(format!("{:?}", err))
+    }
+}
+
+impl From<String> for JobError {
+    fn from(message: String) -> Self {
+        JobError(message)
+    }
+}
+
+// Queues a job as part of the caller's transaction, so it only runs if the
+// work that asked for it is committed
+pub async fn enqueue(conn: &mut PgConnection, job: &Job) -> Result<i64, AppError> {
+    let (kind, payload) = job.to_columns();
+
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO jobs (kind, payload, max_attempts) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(kind)
+    .bind(payload)
+    .bind(job.max_attempts())
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(id)
+}
+
+// Starts the job workers and the cron scheduler for the lifetime of the server
+pub async fn start(ctx: JobContext, workers: usize) -> Result<(), AppError> {
+    schedule::sync_schedules(&ctx.pool).await?;
+
+    let ctx = Arc::new(ctx);
+    worker::spawn_workers(ctx.clone(), workers);
+    schedule::spawn_scheduler(ctx.pool.clone());
+
+    Ok(())
+}
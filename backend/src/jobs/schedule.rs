@@ -0,0 +1,136 @@
+use chrono::{NaiveDateTime, Utc};
+use cron::Schedule;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::{enqueue, Job};
+use crate::error::AppError;
+
+// How often due schedules are checked, the precision of every schedule
+const TICK_INTERVAL: Duration = Duration::from_secs(15);
+
+// A job queued on a cron schedule
+pub struct ScheduledJob {
+    pub name: &'static str,
+    // sec min hour day-of-month month day-of-week, in UTC
+    pub cron: &'static str,
+    pub job: Job,
+}
+
+pub const SCHEDULES: &[ScheduledJob] = &[
+    ScheduledJob {
+        name: "release_expired_reservations",
+        cron: "0 * * * * *",
+        job: Job::ReleaseExpiredReservations,
+    },
+    ScheduledJob {
+        name: "purge_idempotency_keys",
+        cron: "0 15 * * * *",
+        job: Job::PurgeIdempotencyKeys,
+    },
+    ScheduledJob {
+        name: "purge_finished_jobs",
+        cron: "0 30 3 * * *",
+        job: Job::PurgeFinishedJobs,
+    },
+    ScheduledJob {
+        name: "poll_shipments",
+        cron: "0 */20 * * * *",
+        job: Job::PollShipments,
+    },
+    ScheduledJob {
+        name: "cancel_unpaid_transfers",
+        cron: "0 45 * * * *",
+        job: Job::CancelUnpaidTransfers,
+    },
+    ScheduledJob {
+        name: "cancel_abandoned_card_payments",
+        cron: "0 */10 * * * *",
+        job: Job::CancelAbandonedCardPayments,
+    },
+];
+
+fn parse(scheduled: &ScheduledJob) -> Schedule {
+    Schedule::from_str(scheduled.cron)
+        .unwrap_or_else(|e| panic!("Invalid cron for {}: {}", scheduled.name, e))
+}
+
+fn next_run(schedule: &Schedule, after: NaiveDateTime) -> Result<NaiveDateTime, AppError> {
+    schedule
+        .after(&after.and_utc())
+        .next()
+        .map(|next| next.naive_utc())
+        .ok_or_else(|| AppError::InternalServerError("Schedule has no upcoming run".to_string()))
+}
+
+// Registers the schedules, restarting the ones whose cron changed
+pub(super) async fn sync_schedules(pool: &PgPool) -> Result<(), AppError> {
+    let now = Utc::now().naive_utc();
+
+    for scheduled in SCHEDULES {
+        let next_run_at = next_run(&parse(scheduled), now)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO job_schedules (name, schedule, next_run_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (name) DO UPDATE SET
+                schedule = EXCLUDED.schedule,
+                next_run_at = EXCLUDED.next_run_at
+            WHERE job_schedules.schedule <> EXCLUDED.schedule
+            "#,
+        )
+        .bind(scheduled.name)
+        .bind(scheduled.cron)
+        .bind(next_run_at)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub(super) fn spawn_scheduler(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            for scheduled in SCHEDULES {
+                if let Err(e) = queue_if_due(&pool, scheduled).await {
+                    tracing::error!("Failed to queue scheduled job {}: {:?}", scheduled.name, e);
+                }
+            }
+        }
+    });
+}
+
+// Queues the job when its time has come. Advancing the schedule and queueing
+// happen in one transaction, so each run is queued once across all instances;
+// runs missed while the API was down collapse into one.
+async fn queue_if_due(pool: &PgPool, scheduled: &ScheduledJob) -> Result<(), AppError> {
+    let now = Utc::now().naive_utc();
+    let next_run_at = next_run(&parse(scheduled), now)?;
+
+    let mut tx = pool.begin().await?;
+
+    let claimed = sqlx::query(
+        r#"
+        UPDATE job_schedules SET next_run_at = $2, last_run_at = $3
+        WHERE name = $1 AND next_run_at <= $3
+        "#,
+    )
+    .bind(scheduled.name)
+    .bind(next_run_at)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+
+    if claimed.rows_affected() == 1 {
+        enqueue(&mut tx, &scheduled.job).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{Attempt, Job, JobContext, JobError};
+use crate::error::AppError;
+
+// How long an idle worker waits before looking for due jobs again
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// A job still running after this long is assumed lost with its worker and
+// handed to another one
+const STALE_CLAIM_MINUTES: i32 = 15;
+
+// First retry delay, doubled on every further failure
+const BASE_RETRY_SECONDS: i64 = 30;
+const MAX_RETRY_SECONDS: i64 = 6 * 60 * 60;
+
+// Succeeded jobs are kept this long for inspection
+const SUCCEEDED_RETENTION_DAYS: i32 = 7;
+
+#[derive(sqlx::FromRow)]
+struct ClaimedJob {
+    id: i64,
+    kind: String,
+    payload: serde_json::Value,
+    attempts: i32,
+    max_attempts: i32,
+}
+
+pub(super) fn spawn_workers(ctx: Arc<JobContext>, count: usize) {
+    for _ in 0..count {
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            loop {
+                match run_next(&ctx).await {
+                    Ok(true) => {}
+                    Ok(false) => tokio::time::sleep(POLL_INTERVAL).await,
+                    Err(e) => {
+                        tracing::error!("Job worker error: {:?}", e);
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                }
+            }
+        });
+    }
+}
+
+// Claims and runs one due job. Returns false when there was nothing to do.
+async fn run_next(ctx: &Arc<JobContext>) -> Result<bool, AppError> {
+    let claimed = sqlx::query_as::<_, ClaimedJob>(
+        r#"
+        UPDATE jobs
+        SET status = 'running', attempts = attempts + 1, locked_at = NOW()
+        WHERE id = (
+            SELECT id FROM jobs
+            WHERE (status = 'queued' AND run_at <= NOW())
+               OR (status = 'running' AND locked_at < NOW() - make_interval(mins => $1))
+            ORDER BY run_at, id
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, kind, payload, attempts, max_attempts
+        "#,
+    )
+    .bind(STALE_CLAIM_MINUTES)
+    .fetch_optional(&ctx.pool)
+    .await?;
+
+    let Some(claimed) = claimed else {
+        return Ok(false);
+    };
+
+    let job = match Job::from_columns(&claimed.kind, claimed.payload) {
+        Ok(job) => job,
+        Err(error) => {
+            // Retrying cannot fix a payload this build does not understand
+            mark_dead(&ctx.pool, claimed.id, &error).await?;
+            return Ok(true);
+        }
+    };
+
+    let attempt = Attempt {
+        number: claimed.attempts,
+        max: claimed.max_attempts,
+    };
+
+    // Run on its own task so a panicking job fails instead of killing the worker
+    let task_ctx = ctx.clone();
+    let result = tokio::spawn(async move { job.run(&task_ctx, attempt).await })
+        .await
+        .unwrap_or_else(|e| Err(JobError(format!("Job panicked: {}", e))));
+
+    match result {
+        Ok(()) => {
+            sqlx::query(
+                "UPDATE jobs SET status = 'succeeded', finished_at = NOW(), locked_at = NULL, last_error = NULL WHERE id = $1",
+            )
+            .bind(claimed.id)
+            .execute(&ctx.pool)
+            .await?;
+        }
+        Err(JobError(error)) if attempt.is_last() => {
+            tracing::error!(
+                "Job {} ({}) failed for good after {} attempts: {}",
+                claimed.id,
+                claimed.kind,
+                attempt.number,
+                error
+            );
+            mark_dead(&ctx.pool, claimed.id, &error).await?;
+        }
+        Err(JobError(error)) => {
+            tracing::warn!(
+                "Job {} ({}) failed (attempt {} of {}): {}",
+                claimed.id,
+                claimed.kind,
+                attempt.number,
+                attempt.max,
+                error
+            );
+
+            sqlx::query(
+                r#"
+                UPDATE jobs
+                SET status = 'queued', locked_at = NULL, last_error = $2,
+                    run_at = NOW() + make_interval(secs => $3)
+                WHERE id = $1
+                "#,
+            )
+            .bind(claimed.id)
+            .bind(&error)
+            .bind(retry_delay_seconds(attempt.number) as f64)
+            .execute(&ctx.pool)
+            .await?;
+        }
+    }
+
+    Ok(true)
+}
+
+// 30s, 1m, 2m, 4m... capped at six hours
+fn retry_delay_seconds(attempt: i32) -> i64 {
+    let doublings = (attempt - 1).clamp(0, 20) as u32;
+    BASE_RETRY_SECONDS
+        .saturating_mul(2_i64.pow(doublings))
+        .min(MAX_RETRY_SECONDS)
+}
+
+async fn mark_dead(pool: &PgPool, id: i64, error: &str) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE jobs SET status = 'dead', finished_at = NOW(), locked_at = NULL, last_error = $2 WHERE id = $1",
+    )
+    .bind(id)
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Deletes succeeded jobs past their retention; dead jobs stay until retried
+pub(super) async fn purge_finished(pool: &PgPool) -> Result<u64, AppError> {
+    let purged = sqlx::query(
+        "DELETE FROM jobs WHERE status = 'succeeded' AND finished_at < NOW() - make_interval(days => $1)",
+    )
+    .bind(SUCCEEDED_RETENTION_DAYS)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(purged)
+}
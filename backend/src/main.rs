@@ -1,6 +1,7 @@
 use axum::{
+    extract::DefaultBodyLimit,
     middleware,
-    routing::{delete, get, post, put},
+    routing::{get, post, put},
     Router,
 };
 use axum_prometheus::PrometheusMetricLayer;
@@ -14,9 +15,28 @@ use tower_http::{
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod auth;
+mod couriers;
+mod email;
 mod error;
 mod handlers;
+mod idempotency;
+mod inventory;
+mod invoices;
+mod jobs;
+mod media;
 mod models;
+mod notifications;
+mod order_status;
+mod order_tracking;
+mod payments;
+mod promotions;
+mod rate_limit;
+mod reservations;
+mod shipping;
+mod slug;
+mod state;
+mod storage;
+mod tax;
 
 #[tokio::main]
 async fn main() {
@@ -53,34 +73,143 @@ async fn main() {
 
     tracing::info!("Database ready!");
 
+    // Econt and Speedy, for the couriers that have credentials
+    let couriers = couriers::from_env();
+
+    // Card payments and the bank account for transfers, when configured
+    let payments = payments::from_env();
+
+    // Background jobs: queued emails (SMTP, or .eml files for development),
+    // expired reservations, shipment tracking and other housekeeping
+    let job_workers = std::env::var("JOB_WORKERS")
+        .unwrap_or_else(|_| "2".to_string())
+        .parse::<usize>()
+        .expect("JOB_WORKERS must be a valid number");
+
+    jobs::start(
+        jobs::JobContext {
+            pool: db_pool.clone(),
+            mailer: email::from_env(),
+            sender: email::sender_from_env(),
+            couriers: couriers.clone(),
+            payments: payments.clone(),
+        },
+        job_workers,
+    )
+    .await
+    .expect("Failed to start background jobs");
+
     // CORS configuration
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any);
 
-    // Get upload directory from env
+    // Get upload directories from env; private files such as waybill labels
+    // go to PRIVATE_DIR, which must not be served
     let upload_dir = std::env::var("UPLOAD_DIR").unwrap_or_else(|_| "./uploads".to_string());
+    let private_dir = std::env::var("PRIVATE_DIR").unwrap_or_else(|_| "./private".to_string());
 
-    // Create upload directory if it doesn't exist
-    tokio::fs::create_dir_all(&upload_dir)
-        .await
-        .expect("Failed to create upload directory");
+    // Media storage backend (local disk or S3-compatible)
+    let storage = storage::from_env(&upload_dir, &private_dir);
+
+    // Create upload directories if they don't exist
+    if let (Some(dir), Some(private)) = (storage.local_dir(), storage.private_dir()) {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .expect("Failed to create upload directory");
+        tokio::fs::create_dir_all(private)
+            .await
+            .expect("Failed to create private upload directory");
+
+        let served = dir.canonicalize().expect("Failed to resolve UPLOAD_DIR");
+        let private = private
+            .canonicalize()
+            .expect("Failed to resolve PRIVATE_DIR");
+        if private.starts_with(&served) || served.starts_with(&private) {
+            panic!("PRIVATE_DIR must be outside UPLOAD_DIR, which is served at /uploads");
+        }
+    }
+
+    // Labels booked before they were kept private
+    if let Err(err) = couriers::make_labels_private(&db_pool, &storage).await {
+        tracing::warn!(
+            "Failed to move waybill labels to private storage: {:?}",
+            err
+        );
+    }
+
+    // Request body limit for image uploads
+    let max_upload_size = std::env::var("MAX_UPLOAD_SIZE")
+        .unwrap_or_else(|_| "5242880".to_string())
+        .parse::<usize>()
+        .expect("MAX_UPLOAD_SIZE must be a valid number");
 
     // Set up Prometheus metrics
     let (prometheus_layer, metric_handle) = PrometheusMetricLayer::pair();
     tracing::info!("Prometheus metrics enabled at /metrics");
 
     // Metrics route (separate from main app to avoid auth/CORS issues)
-    let metrics_app = Router::new()
-        .route("/metrics", get(|| async move { metric_handle.render() }));
+    let metrics_app =
+        Router::new().route("/metrics", get(|| async move { metric_handle.render() }));
 
     // Public routes
     let public_routes = Router::new()
         .route("/api/health", get(handlers::health::health_check))
         .route("/api/products", get(handlers::products::list_products))
+        .route(
+            "/api/products/search",
+            get(handlers::products::search_products),
+        )
         .route("/api/products/:id", get(handlers::products::get_product))
+        .route(
+            "/api/categories",
+            get(handlers::categories::list_categories),
+        )
+        .route(
+            "/api/categories/:slug/products",
+            get(handlers::products::list_category_products),
+        )
         .route("/api/orders", post(handlers::orders::create_order))
+        .route("/api/orders/lookup", post(handlers::orders::lookup_order))
+        .route(
+            "/api/orders/track/:token",
+            get(handlers::orders::track_order),
+        )
+        .route(
+            "/api/payments/config",
+            get(handlers::payments::get_payment_config),
+        )
+        .route(
+            "/api/payments/webhook",
+            post(handlers::payments::payment_webhook),
+        )
+        .route(
+            "/api/promotions/validate",
+            post(handlers::promotions::validate_promotion),
+        )
+        .route(
+            "/api/shipping/methods",
+            get(handlers::shipping::list_shipping_methods),
+        )
+        .route(
+            "/api/shipping/quote",
+            post(handlers::shipping::quote_shipping),
+        )
+        .route(
+            "/api/shipping/offices",
+            get(handlers::shipping::list_courier_offices),
+        )
+        .route(
+            "/api/reservations",
+            post(handlers::reservations::create_reservation),
+        )
+        .route(
+            "/api/reservations/:id",
+            get(handlers::reservations::get_reservation)
+                .put(handlers::reservations::update_reservation)
+                .delete(handlers::reservations::delete_reservation),
+        )
         .route("/api/admin/login", post(handlers::admin::login));
 
     // Admin routes (protected with JWT)
@@ -95,24 +224,203 @@ async fn main() {
         )
         .route(
             "/api/admin/products/:id/image",
-            post(handlers::products::upload_product_image),
+            post(handlers::product_images::upload_product_image),
+        )
+        .route(
+            "/api/admin/products/:id/images",
+            get(handlers::product_images::list_product_images)
+                .post(handlers::product_images::upload_product_images),
+        )
+        .route(
+            "/api/admin/products/:id/images/order",
+            put(handlers::product_images::reorder_product_images),
+        )
+        .route(
+            "/api/admin/products/:id/images/:image_id",
+            put(handlers::product_images::update_product_image)
+                .delete(handlers::product_images::delete_product_image),
+        )
+        .route(
+            "/api/admin/products/:id/images/:image_id/primary",
+            post(handlers::product_images::set_primary_product_image),
+        )
+        .route(
+            "/api/admin/products/:id/options",
+            get(handlers::product_variants::list_product_options)
+                .post(handlers::product_variants::create_product_option),
+        )
+        .route(
+            "/api/admin/products/:id/options/:option_id",
+            put(handlers::product_variants::update_product_option)
+                .delete(handlers::product_variants::delete_product_option),
+        )
+        .route(
+            "/api/admin/products/:id/variants",
+            get(handlers::product_variants::list_product_variants)
+                .post(handlers::product_variants::create_product_variant),
+        )
+        .route(
+            "/api/admin/products/:id/variants/:variant_id",
+            put(handlers::product_variants::update_product_variant)
+                .delete(handlers::product_variants::delete_product_variant),
+        )
+        .route(
+            "/api/admin/products/:id/inventory",
+            get(handlers::inventory::list_inventory_movements)
+                .post(handlers::inventory::create_inventory_adjustment),
+        )
+        .route(
+            "/api/admin/products/:id/categories",
+            get(handlers::categories::get_product_categories)
+                .put(handlers::categories::set_product_categories),
+        )
+        .route(
+            "/api/admin/categories",
+            get(handlers::categories::list_all_categories)
+                .post(handlers::categories::create_category),
+        )
+        .route(
+            "/api/admin/categories/:id",
+            put(handlers::categories::update_category)
+                .delete(handlers::categories::delete_category),
         )
         .route("/api/admin/orders", get(handlers::orders::list_orders))
         .route(
             "/api/admin/orders/:id",
             get(handlers::orders::get_order).put(handlers::orders::update_order_status),
         )
+        .route(
+            "/api/admin/orders/:id/payments",
+            get(handlers::payments::list_order_payments),
+        )
+        .route(
+            "/api/admin/orders/:id/refunds",
+            post(handlers::payments::create_refund),
+        )
+        .route(
+            "/api/admin/orders/:id/invoice",
+            post(handlers::invoices::issue_invoice),
+        )
+        .route(
+            "/api/admin/invoices",
+            get(handlers::invoices::list_invoices),
+        )
+        .route(
+            "/api/admin/invoices/:id",
+            get(handlers::invoices::get_invoice),
+        )
+        .route(
+            "/api/admin/invoices/:id/pdf",
+            get(handlers::invoices::get_invoice_pdf),
+        )
+        .route(
+            "/api/admin/invoices/:id/credit-notes",
+            post(handlers::invoices::create_credit_note),
+        )
+        .route(
+            "/api/admin/cod-remittances",
+            get(handlers::payments::list_cod_remittances)
+                .post(handlers::payments::import_cod_remittance),
+        )
+        .route(
+            "/api/admin/cod-remittances/:id",
+            get(handlers::payments::get_cod_remittance),
+        )
+        .route(
+            "/api/admin/bank-statements",
+            get(handlers::payments::list_bank_statements)
+                .post(handlers::payments::import_bank_statement),
+        )
+        .route(
+            "/api/admin/bank-statements/:id",
+            get(handlers::payments::get_bank_statement),
+        )
+        .route(
+            "/api/admin/orders/:id/waybill",
+            get(handlers::shipments::get_waybill).post(handlers::shipments::create_waybill),
+        )
+        .route(
+            "/api/admin/orders/:id/waybill/label",
+            get(handlers::shipments::get_waybill_label),
+        )
+        .route(
+            "/api/admin/promotions",
+            get(handlers::promotions::list_promotions).post(handlers::promotions::create_promotion),
+        )
+        .route(
+            "/api/admin/promotions/:id",
+            get(handlers::promotions::get_promotion)
+                .put(handlers::promotions::update_promotion)
+                .delete(handlers::promotions::delete_promotion),
+        )
+        .route(
+            "/api/admin/shipping/methods",
+            get(handlers::shipping::list_all_shipping_methods)
+                .post(handlers::shipping::create_shipping_method),
+        )
+        .route(
+            "/api/admin/shipping/methods/:id",
+            get(handlers::shipping::get_shipping_method)
+                .put(handlers::shipping::update_shipping_method)
+                .delete(handlers::shipping::delete_shipping_method),
+        )
+        .route(
+            "/api/admin/shipping/zones",
+            get(handlers::shipping::list_shipping_zones)
+                .post(handlers::shipping::create_shipping_zone),
+        )
+        .route(
+            "/api/admin/shipping/zones/:id",
+            put(handlers::shipping::update_shipping_zone)
+                .delete(handlers::shipping::delete_shipping_zone),
+        )
+        .route(
+            "/api/admin/tax-classes",
+            get(handlers::tax_classes::list_tax_classes)
+                .post(handlers::tax_classes::create_tax_class),
+        )
+        .route(
+            "/api/admin/tax-classes/:code",
+            put(handlers::tax_classes::update_tax_class)
+                .delete(handlers::tax_classes::delete_tax_class),
+        )
+        .route("/api/admin/jobs", get(handlers::jobs::list_jobs))
+        .route(
+            "/api/admin/jobs/schedules",
+            get(handlers::jobs::list_job_schedules),
+        )
+        .route("/api/admin/jobs/:id", get(handlers::jobs::get_job))
+        .route("/api/admin/jobs/:id/retry", post(handlers::jobs::retry_job))
+        .layer(DefaultBodyLimit::max(max_upload_size))
         .layer(middleware::from_fn(auth::auth_middleware));
 
     // Combine routes and add state
-    let app = Router::new()
-        .merge(public_routes)
-        .merge(admin_routes)
-        .nest_service("/uploads", ServeDir::new(upload_dir))
+    let mut app = Router::new().merge(public_routes).merge(admin_routes);
+
+    // Locally stored uploads are served by the API itself
+    if let Some(dir) = storage.local_dir() {
+        app = app.nest_service("/uploads", ServeDir::new(dir));
+    }
+
+    let app = app
         .layer(prometheus_layer)
         .layer(TraceLayer::new_for_http())
         .layer(cors)
-        .with_state(db_pool)
+        .with_state(state::AppState {
+            pool: db_pool,
+            storage,
+            couriers,
+            payments,
+            invoicing: invoices::from_env(),
+            reservation_limiter: rate_limit::RateLimiter::new(
+                reservations::RATE_LIMIT,
+                reservations::RATE_LIMIT_WINDOW,
+            ),
+            order_lookup_limiter: order_tracking::OrderLookupLimiter(rate_limit::RateLimiter::new(
+                order_tracking::LOOKUP_RATE_LIMIT,
+                order_tracking::LOOKUP_RATE_LIMIT_WINDOW,
+            )),
+        })
         .merge(metrics_app);
 
     // Get host and port from environment
@@ -129,7 +437,11 @@ async fn main() {
         .await
         .expect("Failed to bind to address");
 
-    axum::serve(listener, app)
-        .await
-        .expect("Server error");
+    // The client address is needed for rate limiting
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .expect("Server error");
 }
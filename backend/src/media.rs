@@ -0,0 +1,128 @@
+use image::{imageops::FilterType, DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+
+use crate::error::AppError;
+
+// Longest edge of each generated variant; smaller sources are never upscaled
+const VARIANT_SIZES: [(&str, u32); 3] = [("thumbnail", 320), ("medium", 800), ("large", 1600)];
+
+// Largest source image accepted, guards against decompression bombs
+const MAX_SOURCE_DIMENSION: u32 = 12_000;
+const MAX_DECODE_ALLOC: u64 = 256 * 1024 * 1024;
+
+const WEBP_QUALITY: f32 = 82.0;
+#[cfg(feature = "avif")]
+const AVIF_QUALITY: u8 = 70;
+#[cfg(feature = "avif")]
+const AVIF_SPEED: u8 = 8;
+
+// One encoded rendition of an uploaded image
+pub struct EncodedVariant {
+    pub size: &'static str,
+    pub format: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+// Decodes an upload, applies its EXIF orientation and re-encodes it into the
+// standard variant sizes. Re-encoding drops all metadata, including EXIF/GPS
+// from phone photos. CPU bound, so it runs on the blocking pool.
+pub async fn process_image(data: bytes::Bytes) -> Result<Vec<EncodedVariant>, AppError> {
+    tokio::task::spawn_blocking(move || process_image_blocking(&data))
+        .await
+        .map_err(|e| {
+            tracing::error!("Image processing task failed: {:?}", e);
+            AppError::InternalServerError("Failed to process image".to_string())
+        })?
+}
+
+fn process_image_blocking(data: &[u8]) -> Result<Vec<EncodedVariant>, AppError> {
+    // Trust the content, not the file name
+    let format = image::guess_format(data)
+        .map_err(|_| AppError::BadRequest("File is not a recognised image".to_string()))?;
+    if !matches!(
+        format,
+        ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP
+    ) {
+        return Err(AppError::BadRequest(
+            "Only jpg, jpeg, png, and webp images are allowed".to_string(),
+        ));
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(invalid_image)?;
+    let orientation = decoder.orientation().map_err(invalid_image)?;
+    let mut source = DynamicImage::from_decoder(decoder).map_err(invalid_image)?;
+    source.apply_orientation(orientation);
+
+    let mut variants = Vec::new();
+    for (size, max_edge) in VARIANT_SIZES {
+        let resized = if source.width() > max_edge || source.height() > max_edge {
+            source.resize(max_edge, max_edge, FilterType::Lanczos3)
+        } else {
+            source.clone()
+        };
+
+        variants.push(encode_webp(size, &resized)?);
+
+        #[cfg(feature = "avif")]
+        variants.push(encode_avif(size, &resized)?);
+    }
+
+    Ok(variants)
+}
+
+fn encode_webp(size: &'static str, image: &DynamicImage) -> Result<EncodedVariant, AppError> {
+    // libwebp only takes 8-bit RGB(A)
+    let image = if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+
+    let encoder = webp::Encoder::from_image(&image).map_err(|e| {
+        tracing::error!("WebP encoder error: {}", e);
+        AppError::InternalServerError("Failed to process image".to_string())
+    })?;
+
+    Ok(EncodedVariant {
+        size,
+        format: "webp",
+        width: image.width(),
+        height: image.height(),
+        data: encoder.encode(WEBP_QUALITY).to_vec(),
+    })
+}
+
+#[cfg(feature = "avif")]
+fn encode_avif(size: &'static str, image: &DynamicImage) -> Result<EncodedVariant, AppError> {
+    use image::codecs::avif::AvifEncoder;
+
+    let mut data = Vec::new();
+    let encoder = AvifEncoder::new_with_speed_quality(&mut data, AVIF_SPEED, AVIF_QUALITY);
+    image.write_with_encoder(encoder).map_err(|e| {
+        tracing::error!("AVIF encoder error: {:?}", e);
+        AppError::InternalServerError("Failed to process image".to_string())
+    })?;
+
+    Ok(EncodedVariant {
+        size,
+        format: "avif",
+        width: image.width(),
+        height: image.height(),
+        data,
+    })
+}
+
+fn invalid_image(err: image::ImageError) -> AppError {
+    tracing::warn!("Rejected image upload: {:?}", err);
+    AppError::BadRequest("Image file is corrupt or too large".to_string())
+}
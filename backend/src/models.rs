@@ -1,8 +1,198 @@
-use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::{types::Json, FromRow};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 use uuid::Uuid;
 
+use crate::{
+    inventory::MovementReason,
+    jobs::JobStatus,
+    order_status::OrderStatus,
+    payments::PaymentMethod,
+    promotions::PromotionKind,
+    shipping::{Carrier, ShippingKind},
+};
+
+// Leva to the euro, the fixed rate Bulgaria joined the euro area at
+pub const BGN_PER_EUR: Decimal = Decimal::from_parts(195583, 0, 0, false, 5);
+
+// Currency an amount is given in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    Eur,
+    // Shown alongside the euro while the law asks for both
+    Bgn,
+}
+
+impl Currency {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Currency::Eur => "EUR",
+            Currency::Bgn => "BGN",
+        }
+    }
+}
+
+// An amount in euro, always in whole cents. Stored as a plain DECIMAL(10, 2)
+// and serialized with its currency and the lev equivalent:
+// `{"amount": "12.50", "currency": "EUR", "bgn": "24.45"}`.
+//
+// Conversions follow the euro adoption rules: the full fixed rate, never an
+// inverse one, and rounding to the nearest cent with halves rounded up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct Money(Decimal);
+
+impl Money {
+    pub const ZERO: Money = Money(Decimal::from_parts(0, 0, 0, false, 2));
+
+    // Rounds to the cent
+    pub fn eur(amount: Decimal) -> Self {
+        Money(round_cents(amount))
+    }
+
+    // Converts leva at the fixed rate
+    pub fn from_bgn(amount: Decimal) -> Self {
+        Money(round_cents(amount / BGN_PER_EUR))
+    }
+
+    pub fn amount(self) -> Decimal {
+        self.0
+    }
+
+    // The amount in leva
+    pub fn bgn(self) -> Decimal {
+        round_cents(self.0 * BGN_PER_EUR)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0.is_zero()
+    }
+
+    // `percent` percent of the amount, e.g. a percentage discount
+    pub fn percent(self, percent: Decimal) -> Self {
+        Money::eur(self.0 * percent / Decimal::ONE_HUNDRED)
+    }
+
+    // The share `part / whole` of the amount, e.g. of a discount spread over
+    // lines by price. Nothing is shared out of a zero whole.
+    pub fn share(self, part: Money, whole: Money) -> Self {
+        if whole.is_zero() {
+            return Money::ZERO;
+        }
+        Money::eur(self.0 * part.0 / whole.0)
+    }
+}
+
+// Both amounts for text such as emails: "12.50 € (24.45 лв.)"
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} € ({} лв.)", round_cents(self.0), self.bgn())
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+// Price times quantity
+impl Mul<i32> for Money {
+    type Output = Money;
+
+    fn mul(self, quantity: i32) -> Money {
+        Money(self.0 * Decimal::from(quantity))
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(amounts: I) -> Money {
+        amounts.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(amounts: I) -> Money {
+        amounts.copied().sum()
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut money = serializer.serialize_struct("Money", 3)?;
+        money.serialize_field("amount", &round_cents(self.0))?;
+        money.serialize_field("currency", &Currency::Eur)?;
+        money.serialize_field("bgn", &self.bgn())?;
+        money.end()
+    }
+}
+
+// Accepts a bare amount in euro, or an amount with its currency:
+// `"12.50"`, `12.5` or `{"amount": "24.45", "currency": "BGN"}`. Leva are
+// converted, and both are rounded to the cent. Amounts sent in are prices,
+// payments and the like, never negative.
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Input {
+            Amount(Decimal),
+            WithCurrency { amount: Decimal, currency: Currency },
+        }
+
+        let input = Input::deserialize(deserializer)?;
+        let (Input::Amount(amount) | Input::WithCurrency { amount, .. }) = &input;
+        if *amount < Decimal::ZERO {
+            return Err(serde::de::Error::custom("amount must not be negative"));
+        }
+
+        Ok(match input {
+            Input::Amount(amount) => Money::eur(amount),
+            Input::WithCurrency {
+                amount,
+                currency: Currency::Eur,
+            } => Money::eur(amount),
+            Input::WithCurrency {
+                amount,
+                currency: Currency::Bgn,
+            } => Money::from_bgn(amount),
+        })
+    }
+}
+
+fn round_cents(amount: Decimal) -> Decimal {
+    let mut rounded = amount.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+    rounded.rescale(2);
+    rounded
+}
+
 // Admin model
 #[derive(Debug, Clone, FromRow, Serialize)]
 pub struct Admin {
@@ -18,36 +208,360 @@ pub struct Admin {
 pub struct Product {
     pub id: i32,
     pub name: String,
+    pub slug: String,
     pub description: Option<String>,
-    pub price: rust_decimal::Decimal,
+    pub price: Money,
     pub image_url: Option<String>,
     pub stock_quantity: i32,
     pub is_active: bool,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    // Packed weight, used to price shipping
+    pub weight_grams: i32,
+    // Code of the tax class; the price includes its VAT
+    pub tax_class: String,
+    // Rate and parts of the price when the studio charges VAT, filled in by
+    // `tax::fill_product_tax`; None otherwise
+    #[sqlx(skip)]
+    pub tax_rate: Option<Decimal>,
+    #[sqlx(skip)]
+    pub net_price: Option<Money>,
+    #[sqlx(skip)]
+    pub vat_amount: Option<Money>,
 }
 
 // Create product request
 #[derive(Debug, Deserialize)]
 pub struct CreateProductRequest {
     pub name: String,
+    // Generated from the name when omitted
+    pub slug: Option<String>,
     pub description: Option<String>,
-    pub price: rust_decimal::Decimal,
+    pub price: Money,
     pub stock_quantity: i32,
+    pub weight_grams: Option<i32>,
+    // The standard class when omitted
+    pub tax_class: Option<String>,
 }
 
 // Update product request
 #[derive(Debug, Deserialize)]
 pub struct UpdateProductRequest {
     pub name: Option<String>,
+    // Regenerated from the new name on rename when omitted
+    pub slug: Option<String>,
     pub description: Option<String>,
-    pub price: Option<rust_decimal::Decimal>,
+    pub price: Option<Money>,
     pub stock_quantity: Option<i32>,
     pub is_active: Option<bool>,
+    pub weight_grams: Option<i32>,
+    pub tax_class: Option<String>,
 }
 
-// Order model
+// Product image model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ProductImage {
+    pub id: i32,
+    pub product_id: i32,
+    pub url: String,
+    pub alt_text: Option<String>,
+    pub position: i32,
+    pub is_primary: bool,
+    pub variants: Json<Vec<ImageVariant>>,
+    pub created_at: NaiveDateTime,
+}
+
+// Resized rendition of a product image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageVariant {
+    pub size: String,
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    pub url: String,
+}
+
+// Update product image request
+#[derive(Debug, Deserialize)]
+pub struct UpdateProductImageRequest {
+    pub alt_text: Option<String>,
+}
+
+// Reorder product images request (full list of image ids in display order)
+#[derive(Debug, Deserialize)]
+pub struct ReorderProductImagesRequest {
+    pub image_ids: Vec<i32>,
+}
+
+// Product option model (e.g. "Цвят" or "Размер")
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ProductOption {
+    pub id: i32,
+    pub product_id: i32,
+    pub name: String,
+    pub position: i32,
+    pub created_at: NaiveDateTime,
+}
+
+// Product option value model (e.g. "Червен" for "Цвят")
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ProductOptionValue {
+    pub id: i32,
+    pub option_id: i32,
+    pub value: String,
+    pub position: i32,
+}
+
+// Option with its values in display order
+#[derive(Debug, Serialize)]
+pub struct ProductOptionDetail {
+    #[serde(flatten)]
+    pub option: ProductOption,
+    pub values: Vec<ProductOptionValue>,
+}
+
+// Create product option request
+#[derive(Debug, Deserialize)]
+pub struct CreateProductOptionRequest {
+    pub name: String,
+    pub position: Option<i32>,
+    pub values: Vec<String>,
+}
+
+// Update product option request; `values` replaces the full value list
+#[derive(Debug, Deserialize)]
+pub struct UpdateProductOptionRequest {
+    pub name: Option<String>,
+    pub position: Option<i32>,
+    pub values: Option<Vec<String>>,
+}
+
+// Product variant model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ProductVariant {
+    pub id: i32,
+    pub product_id: i32,
+    pub sku: String,
+    // Overrides the product price when set
+    pub price: Option<Money>,
+    pub stock_quantity: i32,
+    pub is_active: bool,
+    pub position: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Variant with its option values, e.g. label "Червен / M"
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ProductVariantDetail {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub variant: ProductVariant,
+    pub option_value_ids: Vec<i32>,
+    pub label: String,
+    // Stock minus the units held by active reservations
+    pub available_quantity: i32,
+}
+
+// Create product variant request
+#[derive(Debug, Deserialize)]
+pub struct CreateProductVariantRequest {
+    pub sku: String,
+    pub price: Option<Money>,
+    pub stock_quantity: i32,
+    pub is_active: Option<bool>,
+    pub position: Option<i32>,
+    // Exactly one value of every product option
+    pub option_value_ids: Vec<i32>,
+}
+
+// Update product variant request
+#[derive(Debug, Deserialize)]
+pub struct UpdateProductVariantRequest {
+    pub sku: Option<String>,
+    // `null` clears the override, a missing field leaves it unchanged
+    #[serde(default, deserialize_with = "double_option")]
+    pub price: Option<Option<Money>>,
+    pub stock_quantity: Option<i32>,
+    pub is_active: Option<bool>,
+    pub position: Option<i32>,
+    pub option_value_ids: Option<Vec<i32>>,
+}
+
+// Inventory movement model (append-only stock ledger)
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct InventoryMovement {
+    pub id: i64,
+    pub product_id: i32,
+    pub variant_id: Option<i32>,
+    pub quantity_change: i32,
+    pub stock_after: i32,
+    pub reason: String,
+    pub order_id: Option<Uuid>,
+    pub order_item_id: Option<i32>,
+    pub admin_id: Option<i32>,
+    pub admin_email: Option<String>,
+    pub note: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+// Inventory movement history query parameters
+#[derive(Debug, Deserialize)]
+pub struct InventoryMovementQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub variant_id: Option<i32>,
+    pub reason: Option<MovementReason>,
+}
+
+// Inventory adjustment request
+#[derive(Debug, Deserialize)]
+pub struct CreateInventoryAdjustmentRequest {
+    // Required for products that have variants
+    pub variant_id: Option<i32>,
+    // manual_adjustment, production_batch, damage or stock_count
+    pub reason: MovementReason,
+    // Units added (positive) or removed (negative); the counted level for stock_count
+    pub quantity: i32,
+    pub note: Option<String>,
+}
+
+// Stock level after an adjustment
+#[derive(Debug, Serialize)]
+pub struct StockLevel {
+    pub product_id: i32,
+    pub variant_id: Option<i32>,
+    pub stock_quantity: i32,
+}
+
+// Product with its image gallery and variants
+#[derive(Debug, Serialize)]
+pub struct ProductDetail {
+    #[serde(flatten)]
+    pub product: Product,
+    // Stock minus the units held by active reservations
+    pub available_quantity: i32,
+    pub images: Vec<ProductImage>,
+    pub options: Vec<ProductOptionDetail>,
+    pub variants: Vec<ProductVariantDetail>,
+}
+
+// Product listing query parameters (public and admin)
+#[derive(Debug, Default, Deserialize)]
+pub struct ProductListQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    // In euro
+    pub min_price: Option<rust_decimal::Decimal>,
+    pub max_price: Option<rust_decimal::Decimal>,
+    pub in_stock: Option<bool>,
+    pub sort: Option<ProductSort>,
+    // Admin only; the public listing always shows active products
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductSort {
+    #[default]
+    Newest,
+    PriceAsc,
+    PriceDesc,
+    Name,
+}
+
+// Paginated response envelope
+#[derive(Debug, Serialize)]
+pub struct PaginatedResponse<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+    pub total_pages: i64,
+}
+
+// Product search query parameters
+#[derive(Debug, Deserialize)]
+pub struct ProductSearchQuery {
+    pub q: String,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+// Ranked search hit with highlighted name and description snippet
+#[derive(Debug, FromRow, Serialize)]
+pub struct ProductSearchResult {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub product: Product,
+    pub rank: f32,
+    pub name_highlight: String,
+    pub snippet: Option<String>,
+}
+
+// Product looked up by id or slug
+#[derive(Debug, Serialize)]
+pub struct ProductLookupResponse {
+    #[serde(flatten)]
+    pub product: ProductDetail,
+    // True when requested by a previous slug; clients should redirect to `slug`
+    pub moved: bool,
+}
+
+// Category model
 #[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Category {
+    pub id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub position: i32,
+    pub is_active: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Category with its nested subcategories (public tree)
+#[derive(Debug, Serialize)]
+pub struct CategoryTree {
+    #[serde(flatten)]
+    pub category: Category,
+    pub children: Vec<CategoryTree>,
+}
+
+// Create category request
+#[derive(Debug, Deserialize)]
+pub struct CreateCategoryRequest {
+    pub name: String,
+    // Generated from the name when omitted
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    pub parent_id: Option<i32>,
+    pub position: Option<i32>,
+}
+
+// Update category request
+#[derive(Debug, Deserialize)]
+pub struct UpdateCategoryRequest {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    // `null` moves the category to the top level, a missing field leaves it unchanged
+    #[serde(default, deserialize_with = "double_option")]
+    pub parent_id: Option<Option<i32>>,
+    pub position: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+// Assign product to categories request
+#[derive(Debug, Deserialize)]
+pub struct SetProductCategoriesRequest {
+    pub category_ids: Vec<i32>,
+}
+
+// Order model
+#[derive(Debug, Clone, Default, FromRow, Serialize)]
 pub struct Order {
     pub id: Uuid,
     pub customer_name: String,
@@ -56,11 +570,94 @@ pub struct Order {
     pub delivery_address: String,
     pub delivery_city: String,
     pub delivery_postal_code: Option<String>,
-    pub total_amount: rust_decimal::Decimal,
+    pub total_amount: Money,
     pub status: String,
     pub notes: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub processing_at: Option<NaiveDateTime>,
+    pub shipped_at: Option<NaiveDateTime>,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub cancelled_at: Option<NaiveDateTime>,
+    pub returned_at: Option<NaiveDateTime>,
+    pub tracking_number: Option<String>,
+    // Items before the discount; total_amount is what the customer pays,
+    // shipping included
+    pub subtotal_amount: Money,
+    pub discount_amount: Money,
+    pub promotion_id: Option<i32>,
+    pub promotion_code: Option<String>,
+    pub shipping_method_id: Option<i32>,
+    pub shipping_method_name: Option<String>,
+    pub shipping_amount: Money,
+    // Carrier office the parcel goes to, for office delivery
+    pub courier_office_code: Option<String>,
+    pub shipping_weight_grams: i32,
+    pub payment_method: String,
+    pub payment_status: String,
+    pub paid_at: Option<NaiveDateTime>,
+    // Sum of the succeeded refunds; those made in leva count converted one by
+    // one
+    pub refunded_amount: Money,
+    // Charged for collecting cash on delivery, included in the total
+    pub cod_fee: Money,
+    // What was received for the order, once reconciled
+    pub collected_amount: Option<Money>,
+    // Quoted with a bank transfer so the payment can be matched to the order
+    pub payment_reference: Option<String>,
+    // Unpaid transfer orders are cancelled after this
+    pub payment_due_at: Option<NaiveDateTime>,
+    // Parts of total_amount; without VAT the net amount is the total
+    pub net_amount: Money,
+    pub vat_amount: Money,
+    // Rate charged on shipping and the cash on delivery fee, None without VAT
+    pub shipping_tax_rate: Option<Decimal>,
+    pub tax_breakdown: Json<Vec<TaxLine>>,
+    // Company to invoice, when the customer asked for an invoice
+    pub invoice_company: Option<String>,
+    pub invoice_eik: Option<String>,
+    pub invoice_vat_number: Option<String>,
+    pub invoice_mol: Option<String>,
+    pub invoice_address: Option<String>,
+}
+
+// Order as created at checkout, with what the browser needs to take a card
+// payment or what the customer needs to pay by bank transfer
+#[derive(Debug, Serialize)]
+pub struct CreatedOrder {
+    #[serde(flatten)]
+    pub order: Order,
+    pub payment: Option<CheckoutPayment>,
+    pub bank_transfer: Option<BankTransferInstructions>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckoutPayment {
+    pub provider: String,
+    pub client_secret: String,
+    pub publishable_key: Option<String>,
+}
+
+// Where and how to send a bank transfer for an order
+#[derive(Debug, Clone, Serialize)]
+pub struct BankTransferInstructions {
+    pub iban: String,
+    pub bic: Option<String>,
+    pub beneficiary: String,
+    // Goes in the payment details
+    pub reference: String,
+    pub amount: Money,
+    pub due_at: NaiveDateTime,
+}
+
+// Amounts of an order charged at one VAT rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxLine {
+    // Percent
+    pub rate: Decimal,
+    pub net_amount: Money,
+    pub vat_amount: Money,
+    pub gross_amount: Money,
 }
 
 // Order item model
@@ -70,10 +667,100 @@ pub struct OrderItem {
     pub order_id: Uuid,
     pub product_id: Option<i32>,
     pub product_name: String,
-    pub product_price: rust_decimal::Decimal,
+    pub product_price: Money,
     pub quantity: i32,
-    pub subtotal: rust_decimal::Decimal,
+    pub subtotal: Money,
+    pub created_at: NaiveDateTime,
+    pub variant_id: Option<i32>,
+    pub variant_label: Option<String>,
+    pub sku: Option<String>,
+    // Share of the order discount, already taken off the order total
+    pub discount_amount: Money,
+    // Parts of what was paid for the line, after its discount; the rate is
+    // None when no VAT was charged
+    pub tax_rate: Option<Decimal>,
+    pub net_amount: Money,
+    pub vat_amount: Money,
+}
+
+// Order status change model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct OrderStatusChange {
+    pub id: i32,
+    pub order_id: Uuid,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub admin_id: Option<i32>,
+    pub admin_email: Option<String>,
+    pub comment: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+// Order with its items and status history
+#[derive(Debug, Serialize)]
+pub struct OrderDetail {
+    #[serde(flatten)]
+    pub order: Order,
+    pub items: Vec<OrderItem>,
+    pub history: Vec<OrderStatusChange>,
+    pub allowed_transitions: Vec<OrderStatus>,
+}
+
+// Order as shown to the customer tracking it, without contact details,
+// internal comments or who made each change
+#[derive(Debug, Serialize)]
+pub struct OrderTracking {
+    pub id: Uuid,
+    // Short order number used in emails
+    pub reference: String,
+    pub status: String,
+    pub customer_name: String,
+    pub delivery_city: String,
+    pub subtotal_amount: Money,
+    pub discount_amount: Money,
+    pub promotion_code: Option<String>,
+    pub shipping_method_name: Option<String>,
+    pub shipping_amount: Money,
+    pub cod_fee: Money,
+    pub total_amount: Money,
+    // VAT included in the total, by rate
+    pub vat_amount: Money,
+    pub tax_breakdown: Vec<TaxLine>,
+    pub payment_method: String,
+    pub payment_status: String,
+    // For paying by bank transfer
+    pub payment_reference: Option<String>,
+    pub payment_due_at: Option<NaiveDateTime>,
+    pub tracking_number: Option<String>,
     pub created_at: NaiveDateTime,
+    pub items: Vec<OrderTrackingItem>,
+    pub history: Vec<OrderTrackingEvent>,
+    // Signed token for /api/orders/track/:token, for bookmarkable links
+    pub tracking_token: String,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct OrderTrackingItem {
+    pub product_name: String,
+    pub variant_label: Option<String>,
+    pub product_price: Money,
+    pub quantity: i32,
+    pub subtotal: Money,
+    pub discount_amount: Money,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct OrderTrackingEvent {
+    pub status: String,
+    pub created_at: NaiveDateTime,
+}
+
+// Order lookup request
+#[derive(Debug, Deserialize)]
+pub struct OrderLookupRequest {
+    // Full order id or the short number from the emails
+    pub order_number: String,
+    pub email: String,
 }
 
 // Create order request
@@ -82,16 +769,43 @@ pub struct CreateOrderRequest {
     pub customer_name: String,
     pub customer_email: String,
     pub customer_phone: String,
+    // Street address, or the carrier office for office delivery
     pub delivery_address: String,
     pub delivery_city: String,
     pub delivery_postal_code: Option<String>,
     pub notes: Option<String>,
     pub items: Vec<OrderItemRequest>,
+    // Stock held for this checkout, see /api/reservations
+    pub reservation_id: Option<Uuid>,
+    pub promotion_code: Option<String>,
+    pub shipping_method_id: i32,
+    // Office code from /api/shipping/offices, for office delivery
+    pub courier_office_code: Option<String>,
+    // Cash on delivery when not given
+    #[serde(default)]
+    pub payment_method: PaymentMethod,
+    // Company details for an invoice, if the customer wants one
+    pub invoice: Option<InvoiceBuyerRequest>,
+}
+
+// Company an invoice is made out to
+#[derive(Debug, Clone, Deserialize)]
+pub struct InvoiceBuyerRequest {
+    pub company: String,
+    // ЕИК (BULSTAT), 9 or 13 digits
+    pub eik: String,
+    // Only for VAT-registered companies
+    pub vat_number: Option<String>,
+    // МОЛ, the person legally representing the company
+    pub mol: Option<String>,
+    pub address: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct OrderItemRequest {
     pub product_id: i32,
+    // Required for products that have variants
+    pub variant_id: Option<i32>,
     pub quantity: i32,
 }
 
@@ -99,6 +813,609 @@ pub struct OrderItemRequest {
 #[derive(Debug, Deserialize)]
 pub struct UpdateOrderStatusRequest {
     pub status: String,
+    pub comment: Option<String>,
+    // Shown in the shipping notification
+    pub tracking_number: Option<String>,
+}
+
+// Stock reservation model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct StockReservation {
+    pub id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub order_id: Option<Uuid>,
+    pub converted_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Stock reservation item model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct StockReservationItem {
+    pub id: i32,
+    pub reservation_id: Uuid,
+    pub product_id: i32,
+    pub variant_id: Option<i32>,
+    pub quantity: i32,
+}
+
+// Reservation with the items it holds
+#[derive(Debug, Serialize)]
+pub struct StockReservationDetail {
+    #[serde(flatten)]
+    pub reservation: StockReservation,
+    pub items: Vec<StockReservationItem>,
+}
+
+// Create or update stock reservation request
+#[derive(Debug, Deserialize)]
+pub struct StockReservationRequest {
+    pub items: Vec<OrderItemRequest>,
+}
+
+// Promotion model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Promotion {
+    pub id: i32,
+    pub code: String,
+    pub description: Option<String>,
+    pub kind: String,
+    // Percent for percentage promotions, euro for fixed amounts
+    pub value: rust_decimal::Decimal,
+    pub starts_at: Option<NaiveDateTime>,
+    pub ends_at: Option<NaiveDateTime>,
+    pub min_order_amount: Option<Money>,
+    pub usage_limit: Option<i32>,
+    pub usage_limit_per_customer: Option<i32>,
+    pub is_active: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Promotion with its restrictions and how often it was used
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PromotionDetail {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub promotion: Promotion,
+    pub product_ids: Vec<i32>,
+    pub category_ids: Vec<i32>,
+    pub times_used: i64,
+}
+
+// Create promotion request
+#[derive(Debug, Deserialize)]
+pub struct CreatePromotionRequest {
+    pub code: String,
+    pub description: Option<String>,
+    pub kind: PromotionKind,
+    // Percent (up to 100) or amount off; ignored for free_shipping
+    pub value: Option<rust_decimal::Decimal>,
+    pub starts_at: Option<NaiveDateTime>,
+    pub ends_at: Option<NaiveDateTime>,
+    pub min_order_amount: Option<Money>,
+    pub usage_limit: Option<i32>,
+    pub usage_limit_per_customer: Option<i32>,
+    pub is_active: Option<bool>,
+    // Empty or missing applies the promotion to every product
+    pub product_ids: Option<Vec<i32>>,
+    pub category_ids: Option<Vec<i32>>,
+}
+
+// Update promotion request. `null` clears an optional field, a missing field
+// leaves it unchanged.
+#[derive(Debug, Deserialize)]
+pub struct UpdatePromotionRequest {
+    pub code: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub description: Option<Option<String>>,
+    pub kind: Option<PromotionKind>,
+    pub value: Option<rust_decimal::Decimal>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub starts_at: Option<Option<NaiveDateTime>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub ends_at: Option<Option<NaiveDateTime>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub min_order_amount: Option<Option<Money>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub usage_limit: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub usage_limit_per_customer: Option<Option<i32>>,
+    pub is_active: Option<bool>,
+    pub product_ids: Option<Vec<i32>>,
+    pub category_ids: Option<Vec<i32>>,
+}
+
+// Discount code check for a cart
+#[derive(Debug, Deserialize)]
+pub struct ValidatePromotionRequest {
+    pub code: String,
+    pub items: Vec<OrderItemRequest>,
+    // Checks the per-customer limit when given
+    pub customer_email: Option<String>,
+}
+
+// What a discount code takes off a cart
+#[derive(Debug, Serialize)]
+pub struct PromotionQuote {
+    pub code: String,
+    pub description: Option<String>,
+    pub kind: PromotionKind,
+    pub subtotal_amount: Money,
+    pub discount_amount: Money,
+    pub total_amount: Money,
+    pub free_shipping: bool,
+    pub items: Vec<PromotionQuoteItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromotionQuoteItem {
+    pub product_id: i32,
+    pub variant_id: Option<i32>,
+    pub quantity: i32,
+    pub subtotal: Money,
+    pub discount_amount: Money,
+}
+
+// Shipping method model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ShippingMethod {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub kind: String,
+    pub carrier: Option<String>,
+    // Orders of at least this much after discounts ship for free
+    pub free_shipping_threshold: Option<Money>,
+    // Added to orders paid in cash on delivery
+    pub cod_fee: Money,
+    pub is_active: bool,
+    pub position: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Shipping rate model: the price of parcels up to a weight, in one zone or,
+// without a zone, everywhere else
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ShippingRate {
+    pub id: i32,
+    pub method_id: i32,
+    pub zone_id: Option<i32>,
+    // None for no limit
+    pub max_weight_grams: Option<i32>,
+    pub price: Money,
+}
+
+// Shipping method with its price table
+#[derive(Debug, Serialize)]
+pub struct ShippingMethodDetail {
+    #[serde(flatten)]
+    pub method: ShippingMethod,
+    pub rates: Vec<ShippingRate>,
+}
+
+// Create shipping method request
+#[derive(Debug, Deserialize)]
+pub struct CreateShippingMethodRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub kind: ShippingKind,
+    // Required for courier methods, not allowed for pickup
+    pub carrier: Option<Carrier>,
+    pub free_shipping_threshold: Option<Money>,
+    pub cod_fee: Option<Money>,
+    pub is_active: Option<bool>,
+    pub position: Option<i32>,
+    pub rates: Vec<ShippingRateRequest>,
+}
+
+// Update shipping method request. `null` clears an optional field, a missing
+// field leaves it unchanged; `rates` replaces the whole price table.
+#[derive(Debug, Deserialize)]
+pub struct UpdateShippingMethodRequest {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub description: Option<Option<String>>,
+    pub kind: Option<ShippingKind>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub carrier: Option<Option<Carrier>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub free_shipping_threshold: Option<Option<Money>>,
+    pub cod_fee: Option<Money>,
+    pub is_active: Option<bool>,
+    pub position: Option<i32>,
+    pub rates: Option<Vec<ShippingRateRequest>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShippingRateRequest {
+    pub zone_id: Option<i32>,
+    pub max_weight_grams: Option<i32>,
+    pub price: Money,
+}
+
+// Shipping zone model: a range of postal codes
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ShippingZone {
+    pub id: i32,
+    pub name: String,
+    pub postal_code_from: i32,
+    pub postal_code_to: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Create or update shipping zone request
+#[derive(Debug, Deserialize)]
+pub struct ShippingZoneRequest {
+    pub name: String,
+    pub postal_code_from: i32,
+    pub postal_code_to: i32,
+}
+
+// Tax class model: the VAT rate of the products in it
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct TaxClass {
+    pub code: String,
+    pub name: String,
+    // Percent
+    pub rate: Decimal,
+    // Legal ground printed on invoices for lines charged no VAT
+    pub exemption_reason: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Create tax class request
+#[derive(Debug, Deserialize)]
+pub struct CreateTaxClassRequest {
+    pub code: String,
+    pub name: String,
+    pub rate: Decimal,
+    pub exemption_reason: Option<String>,
+}
+
+// Update tax class request; the code cannot change
+#[derive(Debug, Deserialize)]
+pub struct UpdateTaxClassRequest {
+    pub name: String,
+    pub rate: Decimal,
+    pub exemption_reason: Option<String>,
+}
+
+// Shipping quote request for a cart
+#[derive(Debug, Deserialize)]
+pub struct ShippingQuoteRequest {
+    pub items: Vec<OrderItemRequest>,
+    pub postal_code: Option<String>,
+    // Counted like on the order: the discount lowers the amount compared with
+    // free shipping thresholds, and free shipping codes waive the price
+    pub promotion_code: Option<String>,
+    pub customer_email: Option<String>,
+}
+
+// Shipping options for a cart
+#[derive(Debug, Serialize)]
+pub struct ShippingQuote {
+    pub weight_grams: i32,
+    pub subtotal_amount: Money,
+    pub discount_amount: Money,
+    // Methods that can deliver the cart, in display order
+    pub options: Vec<ShippingOption>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShippingOption {
+    #[serde(flatten)]
+    pub method: ShippingMethod,
+    pub price: Money,
+    // Still to add to the cart for free shipping, when the method offers it
+    pub free_shipping_remaining: Option<Money>,
+    pub total_amount: Money,
+}
+
+// Shipment model: the courier waybill of an order
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Shipment {
+    pub id: i32,
+    pub order_id: Uuid,
+    pub carrier: String,
+    pub tracking_number: String,
+    #[serde(skip_serializing)]
+    pub label_key: String,
+    pub status: String,
+    pub last_event: Option<String>,
+    pub last_checked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Create waybill request
+#[derive(Debug, Deserialize)]
+pub struct CreateWaybillRequest {
+    // Overrides the office chosen at checkout
+    pub office_code: Option<String>,
+    // Contents declared to the courier, the product names by default
+    pub description: Option<String>,
+}
+
+// Courier office search
+#[derive(Debug, Deserialize)]
+pub struct CourierOfficeQuery {
+    pub carrier: Carrier,
+    pub city: String,
+}
+
+// Payment model: one card payment taken through the payment provider
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Payment {
+    pub id: i32,
+    pub order_id: Uuid,
+    pub provider: String,
+    pub provider_payment_id: String,
+    // As charged, in `currency`; payments from before the euro are in leva
+    pub amount: rust_decimal::Decimal,
+    pub currency: String,
+    pub status: String,
+    pub failure_message: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Refund model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Refund {
+    pub id: i32,
+    pub payment_id: i32,
+    // Set once the provider has taken the refund
+    pub provider_refund_id: Option<String>,
+    // In the currency of the payment
+    pub amount: rust_decimal::Decimal,
+    pub currency: String,
+    pub reason: Option<String>,
+    pub admin_id: Option<i32>,
+    // pending until the provider answers, then succeeded or failed
+    pub status: String,
+    pub failure_message: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+// Payments of an order with their refunds
+#[derive(Debug, Serialize)]
+pub struct OrderPayments {
+    pub payment_method: String,
+    pub payment_status: String,
+    pub refunded_amount: Money,
+    pub payments: Vec<Payment>,
+    pub refunds: Vec<Refund>,
+}
+
+// Create refund request
+#[derive(Debug, Deserialize)]
+pub struct CreateRefundRequest {
+    // Everything still refundable when not given
+    pub amount: Option<Money>,
+    pub reason: Option<String>,
+}
+
+// Payment options offered at checkout
+#[derive(Debug, Serialize)]
+pub struct PaymentConfig {
+    pub methods: Vec<PaymentMethod>,
+    // Card provider, e.g. "stripe"
+    pub provider: Option<&'static str>,
+    pub publishable_key: Option<String>,
+}
+
+// COD remittance model: a courier's cash on delivery payout report as
+// imported
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct CodRemittance {
+    pub id: i32,
+    pub carrier: Option<String>,
+    pub file_name: Option<String>,
+    pub total_amount: Money,
+    pub line_count: i32,
+    pub matched_count: i32,
+    pub admin_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+// COD remittance line model: one parcel of the report and how it matched
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct CodRemittanceLine {
+    pub id: i32,
+    pub remittance_id: i32,
+    pub line_number: i32,
+    pub tracking_number: String,
+    // None when the report's amount could not be read
+    pub amount: Option<Money>,
+    // The amount as written, when it could not be read
+    pub raw_amount: Option<String>,
+    pub order_id: Option<Uuid>,
+    pub expected_amount: Option<Money>,
+    pub status: String,
+}
+
+// COD remittance with its lines, discrepancies first
+#[derive(Debug, Serialize)]
+pub struct CodRemittanceDetail {
+    #[serde(flatten)]
+    pub remittance: CodRemittance,
+    pub lines: Vec<CodRemittanceLine>,
+}
+
+// Invoice model: an invoice or credit note as issued
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Invoice {
+    pub id: i32,
+    pub number: i64,
+    // "invoice" or "credit_note"
+    pub kind: String,
+    pub order_id: Uuid,
+    // Invoice a credit note corrects
+    pub invoice_id: Option<i32>,
+    pub refund_id: Option<i32>,
+    pub issue_date: NaiveDate,
+    pub tax_event_date: NaiveDate,
+    pub seller_name: String,
+    pub seller_eik: String,
+    pub seller_vat_number: Option<String>,
+    pub seller_mol: Option<String>,
+    pub seller_address: String,
+    pub buyer_name: String,
+    pub buyer_eik: Option<String>,
+    pub buyer_vat_number: Option<String>,
+    pub buyer_mol: Option<String>,
+    pub buyer_address: String,
+    pub payment_method: String,
+    pub net_amount: Money,
+    pub vat_amount: Money,
+    pub total_amount: Money,
+    pub tax_breakdown: Json<Vec<TaxLine>>,
+    // Legal ground for lines charged no VAT
+    pub vat_exemption_reason: Option<String>,
+    // Why a credit note was issued
+    pub reason: Option<String>,
+    pub admin_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+// Invoice line model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct InvoiceLine {
+    pub id: i32,
+    pub invoice_id: i32,
+    pub position: i32,
+    pub description: String,
+    pub quantity: i32,
+    // Without VAT, after any discount
+    pub unit_price: Money,
+    // None when no VAT is charged
+    pub tax_rate: Option<Decimal>,
+    pub net_amount: Money,
+    pub vat_amount: Money,
+    pub total_amount: Money,
+}
+
+// Invoice with its lines; an invoice lists its credit notes, a credit note
+// the number of the invoice it corrects
+#[derive(Debug, Serialize)]
+pub struct InvoiceDetail {
+    #[serde(flatten)]
+    pub invoice: Invoice,
+    // Zero-padded to 10 digits, as printed
+    pub formatted_number: String,
+    pub lines: Vec<InvoiceLine>,
+    pub credit_notes: Vec<Invoice>,
+    pub corrected_invoice: Option<Invoice>,
+}
+
+// Issue invoice request; everything is taken from the order when omitted
+#[derive(Debug, Deserialize)]
+pub struct IssueInvoiceRequest {
+    // Replaces the company given at checkout
+    pub buyer: Option<InvoiceBuyerRequest>,
+    // Today when omitted
+    pub issue_date: Option<NaiveDate>,
+    // The issue date when omitted
+    pub tax_event_date: Option<NaiveDate>,
+}
+
+// Credit note request: for a refund, or for an amount of the invoice
+#[derive(Debug, Deserialize)]
+pub struct CreateCreditNoteRequest {
+    // The refund's amount is credited
+    pub refund_id: Option<i32>,
+    // What is left of the invoice when neither is given
+    pub amount: Option<Money>,
+    pub reason: Option<String>,
+    // Today when omitted
+    pub issue_date: Option<NaiveDate>,
+}
+
+// Invoice list query parameters
+#[derive(Debug, Deserialize)]
+pub struct InvoiceListQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    // "invoice" or "credit_note"
+    pub kind: Option<String>,
+    pub order_id: Option<Uuid>,
+}
+
+// Bank statement model: an imported statement of the studio's account
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct BankStatement {
+    pub id: i32,
+    pub file_name: Option<String>,
+    // "camt053" or "csv"
+    pub format: String,
+    // Sum of the incoming payments
+    pub total_amount: Money,
+    pub entry_count: i32,
+    pub matched_count: i32,
+    pub admin_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+// Bank statement entry model: one incoming payment and how it matched
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct BankStatementEntry {
+    pub id: i32,
+    pub statement_id: i32,
+    pub bank_reference: String,
+    pub booking_date: Option<NaiveDate>,
+    pub amount: rust_decimal::Decimal,
+    pub currency: String,
+    pub payer_name: Option<String>,
+    pub remittance_info: Option<String>,
+    pub order_id: Option<Uuid>,
+    pub status: String,
+}
+
+// Bank statement with its entries, discrepancies first
+#[derive(Debug, Serialize)]
+pub struct BankStatementDetail {
+    #[serde(flatten)]
+    pub statement: BankStatement,
+    pub entries: Vec<BankStatementEntry>,
+}
+
+// Background job model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct BackgroundJob {
+    pub id: i64,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: NaiveDateTime,
+    pub locked_at: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Background job listing query parameters
+#[derive(Debug, Deserialize)]
+pub struct JobListQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub status: Option<JobStatus>,
+    pub kind: Option<String>,
+}
+
+// Cron schedule model
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct JobSchedule {
+    pub name: String,
+    pub schedule: String,
+    pub next_run_at: NaiveDateTime,
+    pub last_run_at: Option<NaiveDateTime>,
 }
 
 // Login request
@@ -120,3 +1437,96 @@ pub struct AdminResponse {
     pub id: i32,
     pub email: String,
 }
+
+// Distinguishes an explicit `null` from a missing field in partial updates
+fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(amount: &str) -> Decimal {
+        amount.parse().unwrap()
+    }
+
+    #[test]
+    fn rounds_to_the_cent_with_halves_up() {
+        assert_eq!(Money::eur(dec("1.005")).amount(), dec("1.01"));
+        assert_eq!(Money::eur(dec("1.004")).amount(), dec("1.00"));
+        assert_eq!(Money::eur(dec("-1.005")).amount(), dec("-1.01"));
+        assert_eq!(Money::eur(dec("3")).amount().to_string(), "3.00");
+    }
+
+    #[test]
+    fn converts_leva_at_the_fixed_rate() {
+        assert_eq!(Money::from_bgn(dec("1.95583")).amount(), dec("1.00"));
+        assert_eq!(Money::from_bgn(dec("24.45")).amount(), dec("12.50"));
+        assert_eq!(Money::eur(dec("12.50")).bgn(), dec("24.45"));
+        assert_eq!(Money::eur(dec("12.5")).to_string(), "12.50 € (24.45 лв.)");
+    }
+
+    #[test]
+    fn takes_percentages_and_shares() {
+        let amount = Money::eur(dec("10.00"));
+        assert_eq!(amount.percent(dec("15")).amount(), dec("1.50"));
+        assert_eq!(
+            amount
+                .share(Money::eur(dec("1.00")), Money::eur(dec("3.00")))
+                .amount(),
+            dec("3.33")
+        );
+        assert_eq!(
+            amount.share(Money::eur(dec("1.00")), Money::ZERO),
+            Money::ZERO
+        );
+    }
+
+    #[test]
+    fn adds_up_and_multiplies_by_quantity() {
+        let price = Money::eur(dec("2.35"));
+        assert_eq!((price * 3).amount(), dec("7.05"));
+        assert_eq!(
+            [price, price, Money::ZERO].iter().sum::<Money>(),
+            Money::eur(dec("4.70"))
+        );
+        assert_eq!(price - price, Money::ZERO);
+    }
+
+    #[test]
+    fn serializes_with_the_lev_equivalent() {
+        let json = serde_json::to_value(Money::eur(dec("12.5"))).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "amount": "12.50", "currency": "EUR", "bgn": "24.45" })
+        );
+    }
+
+    #[test]
+    fn deserializes_euro_and_leva() {
+        let parse = |json: &str| serde_json::from_str::<Money>(json);
+
+        assert_eq!(parse(r#""12.50""#).unwrap(), Money::eur(dec("12.50")));
+        assert_eq!(parse("12.5").unwrap(), Money::eur(dec("12.50")));
+        assert_eq!(
+            parse(r#"{"amount": "24.45", "currency": "BGN"}"#).unwrap(),
+            Money::eur(dec("12.50"))
+        );
+        assert_eq!(parse(r#""0""#).unwrap(), Money::ZERO);
+    }
+
+    #[test]
+    fn refuses_negative_amounts() {
+        let parse = |json: &str| serde_json::from_str::<Money>(json);
+
+        assert!(parse(r#""-0.01""#).is_err());
+        assert!(parse("-5").is_err());
+        assert!(parse(r#"{"amount": "-1", "currency": "EUR"}"#).is_err());
+        assert!(parse(r#""abc""#).is_err());
+    }
+}
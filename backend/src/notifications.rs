@@ -0,0 +1,93 @@
+use sqlx::PgConnection;
+
+use crate::{
+    email::{self, templates},
+    error::AppError,
+    models::{BankTransferInstructions, Order, OrderItem},
+    order_status::OrderStatus,
+    order_tracking,
+};
+
+const DEFAULT_SITE_URL: &str = "https://studiozemya.com";
+
+// Queues the confirmation to the customer and the new-order alert to the
+// studio, in the transaction that created the order. Transfer orders carry
+// the payment instructions in the confirmation.
+pub async fn order_placed(
+    conn: &mut PgConnection,
+    order: &Order,
+    bank_transfer: Option<&BankTransferInstructions>,
+) -> Result<(), AppError> {
+    let items =
+        sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id = $1 ORDER BY id")
+            .bind(order.id)
+            .fetch_all(&mut *conn)
+            .await?;
+    let site_url = site_url();
+    let tracking_url = order_tracking::url(&site_url, order.id)?;
+
+    email::enqueue(
+        conn,
+        &order.customer_email,
+        templates::order_confirmation(order, &items, bank_transfer, &tracking_url, &site_url),
+        Some(order.id),
+    )
+    .await?;
+
+    for recipient in admin_recipients(conn).await? {
+        email::enqueue(
+            conn,
+            &recipient,
+            templates::new_order_alert(order, &items, &site_url),
+            Some(order.id),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+// Queues the customer email for a status the customer hears about
+pub async fn order_status_changed(
+    conn: &mut PgConnection,
+    order: &Order,
+    status: OrderStatus,
+) -> Result<(), AppError> {
+    let site_url = site_url();
+    let content = match status {
+        OrderStatus::Shipped => {
+            let tracking_url = order_tracking::url(&site_url, order.id)?;
+            templates::order_shipped(order, &tracking_url, &site_url)
+        }
+        OrderStatus::Delivered => templates::order_delivered(order, &site_url),
+        OrderStatus::Cancelled => templates::order_cancelled(order, &site_url),
+        _ => return Ok(()),
+    };
+
+    email::enqueue(conn, &order.customer_email, content, Some(order.id)).await
+}
+
+fn site_url() -> String {
+    std::env::var("SITE_URL").unwrap_or_else(|_| DEFAULT_SITE_URL.to_string())
+}
+
+// ADMIN_NOTIFICATION_EMAILS (comma separated), or every admin account
+async fn admin_recipients(conn: &mut PgConnection) -> Result<Vec<String>, AppError> {
+    let configured: Vec<String> = std::env::var("ADMIN_NOTIFICATION_EMAILS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    if !configured.is_empty() {
+        return Ok(configured);
+    }
+
+    let admins = sqlx::query_scalar::<_, String>("SELECT email FROM admins ORDER BY id")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(admins)
+}
@@ -0,0 +1,145 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::error::AppError;
+
+// Lifecycle of an order. Stored as text in `orders.status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Pending,
+    Processing,
+    Shipped,
+    Delivered,
+    Cancelled,
+    // Refused on delivery or sent back by the customer
+    Returned,
+}
+
+impl OrderStatus {
+    pub const ALL: [OrderStatus; 6] = [
+        OrderStatus::Pending,
+        OrderStatus::Processing,
+        OrderStatus::Shipped,
+        OrderStatus::Delivered,
+        OrderStatus::Cancelled,
+        OrderStatus::Returned,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Processing => "processing",
+            OrderStatus::Shipped => "shipped",
+            OrderStatus::Delivered => "delivered",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Returned => "returned",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == value)
+            .ok_or_else(|| {
+                let valid: Vec<&str> = Self::ALL.iter().map(|s| s.as_str()).collect();
+                AppError::BadRequest(format!(
+                    "Invalid status. Must be one of: {}",
+                    valid.join(", ")
+                ))
+            })
+    }
+
+    // Statuses an order may move to next; cancelled and returned orders are final
+    pub fn allowed_transitions(self) -> &'static [OrderStatus] {
+        match self {
+            OrderStatus::Pending => &[OrderStatus::Processing, OrderStatus::Cancelled],
+            OrderStatus::Processing => &[OrderStatus::Shipped, OrderStatus::Cancelled],
+            OrderStatus::Shipped => &[OrderStatus::Delivered, OrderStatus::Returned],
+            OrderStatus::Delivered => &[OrderStatus::Returned],
+            OrderStatus::Cancelled | OrderStatus::Returned => &[],
+        }
+    }
+
+    pub fn can_transition_to(self, next: OrderStatus) -> bool {
+        self.allowed_transitions().contains(&next)
+    }
+
+    // Column on `orders` holding the time the order reached this status
+    pub fn timestamp_column(self) -> Option<&'static str> {
+        match self {
+            OrderStatus::Pending => None,
+            OrderStatus::Processing => Some("processing_at"),
+            OrderStatus::Shipped => Some("shipped_at"),
+            OrderStatus::Delivered => Some("delivered_at"),
+            OrderStatus::Cancelled => Some("cancelled_at"),
+            OrderStatus::Returned => Some("returned_at"),
+        }
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_its_own_names() {
+        for status in OrderStatus::ALL {
+            assert_eq!(OrderStatus::parse(status.as_str()).unwrap(), status);
+            assert_eq!(status.to_string(), status.as_str());
+        }
+        assert!(matches!(
+            OrderStatus::parse("Shipped"),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(OrderStatus::parse("lost").is_err());
+    }
+
+    #[test]
+    fn moves_forward_only() {
+        use OrderStatus::*;
+
+        let allowed = [
+            (Pending, Processing),
+            (Pending, Cancelled),
+            (Processing, Shipped),
+            (Processing, Cancelled),
+            (Shipped, Delivered),
+            (Shipped, Returned),
+            (Delivered, Returned),
+        ];
+
+        for from in OrderStatus::ALL {
+            for to in OrderStatus::ALL {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed.contains(&(from, to)),
+                    "{from} -> {to}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn cancelled_and_returned_are_final() {
+        assert!(OrderStatus::Cancelled.allowed_transitions().is_empty());
+        assert!(OrderStatus::Returned.allowed_transitions().is_empty());
+    }
+
+    #[test]
+    fn every_status_but_pending_is_timestamped() {
+        assert_eq!(OrderStatus::Pending.timestamp_column(), None);
+        for status in &OrderStatus::ALL[1..] {
+            assert_eq!(
+                status.timestamp_column(),
+                Some(format!("{}_at", status.as_str()).as_str())
+            );
+        }
+    }
+}
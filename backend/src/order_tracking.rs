@@ -0,0 +1,65 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{error::AppError, rate_limit::RateLimiter};
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Order lookups per client address
+pub const LOOKUP_RATE_LIMIT: u32 = 20;
+pub const LOOKUP_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+// Counts order lookups apart from the other rate limited endpoints
+#[derive(Clone)]
+pub struct OrderLookupLimiter(pub RateLimiter);
+
+// Signed token that lets a customer follow one order without an account. It
+// is the order id plus an HMAC of it, so it never expires; rotating
+// ORDER_TRACKING_SECRET (JWT_SECRET when unset) invalidates every link sent.
+pub fn token(order_id: Uuid) -> Result<String, AppError> {
+    let signature = mac(order_id)?.finalize().into_bytes();
+
+    Ok(format!("{}.{}", order_id.simple(), hex::encode(signature)))
+}
+
+// Order the token was issued for. Bad tokens look like unknown orders.
+pub fn verify(token: &str) -> Result<Uuid, AppError> {
+    let not_found = || AppError::NotFound("Order not found".to_string());
+
+    let (id, signature) = token.split_once('.').ok_or_else(not_found)?;
+    let order_id = Uuid::try_parse(id).map_err(|_| not_found())?;
+    let signature = hex::decode(signature).map_err(|_| not_found())?;
+
+    mac(order_id)?
+        .verify_slice(&signature)
+        .map_err(|_| not_found())?;
+
+    Ok(order_id)
+}
+
+// Customer page showing the order, linked from the order emails
+pub fn url(site_url: &str, order_id: Uuid) -> Result<String, AppError> {
+    Ok(format!(
+        "{}/orders/track?token={}",
+        site_url.trim_end_matches('/'),
+        token(order_id)?
+    ))
+}
+
+fn mac(order_id: Uuid) -> Result<HmacSha256, AppError> {
+    let secret = std::env::var("ORDER_TRACKING_SECRET")
+        .ok()
+        .filter(|secret| !secret.is_empty())
+        .or_else(|| std::env::var("JWT_SECRET").ok())
+        .ok_or_else(|| {
+            AppError::InternalServerError("ORDER_TRACKING_SECRET not configured".to_string())
+        })?;
+
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(order_id.as_bytes());
+
+    Ok(mac)
+}
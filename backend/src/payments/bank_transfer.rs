@@ -0,0 +1,743 @@
+use chrono::{Duration, NaiveDate};
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashSet;
+
+use super::{report, PaymentMethod, PaymentStatus, CURRENCY};
+use crate::{
+    error::AppError,
+    handlers::orders::transition_order,
+    models::{BankStatement, BankTransferInstructions, Money, Order},
+    order_status::OrderStatus,
+};
+
+const DEFAULT_DEADLINE_DAYS: i64 = 3;
+
+// References are "SZ" and 8 characters from an alphabet without the easily
+// confused 0/O and 1/I, so they survive being typed into a banking app
+const REFERENCE_PREFIX: &str = "SZ";
+const REFERENCE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const REFERENCE_LENGTH: usize = 8;
+
+// Header words recognised in bank CSV exports, in order of preference
+const AMOUNT_HEADERS: [&str; 4] = ["кредит", "credit", "сума", "amount"];
+const DETAILS_HEADERS: [&str; 6] = [
+    "основание",
+    "описание",
+    "remittance",
+    "details",
+    "description",
+    "reference",
+];
+const PAYER_HEADERS: [&str; 5] = ["наредител", "контрагент", "payer", "name", "име"];
+const DATE_HEADERS: [&str; 2] = ["дата", "date"];
+const CURRENCY_HEADERS: [&str; 3] = ["валута", "currency", "ccy"];
+
+// Account the studio takes transfers on
+pub struct BankAccount {
+    pub iban: String,
+    pub bic: Option<String>,
+    pub beneficiary: String,
+    // Days a transfer order waits for its payment
+    pub deadline_days: i64,
+}
+
+impl BankAccount {
+    // Enabled by BANK_TRANSFER_IBAN
+    pub fn from_env() -> Option<Self> {
+        // docker-compose passes unset variables as empty strings
+        let optional = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+        let iban = optional("BANK_TRANSFER_IBAN")?;
+        let deadline_days = optional("BANK_TRANSFER_DEADLINE_DAYS")
+            .map(|days| {
+                days.parse::<i64>()
+                    .ok()
+                    .filter(|days| *days > 0)
+                    .expect("BANK_TRANSFER_DEADLINE_DAYS must be a positive number of days")
+            })
+            .unwrap_or(DEFAULT_DEADLINE_DAYS);
+
+        Some(Self {
+            iban: iban.split_whitespace().collect(),
+            bic: optional("BANK_TRANSFER_BIC"),
+            beneficiary: optional("BANK_TRANSFER_BENEFICIARY")
+                .expect("BANK_TRANSFER_BENEFICIARY must be set for bank transfers"),
+            deadline_days,
+        })
+    }
+}
+
+// An incoming payment read from a statement
+pub struct StatementEntry {
+    pub bank_reference: String,
+    pub booking_date: Option<NaiveDate>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub payer_name: Option<String>,
+    pub remittance_info: Option<String>,
+}
+
+impl StatementEntry {
+    // The amount in euro; leva, still paid in by some, are converted at the
+    // fixed rate. None for other currencies.
+    fn in_euro(&self) -> Option<Money> {
+        match self.currency.as_str() {
+            "EUR" => Some(Money::eur(self.amount)),
+            "BGN" => Some(Money::from_bgn(self.amount)),
+            _ => None,
+        }
+    }
+}
+
+// How a statement entry compares with its order; stored in
+// `bank_statement_entries.status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryStatus {
+    Matched,
+    AmountMismatch,
+    // The payment details quote no reference
+    NoReference,
+    UnknownReference,
+    AlreadyPaid,
+    // Paid after the order was cancelled
+    OrderCancelled,
+    // The payment was imported before
+    Duplicate,
+}
+
+impl EntryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EntryStatus::Matched => "matched",
+            EntryStatus::AmountMismatch => "amount_mismatch",
+            EntryStatus::NoReference => "no_reference",
+            EntryStatus::UnknownReference => "unknown_reference",
+            EntryStatus::AlreadyPaid => "already_paid",
+            EntryStatus::OrderCancelled => "order_cancelled",
+            EntryStatus::Duplicate => "duplicate",
+        }
+    }
+}
+
+// Gives a new transfer order its reference and deadline. Orders with nothing
+// to pay count as paid straight away.
+pub async fn start_transfer(
+    conn: &mut PgConnection,
+    account: &BankAccount,
+    order: Order,
+) -> Result<(Order, Option<BankTransferInstructions>), AppError> {
+    if order.total_amount <= Money::ZERO {
+        let order = sqlx::query_as::<_, Order>(
+            "UPDATE orders SET payment_status = $1, paid_at = NOW() WHERE id = $2 RETURNING *",
+        )
+        .bind(PaymentStatus::Paid.as_str())
+        .bind(order.id)
+        .fetch_one(&mut *conn)
+        .await?;
+        return Ok((order, None));
+    }
+
+    let order = sqlx::query_as::<_, Order>(
+        r#"
+        UPDATE orders SET payment_reference = $1, payment_due_at = created_at + $2
+        WHERE id = $3
+        RETURNING *
+        "#,
+    )
+    .bind(generate_reference())
+    .bind(Duration::days(account.deadline_days))
+    .bind(order.id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let instructions = BankTransferInstructions {
+        iban: account.iban.clone(),
+        bic: account.bic.clone(),
+        beneficiary: account.beneficiary.clone(),
+        reference: order.payment_reference.clone().unwrap_or_default(),
+        amount: order.total_amount,
+        due_at: order.payment_due_at.unwrap_or(order.created_at),
+    };
+
+    Ok((order, Some(instructions)))
+}
+
+// Reads the incoming payments of a CAMT.053 statement (ISO 20022 XML, as
+// exported by Bulgarian banks) or of a CSV export. Outgoing payments are left
+// out. Returns the format alongside.
+pub fn parse_statement(data: &[u8]) -> Result<(&'static str, Vec<StatementEntry>), AppError> {
+    let text = report::decode(data)?;
+
+    let entries = if text.trim_start().starts_with('<') {
+        ("camt053", parse_camt053(text)?)
+    } else {
+        ("csv", parse_csv(data)?)
+    };
+
+    if entries.1.is_empty() {
+        return Err(AppError::BadRequest(
+            "The statement has no incoming payments".to_string(),
+        ));
+    }
+
+    Ok(entries)
+}
+
+// Records the statement and matches each payment to the transfer order whose
+// reference it quotes. Orders paid in full are marked paid; anything else is
+// kept as a discrepancy and leaves the order untouched.
+pub async fn reconcile(
+    conn: &mut PgConnection,
+    format: &str,
+    file_name: Option<&str>,
+    entries: &[StatementEntry],
+    admin_id: i32,
+) -> Result<BankStatement, AppError> {
+    // Incoming payments in euro, leva converted
+    let total_amount: Money = entries.iter().filter_map(StatementEntry::in_euro).sum();
+
+    let statement_id = sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO bank_statements (
+            file_name, format, total_amount, entry_count, matched_count, admin_id
+        ) VALUES ($1, $2, $3, $4, 0, $5)
+        RETURNING id
+        "#,
+    )
+    .bind(file_name)
+    .bind(format)
+    .bind(total_amount)
+    .bind(entries.len() as i32)
+    .bind(admin_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let mut seen = HashSet::new();
+    let mut matched_count = 0;
+    for entry in entries {
+        let imported_before = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM bank_statement_entries WHERE bank_reference = $1)",
+        )
+        .bind(&entry.bank_reference)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let references = entry
+            .remittance_info
+            .as_deref()
+            .map(find_references)
+            .unwrap_or_default();
+        let order = if references.is_empty() {
+            None
+        } else {
+            sqlx::query_as::<_, Order>(
+                r#"
+                SELECT * FROM orders
+                WHERE payment_reference = ANY($1) AND payment_method = $2
+                LIMIT 1
+                FOR UPDATE
+                "#,
+            )
+            .bind(&references)
+            .bind(PaymentMethod::BankTransfer.as_str())
+            .fetch_optional(&mut *conn)
+            .await?
+        };
+
+        let status = match &order {
+            _ if imported_before || !seen.insert(entry.bank_reference.as_str()) => {
+                EntryStatus::Duplicate
+            }
+            None if references.is_empty() => EntryStatus::NoReference,
+            None => EntryStatus::UnknownReference,
+            Some(order) if order.payment_status != PaymentStatus::Pending.as_str() => {
+                EntryStatus::AlreadyPaid
+            }
+            Some(order) if order.status == OrderStatus::Cancelled.as_str() => {
+                EntryStatus::OrderCancelled
+            }
+            Some(order) if entry.in_euro() != Some(order.total_amount) => {
+                EntryStatus::AmountMismatch
+            }
+            Some(_) => EntryStatus::Matched,
+        };
+
+        if let (EntryStatus::Matched, Some(order)) = (status, &order) {
+            sqlx::query(
+                r#"
+                UPDATE orders SET collected_amount = $1, payment_status = $2, paid_at = NOW()
+                WHERE id = $3
+                "#,
+            )
+            .bind(order.total_amount)
+            .bind(PaymentStatus::Paid.as_str())
+            .bind(order.id)
+            .execute(&mut *conn)
+            .await?;
+            matched_count += 1;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO bank_statement_entries (
+                statement_id, bank_reference, booking_date, amount, currency,
+                payer_name, remittance_info, order_id, status
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(statement_id)
+        .bind(&entry.bank_reference)
+        .bind(entry.booking_date)
+        .bind(entry.amount)
+        .bind(&entry.currency)
+        .bind(&entry.payer_name)
+        .bind(&entry.remittance_info)
+        .bind(order.as_ref().map(|order| order.id))
+        .bind(status.as_str())
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    let statement = sqlx::query_as::<_, BankStatement>(
+        "UPDATE bank_statements SET matched_count = $1 WHERE id = $2 RETURNING *",
+    )
+    .bind(matched_count)
+    .bind(statement_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(statement)
+}
+
+// Cancels transfer orders still unpaid at their deadline, putting their items
+// back in stock. Orders an admin has started on are left alone.
+pub async fn cancel_overdue(pool: &PgPool) -> Result<u64, AppError> {
+    let mut cancelled = 0;
+
+    loop {
+        let mut tx = pool.begin().await?;
+
+        let id = sqlx::query_scalar::<_, uuid::Uuid>(
+            r#"
+            SELECT id FROM orders
+            WHERE payment_method = $1 AND payment_status = $2 AND status = $3
+              AND payment_due_at <= NOW()
+            ORDER BY payment_due_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(PaymentMethod::BankTransfer.as_str())
+        .bind(PaymentStatus::Pending.as_str())
+        .bind(OrderStatus::Pending.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(id) = id else {
+            break;
+        };
+
+        transition_order(
+            &mut tx,
+            id,
+            OrderStatus::Cancelled,
+            None,
+            Some("Bank transfer not received by the deadline"),
+        )
+        .await?;
+        tx.commit().await?;
+
+        cancelled += 1;
+    }
+
+    Ok(cancelled)
+}
+
+fn generate_reference() -> String {
+    let random = uuid::Uuid::new_v4();
+    let code: String = random.as_bytes()[..REFERENCE_LENGTH]
+        .iter()
+        .map(|byte| REFERENCE_ALPHABET[*byte as usize % REFERENCE_ALPHABET.len()] as char)
+        .collect();
+
+    format!("{}{}", REFERENCE_PREFIX, code)
+}
+
+// References quoted in payment details, however the payer spaced or cased
+// them ("sz 7k4m-9qxd" reads as SZ7K4M9QXD)
+fn find_references(text: &str) -> Vec<String> {
+    let compact: Vec<char> = text
+        .to_uppercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    let length = REFERENCE_PREFIX.len() + REFERENCE_LENGTH;
+
+    compact
+        .windows(length)
+        .map(|window| window.iter().collect::<String>())
+        .filter(|candidate| {
+            candidate.starts_with(REFERENCE_PREFIX)
+                && candidate[REFERENCE_PREFIX.len()..]
+                    .bytes()
+                    .all(|b| REFERENCE_ALPHABET.contains(&b))
+        })
+        .collect()
+}
+
+fn parse_camt053(text: &str) -> Result<Vec<StatementEntry>, AppError> {
+    let document = roxmltree::Document::parse(text)
+        .map_err(|e| AppError::BadRequest(format!("Invalid statement XML: {}", e)))?;
+
+    let mut entries = Vec::new();
+    let ntries = document
+        .descendants()
+        .filter(|node| node.tag_name().name() == "Ntry");
+    for (index, ntry) in ntries.enumerate() {
+        if path(ntry, &["CdtDbtInd"]).as_deref() != Some("CRDT") {
+            continue;
+        }
+        // Reversals of earlier entries are not payments
+        if path(ntry, &["RvslInd"]).as_deref() == Some("true") {
+            continue;
+        }
+
+        let booking_date = path(ntry, &["BookgDt", "Dt"])
+            .or_else(|| path(ntry, &["BookgDt", "DtTm"]))
+            .and_then(|date| {
+                NaiveDate::parse_from_str(&date[..date.len().min(10)], "%Y-%m-%d").ok()
+            });
+        let entry_reference = path(ntry, &["AcctSvcrRef"]);
+
+        // Batch bookings carry one TxDtls per payment
+        let transactions: Vec<_> = ntry
+            .descendants()
+            .filter(|node| node.tag_name().name() == "TxDtls")
+            .collect();
+        let single = [ntry];
+        let sources: &[Node] = if transactions.is_empty() {
+            &single
+        } else {
+            &transactions
+        };
+
+        for (position, source) in sources.iter().enumerate() {
+            let amount_node = child(*source, "Amt")
+                .or_else(|| {
+                    source
+                        .descendants()
+                        .find(|d| d.tag_name().name() == "TxAmt")
+                        .and_then(|d| child(d, "Amt"))
+                })
+                .or_else(|| child(ntry, "Amt"))
+                .ok_or_else(|| {
+                    AppError::BadRequest(format!("Statement entry {} has no amount", index + 1))
+                })?;
+            let amount = amount_node
+                .text()
+                .and_then(|text| text.trim().parse::<Decimal>().ok())
+                .ok_or_else(|| {
+                    AppError::BadRequest(format!(
+                        "Statement entry {} has an invalid amount",
+                        index + 1
+                    ))
+                })?;
+            let currency = amount_node
+                .attribute("Ccy")
+                .unwrap_or(CURRENCY)
+                .to_uppercase();
+
+            let mut details = descendant_texts(*source, "Ustrd");
+            details.extend(descendant_texts(*source, "Ref"));
+            details.extend(descendant_texts(ntry, "AddtlNtryInf"));
+            details.dedup();
+
+            let payer_name = source
+                .descendants()
+                .find(|d| d.tag_name().name() == "Dbtr")
+                .and_then(|debtor| {
+                    debtor
+                        .descendants()
+                        .find(|d| d.tag_name().name() == "Nm")
+                        .and_then(|name| name.text())
+                })
+                .map(|name| name.trim().to_string());
+
+            // The bank's own id, or one made up from where the payment sits
+            let bank_reference = path(*source, &["Refs", "AcctSvcrRef"])
+                .or_else(|| match (&entry_reference, sources.len()) {
+                    (Some(reference), 1) => Some(reference.clone()),
+                    (Some(reference), _) => Some(format!("{}/{}", reference, position + 1)),
+                    (None, _) => None,
+                })
+                .or_else(|| path(*source, &["Refs", "EndToEndId"]))
+                .unwrap_or_else(|| fallback_reference(booking_date, amount, &details.join(" ")));
+
+            entries.push(StatementEntry {
+                bank_reference,
+                booking_date,
+                amount,
+                currency,
+                payer_name,
+                remittance_info: Some(details.join(" ")).filter(|info| !info.is_empty()),
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+// Elements are looked up by local name, so every camt.053 version reads the
+// same
+type Node<'a> = roxmltree::Node<'a, 'a>;
+
+fn child<'a>(node: Node<'a>, name: &str) -> Option<Node<'a>> {
+    node.children()
+        .find(|child| child.tag_name().name() == name)
+}
+
+// Text of the element at the path below the node
+fn path(node: Node<'_>, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .try_fold(node, |node, name| child(node, name))
+        .and_then(|node| node.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn descendant_texts<'a>(node: Node<'a>, name: &str) -> Vec<&'a str> {
+    node.descendants()
+        .filter(|d| d.tag_name().name() == name)
+        .filter_map(|d| d.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+// Bank CSV exports, with either a signed amount or a separate credit column
+fn parse_csv(data: &[u8]) -> Result<Vec<StatementEntry>, AppError> {
+    let report = report::read_csv(data)?;
+    let amount_index = report.optional_column(&AMOUNT_HEADERS).ok_or_else(|| {
+        AppError::BadRequest("Could not find the amount column of the statement".to_string())
+    })?;
+    let details_index = report.optional_column(&DETAILS_HEADERS).ok_or_else(|| {
+        AppError::BadRequest(
+            "Could not find the payment details column of the statement".to_string(),
+        )
+    })?;
+    let payer_index = report.optional_column(&PAYER_HEADERS);
+    let date_index = report.optional_column(&DATE_HEADERS);
+    let currency_index = report.optional_column(&CURRENCY_HEADERS);
+
+    let field = |record: &csv::StringRecord, index: Option<usize>| {
+        index
+            .and_then(|index| record.get(index))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+
+    let mut entries = Vec::new();
+    for (line_number, record) in &report.rows {
+        let Some(raw_amount) = field(record, Some(amount_index)) else {
+            continue;
+        };
+        let amount =
+            report::parse_amount(&raw_amount, report::DecimalSeparator::Last).ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "Line {}: invalid amount '{}'",
+                    line_number, raw_amount
+                ))
+            })?;
+        // Outgoing payments
+        if amount <= Decimal::ZERO {
+            continue;
+        }
+
+        let booking_date = field(record, date_index).and_then(|date| {
+            ["%d.%m.%Y", "%Y-%m-%d", "%d/%m/%Y"]
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(&date, format).ok())
+        });
+        let remittance_info = field(record, Some(details_index));
+
+        entries.push(StatementEntry {
+            bank_reference: fallback_reference(
+                booking_date,
+                amount,
+                remittance_info.as_deref().unwrap_or_default(),
+            ),
+            booking_date,
+            amount,
+            currency: field(record, currency_index)
+                .map(|currency| currency.to_uppercase())
+                .unwrap_or_else(|| CURRENCY.to_string()),
+            payer_name: field(record, payer_index),
+            remittance_info,
+        });
+    }
+
+    Ok(entries)
+}
+
+// Stands in for the bank's id when the statement has none, so importing the
+// same statement twice is still recognised
+fn fallback_reference(booking_date: Option<NaiveDate>, amount: Decimal, details: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let digest = Sha256::digest(format!(
+        "{}|{}|{}",
+        booking_date.map(|d| d.to_string()).unwrap_or_default(),
+        amount,
+        details
+    ));
+    format!("sha256:{}", hex::encode(&digest[..16]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    const CAMT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <BkToCstmrStmt><Stmt>
+    <Ntry>
+      <Amt Ccy="EUR">45.90</Amt>
+      <CdtDbtInd>CRDT</CdtDbtInd>
+      <BookgDt><Dt>2026-03-02</Dt></BookgDt>
+      <AcctSvcrRef>BNK-1</AcctSvcrRef>
+      <NtryDtls><TxDtls>
+        <RltdPties><Dbtr><Nm> Мария Петрова </Nm></Dbtr></RltdPties>
+        <RmtInf><Ustrd>Поръчка sz 7k4m-9qxd</Ustrd></RmtInf>
+      </TxDtls></NtryDtls>
+    </Ntry>
+    <Ntry>
+      <Amt Ccy="EUR">12.00</Amt>
+      <CdtDbtInd>DBIT</CdtDbtInd>
+      <AcctSvcrRef>BNK-2</AcctSvcrRef>
+    </Ntry>
+    <Ntry>
+      <Amt Ccy="EUR">45.90</Amt>
+      <CdtDbtInd>CRDT</CdtDbtInd>
+      <RvslInd>true</RvslInd>
+      <AcctSvcrRef>BNK-3</AcctSvcrRef>
+    </Ntry>
+    <Ntry>
+      <Amt Ccy="BGN">150.00</Amt>
+      <CdtDbtInd>CRDT</CdtDbtInd>
+      <BookgDt><DtTm>2026-03-03T09:15:00</DtTm></BookgDt>
+      <AcctSvcrRef>BNK-4</AcctSvcrRef>
+      <NtryDtls>
+        <TxDtls>
+          <AmtDtls><TxAmt><Amt Ccy="BGN">100.00</Amt></TxAmt></AmtDtls>
+          <RmtInf><Ustrd>SZABCDEFGH</Ustrd></RmtInf>
+        </TxDtls>
+        <TxDtls>
+          <Refs><AcctSvcrRef>TX-2</AcctSvcrRef></Refs>
+          <AmtDtls><TxAmt><Amt Ccy="BGN">50.00</Amt></TxAmt></AmtDtls>
+          <RmtInf><Ustrd>SZJKLMNPQR</Ustrd></RmtInf>
+        </TxDtls>
+      </NtryDtls>
+    </Ntry>
+  </Stmt></BkToCstmrStmt>
+</Document>"#;
+
+    #[test]
+    fn reads_incoming_camt053_payments() {
+        let (format, entries) = parse_statement(CAMT.as_bytes()).unwrap();
+
+        assert_eq!(format, "camt053");
+        assert_eq!(entries.len(), 3);
+
+        let payment = &entries[0];
+        assert_eq!(payment.bank_reference, "BNK-1");
+        assert_eq!(payment.booking_date, NaiveDate::from_ymd_opt(2026, 3, 2));
+        assert_eq!(payment.amount, dec("45.90"));
+        assert_eq!(payment.currency, "EUR");
+        assert_eq!(payment.payer_name.as_deref(), Some("Мария Петрова"));
+        assert_eq!(
+            payment.remittance_info.as_deref(),
+            Some("Поръчка sz 7k4m-9qxd")
+        );
+        assert_eq!(payment.in_euro(), Some(Money::eur(dec("45.90"))));
+    }
+
+    #[test]
+    fn splits_camt053_batch_bookings() {
+        let entries = parse_camt053(CAMT).unwrap();
+        let batch = &entries[1..];
+
+        assert_eq!(batch[0].bank_reference, "BNK-4/1");
+        assert_eq!(batch[0].amount, dec("100.00"));
+        assert_eq!(batch[0].currency, "BGN");
+        assert_eq!(batch[0].booking_date, NaiveDate::from_ymd_opt(2026, 3, 3));
+        assert_eq!(batch[1].bank_reference, "TX-2");
+        assert_eq!(batch[1].amount, dec("50.00"));
+        assert_eq!(batch[1].remittance_info.as_deref(), Some("SZJKLMNPQR"));
+    }
+
+    #[test]
+    fn refuses_broken_camt053() {
+        assert!(matches!(
+            parse_camt053("<Document><Ntry>"),
+            Err(AppError::BadRequest(_))
+        ));
+
+        let bad_amount =
+            "<Document><Ntry><Amt>12,5O</Amt><CdtDbtInd>CRDT</CdtDbtInd></Ntry></Document>";
+        assert!(parse_camt053(bad_amount).is_err());
+    }
+
+    #[test]
+    fn reads_incoming_csv_payments() {
+        let csv = "Дата;Наредител;Основание;Кредит;Валута\n\
+                   02.03.2026;Иван Иванов;SZ7K4M9QXD;1 234,50;eur\n\
+                   03.03.2026;Банка;Такса;-2,00;EUR\n\
+                   04.03.2026;Петър;;;\n";
+        let (format, entries) = parse_statement(csv.as_bytes()).unwrap();
+
+        assert_eq!(format, "csv");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].amount, dec("1234.50"));
+        assert_eq!(entries[0].currency, "EUR");
+        assert_eq!(entries[0].payer_name.as_deref(), Some("Иван Иванов"));
+        assert_eq!(entries[0].booking_date, NaiveDate::from_ymd_opt(2026, 3, 2));
+        assert!(entries[0].bank_reference.starts_with("sha256:"));
+
+        // Importing the same line again gives the same reference
+        let again = parse_csv(csv.as_bytes()).unwrap();
+        assert_eq!(again[0].bank_reference, entries[0].bank_reference);
+    }
+
+    #[test]
+    fn refuses_csv_without_the_needed_columns() {
+        assert!(parse_csv("Дата;Наредител;Кредит\n02.03.2026;Иван;10,00\n".as_bytes()).is_err());
+        assert!(parse_csv("Дата;Основание;Кредит\n02.03.2026;SZ;десет\n".as_bytes()).is_err());
+        assert!(parse_statement("Основание;Кредит\nТакса;-2,00\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn finds_references_however_they_are_written() {
+        assert_eq!(find_references("Поръчка sz 7k4m-9qxd"), vec!["SZ7K4M9QXD"]);
+        assert_eq!(
+            find_references("SZABCDEFGH и SZJKLMNPQR"),
+            vec!["SZABCDEFGH", "SZJKLMNPQR"]
+        );
+        // 0, O, 1 and I are not in the alphabet
+        assert!(find_references("SZ0BCDEFGH SZ1BCDEFGH").is_empty());
+        assert!(find_references("за цветя").is_empty());
+    }
+
+    #[test]
+    fn generated_references_can_be_found_again() {
+        let reference = generate_reference();
+
+        assert_eq!(reference.len(), REFERENCE_PREFIX.len() + REFERENCE_LENGTH);
+        assert_eq!(find_references(&reference.to_lowercase()), vec![reference]);
+    }
+}
@@ -0,0 +1,263 @@
+use sqlx::PgConnection;
+use std::collections::HashSet;
+
+use super::{
+    report::{self, DecimalSeparator},
+    PaymentMethod, PaymentStatus,
+};
+use crate::{
+    error::AppError,
+    models::{CodRemittance, Money, Order},
+    shipping::Carrier,
+};
+
+// Length of `cod_remittance_lines.raw_amount`
+const MAX_RAW_AMOUNT_CHARS: usize = 100;
+
+// Header words recognised in the couriers' payout reports (Econt, Speedy)
+// and hand-made ones, in order of preference
+const TRACKING_HEADERS: [&str; 6] = [
+    "tracking",
+    "товарителница",
+    "пратка",
+    "waybill",
+    "shipment",
+    "parcel",
+];
+const AMOUNT_HEADERS: [&str; 6] = [
+    "наложен",
+    "cod amount",
+    "cod_amount",
+    "amount",
+    "сума",
+    "стойност",
+];
+
+// One parcel of a payout report
+pub struct PayoutRow {
+    // Row of the file, counting the header as 1
+    pub line_number: i32,
+    pub tracking_number: String,
+    // None when the amount could not be read
+    pub amount: Option<Money>,
+    // The amount as written in the report
+    pub raw_amount: String,
+}
+
+// How a report row compares with its order; stored in
+// `cod_remittance_lines.status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineStatus {
+    Matched,
+    AmountMismatch,
+    UnknownTrackingNumber,
+    NotCashOnDelivery,
+    AlreadyCollected,
+    // The tracking number came up earlier in the same report
+    Duplicate,
+    // The amount is not written with a decimal point, e.g. "1,250"
+    InvalidAmount,
+}
+
+impl LineStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineStatus::Matched => "matched",
+            LineStatus::AmountMismatch => "amount_mismatch",
+            LineStatus::UnknownTrackingNumber => "unknown_tracking_number",
+            LineStatus::NotCashOnDelivery => "not_cash_on_delivery",
+            LineStatus::AlreadyCollected => "already_collected",
+            LineStatus::Duplicate => "duplicate",
+            LineStatus::InvalidAmount => "invalid_amount",
+        }
+    }
+}
+
+// Reads the tracking number and amount of every parcel in a CSV payout
+// report. The columns are found by their header unless named. Rows without a
+// tracking number, like totals, are skipped; amounts that cannot be read are
+// left for `reconcile` to report.
+pub fn parse_payout_report(
+    data: &[u8],
+    tracking_column: Option<&str>,
+    amount_column: Option<&str>,
+) -> Result<Vec<PayoutRow>, AppError> {
+    let report = report::read_csv(data)?;
+    let tracking_index = report.column(tracking_column, &TRACKING_HEADERS, "tracking number")?;
+    let amount_index = report.column(amount_column, &AMOUNT_HEADERS, "amount")?;
+
+    let mut rows = Vec::new();
+    for (line_number, record) in &report.rows {
+        let tracking_number: String = record
+            .get(tracking_index)
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        if tracking_number.is_empty() {
+            continue;
+        }
+
+        let raw_amount = record.get(amount_index).unwrap_or_default();
+        let amount = report::parse_amount(raw_amount, DecimalSeparator::Point)
+            .filter(|amount| !amount.is_sign_negative())
+            .map(Money::eur);
+
+        rows.push(PayoutRow {
+            line_number: *line_number,
+            tracking_number,
+            amount,
+            raw_amount: raw_amount.chars().take(MAX_RAW_AMOUNT_CHARS).collect(),
+        });
+    }
+
+    if rows.is_empty() {
+        return Err(AppError::BadRequest(
+            "The report has no parcels".to_string(),
+        ));
+    }
+
+    Ok(rows)
+}
+
+// Records the report and matches each parcel to the order with its tracking
+// number. Orders whose collected cash matches the total are marked paid;
+// anything else is kept as a discrepancy and leaves the order untouched. The
+// total leaves out amounts that could not be read.
+pub async fn reconcile(
+    conn: &mut PgConnection,
+    carrier: Option<Carrier>,
+    file_name: Option<&str>,
+    rows: &[PayoutRow],
+    admin_id: i32,
+) -> Result<CodRemittance, AppError> {
+    let total_amount: Money = rows.iter().filter_map(|row| row.amount).sum();
+
+    let remittance_id = sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO cod_remittances (
+            carrier, file_name, total_amount, line_count, matched_count, admin_id
+        ) VALUES ($1, $2, $3, $4, 0, $5)
+        RETURNING id
+        "#,
+    )
+    .bind(carrier.map(Carrier::as_str))
+    .bind(file_name)
+    .bind(total_amount)
+    .bind(rows.len() as i32)
+    .bind(admin_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let mut seen = HashSet::new();
+    let mut matched_count = 0;
+    for row in rows {
+        let order = sqlx::query_as::<_, Order>(
+            r#"
+            SELECT * FROM orders WHERE tracking_number = $1
+            ORDER BY created_at DESC
+            LIMIT 1
+            FOR UPDATE
+            "#,
+        )
+        .bind(&row.tracking_number)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let status = match &order {
+            _ if !seen.insert(row.tracking_number.as_str()) => LineStatus::Duplicate,
+            _ if row.amount.is_none() => LineStatus::InvalidAmount,
+            None => LineStatus::UnknownTrackingNumber,
+            Some(order) if order.payment_method != PaymentMethod::CashOnDelivery.as_str() => {
+                LineStatus::NotCashOnDelivery
+            }
+            Some(order) if order.collected_amount.is_some() => LineStatus::AlreadyCollected,
+            Some(order) if Some(order.total_amount) != row.amount => LineStatus::AmountMismatch,
+            Some(_) => LineStatus::Matched,
+        };
+
+        if let (LineStatus::Matched, Some(order)) = (status, &order) {
+            sqlx::query(
+                r#"
+                UPDATE orders SET
+                    collected_amount = $1,
+                    payment_status = $2,
+                    paid_at = COALESCE(paid_at, NOW())
+                WHERE id = $3
+                "#,
+            )
+            .bind(row.amount)
+            .bind(PaymentStatus::Paid.as_str())
+            .bind(order.id)
+            .execute(&mut *conn)
+            .await?;
+            matched_count += 1;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO cod_remittance_lines (
+                remittance_id, line_number, tracking_number, amount, raw_amount,
+                order_id, expected_amount, status
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(remittance_id)
+        .bind(row.line_number)
+        .bind(&row.tracking_number)
+        .bind(row.amount)
+        .bind(row.amount.is_none().then_some(row.raw_amount.as_str()))
+        .bind(order.as_ref().map(|order| order.id))
+        .bind(order.as_ref().map(|order| order.total_amount))
+        .bind(status.as_str())
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    let remittance = sqlx::query_as::<_, CodRemittance>(
+        "UPDATE cod_remittances SET matched_count = $1 WHERE id = $2 RETURNING *",
+    )
+    .bind(matched_count)
+    .bind(remittance_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(remittance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_rows_whose_amount_cannot_be_read() {
+        let report = "Tracking,COD amount\n1051, 12.50\n1052,\"1,250\"\n,1265.50\n1053,-3.00\n";
+        let rows = parse_payout_report(report.as_bytes(), None, None).unwrap();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].line_number, 2);
+        assert_eq!(rows[0].tracking_number, "1051");
+        assert_eq!(rows[0].amount, Some(Money::eur("12.50".parse().unwrap())));
+        assert_eq!(rows[1].line_number, 3);
+        assert_eq!(rows[1].amount, None);
+        assert_eq!(rows[1].raw_amount, "1,250");
+        assert_eq!(rows[2].line_number, 5);
+        assert_eq!(rows[2].amount, None);
+    }
+
+    #[test]
+    fn reads_named_columns() {
+        let report = "ref;Пратка;Сума\nA;1051;12.50\n";
+        let rows = parse_payout_report(report.as_bytes(), Some("Пратка"), Some("сума")).unwrap();
+
+        assert_eq!(rows[0].tracking_number, "1051");
+        assert!(parse_payout_report(report.as_bytes(), Some("waybill"), None).is_err());
+    }
+
+    #[test]
+    fn refuses_reports_without_parcels() {
+        let report = "tracking;amount\n;100.00\n";
+
+        assert!(parse_payout_report(report.as_bytes(), None, None).is_err());
+    }
+}
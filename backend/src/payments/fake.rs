@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use super::{minor_units, Intent, IntentRequest, PaymentOutcome, PaymentProvider, WebhookEvent};
+use crate::{error::AppError, models::Money};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const DEFAULT_WEBHOOK_SECRET: &str = "fake-payment-webhook-secret";
+
+// Header carrying the hex HMAC-SHA256 of the webhook body
+pub const SIGNATURE_HEADER: &str = "X-Fake-Signature";
+
+// Provider for development and tests. Nothing leaves the machine: ids are
+// derived from the order, refunds always go through, and payments complete
+// when a signed event is posted to the webhook, e.g. with
+// `cargo run --example fake_payment`.
+pub struct FakeProvider {
+    webhook_secret: String,
+}
+
+// {"id": "evt_1", "type": "payment.succeeded" | "payment.failed",
+//  "payment_id": "fake_pi_...", "message": "Card declined"}
+#[derive(Deserialize)]
+struct Event {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    payment_id: String,
+    message: Option<String>,
+}
+
+impl FakeProvider {
+    pub fn new(webhook_secret: String) -> Self {
+        Self { webhook_secret }
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for FakeProvider {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    async fn create_intent(&self, intent: &IntentRequest) -> Result<Intent, AppError> {
+        let id = format!("fake_pi_{}", intent.order_id.simple());
+
+        Ok(Intent {
+            client_secret: format!("{}_secret", id),
+            id,
+        })
+    }
+
+    async fn cancel_intent(&self, _provider_payment_id: &str) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    async fn refund(
+        &self,
+        provider_payment_id: &str,
+        amount: Money,
+        idempotency_key: &str,
+    ) -> Result<String, AppError> {
+        Ok(format!(
+            "fake_re_{}_{}_{}",
+            provider_payment_id.trim_start_matches("fake_pi_"),
+            minor_units(amount)?,
+            idempotency_key
+        ))
+    }
+
+    fn parse_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<WebhookEvent, AppError> {
+        let invalid = || AppError::Unauthorized("Invalid webhook signature".to_string());
+
+        let signature = headers
+            .get(SIGNATURE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| hex::decode(value).ok())
+            .ok_or_else(invalid)?;
+        let mut mac = HmacSha256::new_from_slice(self.webhook_secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(body);
+        mac.verify_slice(&signature).map_err(|_| invalid())?;
+
+        let event: Event = serde_json::from_slice(body)
+            .map_err(|e| AppError::BadRequest(format!("Invalid webhook event: {}", e)))?;
+
+        let outcome = match event.kind.as_str() {
+            "payment.succeeded" => Some(PaymentOutcome::Succeeded {
+                provider_payment_id: event.payment_id,
+            }),
+            "payment.failed" => Some(PaymentOutcome::Failed {
+                provider_payment_id: event.payment_id,
+                message: event.message,
+            }),
+            _ => None,
+        };
+
+        Ok(WebhookEvent {
+            id: event.id,
+            outcome,
+        })
+    }
+}
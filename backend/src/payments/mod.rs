@@ -0,0 +1,451 @@
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    email::templates,
+    error::AppError,
+    handlers::orders::transition_order,
+    models::{CheckoutPayment, Currency, Money, Order, Payment},
+    order_status::OrderStatus,
+};
+
+pub mod bank_transfer;
+pub mod cod;
+mod fake;
+mod report;
+mod stripe;
+
+pub use bank_transfer::BankAccount;
+pub use fake::FakeProvider;
+pub use stripe::{StripeConfig, StripeProvider};
+
+// Payments are taken in euro
+pub const CURRENCY: &str = Currency::Eur.as_str();
+
+// How long a card order waits for its payment unless
+// CARD_PAYMENT_TIMEOUT_MINUTES says otherwise
+const DEFAULT_CARD_TIMEOUT_MINUTES: i32 = 60;
+
+// How the customer pays; stored in `orders.payment_method`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentMethod {
+    // Cash to the courier, or at the studio for pickup
+    #[default]
+    CashOnDelivery,
+    BankTransfer,
+    Card,
+}
+
+impl PaymentMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PaymentMethod::CashOnDelivery => "cash_on_delivery",
+            PaymentMethod::BankTransfer => "bank_transfer",
+            PaymentMethod::Card => "card",
+        }
+    }
+}
+
+// Whether the order has been paid for, independent of its fulfilment status;
+// stored in `orders.payment_status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentStatus {
+    Pending,
+    Paid,
+    // The card was declined; the customer can try again
+    Failed,
+    PartiallyRefunded,
+    Refunded,
+}
+
+impl PaymentStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PaymentStatus::Pending => "pending",
+            PaymentStatus::Paid => "paid",
+            PaymentStatus::Failed => "failed",
+            PaymentStatus::PartiallyRefunded => "partially_refunded",
+            PaymentStatus::Refunded => "refunded",
+        }
+    }
+}
+
+// Card payment provider: takes payments, refunds them and reports back
+// through signed webhooks
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    // Stored with each payment, e.g. "stripe"
+    fn name(&self) -> &'static str;
+
+    // Key the browser needs to collect card details, if the provider has one
+    fn publishable_key(&self) -> Option<&str> {
+        None
+    }
+
+    async fn create_intent(&self, intent: &IntentRequest) -> Result<Intent, AppError>;
+
+    // Stops a payment that has not gone through, so it cannot be completed
+    // later. Fails for payments that have already succeeded.
+    async fn cancel_intent(&self, provider_payment_id: &str) -> Result<(), AppError>;
+
+    // Returns the provider's refund id. Sending the same idempotency key
+    // again returns the first refund instead of making another. BadRequest
+    // when the provider refuses the refund.
+    async fn refund(
+        &self,
+        provider_payment_id: &str,
+        amount: Money,
+        idempotency_key: &str,
+    ) -> Result<String, AppError>;
+
+    // Checks the signature and reads the event. Unauthorized when the
+    // signature does not match.
+    fn parse_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<WebhookEvent, AppError>;
+}
+
+pub type SharedPaymentProvider = Arc<dyn PaymentProvider>;
+
+// Payment asked for at checkout
+pub struct IntentRequest {
+    pub order_id: Uuid,
+    // Order number shown on the customer's statement
+    pub reference: String,
+    pub amount: Money,
+    pub customer_email: String,
+}
+
+// Payment waiting for the customer's card
+pub struct Intent {
+    pub id: String,
+    // Lets the browser confirm this one payment
+    pub client_secret: String,
+}
+
+pub struct WebhookEvent {
+    // Provider's event id, for skipping redeliveries
+    pub id: String,
+    // None for events that do not concern payments
+    pub outcome: Option<PaymentOutcome>,
+}
+
+pub enum PaymentOutcome {
+    Succeeded {
+        provider_payment_id: String,
+    },
+    Failed {
+        provider_payment_id: String,
+        message: Option<String>,
+    },
+}
+
+// The configured provider and bank account; without either orders are paid
+// on delivery only
+#[derive(Clone)]
+pub struct Payments {
+    provider: Option<SharedPaymentProvider>,
+    bank_account: Option<Arc<BankAccount>>,
+    // Minutes after checkout an unpaid card order is cancelled
+    card_timeout_minutes: i32,
+}
+
+impl Payments {
+    pub fn provider(&self) -> Result<&SharedPaymentProvider, AppError> {
+        self.provider
+            .as_ref()
+            .ok_or_else(|| AppError::BadRequest("Card payments are not available".to_string()))
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        self.provider.as_ref().map(|provider| provider.name())
+    }
+
+    pub fn publishable_key(&self) -> Option<&str> {
+        self.provider
+            .as_ref()
+            .and_then(|provider| provider.publishable_key())
+    }
+
+    pub fn bank_account(&self) -> Result<&BankAccount, AppError> {
+        self.bank_account
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest("Bank transfer is not available".to_string()))
+    }
+
+    pub fn takes_bank_transfers(&self) -> bool {
+        self.bank_account.is_some()
+    }
+}
+
+// Builds the provider selected by PAYMENT_PROVIDER ("stripe" or "fake") and
+// the bank account for transfers
+pub fn from_env() -> Payments {
+    // docker-compose passes unset variables as empty strings
+    let optional = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let require = |name: &str| {
+        optional(name).unwrap_or_else(|| panic!("{} must be set for card payments", name))
+    };
+
+    let provider: Option<SharedPaymentProvider> = match optional("PAYMENT_PROVIDER").as_deref() {
+        None => None,
+        Some("stripe") => Some(Arc::new(StripeProvider::new(StripeConfig {
+            api_url: optional("STRIPE_API_URL")
+                .unwrap_or_else(|| "https://api.stripe.com".to_string()),
+            secret_key: require("STRIPE_SECRET_KEY"),
+            publishable_key: require("STRIPE_PUBLISHABLE_KEY"),
+            webhook_secret: require("STRIPE_WEBHOOK_SECRET"),
+        }))),
+        Some("fake") => Some(Arc::new(FakeProvider::new(
+            optional("FAKE_PAYMENT_WEBHOOK_SECRET")
+                .unwrap_or_else(|| fake::DEFAULT_WEBHOOK_SECRET.to_string()),
+        ))),
+        Some(other) => panic!(
+            "Unknown PAYMENT_PROVIDER '{}', expected 'stripe' or 'fake'",
+            other
+        ),
+    };
+
+    let card_timeout_minutes = optional("CARD_PAYMENT_TIMEOUT_MINUTES")
+        .map(|minutes| {
+            minutes
+                .parse::<i32>()
+                .ok()
+                .filter(|minutes| *minutes > 0)
+                .expect("CARD_PAYMENT_TIMEOUT_MINUTES must be a positive number of minutes")
+        })
+        .unwrap_or(DEFAULT_CARD_TIMEOUT_MINUTES);
+
+    Payments {
+        provider,
+        bank_account: BankAccount::from_env().map(Arc::new),
+        card_timeout_minutes,
+    }
+}
+
+// Amount in cents, the unit providers charge in
+pub fn minor_units(amount: Money) -> Result<i64, AppError> {
+    (amount.amount() * rust_decimal::Decimal::ONE_HUNDRED)
+        .round()
+        .to_i64()
+        .ok_or_else(|| AppError::BadRequest(format!("Invalid amount {}", amount)))
+}
+
+// Asks the provider for a payment of the order total and records it. Orders
+// with nothing to pay count as paid straight away. Called once the order is
+// committed, so nothing is locked while the provider answers; the intent is
+// keyed by order, so asking again cannot open a second payment.
+pub async fn start_payment(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    order: &Order,
+) -> Result<Option<CheckoutPayment>, AppError> {
+    if order.total_amount <= Money::ZERO {
+        sqlx::query("UPDATE orders SET payment_status = $1, paid_at = NOW() WHERE id = $2")
+            .bind(PaymentStatus::Paid.as_str())
+            .bind(order.id)
+            .execute(pool)
+            .await?;
+        return Ok(None);
+    }
+
+    let intent = provider
+        .create_intent(&IntentRequest {
+            order_id: order.id,
+            reference: templates::order_reference(order),
+            amount: order.total_amount,
+            customer_email: order.customer_email.clone(),
+        })
+        .await?;
+
+    let payment = sqlx::query_as::<_, Payment>(
+        r#"
+        INSERT INTO payments (order_id, provider, provider_payment_id, amount, currency)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (provider, provider_payment_id) DO UPDATE SET updated_at = NOW()
+        RETURNING *
+        "#,
+    )
+    .bind(order.id)
+    .bind(provider.name())
+    .bind(&intent.id)
+    .bind(order.total_amount)
+    .bind(CURRENCY)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Some(CheckoutPayment {
+        provider: payment.provider,
+        client_secret: intent.client_secret,
+        publishable_key: provider.publishable_key().map(str::to_string),
+    }))
+}
+
+// Applies a webhook outcome to the payment and its order. A late failure
+// never undoes a success, and events for payments made elsewhere on the same
+// account are ignored.
+pub async fn record_outcome(
+    conn: &mut PgConnection,
+    provider: &str,
+    outcome: PaymentOutcome,
+) -> Result<(), AppError> {
+    let (provider_payment_id, status, message) = match &outcome {
+        PaymentOutcome::Succeeded {
+            provider_payment_id,
+        } => (provider_payment_id, "succeeded", None),
+        PaymentOutcome::Failed {
+            provider_payment_id,
+            message,
+        } => (provider_payment_id, "failed", message.as_deref()),
+    };
+
+    let payment = sqlx::query_as::<_, Payment>(
+        "SELECT * FROM payments WHERE provider = $1 AND provider_payment_id = $2 FOR UPDATE",
+    )
+    .bind(provider)
+    .bind(provider_payment_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(payment) = payment else {
+        tracing::warn!(
+            "Webhook for unknown {} payment {}",
+            provider,
+            provider_payment_id
+        );
+        return Ok(());
+    };
+    if payment.status == "succeeded" {
+        return Ok(());
+    }
+
+    sqlx::query("UPDATE payments SET status = $1, failure_message = $2 WHERE id = $3")
+        .bind(status)
+        .bind(message)
+        .bind(payment.id)
+        .execute(&mut *conn)
+        .await?;
+
+    match outcome {
+        PaymentOutcome::Succeeded { .. } => {
+            sqlx::query(
+                r#"
+                UPDATE orders SET payment_status = $1, paid_at = COALESCE(paid_at, NOW())
+                WHERE id = $2 AND payment_status IN ('pending', 'failed')
+                "#,
+            )
+            .bind(PaymentStatus::Paid.as_str())
+            .bind(payment.order_id)
+            .execute(&mut *conn)
+            .await?;
+            tracing::info!("Order {} paid by card", payment.order_id);
+        }
+        PaymentOutcome::Failed { .. } => {
+            sqlx::query(
+                "UPDATE orders SET payment_status = $1 WHERE id = $2 AND payment_status = 'pending'",
+            )
+            .bind(PaymentStatus::Failed.as_str())
+            .bind(payment.order_id)
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Cancels card orders still unpaid after the timeout, e.g. when the customer
+// left the payment form or gave up after a declined card, putting their items
+// back in stock. Their payments are cancelled with the provider first, so
+// none can go through for a cancelled order; an order whose payment cannot be
+// cancelled any more is left for its webhook. Orders an admin has started on
+// are left alone.
+pub async fn cancel_abandoned(pool: &PgPool, payments: &Payments) -> Result<u64, AppError> {
+    let overdue = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id FROM orders
+        WHERE payment_method = $1 AND payment_status IN ($2, $3) AND status = $4
+          AND created_at <= NOW() - make_interval(mins => $5)
+        ORDER BY created_at
+        "#,
+    )
+    .bind(PaymentMethod::Card.as_str())
+    .bind(PaymentStatus::Pending.as_str())
+    .bind(PaymentStatus::Failed.as_str())
+    .bind(OrderStatus::Pending.as_str())
+    .bind(payments.card_timeout_minutes)
+    .fetch_all(pool)
+    .await?;
+
+    let mut cancelled = 0;
+    'orders: for id in overdue {
+        let open = sqlx::query_as::<_, (String, String)>(
+            r#"
+            SELECT provider, provider_payment_id FROM payments
+            WHERE order_id = $1 AND status <> 'succeeded'
+            "#,
+        )
+        .bind(id)
+        .fetch_all(pool)
+        .await?;
+        for (provider_name, provider_payment_id) in open {
+            match &payments.provider {
+                Some(provider) if provider.name() == provider_name => {
+                    if let Err(err) = provider.cancel_intent(&provider_payment_id).await {
+                        tracing::warn!(
+                            "Could not cancel payment {} of order {}: {:?}",
+                            provider_payment_id,
+                            id,
+                            err
+                        );
+                        continue 'orders;
+                    }
+                }
+                _ => tracing::warn!(
+                    "Cancelling order {} without its {} payment {}",
+                    id,
+                    provider_name,
+                    provider_payment_id
+                ),
+            }
+        }
+
+        let mut tx = pool.begin().await?;
+
+        // The webhook may have marked it paid in the meantime
+        let still_unpaid = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT payment_status IN ($2, $3) AND status = $4
+            FROM orders WHERE id = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(id)
+        .bind(PaymentStatus::Pending.as_str())
+        .bind(PaymentStatus::Failed.as_str())
+        .bind(OrderStatus::Pending.as_str())
+        .fetch_one(&mut *tx)
+        .await?;
+        if !still_unpaid {
+            continue;
+        }
+
+        transition_order(
+            &mut tx,
+            id,
+            OrderStatus::Cancelled,
+            None,
+            Some("Card payment not completed in time"),
+        )
+        .await?;
+        tx.commit().await?;
+
+        cancelled += 1;
+    }
+
+    Ok(cancelled)
+}
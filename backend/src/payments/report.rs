@@ -0,0 +1,196 @@
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+use crate::error::AppError;
+
+// Rows of an uploaded CSV report, with lowercase headers
+pub struct CsvReport {
+    pub headers: Vec<String>,
+    // Row of the file, counting the header as 1, and its fields
+    pub rows: Vec<(i32, csv::StringRecord)>,
+}
+
+impl CsvReport {
+    // Index of the named column, or of the first header containing one of
+    // the known words
+    pub fn column(
+        &self,
+        name: Option<&str>,
+        known: &[&str],
+        label: &str,
+    ) -> Result<usize, AppError> {
+        if let Some(name) = name {
+            let name = name.trim().to_lowercase();
+            return self
+                .headers
+                .iter()
+                .position(|header| *header == name)
+                .ok_or_else(|| {
+                    AppError::BadRequest(format!("The report has no column '{}'", name))
+                });
+        }
+
+        self.optional_column(known).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Could not find the {} column; name it explicitly",
+                label
+            ))
+        })
+    }
+
+    pub fn optional_column(&self, known: &[&str]) -> Option<usize> {
+        known
+            .iter()
+            .find_map(|word| self.headers.iter().position(|header| header.contains(word)))
+    }
+}
+
+// Text of an upload, without the byte order mark spreadsheets add
+pub fn decode(data: &[u8]) -> Result<&str, AppError> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| AppError::BadRequest("The report must be UTF-8 encoded".to_string()))?;
+
+    Ok(text.trim_start_matches('\u{feff}'))
+}
+
+// Reads a CSV report. The delimiter is taken from the header line, since
+// Bulgarian spreadsheets export with semicolons.
+pub fn read_csv(data: &[u8]) -> Result<CsvReport, AppError> {
+    let text = decode(data)?;
+
+    let header_line = text.lines().next().unwrap_or_default();
+    let delimiter = [b';', b',', b'\t']
+        .into_iter()
+        .max_by_key(|d| header_line.bytes().filter(|b| b == d).count())
+        .unwrap_or(b',');
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| AppError::BadRequest(format!("Invalid CSV: {}", e)))?
+        .iter()
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let line_number = index as i32 + 2;
+        let record =
+            record.map_err(|e| AppError::BadRequest(format!("Line {}: {}", line_number, e)))?;
+        rows.push((line_number, record));
+    }
+
+    Ok(CsvReport { headers, rows })
+}
+
+// How a report writes the decimals of its amounts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecimalSeparator {
+    // "1234.50" or "1 234.50", as the couriers document their payout
+    // reports; a comma anywhere makes the amount unreadable
+    Point,
+    // Whichever of "," and "." comes last, as bank exports follow the
+    // locale: "12,50", "1 234,50" and "1,234.50"
+    Last,
+}
+
+// Parses an amount in euro with at most two decimals and an optional
+// trailing "€" or "EUR"; None when it cannot be read
+pub fn parse_amount(value: &str, separator: DecimalSeparator) -> Option<Decimal> {
+    let mut digits: String = value
+        .trim()
+        .trim_end_matches('€')
+        .trim_end_matches("EUR")
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\u{a0}')
+        .collect();
+
+    match separator {
+        DecimalSeparator::Point if digits.contains(',') => return None,
+        DecimalSeparator::Point => {}
+        DecimalSeparator::Last => {
+            if let Some(position) = digits.rfind([',', '.']) {
+                let (whole, fraction) = digits.split_at(position);
+                digits = format!("{}.{}", whole.replace([',', '.'], ""), &fraction[1..]);
+            }
+        }
+    }
+
+    let amount = Decimal::from_str(&digits).ok()?;
+    (amount.scale() <= 2).then_some(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(amount: &str) -> Decimal {
+        amount.parse().unwrap()
+    }
+
+    #[test]
+    fn reads_courier_amounts_with_a_decimal_point() {
+        let point = DecimalSeparator::Point;
+
+        assert_eq!(parse_amount("12.50", point), Some(dec("12.50")));
+        assert_eq!(parse_amount("1 234.50 €", point), Some(dec("1234.50")));
+        assert_eq!(parse_amount("1\u{a0}234.5EUR", point), Some(dec("1234.5")));
+        assert_eq!(parse_amount("40", point), Some(dec("40")));
+    }
+
+    #[test]
+    fn refuses_courier_amounts_that_read_two_ways() {
+        let point = DecimalSeparator::Point;
+
+        // A thousands separator or a decimal comma
+        assert_eq!(parse_amount("1,250", point), None);
+        assert_eq!(parse_amount("12,50", point), None);
+        assert_eq!(parse_amount("1,234.50", point), None);
+        assert_eq!(parse_amount("1.234", point), None);
+        assert_eq!(parse_amount("1.234.50", point), None);
+        assert_eq!(parse_amount("", point), None);
+        assert_eq!(parse_amount("n/a", point), None);
+    }
+
+    #[test]
+    fn reads_bank_amounts_by_the_last_separator() {
+        let last = DecimalSeparator::Last;
+
+        assert_eq!(parse_amount("12,50", last), Some(dec("12.50")));
+        assert_eq!(parse_amount("1 234,50", last), Some(dec("1234.50")));
+        assert_eq!(parse_amount("1,234.50", last), Some(dec("1234.50")));
+        assert_eq!(parse_amount("1.234,50", last), Some(dec("1234.50")));
+        assert_eq!(parse_amount("-12,50", last), Some(dec("-12.50")));
+        assert_eq!(parse_amount("1,250", last), None);
+    }
+
+    #[test]
+    fn finds_the_delimiter_and_columns_from_the_header() {
+        let report =
+            read_csv("\u{feff}Товарителница;Наложен платеж\n1051;12.50\n;12.50\n".as_bytes())
+                .unwrap();
+
+        assert_eq!(report.headers, ["товарителница", "наложен платеж"]);
+        assert_eq!(report.rows.len(), 2);
+        assert_eq!(report.rows[0].0, 2);
+        assert_eq!(&report.rows[0].1[1], "12.50");
+        assert_eq!(report.optional_column(&["наложен"]), Some(1));
+        assert_eq!(
+            report
+                .column(Some("Товарителница"), &[], "tracking number")
+                .unwrap(),
+            0
+        );
+        assert!(report.column(None, &["amount"], "amount").is_err());
+    }
+
+    #[test]
+    fn refuses_reports_that_are_not_utf8() {
+        assert!(read_csv(b"tracking,amount\n1051,\xff\n").is_err());
+    }
+}
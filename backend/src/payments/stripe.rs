@@ -0,0 +1,384 @@
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use sha2::Sha256;
+
+use super::{
+    minor_units, Intent, IntentRequest, PaymentOutcome, PaymentProvider, WebhookEvent, CURRENCY,
+};
+use crate::{error::AppError, models::Money};
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Webhooks signed longer ago than this are rejected as replays
+const SIGNATURE_TOLERANCE_SECS: i64 = 300;
+
+pub struct StripeConfig {
+    // https://api.stripe.com, or a local mock
+    pub api_url: String,
+    pub secret_key: String,
+    pub publishable_key: String,
+    // Signing secret of the webhook endpoint, whsec_...
+    pub webhook_secret: String,
+}
+
+// Stripe PaymentIntents; the browser confirms them with Stripe.js
+pub struct StripeProvider {
+    config: StripeConfig,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct StripeErrorResponse {
+    error: StripeError,
+}
+
+#[derive(Deserialize)]
+struct StripeError {
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PaymentIntentResponse {
+    id: String,
+    client_secret: String,
+}
+
+#[derive(Deserialize)]
+struct CancelledIntentResponse {
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct RefundResponse {
+    id: String,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct Event {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    data: EventData,
+}
+
+#[derive(Deserialize)]
+struct EventData {
+    object: EventObject,
+}
+
+#[derive(Deserialize)]
+struct EventObject {
+    id: String,
+    last_payment_error: Option<StripeError>,
+}
+
+impl StripeProvider {
+    pub fn new(mut config: StripeConfig) -> Self {
+        config.api_url = config.api_url.trim_end_matches('/').to_string();
+
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn post<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        form: &[(&str, String)],
+        idempotency_key: Option<&str>,
+    ) -> Result<T, AppError> {
+        let mut request = self
+            .client
+            .post(format!("{}{}", self.config.api_url, path))
+            .bearer_auth(&self.config.secret_key)
+            .form(form);
+        if let Some(key) = idempotency_key {
+            request = request.header("Idempotency-Key", key);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            tracing::error!("Stripe request {} failed: {:?}", path, e);
+            AppError::InternalServerError("Payment provider is unavailable".to_string())
+        })?;
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        // Another request with the same idempotency key is still running, its
+        // outcome is not known yet
+        if status == reqwest::StatusCode::CONFLICT {
+            tracing::warn!("Stripe is still busy with {}: {}", path, body);
+            return Err(AppError::Conflict(
+                "The payment provider is still processing this request".to_string(),
+            ));
+        }
+        if status.is_client_error() {
+            let message = serde_json::from_str::<StripeErrorResponse>(&body)
+                .ok()
+                .and_then(|response| response.error.message);
+            tracing::warn!("Stripe rejected {}: {} {}", path, status, body);
+            return Err(AppError::BadRequest(
+                message.unwrap_or_else(|| "Payment was rejected".to_string()),
+            ));
+        }
+        if !status.is_success() {
+            tracing::error!("Stripe failed {}: {} {}", path, status, body);
+            return Err(AppError::InternalServerError(
+                "Payment provider is unavailable".to_string(),
+            ));
+        }
+
+        serde_json::from_str(&body).map_err(|e| {
+            tracing::error!("Unexpected Stripe response to {}: {:?} {}", path, e, body);
+            AppError::InternalServerError("Unexpected response from Stripe".to_string())
+        })
+    }
+
+    // Stripe-Signature is "t=<unix time>,v1=<hex HMAC of "t.body">", with
+    // one v1 per active signing secret
+    fn verify_signature(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), AppError> {
+        let invalid = || AppError::Unauthorized("Invalid webhook signature".to_string());
+
+        let header = headers
+            .get("Stripe-Signature")
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(invalid)?;
+
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in header.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+                Some(("v1", value)) => signatures.extend(hex::decode(value).ok()),
+                _ => {}
+            }
+        }
+        let timestamp = timestamp.ok_or_else(invalid)?;
+        if (Utc::now().timestamp() - timestamp).abs() > SIGNATURE_TOLERANCE_SECS {
+            return Err(invalid());
+        }
+
+        let mac = {
+            let mut mac = HmacSha256::new_from_slice(self.config.webhook_secret.as_bytes())
+                .expect("HMAC accepts keys of any length");
+            mac.update(timestamp.to_string().as_bytes());
+            mac.update(b".");
+            mac.update(body);
+            mac
+        };
+        if signatures
+            .iter()
+            .any(|signature| mac.clone().verify_slice(signature).is_ok())
+        {
+            Ok(())
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for StripeProvider {
+    fn name(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn publishable_key(&self) -> Option<&str> {
+        Some(&self.config.publishable_key)
+    }
+
+    async fn create_intent(&self, intent: &IntentRequest) -> Result<Intent, AppError> {
+        let form = [
+            ("amount", minor_units(intent.amount)?.to_string()),
+            ("currency", CURRENCY.to_lowercase()),
+            ("automatic_payment_methods[enabled]", "true".to_string()),
+            ("description", format!("Поръчка № {}", intent.reference)),
+            ("receipt_email", intent.customer_email.clone()),
+            ("metadata[order_id]", intent.order_id.to_string()),
+        ];
+
+        // Keyed by order, so a retried checkout cannot open a second payment
+        let response: PaymentIntentResponse = self
+            .post(
+                "/v1/payment_intents",
+                &form,
+                Some(&format!("order-{}", intent.order_id)),
+            )
+            .await?;
+
+        Ok(Intent {
+            id: response.id,
+            client_secret: response.client_secret,
+        })
+    }
+
+    async fn cancel_intent(&self, provider_payment_id: &str) -> Result<(), AppError> {
+        let form = [("cancellation_reason", "abandoned".to_string())];
+
+        let response: CancelledIntentResponse = self
+            .post(
+                &format!("/v1/payment_intents/{}/cancel", provider_payment_id),
+                &form,
+                Some(&format!("cancel-{}", provider_payment_id)),
+            )
+            .await?;
+        if response.status != "canceled" {
+            return Err(AppError::BadRequest(format!(
+                "Stripe did not cancel the payment ({})",
+                response.status
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn refund(
+        &self,
+        provider_payment_id: &str,
+        amount: Money,
+        idempotency_key: &str,
+    ) -> Result<String, AppError> {
+        let form = [
+            ("payment_intent", provider_payment_id.to_string()),
+            ("amount", minor_units(amount)?.to_string()),
+        ];
+
+        let response: RefundResponse = self
+            .post("/v1/refunds", &form, Some(idempotency_key))
+            .await?;
+        if matches!(response.status.as_str(), "failed" | "canceled") {
+            return Err(AppError::BadRequest(format!(
+                "Stripe refused the refund ({})",
+                response.status
+            )));
+        }
+
+        Ok(response.id)
+    }
+
+    fn parse_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<WebhookEvent, AppError> {
+        self.verify_signature(headers, body)?;
+
+        let event: Event = serde_json::from_slice(body)
+            .map_err(|e| AppError::BadRequest(format!("Invalid webhook event: {}", e)))?;
+
+        let outcome = match event.kind.as_str() {
+            "payment_intent.succeeded" => Some(PaymentOutcome::Succeeded {
+                provider_payment_id: event.data.object.id,
+            }),
+            "payment_intent.payment_failed" => Some(PaymentOutcome::Failed {
+                provider_payment_id: event.data.object.id,
+                message: event
+                    .data
+                    .object
+                    .last_payment_error
+                    .and_then(|error| error.message),
+            }),
+            _ => None,
+        };
+
+        Ok(WebhookEvent {
+            id: event.id,
+            outcome,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "whsec_test";
+    const BODY: &[u8] = br#"{"id":"evt_1","type":"payment_intent.succeeded","data":{"object":{"id":"pi_1","last_payment_error":null}}}"#;
+
+    fn provider() -> StripeProvider {
+        StripeProvider::new(StripeConfig {
+            api_url: "http://localhost/".to_string(),
+            secret_key: "sk_test".to_string(),
+            publishable_key: "pk_test".to_string(),
+            webhook_secret: SECRET.to_string(),
+        })
+    }
+
+    fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn headers(signature: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("Stripe-Signature", signature.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn accepts_a_signed_event() {
+        let now = Utc::now().timestamp();
+        let header = format!("t={},v1={}", now, sign(SECRET, now, BODY));
+
+        let event = provider().parse_webhook(&headers(&header), BODY).unwrap();
+
+        assert_eq!(event.id, "evt_1");
+        assert!(matches!(
+            event.outcome,
+            Some(PaymentOutcome::Succeeded { provider_payment_id }) if provider_payment_id == "pi_1"
+        ));
+    }
+
+    #[test]
+    fn accepts_any_of_several_signatures() {
+        let now = Utc::now().timestamp();
+        let header = format!(
+            "t={},v1={},v0=ignored,v1={}",
+            now,
+            sign("whsec_old", now, BODY),
+            sign(SECRET, now, BODY)
+        );
+
+        assert!(provider().verify_signature(&headers(&header), BODY).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_wrong_signature_or_body() {
+        let now = Utc::now().timestamp();
+        let wrong_secret = format!("t={},v1={}", now, sign("whsec_other", now, BODY));
+        let right = format!("t={},v1={}", now, sign(SECRET, now, BODY));
+
+        for (header, body) in [
+            (wrong_secret.as_str(), BODY),
+            (right.as_str(), br#"{"id":"evt_2"}"#.as_slice()),
+            (&format!("t={},v1=not-hex", now), BODY),
+            (&format!("v1={}", sign(SECRET, now, BODY)), BODY),
+        ] {
+            assert!(matches!(
+                provider().verify_signature(&headers(header), body),
+                Err(AppError::Unauthorized(_))
+            ));
+        }
+        assert!(provider()
+            .verify_signature(&HeaderMap::new(), BODY)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_old_and_future_timestamps() {
+        let now = Utc::now().timestamp();
+
+        for timestamp in [
+            now - SIGNATURE_TOLERANCE_SECS - 10,
+            now + SIGNATURE_TOLERANCE_SECS + 10,
+        ] {
+            let header = format!("t={},v1={}", timestamp, sign(SECRET, timestamp, BODY));
+            assert!(provider()
+                .verify_signature(&headers(&header), BODY)
+                .is_err());
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+
+use crate::{
+    error::AppError,
+    models::{Money, Promotion},
+};
+
+// What a promotion takes off; stored in `promotions.kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromotionKind {
+    // `value` percent off the eligible items
+    Percentage,
+    // `value` off the eligible items, spread over them by price
+    FixedAmount,
+    // Shipping is waived, the items keep their price
+    FreeShipping,
+}
+
+impl PromotionKind {
+    pub const ALL: [PromotionKind; 3] = [
+        PromotionKind::Percentage,
+        PromotionKind::FixedAmount,
+        PromotionKind::FreeShipping,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PromotionKind::Percentage => "percentage",
+            PromotionKind::FixedAmount => "fixed_amount",
+            PromotionKind::FreeShipping => "free_shipping",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+            .ok_or_else(|| {
+                AppError::InternalServerError(format!("Unknown promotion kind '{}'", value))
+            })
+    }
+}
+
+// A cart or order line as far as promotions are concerned
+pub struct CartLine {
+    pub product_id: i32,
+    pub subtotal: Money,
+}
+
+// A promotion worked out for one cart
+pub struct AppliedPromotion {
+    pub promotion: Promotion,
+    // Amount off each line, in the order the lines were given
+    pub line_discounts: Vec<Money>,
+    pub discount_amount: Money,
+    pub free_shipping: bool,
+}
+
+// Looks up `code` and works out what it takes off the cart, or explains why it
+// does not apply. The promotion stays locked until the caller's transaction
+// ends, so concurrent orders cannot use it past its limits. The per-customer
+// limit is only checked when the customer's email is known.
+pub async fn apply(
+    conn: &mut PgConnection,
+    code: &str,
+    customer_email: Option<&str>,
+    lines: &[CartLine],
+) -> Result<AppliedPromotion, AppError> {
+    let promotion = sqlx::query_as::<_, Promotion>(
+        "SELECT * FROM promotions WHERE UPPER(code) = UPPER($1) AND is_active = true FOR UPDATE",
+    )
+    .bind(code.trim())
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Invalid discount code".to_string()))?;
+
+    let now = sqlx::query_scalar::<_, NaiveDateTime>("SELECT LOCALTIMESTAMP")
+        .fetch_one(&mut *conn)
+        .await?;
+    if promotion.starts_at.is_some_and(|starts_at| now < starts_at) {
+        return Err(AppError::BadRequest(
+            "This discount code is not valid yet".to_string(),
+        ));
+    }
+    if promotion.ends_at.is_some_and(|ends_at| now >= ends_at) {
+        return Err(AppError::BadRequest(
+            "This discount code has expired".to_string(),
+        ));
+    }
+
+    let order_subtotal: Money = lines.iter().map(|line| line.subtotal).sum();
+    if let Some(min_order_amount) = promotion.min_order_amount {
+        if order_subtotal < min_order_amount {
+            return Err(AppError::BadRequest(format!(
+                "This discount code requires an order of at least {}",
+                min_order_amount
+            )));
+        }
+    }
+
+    if let Some(usage_limit) = promotion.usage_limit {
+        if times_used(conn, promotion.id, None).await? >= i64::from(usage_limit) {
+            return Err(AppError::BadRequest(
+                "This discount code has been used up".to_string(),
+            ));
+        }
+    }
+    if let (Some(limit), Some(email)) = (promotion.usage_limit_per_customer, customer_email) {
+        if times_used(conn, promotion.id, Some(email)).await? >= i64::from(limit) {
+            return Err(AppError::BadRequest(
+                "You have already used this discount code".to_string(),
+            ));
+        }
+    }
+
+    let product_ids: Vec<i32> = lines.iter().map(|line| line.product_id).collect();
+    let eligible = eligible_products(conn, promotion.id, &product_ids).await?;
+    let eligible_subtotal: Money = lines
+        .iter()
+        .filter(|line| eligible.contains(&line.product_id))
+        .map(|line| line.subtotal)
+        .sum();
+    if eligible_subtotal.is_zero() {
+        return Err(AppError::BadRequest(
+            "This discount code does not apply to the items in your cart".to_string(),
+        ));
+    }
+
+    let kind = PromotionKind::parse(&promotion.kind)?;
+    let mut line_discounts = vec![Money::ZERO; lines.len()];
+
+    match kind {
+        PromotionKind::Percentage => {
+            for (discount, line) in line_discounts.iter_mut().zip(lines) {
+                if eligible.contains(&line.product_id) {
+                    *discount = line.subtotal.percent(promotion.value);
+                }
+            }
+        }
+        PromotionKind::FixedAmount => {
+            // Shared by price, the last eligible line takes the rounding difference
+            let total = Money::eur(promotion.value).min(eligible_subtotal);
+            let last = lines
+                .iter()
+                .rposition(|line| eligible.contains(&line.product_id));
+            let mut remaining = total;
+
+            for (index, line) in lines.iter().enumerate() {
+                if !eligible.contains(&line.product_id) {
+                    continue;
+                }
+                let share = if Some(index) == last {
+                    remaining
+                } else {
+                    total.share(line.subtotal, eligible_subtotal).min(remaining)
+                };
+                line_discounts[index] = share;
+                remaining -= share;
+            }
+        }
+        PromotionKind::FreeShipping => {}
+    }
+
+    Ok(AppliedPromotion {
+        discount_amount: line_discounts.iter().sum(),
+        free_shipping: kind == PromotionKind::FreeShipping,
+        line_discounts,
+        promotion,
+    })
+}
+
+// Orders placed with the promotion, of one customer when `customer_email` is
+// given. Cancelled orders give their use back.
+pub async fn times_used(
+    conn: &mut PgConnection,
+    promotion_id: i32,
+    customer_email: Option<&str>,
+) -> Result<i64, AppError> {
+    let used = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) FROM orders
+        WHERE promotion_id = $1
+          AND status <> 'cancelled'
+          AND ($2::TEXT IS NULL OR LOWER(customer_email) = LOWER($2))
+        "#,
+    )
+    .bind(promotion_id)
+    .bind(customer_email.map(str::trim))
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(used)
+}
+
+// Products among `product_ids` the promotion applies to: all of them when it
+// has no restrictions, otherwise the listed products and the products of the
+// listed categories and their active subcategories
+async fn eligible_products(
+    conn: &mut PgConnection,
+    promotion_id: i32,
+    product_ids: &[i32],
+) -> Result<Vec<i32>, AppError> {
+    let eligible = sqlx::query_scalar::<_, i32>(
+        r#"
+        SELECT p.id FROM products p
+        WHERE p.id = ANY($2)
+          AND (
+            (
+                NOT EXISTS (SELECT 1 FROM promotion_products WHERE promotion_id = $1)
+                AND NOT EXISTS (SELECT 1 FROM promotion_categories WHERE promotion_id = $1)
+            )
+            OR p.id IN (SELECT product_id FROM promotion_products WHERE promotion_id = $1)
+            OR p.id IN (
+                WITH RECURSIVE subtree AS (
+                    SELECT category_id AS id FROM promotion_categories WHERE promotion_id = $1
+                    UNION ALL
+                    SELECT c.id FROM categories c
+                    JOIN subtree s ON c.parent_id = s.id
+                    WHERE c.is_active = true
+                )
+                SELECT pc.product_id FROM product_categories pc
+                WHERE pc.category_id IN (SELECT id FROM subtree)
+            )
+          )
+        "#,
+    )
+    .bind(promotion_id)
+    .bind(product_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(eligible)
+}
@@ -0,0 +1,132 @@
+use axum::http::HeaderMap;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::error::AppError;
+
+// Clients seen in the current window before old windows are swept out
+const SWEEP_AFTER_CLIENTS: usize = 10_000;
+
+// Counts requests per client address in fixed windows. Kept in memory, so
+// each API process counts on its own.
+#[derive(Clone)]
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    hits: Arc<Mutex<HashMap<IpAddr, (Instant, u32)>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // Counts a request from the client, refusing it once the client has used
+    // up the window
+    pub fn check(&self, client: IpAddr) -> Result<(), AppError> {
+        let now = Instant::now();
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+
+        if hits.len() >= SWEEP_AFTER_CLIENTS {
+            hits.retain(|_, (started, _)| now.duration_since(*started) < self.window);
+        }
+
+        let (started, count) = hits.entry(client).or_insert((now, 0));
+        if now.duration_since(*started) >= self.window {
+            *started = now;
+            *count = 0;
+        }
+        if *count >= self.limit {
+            return Err(AppError::TooManyRequests(
+                "Too many requests, please try again later".to_string(),
+            ));
+        }
+        *count += 1;
+
+        Ok(())
+    }
+}
+
+// Address of the client. Behind nginx, which connects from the same host or
+// network, the client is the one it passes on in X-Real-IP.
+pub fn client_ip(peer: SocketAddr, headers: &HeaderMap) -> IpAddr {
+    let behind_proxy = match peer.ip() {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private(),
+        IpAddr::V6(ip) => ip.is_loopback(),
+    };
+    let forwarded = headers
+        .get("x-real-ip")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok());
+
+    match forwarded {
+        Some(ip) if behind_proxy => ip,
+        _ => peer.ip(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(ip: &str) -> SocketAddr {
+        SocketAddr::new(ip.parse().unwrap(), 40000)
+    }
+
+    #[test]
+    fn refuses_requests_over_the_limit() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+
+        assert!(limiter.check(client).is_ok());
+        assert!(limiter.check(client).is_ok());
+        assert!(matches!(
+            limiter.check(client),
+            Err(AppError::TooManyRequests(_))
+        ));
+        // Other clients have their own count
+        assert!(limiter.check("203.0.113.8".parse().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn starts_over_in_a_new_window() {
+        let limiter = RateLimiter::new(1, Duration::from_millis(20));
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+
+        assert!(limiter.check(client).is_ok());
+        assert!(limiter.check(client).is_err());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(limiter.check(client).is_ok());
+    }
+
+    #[test]
+    fn trusts_the_forwarded_address_only_from_a_proxy() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-real-ip", "198.51.100.4".parse().unwrap());
+
+        assert_eq!(
+            client_ip(peer("127.0.0.1"), &headers),
+            "198.51.100.4".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            client_ip(peer("172.18.0.1"), &headers),
+            "198.51.100.4".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            client_ip(peer("203.0.113.7"), &headers),
+            "203.0.113.7".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            client_ip(peer("127.0.0.1"), &HeaderMap::new()),
+            "127.0.0.1".parse::<IpAddr>().unwrap()
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use sqlx::{PgConnection, PgPool};
+use std::{collections::HashMap, time::Duration};
+use uuid::Uuid;
+
+use crate::{error::AppError, models::OrderItemRequest};
+
+// How long a checkout holds stock unless RESERVATION_TTL_MINUTES says otherwise
+const DEFAULT_TTL_MINUTES: i32 = 15;
+
+// However often it is renewed, a reservation holds stock for at most this
+// long after it was created
+pub const MAX_LIFETIME_MINUTES: i32 = 60;
+
+// Units a reservation may hold of one product or variant, and in total. A
+// cart is a handful of pots, not the whole shelf.
+pub const MAX_LINE_QUANTITY: i32 = 20;
+pub const MAX_TOTAL_QUANTITY: i32 = 50;
+
+// Requests per client address to create or change reservations
+pub const RATE_LIMIT: u32 = 30;
+pub const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+// Minutes a new or renewed reservation holds its stock
+pub fn ttl_minutes() -> i32 {
+    std::env::var("RESERVATION_TTL_MINUTES")
+        .ok()
+        .and_then(|value| value.parse::<i32>().ok())
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(DEFAULT_TTL_MINUTES)
+}
+
+// Checks the cart against the per line and per reservation caps. Lines of
+// the same product or variant count together.
+pub fn check_quantities(items: &[OrderItemRequest]) -> Result<(), AppError> {
+    let mut per_line: HashMap<(i32, Option<i32>), i32> = HashMap::new();
+    for item in items {
+        let quantity = per_line
+            .entry((item.product_id, item.variant_id))
+            .or_insert(0);
+        *quantity = quantity.saturating_add(item.quantity.max(0));
+        if *quantity > MAX_LINE_QUANTITY {
+            return Err(AppError::BadRequest(format!(
+                "At most {} units of a product can be reserved",
+                MAX_LINE_QUANTITY
+            )));
+        }
+    }
+
+    let total = per_line
+        .values()
+        .fold(0i32, |total, quantity| total.saturating_add(*quantity));
+    if total > MAX_TOTAL_QUANTITY {
+        return Err(AppError::BadRequest(format!(
+            "At most {} units can be reserved at once",
+            MAX_TOTAL_QUANTITY
+        )));
+    }
+
+    Ok(())
+}
+
+// Units of a product, or of one of its variants, held by active reservations.
+// Without a variant every hold on the product counts. The reservation being
+// checked out or edited can be left out, its stock is already its own.
+pub async fn held_quantity(
+    conn: &mut PgConnection,
+    product_id: i32,
+    variant_id: Option<i32>,
+    except: Option<Uuid>,
+) -> Result<i32, AppError> {
+    let held = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COALESCE(SUM(quantity), 0)
+        FROM active_stock_holds
+        WHERE product_id = $1
+          AND ($2::INTEGER IS NULL OR variant_id = $2)
+          AND ($3::UUID IS NULL OR reservation_id <> $3)
+        "#,
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .bind(except)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(i32::try_from(held).unwrap_or(i32::MAX))
+}
+
+// Units held per product, for listings
+pub async fn held_by_product(
+    pool: &PgPool,
+    product_ids: &[i32],
+) -> Result<HashMap<i32, i32>, AppError> {
+    let held = sqlx::query_as::<_, (i32, i64)>(
+        r#"
+        SELECT product_id, SUM(quantity)
+        FROM active_stock_holds
+        WHERE product_id = ANY($1)
+        GROUP BY product_id
+        "#,
+    )
+    .bind(product_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(held
+        .into_iter()
+        .map(|(product_id, quantity)| (product_id, i32::try_from(quantity).unwrap_or(i32::MAX)))
+        .collect())
+}
+
+// Deletes reservations that expired without becoming an order. Their holds
+// already stopped counting at expiry, this only keeps the tables small. Run
+// every minute by the job scheduler.
+pub async fn release_expired(pool: &PgPool) -> Result<u64, AppError> {
+    let released = sqlx::query(
+        "DELETE FROM stock_reservations WHERE converted_at IS NULL AND expires_at <= NOW()",
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(released)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(product_id: i32, variant_id: Option<i32>, quantity: i32) -> OrderItemRequest {
+        OrderItemRequest {
+            product_id,
+            variant_id,
+            quantity,
+        }
+    }
+
+    #[test]
+    fn accepts_carts_within_the_caps() {
+        assert!(check_quantities(&[item(1, None, MAX_LINE_QUANTITY), item(2, Some(3), 1)]).is_ok());
+    }
+
+    #[test]
+    fn caps_the_quantity_per_line() {
+        assert!(check_quantities(&[item(1, None, MAX_LINE_QUANTITY + 1)]).is_err());
+        // Splitting a product over several lines does not get around it
+        assert!(check_quantities(&[item(1, Some(2), 15), item(1, Some(2), 15)]).is_err());
+        // Different variants are different lines
+        assert!(check_quantities(&[item(1, Some(2), 15), item(1, Some(3), 15)]).is_ok());
+    }
+
+    #[test]
+    fn caps_the_quantity_per_reservation() {
+        let items: Vec<_> = (1..=3)
+            .map(|id| item(id, None, MAX_LINE_QUANTITY))
+            .collect();
+        assert!(check_quantities(&items).is_err());
+    }
+
+    #[test]
+    fn does_not_overflow_on_huge_quantities() {
+        assert!(check_quantities(&[item(1, None, i32::MAX), item(1, None, i32::MAX)]).is_err());
+    }
+}
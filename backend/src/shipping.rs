@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+
+use crate::{
+    error::AppError,
+    models::{Money, ShippingMethod},
+};
+
+// How the parcel reaches the customer; stored in `shipping_methods.kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShippingKind {
+    // Collected from an office of the carrier, named in the delivery address
+    CourierOffice,
+    // Delivered by the carrier to the delivery address
+    CourierAddress,
+    // Collected from the studio
+    Pickup,
+}
+
+impl ShippingKind {
+    pub const ALL: [ShippingKind; 3] = [
+        ShippingKind::CourierOffice,
+        ShippingKind::CourierAddress,
+        ShippingKind::Pickup,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ShippingKind::CourierOffice => "courier_office",
+            ShippingKind::CourierAddress => "courier_address",
+            ShippingKind::Pickup => "pickup",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+            .ok_or_else(|| {
+                AppError::InternalServerError(format!("Unknown shipping kind '{}'", value))
+            })
+    }
+}
+
+// Courier company; stored in `shipping_methods.carrier`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Carrier {
+    Econt,
+    Speedy,
+}
+
+impl Carrier {
+    pub const ALL: [Carrier; 2] = [Carrier::Econt, Carrier::Speedy];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Carrier::Econt => "econt",
+            Carrier::Speedy => "speedy",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        Self::ALL
+            .into_iter()
+            .find(|carrier| carrier.as_str() == value)
+            .ok_or_else(|| AppError::InternalServerError(format!("Unknown carrier '{}'", value)))
+    }
+}
+
+// The parcel and order a shipping price is worked out for
+pub struct Parcel {
+    pub weight_grams: i32,
+    // Zone of the delivery postal code, if it falls in one
+    pub zone_id: Option<i32>,
+    // Items after discounts, compared with the free shipping threshold
+    pub order_amount: Money,
+    // Waived by a free shipping discount code
+    pub free_shipping: bool,
+}
+
+// Zone of a Bulgarian postal code. Overlapping zones resolve to the narrowest
+// one, so a city can be carved out of a wider region.
+pub async fn zone_for_postal_code(
+    conn: &mut PgConnection,
+    postal_code: Option<&str>,
+) -> Result<Option<i32>, AppError> {
+    let Some(code) = postal_code.and_then(|code| code.trim().parse::<i32>().ok()) else {
+        return Ok(None);
+    };
+
+    let zone_id = sqlx::query_scalar::<_, i32>(
+        r#"
+        SELECT id FROM shipping_zones
+        WHERE $1 BETWEEN postal_code_from AND postal_code_to
+        ORDER BY postal_code_to - postal_code_from, id
+        LIMIT 1
+        "#,
+    )
+    .bind(code)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(zone_id)
+}
+
+// What `method` charges for the parcel, or None when none of its rates covers
+// it (too heavy). A rate for the parcel's zone wins over the method's general
+// rates, and the lightest weight band the parcel fits in is used.
+pub async fn price(
+    conn: &mut PgConnection,
+    method: &ShippingMethod,
+    parcel: &Parcel,
+) -> Result<Option<Money>, AppError> {
+    let rate = sqlx::query_scalar::<_, Money>(
+        r#"
+        SELECT price FROM shipping_rates
+        WHERE method_id = $1
+          AND (zone_id IS NULL OR zone_id = $2)
+          AND (max_weight_grams IS NULL OR max_weight_grams >= $3)
+        ORDER BY zone_id IS NULL, max_weight_grams NULLS LAST
+        LIMIT 1
+        "#,
+    )
+    .bind(method.id)
+    .bind(parcel.zone_id)
+    .bind(parcel.weight_grams)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(rate) = rate else {
+        return Ok(None);
+    };
+
+    let free = parcel.free_shipping
+        || method
+            .free_shipping_threshold
+            .is_some_and(|threshold| parcel.order_amount >= threshold);
+    Ok(Some(if free { Money::ZERO } else { rate }))
+}
+
+// Active method `method_id` and what it charges for the parcel, for an order
+// being placed
+pub async fn charge(
+    conn: &mut PgConnection,
+    method_id: i32,
+    parcel: &Parcel,
+) -> Result<(ShippingMethod, Money), AppError> {
+    let method = sqlx::query_as::<_, ShippingMethod>(
+        "SELECT * FROM shipping_methods WHERE id = $1 AND is_active = true",
+    )
+    .bind(method_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Unknown shipping method".to_string()))?;
+
+    let amount = price(conn, &method, parcel).await?.ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "'{}' cannot deliver a parcel of {} g",
+            method.name, parcel.weight_grams
+        ))
+    })?;
+
+    Ok((method, amount))
+}
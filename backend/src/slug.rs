@@ -0,0 +1,147 @@
+use crate::error::AppError;
+
+// Builds a URL slug from a Bulgarian or Latin name using the official
+// streamlined transliteration system, e.g. "Лунен заек" -> "lunen-zaek".
+pub fn slugify(name: &str) -> String {
+    let lower = name.to_lowercase();
+    let chars: Vec<char> = lower.chars().collect();
+    let mut slug = String::with_capacity(lower.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        // "-ия" at the end of a word is written "-ia" (София -> Sofia)
+        if c == 'я' && i > 0 && chars[i - 1] == 'и' {
+            let word_ends = chars.get(i + 1).is_none_or(|next| !next.is_alphanumeric());
+            if word_ends {
+                slug.push('a');
+                continue;
+            }
+        }
+
+        match transliterate(c) {
+            Some(latin) => slug.push_str(latin),
+            None if c.is_ascii_alphanumeric() => slug.push(c),
+            None => {
+                if !slug.is_empty() && !slug.ends_with('-') {
+                    slug.push('-');
+                }
+            }
+        }
+    }
+
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+
+    slug
+}
+
+pub fn validate(slug: &str) -> Result<(), AppError> {
+    let valid = !slug.is_empty()
+        && !slug.starts_with('-')
+        && !slug.ends_with('-')
+        && !slug.contains("--")
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+    if !valid {
+        return Err(AppError::BadRequest(
+            "Slug may only contain lowercase latin letters, digits and single hyphens".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn transliterate(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' => "e",
+        'ж' => "zh",
+        'з' => "z",
+        'и' => "i",
+        'й' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "h",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "sht",
+        'ъ' => "a",
+        'ь' => "y",
+        'ю' => "yu",
+        'я' => "ya",
+        _ => return None,
+    };
+
+    Some(latin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transliterates_bulgarian_names() {
+        assert_eq!(slugify("Лунен заек"), "lunen-zaek");
+        assert_eq!(slugify("Щастлива ябълка"), "shtastliva-yabalka");
+        assert_eq!(slugify("Жълт цвят"), "zhalt-tsvyat");
+        assert_eq!(slugify("Юлски Хор"), "yulski-hor");
+    }
+
+    #[test]
+    fn writes_final_iya_as_ia() {
+        assert_eq!(slugify("България"), "balgaria");
+        assert_eq!(slugify("Мария и София"), "maria-i-sofia");
+        // Only at the end of a word
+        assert_eq!(slugify("Ияна"), "iyana");
+    }
+
+    #[test]
+    fn collapses_separators_and_keeps_latin() {
+        assert_eq!(slugify("  Ваза №3 -- Blue!  "), "vaza-3-blue");
+        assert_eq!(slugify("Mug, 350ml"), "mug-350ml");
+        assert_eq!(slugify("!!!"), "");
+    }
+
+    #[test]
+    fn validates_slugs() {
+        assert!(validate("lunen-zaek-2").is_ok());
+
+        for slug in [
+            "",
+            "-zaek",
+            "zaek-",
+            "lunen--zaek",
+            "Lunen",
+            "лунен",
+            "lunen_zaek",
+        ] {
+            assert!(
+                matches!(validate(slug), Err(AppError::BadRequest(_))),
+                "{slug:?} should be refused"
+            );
+        }
+    }
+
+    #[test]
+    fn slugify_output_validates() {
+        for name in ["Лунен заек", "Ваза №3 -- Blue!", "България"] {
+            assert!(validate(&slugify(name)).is_ok(), "{name:?}");
+        }
+    }
+}
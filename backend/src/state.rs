@@ -0,0 +1,20 @@
+use axum::extract::FromRef;
+use sqlx::PgPool;
+
+use crate::{
+    couriers::Couriers, invoices::Invoicing, order_tracking::OrderLookupLimiter,
+    payments::Payments, rate_limit::RateLimiter, storage::SharedStorage,
+};
+
+// Shared application state; handlers extract only the parts they need
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub pool: PgPool,
+    pub storage: SharedStorage,
+    pub couriers: Couriers,
+    pub payments: Payments,
+    pub invoicing: Invoicing,
+    // Shared by the endpoints that create and change reservations
+    pub reservation_limiter: RateLimiter,
+    pub order_lookup_limiter: OrderLookupLimiter,
+}
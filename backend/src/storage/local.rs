@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use super::{Storage, PRIVATE_PREFIX};
+use crate::error::AppError;
+
+// Files under UPLOAD_DIR, served by the API itself at `/uploads`, and
+// private files under PRIVATE_DIR, which is not served
+pub struct LocalStorage {
+    root: PathBuf,
+    private_root: PathBuf,
+    url_prefix: String,
+}
+
+impl LocalStorage {
+    pub fn new(
+        root: impl Into<PathBuf>,
+        private_root: impl Into<PathBuf>,
+        url_prefix: &str,
+    ) -> Self {
+        Self {
+            root: root.into(),
+            private_root: private_root.into(),
+            url_prefix: url_prefix.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn path_for(&self, key: &str) -> Result<PathBuf, AppError> {
+        let (root, key) = match key.strip_prefix(PRIVATE_PREFIX) {
+            Some(key) => (&self.private_root, key),
+            None => (&self.root, key),
+        };
+
+        // Keys may come back from stored URLs, so never let them escape the root
+        let relative = Path::new(key);
+        if key.is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(AppError::BadRequest("Invalid storage key".to_string()));
+        }
+
+        Ok(root.join(relative))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), AppError> {
+        let file_path = self.path_for(key)?;
+
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).await.map_err(|e| {
+                tracing::error!("Failed to create upload directory: {:?}", e);
+                AppError::InternalServerError("Failed to create upload directory".to_string())
+            })?;
+        }
+
+        let mut file = fs::File::create(&file_path).await.map_err(|e| {
+            tracing::error!("Failed to create file: {:?}", e);
+            AppError::InternalServerError("Failed to save file".to_string())
+        })?;
+
+        file.write_all(&data).await.map_err(|e| {
+            tracing::error!("Failed to write file: {:?}", e);
+            AppError::InternalServerError("Failed to save file".to_string())
+        })?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError> {
+        let file_path = self.path_for(key)?;
+
+        fs::read(&file_path).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AppError::NotFound("File not found".to_string())
+            } else {
+                tracing::error!("Failed to read file {:?}: {:?}", file_path, e);
+                AppError::InternalServerError("Failed to read file".to_string())
+            }
+        })
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        let file_path = self.path_for(key)?;
+
+        match fs::remove_file(&file_path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => {
+                tracing::error!("Failed to remove file {:?}: {:?}", file_path, e);
+                Err(AppError::InternalServerError(
+                    "Failed to remove file".to_string(),
+                ))
+            }
+        }
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.url_prefix, key)
+    }
+
+    fn signed_url(&self, _key: &str, _expires_in: Duration) -> Option<String> {
+        None
+    }
+
+    fn local_dir(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+
+    fn private_dir(&self) -> Option<&Path> {
+        Some(&self.private_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_private_keys_outside_the_served_directory() {
+        let storage = LocalStorage::new("/srv/uploads", "/srv/private", "/uploads");
+
+        assert_eq!(
+            storage.path_for("product-1-large.webp").unwrap(),
+            PathBuf::from("/srv/uploads/product-1-large.webp")
+        );
+        assert_eq!(
+            storage.path_for("private/waybill-econt-1.pdf").unwrap(),
+            PathBuf::from("/srv/private/waybill-econt-1.pdf")
+        );
+        assert!(storage.path_for("private/../uploads/x.pdf").is_err());
+        assert!(storage.path_for("private/").is_err());
+    }
+
+    #[test]
+    fn private_keys_have_no_public_url() {
+        let storage = LocalStorage::new("/srv/uploads", "/srv/private", "/uploads");
+
+        assert_eq!(
+            storage.key_from_url("/uploads/product-1-large.webp"),
+            Some("product-1-large.webp".to_string())
+        );
+        assert_eq!(
+            storage.key_from_url("/uploads/private/waybill-econt-1.pdf"),
+            None
+        );
+    }
+}
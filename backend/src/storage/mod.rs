@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::AppError;
+
+mod local;
+mod s3;
+
+pub use local::LocalStorage;
+pub use s3::{S3Config, S3Storage};
+
+// Keys of private objects, such as waybill labels naming the customer. They
+// are only ever handed out by admin endpoints: local storage keeps them
+// outside the served directory, and an S3 bucket must not make them public.
+pub const PRIVATE_PREFIX: &str = "private/";
+
+// Backend for uploaded media. Objects are addressed by flat keys such as
+// "product-3-<uuid>-large.webp"; the backend decides where they live and how
+// they are reached from the browser.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), AppError>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError>;
+
+    // Deleting a missing object is not an error
+    async fn delete(&self, key: &str) -> Result<(), AppError>;
+
+    // Permanent URL for publicly readable media such as product images
+    fn public_url(&self, key: &str) -> String;
+
+    // Time-limited URL for private objects. None when the backend cannot sign
+    // URLs and the object has to be streamed through the API instead.
+    fn signed_url(&self, key: &str, expires_in: Duration) -> Option<String>;
+
+    // Directory the API has to serve at `/uploads`, for disk-backed storage
+    fn local_dir(&self) -> Option<&Path> {
+        None
+    }
+
+    // Directory of private objects, for disk-backed storage; never served
+    fn private_dir(&self) -> Option<&Path> {
+        None
+    }
+
+    // Reverses `public_url`; None for URLs this backend did not issue, and
+    // for private keys, which never have public URLs
+    fn key_from_url(&self, url: &str) -> Option<String> {
+        url.strip_prefix(&self.public_url(""))
+            .filter(|key| !key.is_empty() && !key.starts_with(PRIVATE_PREFIX))
+            .map(str::to_string)
+    }
+}
+
+pub type SharedStorage = Arc<dyn Storage>;
+
+// Builds the backend selected by STORAGE_BACKEND ("local" or "s3"); private
+// objects are only kept in `private_dir` by local storage
+pub fn from_env(upload_dir: &str, private_dir: &str) -> SharedStorage {
+    let backend = std::env::var("STORAGE_BACKEND").unwrap_or_else(|_| "local".to_string());
+
+    match backend.as_str() {
+        "local" => Arc::new(LocalStorage::new(upload_dir, private_dir, "/uploads")),
+        "s3" => {
+            let require = |name: &str| {
+                std::env::var(name)
+                    .unwrap_or_else(|_| panic!("{} must be set for S3 storage", name))
+            };
+
+            Arc::new(S3Storage::new(S3Config {
+                endpoint: require("S3_ENDPOINT"),
+                region: std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                bucket: require("S3_BUCKET"),
+                access_key_id: require("S3_ACCESS_KEY_ID"),
+                secret_access_key: require("S3_SECRET_ACCESS_KEY"),
+                public_url: std::env::var("S3_PUBLIC_URL")
+                    .ok()
+                    .filter(|url| !url.is_empty()),
+            }))
+        }
+        other => panic!(
+            "Unknown STORAGE_BACKEND '{}', expected 'local' or 's3'",
+            other
+        ),
+    }
+}
+
+// Content type stored alongside the object, derived from the key's extension
+pub(crate) fn content_type(key: &str) -> String {
+    mime_guess::from_path(key)
+        .first_or_octet_stream()
+        .essence_str()
+        .to_string()
+}
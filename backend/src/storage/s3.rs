@@ -0,0 +1,270 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+use super::{content_type, Storage};
+use crate::error::AppError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Only RFC 3986 unreserved characters stay unescaped in signed URIs
+const URI_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+// Presigned URLs cannot outlive seven days
+const MAX_PRESIGN_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+pub struct S3Config {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    // CDN or bucket website in front of the bucket; defaults to the endpoint
+    pub public_url: Option<String>,
+}
+
+// Any S3-compatible object store (AWS, MinIO, Cloudflare R2, Backblaze B2...).
+// Uses path-style addressing and signs requests with AWS Signature Version 4.
+pub struct S3Storage {
+    config: S3Config,
+    host: String,
+    client: reqwest::Client,
+}
+
+impl S3Storage {
+    pub fn new(mut config: S3Config) -> Self {
+        config.endpoint = config.endpoint.trim_end_matches('/').to_string();
+
+        let host = config
+            .endpoint
+            .split_once("://")
+            .map_or(config.endpoint.as_str(), |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        Self {
+            config,
+            host,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn object_path(&self, key: &str) -> String {
+        format!("/{}/{}", uri_encode(&self.config.bucket), encode_key(key))
+    }
+
+    fn object_url(&self, key: &str) -> String {
+        format!("{}{}", self.config.endpoint, self.object_path(key))
+    }
+
+    fn credential_scope(&self, date: &str) -> String {
+        format!("{}/{}/s3/aws4_request", date, self.config.region)
+    }
+
+    fn signature(&self, now: &DateTime<Utc>, canonical_request: &str) -> String {
+        let date = now.format("%Y%m%d").to_string();
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            now.format("%Y%m%dT%H%M%SZ"),
+            self.credential_scope(&date),
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let secret = format!("AWS4{}", self.config.secret_access_key);
+        let key = hmac(secret.as_bytes(), date.as_bytes());
+        let key = hmac(&key, self.config.region.as_bytes());
+        let key = hmac(&key, b"s3");
+        let key = hmac(&key, b"aws4_request");
+
+        hex::encode(hmac(&key, string_to_sign.as_bytes()))
+    }
+
+    // Sends a header-signed request for a single object
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<reqwest::Response, AppError> {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(body.as_deref().unwrap_or_default()));
+
+        let mut headers = vec![("host", self.host.clone())];
+        if body.is_some() {
+            headers.push(("content-type", content_type(key)));
+        }
+        headers.push(("x-amz-content-sha256", payload_hash.clone()));
+        headers.push(("x-amz-date", amz_date));
+
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method,
+            self.object_path(key),
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.config.access_key_id,
+            self.credential_scope(&now.format("%Y%m%d").to_string()),
+            signed_headers,
+            self.signature(&now, &canonical_request)
+        );
+
+        // reqwest derives the Host header from the URL itself
+        let mut request = self
+            .client
+            .request(method, self.object_url(key))
+            .header("authorization", authorization);
+        for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+
+        request.send().await.map_err(|e| {
+            tracing::error!("Storage request for {} failed: {:?}", key, e);
+            AppError::InternalServerError("Storage is unavailable".to_string())
+        })
+    }
+
+    fn presign(&self, key: &str, expires_in: Duration, now: DateTime<Utc>) -> String {
+        let date = now.format("%Y%m%d").to_string();
+        let credential = format!(
+            "{}/{}",
+            self.config.access_key_id,
+            self.credential_scope(&date)
+        );
+        let expires = expires_in.as_secs().clamp(1, MAX_PRESIGN_SECONDS);
+
+        // Parameters must already be in sorted order
+        let query = format!(
+            "X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential={}&X-Amz-Date={}&X-Amz-Expires={}&X-Amz-SignedHeaders=host",
+            uri_encode(&credential),
+            now.format("%Y%m%dT%H%M%SZ"),
+            expires
+        );
+
+        let canonical_request = format!(
+            "GET\n{}\n{}\nhost:{}\n\nhost\n{}",
+            self.object_path(key),
+            query,
+            self.host,
+            UNSIGNED_PAYLOAD
+        );
+
+        format!(
+            "{}?{}&X-Amz-Signature={}",
+            self.object_url(key),
+            query,
+            self.signature(&now, &canonical_request)
+        )
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), AppError> {
+        let response = self.send(reqwest::Method::PUT, key, Some(data)).await?;
+
+        if !response.status().is_success() {
+            tracing::error!(
+                "Storage rejected upload of {}: {} {}",
+                key,
+                response.status(),
+                response.text().await.unwrap_or_default()
+            );
+            return Err(AppError::InternalServerError(
+                "Failed to save file".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError> {
+        let response = self.send(reqwest::Method::GET, key, None).await?;
+
+        match response.status() {
+            status if status.is_success() => {
+                let body = response.bytes().await.map_err(|e| {
+                    tracing::error!("Failed to read {} from storage: {:?}", key, e);
+                    AppError::InternalServerError("Failed to read file".to_string())
+                })?;
+                Ok(body.to_vec())
+            }
+            reqwest::StatusCode::NOT_FOUND => Err(AppError::NotFound("File not found".to_string())),
+            status => {
+                tracing::error!("Storage rejected download of {}: {}", key, status);
+                Err(AppError::InternalServerError(
+                    "Failed to read file".to_string(),
+                ))
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        let response = self.send(reqwest::Method::DELETE, key, None).await?;
+
+        let status = response.status();
+        if !status.is_success() && status != reqwest::StatusCode::NOT_FOUND {
+            tracing::error!("Storage rejected delete of {}: {}", key, status);
+            return Err(AppError::InternalServerError(
+                "Failed to remove file".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        match &self.config.public_url {
+            Some(base) => format!("{}/{}", base.trim_end_matches('/'), encode_key(key)),
+            None => self.object_url(key),
+        }
+    }
+
+    fn signed_url(&self, key: &str, expires_in: Duration) -> Option<String> {
+        Some(self.presign(key, expires_in, Utc::now()))
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn uri_encode(value: &str) -> String {
+    utf8_percent_encode(value, URI_ENCODE).to_string()
+}
+
+// Object keys keep their slashes, every segment is escaped
+fn encode_key(key: &str) -> String {
+    key.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+}
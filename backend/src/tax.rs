@@ -0,0 +1,257 @@
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    error::AppError,
+    models::{Money, Product, TaxLine},
+};
+
+// Class of products without one of their own, and of shipping and fees
+pub const STANDARD_CLASS: &str = "standard";
+
+// Whether the studio charges VAT. Until it is registered under the VAT act
+// (VAT_REGISTERED=true) prices carry no VAT and orders record none.
+pub fn vat_registered() -> bool {
+    std::env::var("VAT_REGISTERED")
+        .ok()
+        .is_some_and(|value| matches!(value.trim(), "true" | "1" | "yes"))
+}
+
+// Rate of every tax class, or none when the studio charges no VAT
+pub async fn current_rates(conn: &mut PgConnection) -> Result<Option<TaxRates>, AppError> {
+    if !vat_registered() {
+        return Ok(None);
+    }
+
+    let rates = sqlx::query_as::<_, (String, Decimal)>("SELECT code, rate FROM tax_classes")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(Some(TaxRates(rates.into_iter().collect())))
+}
+
+pub struct TaxRates(HashMap<String, Decimal>);
+
+impl TaxRates {
+    pub fn rate(&self, class: &str) -> Result<Decimal, AppError> {
+        self.0
+            .get(class)
+            .copied()
+            .ok_or_else(|| AppError::InternalServerError(format!("Unknown tax class '{}'", class)))
+    }
+}
+
+// Fills in the rate, net price and VAT of each product's price; nothing when
+// the studio charges no VAT
+pub async fn fill_product_tax<'a>(
+    conn: &mut PgConnection,
+    products: impl IntoIterator<Item = &'a mut Product>,
+) -> Result<(), AppError> {
+    let Some(rates) = current_rates(conn).await? else {
+        return Ok(());
+    };
+
+    for product in products {
+        let rate = rates.rate(&product.tax_class)?;
+        let (net_price, vat_amount) = split(product.price, rate);
+        product.tax_rate = Some(rate);
+        product.net_price = Some(net_price);
+        product.vat_amount = Some(vat_amount);
+    }
+
+    Ok(())
+}
+
+// Net and VAT parts of a price that includes VAT at `rate` percent. The VAT
+// is rounded to the cent and the net part is what is left.
+pub fn split(gross: Money, rate: Decimal) -> (Money, Money) {
+    let vat = Money::eur(gross.amount() * rate / (Decimal::ONE_HUNDRED + rate));
+    (gross - vat, vat)
+}
+
+// Amounts by rate, added up line by line
+#[derive(Default)]
+pub struct Breakdown(BTreeMap<Decimal, TaxLine>);
+
+impl Breakdown {
+    pub fn add(&mut self, rate: Decimal, net: Money, vat: Money) {
+        let line = self.0.entry(rate.normalize()).or_insert(TaxLine {
+            rate,
+            net_amount: Money::ZERO,
+            vat_amount: Money::ZERO,
+            gross_amount: Money::ZERO,
+        });
+        line.net_amount += net;
+        line.vat_amount += vat;
+        line.gross_amount += net + vat;
+    }
+
+    // Highest rate first, as invoices list them
+    pub fn into_lines(self) -> Vec<TaxLine> {
+        self.0.into_values().rev().collect()
+    }
+}
+
+// VAT of one order line
+pub struct LineTax {
+    // None when no VAT was charged
+    pub rate: Option<Decimal>,
+    pub net_amount: Money,
+    pub vat_amount: Money,
+}
+
+// VAT of a whole order
+pub struct OrderTax {
+    // In the order the lines were given
+    pub lines: Vec<LineTax>,
+    pub shipping_rate: Option<Decimal>,
+    pub breakdown: Vec<TaxLine>,
+    pub net_amount: Money,
+    pub vat_amount: Money,
+}
+
+// Works out the VAT in what is paid for each line (tax class and amount
+// after discount) and for shipping, which includes the cash on delivery fee
+// and is charged at the standard rate. Every line is split on its own and the
+// breakdown adds up the lines of each rate.
+pub fn order_tax(
+    rates: Option<&TaxRates>,
+    lines: &[(&str, Money)],
+    shipping: Money,
+) -> Result<OrderTax, AppError> {
+    let Some(rates) = rates else {
+        let total: Money = lines.iter().map(|(_, paid)| *paid).sum();
+        return Ok(OrderTax {
+            lines: lines
+                .iter()
+                .map(|(_, paid)| LineTax {
+                    rate: None,
+                    net_amount: *paid,
+                    vat_amount: Money::ZERO,
+                })
+                .collect(),
+            shipping_rate: None,
+            breakdown: Vec::new(),
+            net_amount: total + shipping,
+            vat_amount: Money::ZERO,
+        });
+    };
+
+    let mut breakdown = Breakdown::default();
+    let mut add = |rate: Decimal, gross: Money| {
+        let (net, vat) = split(gross, rate);
+        breakdown.add(rate, net, vat);
+        (net, vat)
+    };
+
+    let mut line_taxes = Vec::with_capacity(lines.len());
+    for (class, paid) in lines {
+        let rate = rates.rate(class)?;
+        let (net_amount, vat_amount) = add(rate, *paid);
+        line_taxes.push(LineTax {
+            rate: Some(rate),
+            net_amount,
+            vat_amount,
+        });
+    }
+
+    let shipping_rate = rates.rate(STANDARD_CLASS)?;
+    if !shipping.is_zero() {
+        add(shipping_rate, shipping);
+    }
+
+    let breakdown = breakdown.into_lines();
+    Ok(OrderTax {
+        lines: line_taxes,
+        shipping_rate: Some(shipping_rate),
+        net_amount: breakdown.iter().map(|line| line.net_amount).sum(),
+        vat_amount: breakdown.iter().map(|line| line.vat_amount).sum(),
+        breakdown,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(amount: &str) -> Decimal {
+        amount.parse().unwrap()
+    }
+
+    fn eur(amount: &str) -> Money {
+        Money::eur(dec(amount))
+    }
+
+    fn rates() -> TaxRates {
+        TaxRates(HashMap::from([
+            ("standard".to_string(), dec("20")),
+            ("reduced".to_string(), dec("9")),
+            ("exempt".to_string(), dec("0")),
+        ]))
+    }
+
+    #[test]
+    fn splits_the_vat_out_of_a_gross_price() {
+        assert_eq!(split(eur("12.00"), dec("20")), (eur("10.00"), eur("2.00")));
+        // 1.6667 of VAT rounds up; the net part takes what is left
+        assert_eq!(split(eur("10.00"), dec("20")), (eur("8.33"), eur("1.67")));
+        assert_eq!(split(eur("10.90"), dec("9")), (eur("10.00"), eur("0.90")));
+        assert_eq!(split(eur("5.00"), dec("0")), (eur("5.00"), Money::ZERO));
+    }
+
+    #[test]
+    fn charges_no_vat_without_rates() {
+        let tax = order_tax(None, &[("standard", eur("10.00"))], eur("4.00")).unwrap();
+
+        assert_eq!(tax.lines[0].rate, None);
+        assert_eq!(tax.lines[0].net_amount, eur("10.00"));
+        assert_eq!(tax.shipping_rate, None);
+        assert!(tax.breakdown.is_empty());
+        assert_eq!(tax.net_amount, eur("14.00"));
+        assert_eq!(tax.vat_amount, Money::ZERO);
+    }
+
+    #[test]
+    fn splits_each_line_and_adds_them_up_by_rate() {
+        let rates = rates();
+        let lines = [
+            ("standard", eur("10.00")),
+            ("reduced", eur("10.90")),
+            ("standard", eur("10.00")),
+        ];
+        let tax = order_tax(Some(&rates), &lines, eur("6.00")).unwrap();
+
+        assert_eq!(tax.lines[0].rate, Some(dec("20")));
+        assert_eq!(tax.lines[0].vat_amount, eur("1.67"));
+        assert_eq!(tax.lines[1].vat_amount, eur("0.90"));
+        assert_eq!(tax.shipping_rate, Some(dec("20")));
+
+        // Highest rate first; shipping goes in at the standard rate
+        assert_eq!(tax.breakdown.len(), 2);
+        assert_eq!(tax.breakdown[0].rate, dec("20"));
+        assert_eq!(tax.breakdown[0].vat_amount, eur("4.34"));
+        assert_eq!(tax.breakdown[0].gross_amount, eur("26.00"));
+        assert_eq!(tax.breakdown[1].rate, dec("9"));
+        assert_eq!(tax.net_amount, eur("31.66"));
+        assert_eq!(tax.vat_amount, eur("5.24"));
+        assert_eq!(tax.net_amount + tax.vat_amount, eur("36.90"));
+    }
+
+    #[test]
+    fn leaves_free_shipping_out_of_the_breakdown() {
+        let rates = rates();
+        let tax = order_tax(Some(&rates), &[("exempt", eur("5.00"))], Money::ZERO).unwrap();
+
+        assert_eq!(tax.breakdown.len(), 1);
+        assert_eq!(tax.breakdown[0].rate, dec("0"));
+        assert_eq!(tax.vat_amount, Money::ZERO);
+    }
+
+    #[test]
+    fn refuses_unknown_tax_classes() {
+        let rates = rates();
+
+        assert!(order_tax(Some(&rates), &[("luxury", eur("5.00"))], Money::ZERO).is_err());
+    }
+}
@@ -4,18 +4,53 @@ import { useEffect, useState } from "react";
 import { useRouter } from "next/navigation";
 import toast from "react-hot-toast";
 import { useAuthStore } from "@/store/useAuthStore";
-import { getAdminOrders, getAdminOrder, updateOrderStatus } from "@/lib/api";
+import {
+  createCreditNote,
+  createRefund,
+  createWaybill,
+  getAdminOrders,
+  getAdminOrder,
+  getInvoice,
+  getInvoicePdf,
+  getInvoices,
+  getOrderPayments,
+  getWaybill,
+  getWaybillLabel,
+  issueInvoice,
+  updateOrderStatus,
+} from "@/lib/api";
 import { Button } from "@/components/Button";
-import type { Order, OrderItemResponse } from "@/types";
+import { formatMoney } from "@/lib/utils";
+import type {
+  InvoiceDetail,
+  Order,
+  OrderDetail,
+  OrderPayments,
+  OrderStatus,
+  PaymentMethod,
+  PaymentStatus,
+  Shipment,
+  ShipmentStatus,
+} from "@/types";
 
 export default function AdminOrdersPage() {
   const router = useRouter();
   const { token, isAuthenticated, clearAuth } = useAuthStore();
   const [orders, setOrders] = useState<Order[]>([]);
-  const [selectedOrder, setSelectedOrder] = useState<{
-    order: Order;
-    items: OrderItemResponse[];
-  } | null>(null);
+  const [selectedOrder, setSelectedOrder] = useState<OrderDetail | null>(null);
+  const [statusComment, setStatusComment] = useState("");
+  const [trackingNumber, setTrackingNumber] = useState("");
+  const [waybill, setWaybill] = useState<Shipment | null>(null);
+  const [creatingWaybill, setCreatingWaybill] = useState(false);
+  const [payments, setPayments] = useState<OrderPayments | null>(null);
+  const [refundAmount, setRefundAmount] = useState("");
+  const [refundReason, setRefundReason] = useState("");
+  const [refunding, setRefunding] = useState(false);
+  const [invoice, setInvoice] = useState<InvoiceDetail | null>(null);
+  const [issuingInvoice, setIssuingInvoice] = useState(false);
+  const [creditAmount, setCreditAmount] = useState("");
+  const [creditReason, setCreditReason] = useState("");
+  const [crediting, setCrediting] = useState(false);
   const [loading, setLoading] = useState(true);
 
   useEffect(() => {
@@ -42,27 +77,149 @@ export default function AdminOrdersPage() {
   async function viewOrderDetails(orderId: string) {
     if (!token) return;
     try {
-      const [order, items] = await getAdminOrder(token, orderId);
-      setSelectedOrder({ order, items });
+      setSelectedOrder(await getAdminOrder(token, orderId));
+      setWaybill(await getWaybill(token, orderId));
+      setPayments(await getOrderPayments(token, orderId));
+      setInvoice(await loadInvoice(orderId));
+      setRefundAmount("");
+      setRefundReason("");
+      setCreditAmount("");
+      setCreditReason("");
+      setStatusComment("");
+      setTrackingNumber("");
     } catch (err) {
       console.error(err);
       toast.error("Грешка при зареждане на детайли");
     }
   }
 
-  async function handleUpdateStatus(orderId: string, status: string) {
+  async function handleUpdateStatus(orderId: string, status: OrderStatus) {
     if (!token) return;
     try {
-      await updateOrderStatus(token, orderId, status);
+      await updateOrderStatus(
+        token,
+        orderId,
+        status,
+        statusComment || undefined,
+        trackingNumber || undefined
+      );
       await loadOrders();
       if (selectedOrder) {
-        const [order, items] = await getAdminOrder(token, orderId);
-        setSelectedOrder({ order, items });
+        setSelectedOrder(await getAdminOrder(token, orderId));
       }
+      setStatusComment("");
+      setTrackingNumber("");
       toast.success("Статусът е актуализиран успешно!");
-    } catch (err) {
+    } catch (err: any) {
+      console.error(err);
+      toast.error(err.message || "Грешка при актуализиране на статус");
+    }
+  }
+
+  async function handleCreateWaybill(orderId: string) {
+    if (!token) return;
+    try {
+      setCreatingWaybill(true);
+      setWaybill(await createWaybill(token, orderId));
+      setSelectedOrder(await getAdminOrder(token, orderId));
+      toast.success("Товарителницата е създадена");
+    } catch (err: any) {
+      console.error(err);
+      toast.error(err.message || "Грешка при създаване на товарителница");
+    } finally {
+      setCreatingWaybill(false);
+    }
+  }
+
+  async function handleRefund(orderId: string) {
+    if (!token) return;
+    try {
+      setRefunding(true);
+      const refund = await createRefund(token, orderId, {
+        amount: refundAmount || undefined,
+        reason: refundReason || undefined,
+      });
+      setPayments(await getOrderPayments(token, orderId));
+      setRefundAmount("");
+      setRefundReason("");
+      toast.success(`Възстановени ${refund.amount} ${refund.currency}`);
+    } catch (err: any) {
       console.error(err);
-      toast.error("Грешка при актуализиране на статус");
+      toast.error(err.message || "Грешка при възстановяване на сумата");
+    } finally {
+      setRefunding(false);
+    }
+  }
+
+  // The order's invoice with its credit notes, if it has been issued
+  async function loadInvoice(orderId: string): Promise<InvoiceDetail | null> {
+    if (!token) return null;
+    const found = await getInvoices(token, { order_id: orderId, kind: "invoice" });
+    return found.items.length > 0 ? getInvoice(token, found.items[0].id) : null;
+  }
+
+  async function handleIssueInvoice(orderId: string) {
+    if (!token) return;
+    try {
+      setIssuingInvoice(true);
+      const issued = await issueInvoice(token, orderId);
+      setInvoice(issued);
+      toast.success(`Издадена фактура № ${issued.formatted_number}`);
+    } catch (err: any) {
+      console.error(err);
+      toast.error(err.message || "Грешка при издаване на фактура");
+    } finally {
+      setIssuingInvoice(false);
+    }
+  }
+
+  // For a refund, or for the amount entered (the rest of the invoice when
+  // empty)
+  async function handleCreditNote(orderId: string, refundId?: number) {
+    if (!token || !invoice) return;
+    try {
+      setCrediting(true);
+      const creditNote = await createCreditNote(
+        token,
+        invoice.id,
+        refundId !== undefined
+          ? { refund_id: refundId }
+          : {
+              amount: creditAmount || undefined,
+              reason: creditReason || undefined,
+            }
+      );
+      setInvoice(await loadInvoice(orderId));
+      setCreditAmount("");
+      setCreditReason("");
+      toast.success(`Издадено кредитно известие № ${creditNote.formatted_number}`);
+    } catch (err: any) {
+      console.error(err);
+      toast.error(err.message || "Грешка при издаване на кредитно известие");
+    } finally {
+      setCrediting(false);
+    }
+  }
+
+  async function openInvoicePdf(id: number) {
+    if (!token) return;
+    try {
+      const pdf = await getInvoicePdf(token, id);
+      window.open(URL.createObjectURL(pdf), "_blank");
+    } catch (err: any) {
+      console.error(err);
+      toast.error(err.message || "Грешка при изтегляне на фактурата");
+    }
+  }
+
+  async function openWaybillLabel(orderId: string) {
+    if (!token) return;
+    try {
+      const label = await getWaybillLabel(token, orderId);
+      window.open(URL.createObjectURL(label), "_blank");
+    } catch (err: any) {
+      console.error(err);
+      toast.error(err.message || "Грешка при изтегляне на етикета");
     }
   }
 
@@ -71,20 +228,42 @@ export default function AdminOrdersPage() {
     router.push("/admin");
   }
 
-  const statusLabels: Record<string, string> = {
+  const statusLabels: Record<OrderStatus, string> = {
     pending: "Изчакваща",
     processing: "Обработва се",
     shipped: "Изпратена",
     delivered: "Доставена",
     cancelled: "Анулирана",
+    returned: "Върната",
   };
 
-  const statusColors: Record<string, string> = {
+  const shipmentStatusLabels: Record<ShipmentStatus, string> = {
+    created: "Създадена",
+    in_transit: "В куриера",
+    delivered: "Доставена",
+  };
+
+  const paymentMethodLabels: Record<PaymentMethod, string> = {
+    cash_on_delivery: "Наложен платеж",
+    bank_transfer: "Банков превод",
+    card: "С карта",
+  };
+
+  const paymentStatusLabels: Record<PaymentStatus, string> = {
+    pending: "Очаква плащане",
+    paid: "Платена",
+    failed: "Неуспешно плащане",
+    partially_refunded: "Частично възстановена",
+    refunded: "Възстановена",
+  };
+
+  const statusColors: Record<OrderStatus, string> = {
     pending: "bg-yellow-100 text-yellow-800",
     processing: "bg-blue-100 text-blue-800",
     shipped: "bg-purple-100 text-purple-800",
     delivered: "bg-green-100 text-green-800",
     cancelled: "bg-red-100 text-red-800",
+    returned: "bg-gray-200 text-gray-800",
   };
 
   return (
@@ -141,7 +320,7 @@ export default function AdminOrdersPage() {
                       {order.customer_name}
                     </td>
                     <td className="px-6 py-4 text-sm text-gray-900 font-medium">
-                      {order.total_amount} лв
+                      {formatMoney(order.total_amount)}
                     </td>
                     <td className="px-6 py-4 text-sm">
                       <span
@@ -177,7 +356,7 @@ export default function AdminOrdersPage() {
                 <div>
                   <h2 className="text-2xl font-bold mb-2">Детайли на поръчката</h2>
                   <p className="text-sm text-gray-600 font-mono">
-                    {selectedOrder.order.id}
+                    {selectedOrder.id}
                   </p>
                 </div>
                 <button
@@ -194,15 +373,15 @@ export default function AdminOrdersPage() {
                   <div className="space-y-1 text-sm">
                     <p>
                       <span className="text-gray-600">Име:</span>{" "}
-                      {selectedOrder.order.customer_name}
+                      {selectedOrder.customer_name}
                     </p>
                     <p>
                       <span className="text-gray-600">Email:</span>{" "}
-                      {selectedOrder.order.customer_email}
+                      {selectedOrder.customer_email}
                     </p>
                     <p>
                       <span className="text-gray-600">Телефон:</span>{" "}
-                      {selectedOrder.order.customer_phone}
+                      {selectedOrder.customer_phone}
                     </p>
                   </div>
                 </div>
@@ -210,21 +389,48 @@ export default function AdminOrdersPage() {
                 <div>
                   <h3 className="font-semibold mb-2">Адрес за доставка</h3>
                   <div className="space-y-1 text-sm">
-                    <p>{selectedOrder.order.delivery_address}</p>
+                    <p>{selectedOrder.delivery_address}</p>
                     <p>
-                      {selectedOrder.order.delivery_city}
-                      {selectedOrder.order.delivery_postal_code &&
-                        `, ${selectedOrder.order.delivery_postal_code}`}
+                      {selectedOrder.delivery_city}
+                      {selectedOrder.delivery_postal_code &&
+                        `, ${selectedOrder.delivery_postal_code}`}
                     </p>
+                    {selectedOrder.courier_office_code && (
+                      <p className="text-gray-600">
+                        Код на офис: {selectedOrder.courier_office_code}
+                      </p>
+                    )}
                   </div>
                 </div>
               </div>
 
-              {selectedOrder.order.notes && (
+              {selectedOrder.invoice_company && (
+                <div className="mb-6">
+                  <h3 className="font-semibold mb-2">Данни за фактура</h3>
+                  <div className="space-y-1 text-sm">
+                    <p>{selectedOrder.invoice_company}</p>
+                    <p>
+                      <span className="text-gray-600">ЕИК:</span>{" "}
+                      {selectedOrder.invoice_eik}
+                      {selectedOrder.invoice_vat_number &&
+                        ` · ${selectedOrder.invoice_vat_number}`}
+                    </p>
+                    {selectedOrder.invoice_mol && (
+                      <p>
+                        <span className="text-gray-600">МОЛ:</span>{" "}
+                        {selectedOrder.invoice_mol}
+                      </p>
+                    )}
+                    <p>{selectedOrder.invoice_address}</p>
+                  </div>
+                </div>
+              )}
+
+              {selectedOrder.notes && (
                 <div className="mb-6">
                   <h3 className="font-semibold mb-2">Забележки</h3>
                   <p className="text-sm text-gray-700">
-                    {selectedOrder.order.notes}
+                    {selectedOrder.notes}
                   </p>
                 </div>
               )}
@@ -254,20 +460,59 @@ export default function AdminOrdersPage() {
                         <tr key={item.id} className="border-t">
                           <td className="px-4 py-2 text-sm">
                             {item.product_name}
+                            {item.variant_label && (
+                              <span className="text-gray-500">
+                                {" "}
+                                ({item.variant_label})
+                              </span>
+                            )}
                           </td>
                           <td className="px-4 py-2 text-sm text-right">
-                            {item.product_price} лв
+                            {formatMoney(item.product_price)}
                           </td>
                           <td className="px-4 py-2 text-sm text-right">
                             {item.quantity}
                           </td>
                           <td className="px-4 py-2 text-sm text-right font-medium">
-                            {item.subtotal} лв
+                            {formatMoney(item.subtotal)}
                           </td>
                         </tr>
                       ))}
                     </tbody>
                     <tfoot className="bg-gray-50 border-t-2">
+                      {parseFloat(selectedOrder.discount_amount.amount) > 0 && (
+                        <tr>
+                          <td colSpan={3} className="px-4 py-2 text-right">
+                            Отстъпка
+                            {selectedOrder.promotion_code &&
+                              ` (${selectedOrder.promotion_code})`}
+                            :
+                          </td>
+                          <td className="px-4 py-2 text-right text-green-700">
+                            -{formatMoney(selectedOrder.discount_amount)}
+                          </td>
+                        </tr>
+                      )}
+                      {selectedOrder.shipping_method_name && (
+                        <tr>
+                          <td colSpan={3} className="px-4 py-2 text-right">
+                            Доставка ({selectedOrder.shipping_method_name}):
+                          </td>
+                          <td className="px-4 py-2 text-right">
+                            {formatMoney(selectedOrder.shipping_amount)}
+                          </td>
+                        </tr>
+                      )}
+                      {parseFloat(selectedOrder.cod_fee.amount) > 0 && (
+                        <tr>
+                          <td colSpan={3} className="px-4 py-2 text-right">
+                            Такса наложен платеж:
+                          </td>
+                          <td className="px-4 py-2 text-right">
+                            {formatMoney(selectedOrder.cod_fee)}
+                          </td>
+                        </tr>
+                      )}
                       <tr>
                         <td
                           colSpan={3}
@@ -276,33 +521,295 @@ export default function AdminOrdersPage() {
                           Общо:
                         </td>
                         <td className="px-4 py-2 text-right font-bold text-lg">
-                          {selectedOrder.order.total_amount} лв
+                          {formatMoney(selectedOrder.total_amount)}
                         </td>
                       </tr>
+                      {selectedOrder.tax_breakdown.map((tax) => (
+                        <tr key={tax.rate} className="text-sm text-gray-600">
+                          <td colSpan={3} className="px-4 py-1 text-right">
+                            в т.ч. ДДС {parseFloat(tax.rate)}% върху{" "}
+                            {formatMoney(tax.net_amount)}:
+                          </td>
+                          <td className="px-4 py-1 text-right">
+                            {formatMoney(tax.vat_amount)}
+                          </td>
+                        </tr>
+                      ))}
                     </tfoot>
                   </table>
                 </div>
               </div>
 
-              <div>
-                <h3 className="font-semibold mb-2">Статус на поръчката</h3>
-                <div className="flex gap-2 flex-wrap">
-                  {Object.entries(statusLabels).map(([status, label]) => (
-                    <button
-                      key={status}
-                      onClick={() =>
-                        handleUpdateStatus(selectedOrder.order.id, status)
-                      }
-                      className={`px-3 py-1 rounded-full text-sm ${
-                        selectedOrder.order.status === status
-                          ? statusColors[status]
-                          : "bg-gray-100 text-gray-700 hover:bg-gray-200"
-                      }`}
+              {payments && (
+                <div className="mb-6">
+                  <h3 className="font-semibold mb-2">Плащане</h3>
+                  <div className="text-sm space-y-1">
+                    <p>
+                      {paymentMethodLabels[payments.payment_method]}
+                      {" · "}
+                      {paymentStatusLabels[payments.payment_status]}
+                      {selectedOrder.collected_amount &&
+                        ` · събрани ${formatMoney(selectedOrder.collected_amount)}`}
+                      {parseFloat(payments.refunded_amount.amount) > 0 &&
+                        ` · възстановени ${formatMoney(payments.refunded_amount)}`}
+                    </p>
+                    {payments.payments.map((payment) => (
+                      <p key={payment.id} className="text-gray-600">
+                        <span className="font-mono">
+                          {payment.provider_payment_id}
+                        </span>{" "}
+                        {payment.amount} {payment.currency} · {payment.status}
+                        {payment.failure_message &&
+                          ` (${payment.failure_message})`}
+                      </p>
+                    ))}
+                    {payments.refunds.map((refund) => (
+                      <p key={refund.id} className="text-gray-600">
+                        Възстановени {refund.amount} {refund.currency} на{" "}
+                        {new Date(refund.created_at).toLocaleString("bg-BG")}
+                        {refund.reason && ` · ${refund.reason}`}
+                        {refund.status !== "succeeded" &&
+                          ` · ${refund.status}`}
+                        {refund.failure_message &&
+                          ` (${refund.failure_message})`}
+                      </p>
+                    ))}
+                  </div>
+                  {(payments.payment_status === "paid" ||
+                    payments.payment_status === "partially_refunded") &&
+                    payments.payments.some((p) => p.status === "succeeded") && (
+                      <div className="flex gap-2 mt-3">
+                        <input
+                          type="text"
+                          value={refundAmount}
+                          onChange={(e) => setRefundAmount(e.target.value)}
+                          placeholder="Сума (цялата)"
+                          className="w-32 px-3 py-2 border rounded-lg text-sm"
+                        />
+                        <input
+                          type="text"
+                          value={refundReason}
+                          onChange={(e) => setRefundReason(e.target.value)}
+                          placeholder="Причина (по избор)"
+                          className="flex-1 min-w-0 px-3 py-2 border rounded-lg text-sm"
+                        />
+                        <Button
+                          variant="outline"
+                          size="sm"
+                          disabled={refunding}
+                          onClick={() => handleRefund(selectedOrder.id)}
+                        >
+                          {refunding ? "Обработване..." : "Възстанови сума"}
+                        </Button>
+                      </div>
+                    )}
+                </div>
+              )}
+
+              <div className="mb-6">
+                <h3 className="font-semibold mb-2">Фактура</h3>
+                {invoice ? (
+                  <div className="text-sm space-y-2">
+                    <p>
+                      <button
+                        onClick={() => openInvoicePdf(invoice.id)}
+                        className="font-mono text-rose-600 hover:underline"
+                      >
+                        № {invoice.formatted_number}
+                      </button>{" "}
+                      от {new Date(invoice.issue_date).toLocaleDateString("bg-BG")}
+                      {" · "}
+                      {invoice.buyer_name} · {formatMoney(invoice.total_amount)}
+                    </p>
+                    {invoice.credit_notes.map((creditNote) => (
+                      <p key={creditNote.id} className="text-gray-600">
+                        Кредитно известие{" "}
+                        <button
+                          onClick={() => openInvoicePdf(creditNote.id)}
+                          className="font-mono text-rose-600 hover:underline"
+                        >
+                          № {String(creditNote.number).padStart(10, "0")}
+                        </button>{" "}
+                        · -{formatMoney(creditNote.total_amount)}
+                        {creditNote.reason && ` · ${creditNote.reason}`}
+                      </p>
+                    ))}
+                    {payments?.refunds
+                      .filter(
+                        (refund) =>
+                          refund.status === "succeeded" &&
+                          !invoice.credit_notes.some(
+                            (creditNote) => creditNote.refund_id === refund.id
+                          )
+                      )
+                      .map((refund) => (
+                        <div key={refund.id} className="flex items-center gap-2">
+                          <span className="text-gray-600">
+                            Възстановени {refund.amount} {refund.currency} без
+                            кредитно известие
+                          </span>
+                          <Button
+                            variant="outline"
+                            size="sm"
+                            disabled={crediting}
+                            onClick={() =>
+                              handleCreditNote(selectedOrder.id, refund.id)
+                            }
+                          >
+                            Издай
+                          </Button>
+                        </div>
+                      ))}
+                    <div className="flex gap-2">
+                      <input
+                        type="text"
+                        value={creditAmount}
+                        onChange={(e) => setCreditAmount(e.target.value)}
+                        placeholder="Сума (остатъка)"
+                        className="w-32 px-3 py-2 border rounded-lg text-sm"
+                      />
+                      <input
+                        type="text"
+                        value={creditReason}
+                        onChange={(e) => setCreditReason(e.target.value)}
+                        placeholder="Основание (по избор)"
+                        className="flex-1 min-w-0 px-3 py-2 border rounded-lg text-sm"
+                      />
+                      <Button
+                        variant="outline"
+                        size="sm"
+                        disabled={crediting}
+                        onClick={() => handleCreditNote(selectedOrder.id)}
+                      >
+                        {crediting ? "Обработване..." : "Кредитно известие"}
+                      </Button>
+                    </div>
+                  </div>
+                ) : canInvoice(selectedOrder) ? (
+                  <Button
+                    size="sm"
+                    disabled={issuingInvoice}
+                    onClick={() => handleIssueInvoice(selectedOrder.id)}
+                  >
+                    {issuingInvoice ? "Издаване..." : "Издай фактура"}
+                  </Button>
+                ) : (
+                  <p className="text-sm text-gray-500">Няма фактура</p>
+                )}
+              </div>
+
+              <div className="mb-6">
+                <h3 className="font-semibold mb-2">Товарителница</h3>
+                {waybill ? (
+                  <div className="text-sm space-y-1">
+                    <p>
+                      {waybill.carrier === "econt" ? "Еконт" : "Спиди"}{" "}
+                      <span className="font-mono">{waybill.tracking_number}</span>
+                      {" · "}
+                      {shipmentStatusLabels[waybill.status]}
+                    </p>
+                    {waybill.last_event && (
+                      <p className="text-gray-600">
+                        {waybill.last_event}
+                        {waybill.last_checked_at &&
+                          ` (${new Date(waybill.last_checked_at).toLocaleString("bg-BG")})`}
+                      </p>
+                    )}
+                    <Button
+                      variant="outline"
+                      size="sm"
+                      onClick={() => openWaybillLabel(selectedOrder.id)}
                     >
-                      {label}
-                    </button>
+                      Етикет (PDF)
+                    </Button>
+                  </div>
+                ) : selectedOrder.status === "pending" ||
+                  selectedOrder.status === "processing" ? (
+                  <Button
+                    size="sm"
+                    disabled={creatingWaybill}
+                    onClick={() => handleCreateWaybill(selectedOrder.id)}
+                  >
+                    {creatingWaybill ? "Създаване..." : "Създай товарителница"}
+                  </Button>
+                ) : (
+                  <p className="text-sm text-gray-500">Няма товарителница</p>
+                )}
+              </div>
+
+              <div className="mb-6">
+                <h3 className="font-semibold mb-2">Статус на поръчката</h3>
+                <p className="mb-3">
+                  <span
+                    className={`px-3 py-1 rounded-full text-sm ${
+                      statusColors[selectedOrder.status]
+                    }`}
+                  >
+                    {statusLabels[selectedOrder.status]}
+                  </span>
+                </p>
+                {selectedOrder.tracking_number && (
+                  <p className="mb-3 text-sm text-gray-700">
+                    Товарителница: {selectedOrder.tracking_number}
+                  </p>
+                )}
+                {selectedOrder.allowed_transitions.length > 0 && (
+                  <>
+                    <input
+                      type="text"
+                      value={statusComment}
+                      onChange={(e) => setStatusComment(e.target.value)}
+                      placeholder="Коментар (по избор)"
+                      className="w-full mb-3 px-3 py-2 border rounded-lg text-sm"
+                    />
+                    {selectedOrder.allowed_transitions.includes("shipped") && (
+                      <input
+                        type="text"
+                        value={trackingNumber}
+                        onChange={(e) => setTrackingNumber(e.target.value)}
+                        placeholder="Номер за проследяване (изпраща се на клиента)"
+                        className="w-full mb-3 px-3 py-2 border rounded-lg text-sm"
+                      />
+                    )}
+                    <div className="flex gap-2 flex-wrap">
+                      {selectedOrder.allowed_transitions.map((status) => (
+                        <button
+                          key={status}
+                          onClick={() =>
+                            handleUpdateStatus(selectedOrder.id, status)
+                          }
+                          className="px-3 py-1 rounded-full text-sm bg-gray-100 text-gray-700 hover:bg-gray-200"
+                        >
+                          {statusLabels[status]}
+                        </button>
+                      ))}
+                    </div>
+                  </>
+                )}
+              </div>
+
+              <div>
+                <h3 className="font-semibold mb-2">История</h3>
+                <ul className="space-y-2 text-sm">
+                  {selectedOrder.history.map((change) => (
+                    <li key={change.id} className="border-l-2 pl-3">
+                      <p>
+                        {change.from_status
+                          ? `${statusLabels[change.from_status]} → ${
+                              statusLabels[change.to_status]
+                            }`
+                          : statusLabels[change.to_status]}
+                      </p>
+                      <p className="text-gray-500">
+                        {new Date(change.created_at).toLocaleString("bg-BG")}
+                        {change.admin_email && ` · ${change.admin_email}`}
+                      </p>
+                      {change.comment && (
+                        <p className="text-gray-700">{change.comment}</p>
+                      )}
+                    </li>
                   ))}
-                </div>
+                </ul>
               </div>
             </div>
           </div>
@@ -311,3 +818,14 @@ export default function AdminOrdersPage() {
     </main>
   );
 }
+
+// Invoices are issued for paid orders, or for cash orders once delivered
+function canInvoice(order: Order): boolean {
+  if (order.status === "cancelled") return false;
+  const paid: PaymentStatus[] = ["paid", "partially_refunded", "refunded"];
+  return (
+    paid.includes(order.payment_status) ||
+    (order.payment_method === "cash_on_delivery" &&
+      order.status === "delivered")
+  );
+}
@@ -10,9 +10,16 @@ import {
   updateProduct,
   deleteProduct,
   uploadProductImage,
+  getTaxClasses,
 } from "@/lib/api";
 import { Button } from "@/components/Button";
-import type { Product, CreateProductRequest, UpdateProductRequest } from "@/types";
+import { formatMoney } from "@/lib/utils";
+import type {
+  Product,
+  CreateProductRequest,
+  UpdateProductRequest,
+  TaxClass,
+} from "@/types";
 
 export default function AdminProductsPage() {
   const router = useRouter();
@@ -21,6 +28,7 @@ export default function AdminProductsPage() {
   const [loading, setLoading] = useState(true);
   const [editingProduct, setEditingProduct] = useState<Product | null>(null);
   const [showCreateForm, setShowCreateForm] = useState(false);
+  const [taxClasses, setTaxClasses] = useState<TaxClass[]>([]);
 
   useEffect(() => {
     if (!isAuthenticated()) {
@@ -34,8 +42,12 @@ export default function AdminProductsPage() {
     if (!token) return;
     try {
       setLoading(true);
-      const data = await getAdminProducts(token);
-      setProducts(data);
+      const [data, classes] = await Promise.all([
+        getAdminProducts(token, { limit: 100 }),
+        getTaxClasses(token),
+      ]);
+      setProducts(data.items);
+      setTaxClasses(classes);
     } catch (err) {
       console.error(err);
     } finally {
@@ -126,6 +138,7 @@ export default function AdminProductsPage() {
 
         {showCreateForm && (
           <ProductForm
+            taxClasses={taxClasses}
             onSubmit={handleCreate}
             onCancel={() => setShowCreateForm(false)}
           />
@@ -168,7 +181,7 @@ export default function AdminProductsPage() {
                       {product.name}
                     </td>
                     <td className="px-6 py-4 text-sm text-gray-900">
-                      {product.price} лв
+                      {formatMoney(product.price)}
                     </td>
                     <td className="px-6 py-4 text-sm text-gray-900">
                       {product.stock_quantity}
@@ -211,6 +224,7 @@ export default function AdminProductsPage() {
               <h2 className="text-2xl font-bold mb-4">Редактиране на продукт</h2>
               <ProductForm
                 product={editingProduct}
+                taxClasses={taxClasses}
                 onSubmit={(data, imageFile) => handleUpdate(editingProduct.id, data, imageFile)}
                 onCancel={() => setEditingProduct(null)}
               />
@@ -224,18 +238,22 @@ export default function AdminProductsPage() {
 
 function ProductForm({
   product,
+  taxClasses,
   onSubmit,
   onCancel,
 }: {
   product?: Product;
+  taxClasses: TaxClass[];
   onSubmit: (data: any, imageFile?: File) => void;
   onCancel: () => void;
 }) {
   const [formData, setFormData] = useState({
     name: product?.name || "",
     description: product?.description || "",
-    price: product?.price || "",
+    price: product?.price.amount || "",
     stock_quantity: product?.stock_quantity || 0,
+    weight_grams: product?.weight_grams || 0,
+    tax_class: product?.tax_class || "standard",
     is_active: product?.is_active ?? true,
   });
   const [imageFile, setImageFile] = useState<File | null>(null);
@@ -321,7 +339,7 @@ function ProductForm({
 
         <div>
           <label className="block text-sm font-medium text-gray-700 mb-1">
-            Цена (лв)
+            Цена (€)
           </label>
           <input
             type="number"
@@ -348,6 +366,39 @@ function ProductForm({
           />
         </div>
 
+        <div>
+          <label className="block text-sm font-medium text-gray-700 mb-1">
+            Тегло с опаковката (г)
+          </label>
+          <input
+            type="number"
+            min="0"
+            value={formData.weight_grams}
+            onChange={(e) =>
+              setFormData({ ...formData, weight_grams: parseInt(e.target.value) })
+            }
+            className="w-full px-4 py-2 border rounded-lg"
+            required
+          />
+        </div>
+
+        <div>
+          <label className="block text-sm font-medium text-gray-700 mb-1">
+            ДДС група
+          </label>
+          <select
+            value={formData.tax_class}
+            onChange={(e) => setFormData({ ...formData, tax_class: e.target.value })}
+            className="w-full px-4 py-2 border rounded-lg"
+          >
+            {taxClasses.map((taxClass) => (
+              <option key={taxClass.code} value={taxClass.code}>
+                {taxClass.name} ({parseFloat(taxClass.rate)}%)
+              </option>
+            ))}
+          </select>
+        </div>
+
         {product && (
           <div className="col-span-2">
             <label className="flex items-center space-x-2">
@@ -5,6 +5,7 @@ import toast from "react-hot-toast";
 import Image from "next/image";
 import { useCartStore } from "@/store/useCartStore";
 import { Button } from "@/components/Button";
+import { formatEuro, formatMoney } from "@/lib/utils";
 
 export default function CartPage() {
   const router = useRouter();
@@ -61,7 +62,7 @@ export default function CartPage() {
                 ? `${process.env.NEXT_PUBLIC_API_URL || "http://localhost:8000"}${item.product.image_url}`
                 : "/placeholder-clay.svg";
 
-              const price = parseFloat(item.product.price);
+              const price = parseFloat(item.product.price.amount);
               const subtotal = price * item.quantity;
 
               return (
@@ -83,7 +84,7 @@ export default function CartPage() {
                       {item.product.name}
                     </h3>
                     <p className="text-terracotta font-medium mb-2">
-                      {price.toFixed(2)} лв
+                      {formatMoney(item.product.price)}
                     </p>
 
                     <div className="flex items-center space-x-3">
@@ -122,7 +123,7 @@ export default function CartPage() {
 
                   <div className="text-right">
                     <p className="text-lg font-bold text-clay-brown mb-2">
-                      {subtotal.toFixed(2)} лв
+                      {formatEuro(subtotal)}
                     </p>
                     <Button
                       size="sm"
@@ -149,7 +150,7 @@ export default function CartPage() {
               <div className="space-y-2 mb-6">
                 <div className="flex justify-between text-warm-gray">
                   <span>Продукти ({items.length}):</span>
-                  <span>{total.toFixed(2)} лв</span>
+                  <span>{formatEuro(total)}</span>
                 </div>
                 <div className="flex justify-between text-warm-gray">
                   <span>Доставка:</span>
@@ -157,7 +158,7 @@ export default function CartPage() {
                 </div>
                 <div className="border-t border-border pt-2 flex justify-between text-xl font-bold text-clay-brown">
                   <span>Общо:</span>
-                  <span className="text-terracotta">{total.toFixed(2)} лв</span>
+                  <span className="text-terracotta">{formatEuro(total)}</span>
                 </div>
               </div>
 
@@ -1,24 +1,82 @@
 "use client";
 
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { useRouter } from "next/navigation";
 import toast from "react-hot-toast";
 import { useForm } from "react-hook-form";
 import { zodResolver } from "@hookform/resolvers/zod";
 import { z } from "zod";
 import { useCartStore } from "@/store/useCartStore";
-import { createOrder } from "@/lib/api";
+import {
+  createOrder,
+  createReservation,
+  getCourierOffices,
+  getPaymentConfig,
+  quoteShipping,
+  validatePromotion,
+} from "@/lib/api";
+import { BankTransferDetails } from "@/components/BankTransferDetails";
 import { Button } from "@/components/Button";
+import { CardPayment } from "@/components/CardPayment";
+import { formatEuro, formatMoney } from "@/lib/utils";
+import type {
+  CourierOffice,
+  CreatedOrder,
+  PaymentConfig,
+  PaymentMethod,
+  PromotionQuote,
+  ShippingQuote,
+} from "@/types";
 
-const checkoutSchema = z.object({
-  customer_name: z.string().min(2, "Моля въведете име (минимум 2 символа)"),
-  customer_email: z.string().email("Моля въведете валиден email"),
-  customer_phone: z.string().min(10, "Моля въведете валиден телефон"),
-  delivery_address: z.string().min(5, "Моля въведете адрес"),
-  delivery_city: z.string().min(2, "Моля въведете град"),
-  delivery_postal_code: z.string().optional(),
-  notes: z.string().optional(),
-});
+const paymentMethodLabels: Record<PaymentMethod, string> = {
+  cash_on_delivery: "Наложен платеж",
+  bank_transfer: "Банков превод",
+  card: "С карта онлайн",
+};
+
+const checkoutSchema = z
+  .object({
+    customer_name: z.string().min(2, "Моля въведете име (минимум 2 символа)"),
+    customer_email: z.string().email("Моля въведете валиден email"),
+    customer_phone: z.string().min(10, "Моля въведете валиден телефон"),
+    delivery_address: z.string().min(5, "Моля въведете адрес"),
+    delivery_city: z.string().min(2, "Моля въведете град"),
+    delivery_postal_code: z.string().optional(),
+    notes: z.string().optional(),
+    wants_invoice: z.boolean().optional(),
+    invoice_company: z.string().optional(),
+    invoice_eik: z.string().optional(),
+    invoice_vat_number: z.string().optional(),
+    invoice_mol: z.string().optional(),
+    invoice_address: z.string().optional(),
+  })
+  .superRefine((data, ctx) => {
+    // Company details are only needed for an invoice
+    if (!data.wants_invoice) {
+      return;
+    }
+    if (!data.invoice_company || data.invoice_company.trim().length < 2) {
+      ctx.addIssue({
+        code: z.ZodIssueCode.custom,
+        path: ["invoice_company"],
+        message: "Моля въведете име на фирмата",
+      });
+    }
+    if (!/^(\d{9}|\d{13})$/.test((data.invoice_eik ?? "").replace(/\s/g, ""))) {
+      ctx.addIssue({
+        code: z.ZodIssueCode.custom,
+        path: ["invoice_eik"],
+        message: "ЕИК трябва да е 9 или 13 цифри",
+      });
+    }
+    if (!data.invoice_address || data.invoice_address.trim().length < 5) {
+      ctx.addIssue({
+        code: z.ZodIssueCode.custom,
+        path: ["invoice_address"],
+        message: "Моля въведете адрес на регистрация",
+      });
+    }
+  });
 
 type CheckoutFormData = z.infer<typeof checkoutSchema>;
 
@@ -27,16 +85,169 @@ export default function CheckoutPage() {
   const { items, getTotalPrice, clearCart } = useCartStore();
   const [submitting, setSubmitting] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const [reservationId, setReservationId] = useState<string | null>(null);
+  // One key per checkout, so resubmitting after a timeout cannot order twice
+  const [idempotencyKey] = useState(() => crypto.randomUUID());
+  const [promotionCode, setPromotionCode] = useState("");
+  const [promotion, setPromotion] = useState<PromotionQuote | null>(null);
+  const [promotionError, setPromotionError] = useState<string | null>(null);
+  const [shipping, setShipping] = useState<ShippingQuote | null>(null);
+  const [shippingMethodId, setShippingMethodId] = useState<number | null>(null);
+  const [offices, setOffices] = useState<CourierOffice[]>([]);
+  const [officeCode, setOfficeCode] = useState("");
+  const [paymentConfig, setPaymentConfig] = useState<PaymentConfig | null>(null);
+  const [paymentMethod, setPaymentMethod] =
+    useState<PaymentMethod>("cash_on_delivery");
+  // Card or transfer order waiting for the customer to pay
+  const [unpaidOrder, setUnpaidOrder] = useState<CreatedOrder | null>(null);
 
   const {
     register,
     handleSubmit,
+    getValues,
+    setValue,
+    watch,
     formState: { errors },
   } = useForm<CheckoutFormData>({
     resolver: zodResolver(checkoutSchema),
   });
 
-  const total = getTotalPrice();
+  const postalCode = watch("delivery_postal_code");
+  const wantsInvoice = watch("wants_invoice");
+  const city = watch("delivery_city");
+  const shippingOption = shipping?.options.find(
+    (option) => option.id === shippingMethodId
+  );
+  const officeCarrier =
+    shippingOption?.kind === "courier_office" ? shippingOption.carrier : null;
+  const codFee =
+    shippingOption && paymentMethod === "cash_on_delivery"
+      ? parseFloat(shippingOption.cod_fee.amount)
+      : 0;
+  const total =
+    (shippingOption
+      ? parseFloat(shippingOption.total_amount.amount)
+      : promotion
+        ? parseFloat(promotion.total_amount.amount)
+        : getTotalPrice()) + codFee;
+
+  function cartLines() {
+    return items.map((item) => ({
+      product_id: item.product.id,
+      variant_id: item.variant?.id,
+      quantity: item.quantity,
+    }));
+  }
+
+  useEffect(() => {
+    getPaymentConfig()
+      .then(setPaymentConfig)
+      .catch(() => setPaymentConfig(null));
+  }, []);
+
+  // Hold the cart's stock while the customer fills in the form
+  useEffect(() => {
+    if (items.length === 0) return;
+
+    createReservation(cartLines())
+      .then((reservation) => setReservationId(reservation.id))
+      .catch((err) => setError(err.message || "Някои продукти вече не са налични"));
+    // The discount was worked out for the previous cart
+    setPromotion(null);
+  }, [items]);
+
+  // Shipping prices depend on the cart weight, the postal code zone and the
+  // discount
+  useEffect(() => {
+    if (items.length === 0) return;
+
+    quoteShipping(
+      cartLines(),
+      postalCode || undefined,
+      promotion?.code,
+      getValues("customer_email") || undefined
+    )
+      .then((quote) => {
+        setShipping(quote);
+        setShippingMethodId((current) =>
+          quote.options.some((option) => option.id === current)
+            ? current
+            : quote.options[0]?.id ?? null
+        );
+      })
+      .catch(() => setShipping(null));
+  }, [items, postalCode, promotion]);
+
+  // Offices of the chosen carrier in the customer's city. Without a list
+  // (courier not connected, unknown city) the office is typed in the address.
+  useEffect(() => {
+    setOfficeCode("");
+    if (!officeCarrier || !city || city.trim().length < 2) {
+      setOffices([]);
+      return;
+    }
+
+    const timer = setTimeout(() => {
+      getCourierOffices(officeCarrier, city.trim())
+        .then(setOffices)
+        .catch(() => setOffices([]));
+    }, 400);
+    return () => clearTimeout(timer);
+  }, [officeCarrier, city]);
+
+  function selectOffice(code: string) {
+    setOfficeCode(code);
+    const office = offices.find((o) => o.code === code);
+    if (office) {
+      setValue("delivery_address", `${office.name}, ${office.address}`, {
+        shouldValidate: true,
+      });
+    }
+  }
+
+  async function applyPromotion() {
+    if (!promotionCode.trim()) return;
+    try {
+      setPromotionError(null);
+      setPromotion(
+        await validatePromotion(
+          promotionCode,
+          cartLines(),
+          getValues("customer_email") || undefined
+        )
+      );
+    } catch (err: any) {
+      setPromotion(null);
+      setPromotionError(err.message || "Невалиден код за отстъпка");
+    }
+  }
+
+  if (unpaidOrder?.payment) {
+    return (
+      <main className="min-h-screen bg-gray-50 py-12">
+        <div className="container mx-auto px-4">
+          <CardPayment
+            orderId={unpaidOrder.id}
+            payment={unpaidOrder.payment}
+            total={unpaidOrder.total_amount}
+          />
+        </div>
+      </main>
+    );
+  }
+
+  if (unpaidOrder?.bank_transfer) {
+    return (
+      <main className="min-h-screen bg-gray-50 py-12">
+        <div className="container mx-auto px-4">
+          <BankTransferDetails
+            orderId={unpaidOrder.id}
+            transfer={unpaidOrder.bank_transfer}
+          />
+        </div>
+      </main>
+    );
+  }
 
   if (items.length === 0) {
     return (
@@ -56,22 +267,52 @@ export default function CheckoutPage() {
   }
 
   async function onSubmit(data: CheckoutFormData) {
+    if (shippingMethodId === null) {
+      setError("Моля изберете начин на доставка");
+      return;
+    }
+
     try {
       setSubmitting(true);
       setError(null);
 
+      const {
+        wants_invoice,
+        invoice_company,
+        invoice_eik,
+        invoice_vat_number,
+        invoice_mol,
+        invoice_address,
+        ...customer
+      } = data;
+
       const orderData = {
-        ...data,
-        items: items.map((item) => ({
-          product_id: item.product.id,
-          quantity: item.quantity,
-        })),
+        ...customer,
+        invoice: wants_invoice
+          ? {
+              company: invoice_company ?? "",
+              eik: invoice_eik ?? "",
+              vat_number: invoice_vat_number || undefined,
+              mol: invoice_mol || undefined,
+              address: invoice_address ?? "",
+            }
+          : undefined,
+        items: cartLines(),
+        reservation_id: reservationId ?? undefined,
+        promotion_code: promotion?.code,
+        shipping_method_id: shippingMethodId,
+        courier_office_code: officeCode || undefined,
+        payment_method: paymentMethod,
       };
 
-      const order = await createOrder(orderData);
+      const order = await createOrder(orderData, idempotencyKey);
       clearCart();
       toast.success("Поръчката е създадена успешно!");
-      router.push(`/order-confirmation?id=${order.id}`);
+      if (order.payment || order.bank_transfer) {
+        setUnpaidOrder(order);
+      } else {
+        router.push(`/order-confirmation?id=${order.id}`);
+      }
     } catch (err: any) {
       const errorMsg = err.message || "Грешка при създаване на поръчката";
       setError(errorMsg);
@@ -159,7 +400,72 @@ export default function CheckoutPage() {
 
               <div>
                 <label className="block text-sm font-medium text-gray-700 mb-1">
-                  Адрес за доставка *
+                  Начин на доставка *
+                </label>
+                <div className="space-y-2">
+                  {shipping?.options.map((option) => (
+                    <label
+                      key={option.id}
+                      className="flex items-start gap-3 p-3 border border-gray-300 rounded-lg cursor-pointer"
+                    >
+                      <input
+                        type="radio"
+                        name="shipping_method"
+                        checked={option.id === shippingMethodId}
+                        onChange={() => setShippingMethodId(option.id)}
+                        className="mt-1"
+                      />
+                      <span className="flex-1">
+                        <span className="flex justify-between font-medium text-gray-900">
+                          <span>{option.name}</span>
+                          <span>
+                            {parseFloat(option.price.amount) === 0
+                              ? "Безплатно"
+                              : formatMoney(option.price)}
+                          </span>
+                        </span>
+                        {option.description && (
+                          <span className="block text-sm text-gray-600">
+                            {option.description}
+                          </span>
+                        )}
+                        {option.free_shipping_remaining && (
+                          <span className="block text-sm text-gray-500">
+                            Още {formatMoney(option.free_shipping_remaining)} до
+                            безплатна доставка
+                          </span>
+                        )}
+                      </span>
+                    </label>
+                  ))}
+                </div>
+              </div>
+
+              {offices.length > 0 && (
+                <div>
+                  <label className="block text-sm font-medium text-gray-700 mb-1">
+                    Изберете офис
+                  </label>
+                  <select
+                    value={officeCode}
+                    onChange={(e) => selectOffice(e.target.value)}
+                    className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-rose-500 focus:border-transparent"
+                  >
+                    <option value="">—</option>
+                    {offices.map((office) => (
+                      <option key={office.code} value={office.code}>
+                        {office.name} ({office.address})
+                      </option>
+                    ))}
+                  </select>
+                </div>
+              )}
+
+              <div>
+                <label className="block text-sm font-medium text-gray-700 mb-1">
+                  {shippingOption?.kind === "courier_office"
+                    ? "Офис на куриера *"
+                    : "Адрес за доставка *"}
                 </label>
                 <input
                   type="text"
@@ -205,6 +511,121 @@ export default function CheckoutPage() {
                 </div>
               </div>
 
+              {paymentConfig && paymentConfig.methods.length > 1 && (
+                <div>
+                  <label className="block text-sm font-medium text-gray-700 mb-1">
+                    Начин на плащане *
+                  </label>
+                  <div className="space-y-2">
+                    {paymentConfig.methods.map((method) => (
+                      <label
+                        key={method}
+                        className="flex items-center gap-3 p-3 border border-gray-300 rounded-lg cursor-pointer"
+                      >
+                        <input
+                          type="radio"
+                          name="payment_method"
+                          checked={method === paymentMethod}
+                          onChange={() => setPaymentMethod(method)}
+                        />
+                        <span className="font-medium text-gray-900">
+                          {paymentMethodLabels[method]}
+                        </span>
+                      </label>
+                    ))}
+                  </div>
+                </div>
+              )}
+
+              <div>
+                <label className="flex items-center gap-3 cursor-pointer">
+                  <input type="checkbox" {...register("wants_invoice")} />
+                  <span className="text-sm font-medium text-gray-700">
+                    Искам фактура
+                  </span>
+                </label>
+              </div>
+
+              {wantsInvoice && (
+                <div className="space-y-4 p-4 border border-gray-200 rounded-lg">
+                  <div>
+                    <label className="block text-sm font-medium text-gray-700 mb-1">
+                      Фирма *
+                    </label>
+                    <input
+                      type="text"
+                      {...register("invoice_company")}
+                      className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-rose-500 focus:border-transparent"
+                      placeholder="Студио ЕООД"
+                    />
+                    {errors.invoice_company && (
+                      <p className="text-red-600 text-sm mt-1">
+                        {errors.invoice_company.message}
+                      </p>
+                    )}
+                  </div>
+
+                  <div className="grid grid-cols-2 gap-4">
+                    <div>
+                      <label className="block text-sm font-medium text-gray-700 mb-1">
+                        ЕИК *
+                      </label>
+                      <input
+                        type="text"
+                        {...register("invoice_eik")}
+                        className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-rose-500 focus:border-transparent"
+                        placeholder="123456789"
+                      />
+                      {errors.invoice_eik && (
+                        <p className="text-red-600 text-sm mt-1">
+                          {errors.invoice_eik.message}
+                        </p>
+                      )}
+                    </div>
+                    <div>
+                      <label className="block text-sm font-medium text-gray-700 mb-1">
+                        ДДС номер
+                      </label>
+                      <input
+                        type="text"
+                        {...register("invoice_vat_number")}
+                        className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-rose-500 focus:border-transparent"
+                        placeholder="BG123456789"
+                      />
+                    </div>
+                  </div>
+
+                  <div>
+                    <label className="block text-sm font-medium text-gray-700 mb-1">
+                      МОЛ
+                    </label>
+                    <input
+                      type="text"
+                      {...register("invoice_mol")}
+                      className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-rose-500 focus:border-transparent"
+                      placeholder="Иван Иванов"
+                    />
+                  </div>
+
+                  <div>
+                    <label className="block text-sm font-medium text-gray-700 mb-1">
+                      Адрес на регистрация *
+                    </label>
+                    <input
+                      type="text"
+                      {...register("invoice_address")}
+                      className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-rose-500 focus:border-transparent"
+                      placeholder="гр. София, ул. Шипка 5"
+                    />
+                    {errors.invoice_address && (
+                      <p className="text-red-600 text-sm mt-1">
+                        {errors.invoice_address.message}
+                      </p>
+                    )}
+                  </div>
+                </div>
+              )}
+
               <div>
                 <label className="block text-sm font-medium text-gray-700 mb-1">
                   Забележки към поръчката
@@ -247,23 +668,65 @@ export default function CheckoutPage() {
                       {item.product.name} x{item.quantity}
                     </span>
                     <span className="font-medium">
-                      {(parseFloat(item.product.price) * item.quantity).toFixed(
-                        2
-                      )}{" "}
-                      лв
+                      {formatEuro(
+                        parseFloat(item.product.price.amount) * item.quantity
+                      )}
                     </span>
                   </div>
                 ))}
               </div>
 
+              <div className="border-t pt-4 mb-4">
+                <label className="block text-sm font-medium text-gray-700 mb-1">
+                  Код за отстъпка
+                </label>
+                <div className="flex gap-2">
+                  <input
+                    type="text"
+                    value={promotionCode}
+                    onChange={(e) => setPromotionCode(e.target.value)}
+                    className="w-full min-w-0 px-3 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-rose-500 focus:border-transparent uppercase"
+                  />
+                  <Button type="button" variant="outline" size="sm" onClick={applyPromotion}>
+                    Приложи
+                  </Button>
+                </div>
+                {promotionError && (
+                  <p className="text-red-600 text-sm mt-1">{promotionError}</p>
+                )}
+              </div>
+
               <div className="border-t pt-4">
+                <div className="space-y-1 mb-2 text-sm">
+                  {promotion && (
+                    <>
+                      <div className="flex justify-between text-gray-700">
+                        <span>Междинна сума:</span>
+                        <span>{formatMoney(promotion.subtotal_amount)}</span>
+                      </div>
+                      <div className="flex justify-between text-green-700">
+                        <span>Отстъпка ({promotion.code}):</span>
+                        <span>-{formatMoney(promotion.discount_amount)}</span>
+                      </div>
+                    </>
+                  )}
+                  {shippingOption && (
+                    <div className="flex justify-between text-gray-700">
+                      <span>Доставка:</span>
+                      <span>{formatMoney(shippingOption.price)}</span>
+                    </div>
+                  )}
+                  {codFee > 0 && (
+                    <div className="flex justify-between text-gray-700">
+                      <span>Такса наложен платеж:</span>
+                      <span>{formatEuro(codFee)}</span>
+                    </div>
+                  )}
+                </div>
                 <div className="flex justify-between text-lg font-bold text-gray-900">
                   <span>Общо:</span>
-                  <span>{total.toFixed(2)} лв</span>
+                  <span>{formatEuro(total)}</span>
                 </div>
-                <p className="text-sm text-gray-600 mt-2">
-                  Доставката е безплатна
-                </p>
               </div>
             </div>
           </div>
@@ -8,6 +8,8 @@ function OrderConfirmationContent() {
   const router = useRouter();
   const searchParams = useSearchParams();
   const orderId = searchParams.get("id");
+  // Set by Stripe when it sends the customer back after a card payment
+  const paymentFailed = searchParams.get("redirect_status") === "failed";
 
   return (
     <main className="min-h-screen bg-gray-50 py-12">
@@ -17,6 +19,12 @@ function OrderConfirmationContent() {
           <h1 className="text-3xl font-bold text-gray-900 mb-4">
             Поръчката е приета успешно!
           </h1>
+          {paymentFailed && (
+            <div className="bg-red-50 border border-red-200 rounded-lg p-4 text-red-800 mb-6">
+              Плащането с карта не бе успешно. Свържете се с нас, за да
+              завършим поръчката.
+            </div>
+          )}
           <p className="text-lg text-gray-700 mb-6">
             Благодарим ви за поръчката. Ще получите потвърждение на посочения
             email адрес.
@@ -36,9 +44,20 @@ function OrderConfirmationContent() {
             уведоми за статуса й.
           </p>
 
-          <Button size="lg" onClick={() => router.push("/")}>
-            Към началната страница
-          </Button>
+          <div className="flex flex-col sm:flex-row gap-3 justify-center">
+            {orderId && (
+              <Button
+                size="lg"
+                variant="outline"
+                onClick={() => router.push(`/orders/track?id=${orderId}`)}
+              >
+                Проследи поръчката
+              </Button>
+            )}
+            <Button size="lg" onClick={() => router.push("/")}>
+              Към началната страница
+            </Button>
+          </div>
         </div>
       </div>
     </main>
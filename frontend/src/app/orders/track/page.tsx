@@ -0,0 +1,239 @@
+"use client";
+
+import { FormEvent, Suspense, useEffect, useState } from "react";
+import { useRouter, useSearchParams } from "next/navigation";
+import { lookupOrder, trackOrder } from "@/lib/api";
+import { Button } from "@/components/Button";
+import { formatMoney } from "@/lib/utils";
+import type {
+  OrderStatus,
+  OrderTracking,
+  PaymentMethod,
+  PaymentStatus,
+} from "@/types";
+
+const statusLabels: Record<OrderStatus, string> = {
+  pending: "Приета",
+  processing: "Обработва се",
+  shipped: "Изпратена",
+  delivered: "Доставена",
+  cancelled: "Анулирана",
+  returned: "Върната",
+};
+
+const paymentMethodLabels: Record<PaymentMethod, string> = {
+  cash_on_delivery: "наложен платеж",
+  bank_transfer: "банков превод",
+  card: "с карта",
+};
+
+const paymentStatusLabels: Record<PaymentStatus, string> = {
+  pending: "Очаква плащане",
+  paid: "Платена",
+  failed: "Неуспешно плащане",
+  partially_refunded: "Частично възстановена сума",
+  refunded: "Възстановена сума",
+};
+
+function OrderTrackingContent() {
+  const router = useRouter();
+  const searchParams = useSearchParams();
+  const token = searchParams.get("token");
+
+  const [order, setOrder] = useState<OrderTracking | null>(null);
+  const [orderNumber, setOrderNumber] = useState(searchParams.get("id") || "");
+  const [email, setEmail] = useState("");
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!token) return;
+
+    setLoading(true);
+    trackOrder(token)
+      .then(setOrder)
+      .catch(() => setError("Невалиден линк за проследяване"))
+      .finally(() => setLoading(false));
+  }, [token]);
+
+  async function handleLookup(e: FormEvent) {
+    e.preventDefault();
+    try {
+      setLoading(true);
+      setError(null);
+      const found = await lookupOrder(orderNumber, email);
+      setOrder(found);
+      // Keep the page bookmarkable without the email in the URL
+      router.replace(`/orders/track?token=${found.tracking_token}`);
+    } catch {
+      setError("Не намерихме поръчка с този номер и email");
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  if (order) {
+    return (
+      <div className="bg-white rounded-lg shadow-md p-8 max-w-2xl mx-auto">
+        <h1 className="text-2xl font-bold text-gray-900 mb-2">
+          Поръчка № {order.reference}
+        </h1>
+        <p className="text-gray-600 mb-6">
+          {new Date(order.created_at).toLocaleDateString("bg-BG")} ·{" "}
+          {order.customer_name}, {order.delivery_city}
+        </p>
+
+        <div className="bg-gray-50 rounded-lg p-4 mb-6">
+          <p className="text-sm text-gray-600 mb-1">Статус:</p>
+          <p className="text-lg font-semibold text-gray-900">
+            {statusLabels[order.status]}
+          </p>
+          <p className="text-sm text-gray-700 mt-2">
+            Плащане:{" "}
+            {paymentMethodLabels[order.payment_method]},{" "}
+            {paymentStatusLabels[order.payment_status].toLowerCase()}
+          </p>
+          {order.payment_reference &&
+            order.payment_status === "pending" &&
+            order.status === "pending" && (
+              <p className="text-sm text-gray-700 mt-2">
+                Основание за превод:{" "}
+                <span className="font-mono">{order.payment_reference}</span>
+                {order.payment_due_at &&
+                  `, до ${new Date(order.payment_due_at + "Z").toLocaleDateString("bg-BG")}`}
+              </p>
+            )}
+          {order.tracking_number && (
+            <p className="text-sm text-gray-700 mt-2">
+              Номер на пратката:{" "}
+              <span className="font-mono">{order.tracking_number}</span>
+            </p>
+          )}
+        </div>
+
+        <h2 className="text-lg font-semibold text-gray-900 mb-3">Продукти</h2>
+        <div className="divide-y mb-6">
+          {order.items.map((item, index) => (
+            <div key={index} className="flex justify-between py-2">
+              <span className="text-gray-700">
+                {item.quantity} × {item.product_name}
+                {item.variant_label && ` (${item.variant_label})`}
+              </span>
+              <span className="font-medium">{formatMoney(item.subtotal)}</span>
+            </div>
+          ))}
+          {parseFloat(order.discount_amount.amount) > 0 && (
+            <div className="flex justify-between py-2 text-green-700">
+              <span>
+                Отстъпка{order.promotion_code && ` (${order.promotion_code})`}
+              </span>
+              <span>-{formatMoney(order.discount_amount)}</span>
+            </div>
+          )}
+          {order.shipping_method_name && (
+            <div className="flex justify-between py-2">
+              <span className="text-gray-700">
+                Доставка ({order.shipping_method_name})
+              </span>
+              <span>{formatMoney(order.shipping_amount)}</span>
+            </div>
+          )}
+          {parseFloat(order.cod_fee.amount) > 0 && (
+            <div className="flex justify-between py-2">
+              <span className="text-gray-700">Такса наложен платеж</span>
+              <span>{formatMoney(order.cod_fee)}</span>
+            </div>
+          )}
+          <div className="flex justify-between py-2 font-semibold">
+            <span>Общо</span>
+            <span>{formatMoney(order.total_amount)}</span>
+          </div>
+          {order.tax_breakdown
+            .filter((tax) => parseFloat(tax.rate) > 0)
+            .map((tax) => (
+              <div key={tax.rate} className="flex justify-between text-sm text-gray-600">
+                <span>в т.ч. ДДС {parseFloat(tax.rate)}%</span>
+                <span>{formatMoney(tax.vat_amount)}</span>
+              </div>
+            ))}
+        </div>
+
+        <h2 className="text-lg font-semibold text-gray-900 mb-3">История</h2>
+        <ul className="space-y-1">
+          {order.history.map((event, index) => (
+            <li key={index} className="flex justify-between text-sm">
+              <span className="text-gray-700">{statusLabels[event.status]}</span>
+              <span className="text-gray-500">
+                {new Date(event.created_at).toLocaleString("bg-BG")}
+              </span>
+            </li>
+          ))}
+        </ul>
+      </div>
+    );
+  }
+
+  if (token && loading) {
+    return <div className="text-center text-gray-600">Зареждане...</div>;
+  }
+
+  return (
+    <div className="bg-white rounded-lg shadow-md p-8 max-w-md mx-auto">
+      <h1 className="text-2xl font-bold text-gray-900 mb-6">
+        Проследяване на поръчка
+      </h1>
+
+      {error && (
+        <div className="bg-red-50 border border-red-200 text-red-700 px-4 py-3 rounded mb-4">
+          {error}
+        </div>
+      )}
+
+      <form onSubmit={handleLookup} className="space-y-4">
+        <div>
+          <label className="block text-sm font-medium text-gray-700 mb-1">
+            Номер на поръчка
+          </label>
+          <input
+            type="text"
+            required
+            value={orderNumber}
+            onChange={(e) => setOrderNumber(e.target.value)}
+            className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-rose-500 focus:border-transparent"
+            placeholder="A5ECD702"
+          />
+        </div>
+        <div>
+          <label className="block text-sm font-medium text-gray-700 mb-1">
+            Email
+          </label>
+          <input
+            type="email"
+            required
+            value={email}
+            onChange={(e) => setEmail(e.target.value)}
+            className="w-full px-4 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-rose-500 focus:border-transparent"
+            placeholder="ivan@example.com"
+          />
+        </div>
+        <Button type="submit" className="w-full" disabled={loading}>
+          {loading ? "Търсене..." : "Провери статуса"}
+        </Button>
+      </form>
+    </div>
+  );
+}
+
+export default function OrderTrackingPage() {
+  return (
+    <main className="min-h-screen bg-gray-50 py-12">
+      <div className="container mx-auto px-4">
+        <Suspense
+          fallback={<div className="text-center text-gray-600">Зареждане...</div>}
+        >
+          <OrderTrackingContent />
+        </Suspense>
+      </div>
+    </main>
+  );
+}
@@ -6,7 +6,8 @@ const SITE_URL =
 
 async function fetchProducts() {
   try {
-    return await getProducts();
+    const page = await getProducts({ limit: 100 });
+    return page.items;
   } catch (error) {
     console.error("Failed to fetch products:", error);
     return [];
@@ -111,7 +112,7 @@ export default async function Home() {
                   "@type": "Product",
                   name: product.name,
                   description: product.description || product.name,
-                  url: `${SITE_URL}/products/${product.id}`,
+                  url: `${SITE_URL}/products/${product.slug}`,
                   image: product.image_url
                     ? product.image_url.startsWith("http")
                       ? product.image_url
@@ -119,8 +120,8 @@ export default async function Home() {
                     : `${SITE_URL}/placeholder-clay.svg`,
                   offers: {
                     "@type": "Offer",
-                    price: parseFloat(product.price),
-                    priceCurrency: "BGN",
+                    price: parseFloat(product.price.amount),
+                    priceCurrency: "EUR",
                     availability:
                       product.stock_quantity > 0
                         ? "https://schema.org/InStock"
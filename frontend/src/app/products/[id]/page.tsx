@@ -1,7 +1,8 @@
 import { Metadata } from "next";
-import { notFound } from "next/navigation";
+import { notFound, permanentRedirect } from "next/navigation";
 import { getProduct } from "@/lib/api";
 import { ProductDetail } from "@/components/ProductDetail";
+import { formatMoney } from "@/lib/utils";
 
 const SITE_URL =
   process.env.NEXT_PUBLIC_SITE_URL || "https://studiozemya.com";
@@ -12,9 +13,9 @@ interface PageProps {
   };
 }
 
-async function fetchProduct(id: number) {
+async function fetchProduct(slugOrId: string) {
   try {
-    return await getProduct(id);
+    return await getProduct(decodeURIComponent(slugOrId));
   } catch (error) {
     return null;
   }
@@ -23,8 +24,7 @@ async function fetchProduct(id: number) {
 export async function generateMetadata({
   params,
 }: PageProps): Promise<Metadata> {
-  const productId = parseInt(params.id);
-  const product = await fetchProduct(productId);
+  const product = await fetchProduct(params.id);
 
   if (!product) {
     return {
@@ -32,7 +32,7 @@ export async function generateMetadata({
     };
   }
 
-  const price = parseFloat(product.price);
+  const price = formatMoney(product.price);
   const imageUrl = product.image_url
     ? product.image_url.startsWith("http")
       ? product.image_url
@@ -43,14 +43,14 @@ export async function generateMetadata({
     title: product.name,
     description:
       product.description ||
-      `Купете ${product.name} — ръчно изработено от полимерна глина в Studio Zemya. Цена: ${price.toFixed(2)} лв. ${product.stock_quantity > 0 ? "В наличност." : ""}`,
+      `Купете ${product.name} — ръчно изработено от полимерна глина в Studio Zemya. Цена: ${price}. ${product.stock_quantity > 0 ? "В наличност." : ""}`,
     openGraph: {
       title: product.name,
       description:
         product.description ||
-        `Купете ${product.name} за ${price.toFixed(2)} лв`,
+        `Купете ${product.name} за ${price}`,
       type: "website",
-      url: `${SITE_URL}/products/${product.id}`,
+      url: `${SITE_URL}/products/${product.slug}`,
       images: [
         {
           url: imageUrl,
@@ -65,20 +65,24 @@ export async function generateMetadata({
       title: product.name,
       description:
         product.description ||
-        `Купете ${product.name} за ${price.toFixed(2)} лв`,
+        `Купете ${product.name} за ${price}`,
       images: [imageUrl],
     },
   };
 }
 
 export default async function ProductDetailPage({ params }: PageProps) {
-  const productId = parseInt(params.id);
-  const product = await fetchProduct(productId);
+  const product = await fetchProduct(params.id);
 
   if (!product) {
     notFound();
   }
 
+  // Old slugs and numeric ids redirect to the canonical URL
+  if (product.moved || decodeURIComponent(params.id) !== product.slug) {
+    permanentRedirect(`/products/${product.slug}`);
+  }
+
   return (
     <>
       <ProductDetail product={product} />
@@ -98,13 +102,13 @@ export default async function ProductDetailPage({ params }: PageProps) {
               : `${SITE_URL}/placeholder-clay.svg`,
             offers: {
               "@type": "Offer",
-              price: parseFloat(product.price),
-              priceCurrency: "BGN",
+              price: parseFloat(product.price.amount),
+              priceCurrency: "EUR",
               availability:
                 product.stock_quantity > 0
                   ? "https://schema.org/InStock"
                   : "https://schema.org/OutOfStock",
-              url: `${SITE_URL}/products/${product.id}`,
+              url: `${SITE_URL}/products/${product.slug}`,
             },
           }),
         }}
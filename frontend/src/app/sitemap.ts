@@ -6,19 +6,39 @@ const SITE_URL =
 
 interface Product {
   id: number;
+  slug: string;
   updated_at?: string;
 }
 
+interface ProductPage {
+  items: Product[];
+  total_pages: number;
+}
+
 async function getProducts(): Promise<Product[]> {
   try {
-    const response = await fetch(`${API_URL}/api/products`, {
-      next: { revalidate: 3600 }, // Revalidate every hour
-    });
-    if (!response.ok) {
-      console.error("Failed to fetch products for sitemap");
-      return [];
+    const products: Product[] = [];
+    let page = 1;
+    let totalPages = 1;
+
+    while (page <= totalPages) {
+      const response = await fetch(
+        `${API_URL}/api/products?limit=100&page=${page}`,
+        {
+          next: { revalidate: 3600 }, // Revalidate every hour
+        }
+      );
+      if (!response.ok) {
+        console.error("Failed to fetch products for sitemap");
+        return [];
+      }
+      const data: ProductPage = await response.json();
+      products.push(...data.items);
+      totalPages = data.total_pages;
+      page += 1;
     }
-    return response.json();
+
+    return products;
   } catch (error) {
     console.error("Error fetching products for sitemap:", error);
     return [];
@@ -52,7 +72,7 @@ export default async function sitemap(): Promise<MetadataRoute.Sitemap> {
 
   // Product pages
   const productRoutes = products.map((product) => ({
-    url: `${SITE_URL}/products/${product.id}`,
+    url: `${SITE_URL}/products/${product.slug}`,
     lastModified: product.updated_at
       ? new Date(product.updated_at)
       : new Date(),
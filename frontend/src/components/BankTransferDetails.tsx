@@ -0,0 +1,58 @@
+"use client";
+
+import { Button } from "@/components/Button";
+import type { BankTransferInstructions } from "@/types";
+import { formatMoney } from "@/lib/utils";
+
+interface BankTransferDetailsProps {
+  orderId: string;
+  transfer: BankTransferInstructions;
+}
+
+// Shown after checkout for an order paid by bank transfer. The order is
+// marked paid once the studio imports the bank statement.
+export function BankTransferDetails({ orderId, transfer }: BankTransferDetailsProps) {
+  const dueDate = new Date(transfer.due_at + "Z").toLocaleDateString("bg-BG");
+  const rows = [
+    ["Получател", transfer.beneficiary],
+    ["IBAN", transfer.iban],
+    ...(transfer.bic ? [["BIC", transfer.bic]] : []),
+    ["Сума", formatMoney(transfer.amount)],
+    ["Основание", transfer.reference],
+  ];
+
+  return (
+    <div className="bg-white rounded-lg shadow-md p-6 max-w-xl mx-auto">
+      <h2 className="text-xl font-bold text-gray-900 mb-2">
+        Плащане по банков път
+      </h2>
+      <p className="text-gray-700 mb-6">
+        Моля, преведете сумата до {dueDate}, като посочите основанието
+        точно както е дадено. Неплатените навреме поръчки се отменят.
+      </p>
+
+      <dl className="bg-gray-50 rounded-lg p-4 mb-6 space-y-2">
+        {rows.map(([label, value]) => (
+          <div key={label} className="flex justify-between gap-4">
+            <dt className="text-gray-600">{label}</dt>
+            <dd className="font-mono font-semibold text-gray-900 text-right break-all">
+              {value}
+            </dd>
+          </div>
+        ))}
+      </dl>
+
+      <p className="text-sm text-gray-600 mb-6">
+        Данните за плащане са изпратени и на вашия email.
+      </p>
+
+      <Button
+        size="lg"
+        className="w-full"
+        onClick={() => (window.location.href = `/order-confirmation?id=${orderId}`)}
+      >
+        Продължи
+      </Button>
+    </div>
+  );
+}
@@ -0,0 +1,127 @@
+"use client";
+
+import { useEffect, useRef, useState } from "react";
+import { Button } from "@/components/Button";
+import { formatMoney } from "@/lib/utils";
+import type { CheckoutPayment, Money } from "@/types";
+
+const STRIPE_JS_URL = "https://js.stripe.com/v3/";
+
+interface CardPaymentProps {
+  orderId: string;
+  payment: CheckoutPayment;
+  total: Money;
+}
+
+// Stripe.js is loaded from Stripe's CDN, as their terms require
+function loadStripe(): Promise<any> {
+  const w = window as any;
+  if (w.Stripe) return Promise.resolve(w.Stripe);
+
+  return new Promise((resolve, reject) => {
+    const script = document.createElement("script");
+    script.src = STRIPE_JS_URL;
+    script.onload = () => resolve(w.Stripe);
+    script.onerror = () => reject(new Error("Stripe.js could not be loaded"));
+    document.head.appendChild(script);
+  });
+}
+
+// Collects the card for an order created with payment_method "card". The
+// order is marked paid by the provider's webhook, not by this page.
+export function CardPayment({ orderId, payment, total }: CardPaymentProps) {
+  const mountRef = useRef<HTMLDivElement>(null);
+  const [stripe, setStripe] = useState<any>(null);
+  const [elements, setElements] = useState<any>(null);
+  const [submitting, setSubmitting] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const confirmationUrl = `/order-confirmation?id=${orderId}`;
+
+  useEffect(() => {
+    if (payment.provider !== "stripe" || !payment.publishable_key) return;
+
+    let cancelled = false;
+    loadStripe()
+      .then((Stripe) => {
+        if (cancelled || !mountRef.current) return;
+        const instance = Stripe(payment.publishable_key);
+        const elementGroup = instance.elements({
+          clientSecret: payment.client_secret,
+          locale: "bg",
+        });
+        elementGroup.create("payment").mount(mountRef.current);
+        setStripe(instance);
+        setElements(elementGroup);
+      })
+      .catch((err) => setError(err.message));
+
+    return () => {
+      cancelled = true;
+    };
+  }, [payment]);
+
+  async function pay() {
+    if (!stripe || !elements) return;
+
+    setSubmitting(true);
+    setError(null);
+    // Redirects to the return URL unless the card is declined outright
+    const result = await stripe.confirmPayment({
+      elements,
+      confirmParams: {
+        return_url: `${window.location.origin}${confirmationUrl}`,
+      },
+    });
+    if (result.error) {
+      setError(result.error.message || "Плащането не бе успешно");
+    }
+    setSubmitting(false);
+  }
+
+  return (
+    <div className="bg-white rounded-lg shadow-md p-6 max-w-xl mx-auto">
+      <h2 className="text-xl font-bold text-gray-900 mb-2">
+        Плащане с карта
+      </h2>
+      <p className="text-gray-700 mb-6">Сума за плащане: {formatMoney(total)}</p>
+
+      {error && (
+        <div className="bg-red-50 border border-red-200 rounded-lg p-4 text-red-800 mb-6">
+          {error}
+        </div>
+      )}
+
+      {payment.provider === "stripe" ? (
+        <>
+          <div ref={mountRef} className="mb-6" />
+          <Button
+            size="lg"
+            className="w-full"
+            disabled={!elements || submitting}
+            onClick={pay}
+          >
+            {submitting ? "Обработване..." : "Плати"}
+          </Button>
+        </>
+      ) : (
+        <>
+          <p className="text-sm text-gray-600 mb-6">
+            Тестов доставчик на плащания. Плащането се потвърждава с{" "}
+            <code className="font-mono">
+              cargo run --example fake_payment --{" "}
+              {payment.client_secret.replace(/_secret$/, "")}
+            </code>
+          </p>
+          <Button
+            size="lg"
+            className="w-full"
+            onClick={() => (window.location.href = confirmationUrl)}
+          >
+            Продължи
+          </Button>
+        </>
+      )}
+    </div>
+  );
+}
@@ -2,6 +2,7 @@ import Link from "next/link";
 import Image from "next/image";
 import type { Product } from "@/types";
 import { Button } from "./Button";
+import { formatMoney } from "@/lib/utils";
 
 interface ProductCardProps {
   product: Product;
@@ -19,7 +20,7 @@ export function ProductCard({ product, onAddToCart }: ProductCardProps) {
 
   return (
     <div className="bg-card rounded-lg shadow-warm overflow-hidden hover:shadow-warm-lg transition-all duration-300 border border-border/50 group">
-      <Link href={`/products/${product.id}`}>
+      <Link href={`/products/${product.slug}`}>
         <div className="relative h-64 bg-cream">
           {isExternalImage ? (
             <img
@@ -43,7 +44,7 @@ export function ProductCard({ product, onAddToCart }: ProductCardProps) {
       </Link>
 
       <div className="p-4">
-        <Link href={`/products/${product.id}`}>
+        <Link href={`/products/${product.slug}`}>
           <h3 className="text-lg font-semibold text-clay-brown mb-2 hover:text-terracotta transition-colors">
             {product.name}
           </h3>
@@ -57,7 +58,7 @@ export function ProductCard({ product, onAddToCart }: ProductCardProps) {
 
         <div className="flex items-center justify-between">
           <span className="text-2xl font-bold text-terracotta">
-            {parseFloat(product.price).toFixed(2)} лв
+            {formatMoney(product.price)}
           </span>
 
           {product.stock_quantity > 0 ? (
@@ -7,6 +7,7 @@ import Image from "next/image";
 import { useCartStore } from "@/store/useCartStore";
 import { Button } from "@/components/Button";
 import type { Product } from "@/types";
+import { formatEuro, formatMoney } from "@/lib/utils";
 
 interface ProductDetailProps {
   product: Product;
@@ -30,7 +31,7 @@ export function ProductDetail({ product }: ProductDetailProps) {
     : "/placeholder-clay.svg";
 
   const isExternalImage = imageUrl.startsWith("http");
-  const price = parseFloat(product.price);
+  const price = parseFloat(product.price.amount);
   const total = price * quantity;
 
   return (
@@ -76,7 +77,7 @@ export function ProductDetail({ product }: ProductDetailProps) {
               </h1>
 
               <p className="text-3xl font-bold text-terracotta mb-6">
-                {price.toFixed(2)} лв
+                {formatMoney(product.price)}
               </p>
 
               {product.description && (
@@ -158,7 +159,7 @@ export function ProductDetail({ product }: ProductDetailProps) {
                     <p className="text-lg font-medium text-clay-brown mb-4">
                       Обща сума:{" "}
                       <span className="text-terracotta">
-                        {total.toFixed(2)} лв
+                        {formatEuro(total)}
                       </span>
                     </p>
                     <Button
@@ -1,8 +1,63 @@
 import type {
   Product,
+  ProductLookup,
+  ProductImage,
+  ProductOption,
+  ProductVariant,
+  CreateProductOptionRequest,
+  UpdateProductOptionRequest,
+  CreateProductVariantRequest,
+  UpdateProductVariantRequest,
+  ProductListParams,
+  InventoryMovement,
+  InventoryMovementParams,
+  CreateInventoryAdjustmentRequest,
+  StockLevel,
+  PaginatedResponse,
+  ProductSearchResult,
   Order,
-  OrderItemResponse,
+  OrderDetail,
+  OrderStatus,
+  OrderTracking,
   CreateOrderRequest,
+  OrderItem,
+  StockReservation,
+  Promotion,
+  PromotionQuote,
+  CreatePromotionRequest,
+  UpdatePromotionRequest,
+  ShippingMethod,
+  ShippingMethodDetail,
+  ShippingQuote,
+  ShippingZone,
+  CreateShippingMethodRequest,
+  UpdateShippingMethodRequest,
+  ShippingZoneRequest,
+  TaxClass,
+  CreateTaxClassRequest,
+  UpdateTaxClassRequest,
+  Carrier,
+  CourierOffice,
+  Shipment,
+  CreateWaybillRequest,
+  CreatedOrder,
+  PaymentConfig,
+  OrderPayments,
+  Refund,
+  CreateRefundRequest,
+  Invoice,
+  InvoiceDetail,
+  IssueInvoiceRequest,
+  CreateCreditNoteRequest,
+  InvoiceListParams,
+  BankStatement,
+  BankStatementDetail,
+  CodRemittance,
+  CodRemittanceDetail,
+  ImportCodRemittanceOptions,
+  BackgroundJob,
+  JobListParams,
+  JobSchedule,
   LoginRequest,
   LoginResponse,
   CreateProductRequest,
@@ -44,23 +99,160 @@ async function fetchApi<T>(
   return response.json();
 }
 
+function toQueryString(
+  params:
+    | ProductListParams
+    | InventoryMovementParams
+    | JobListParams
+    | InvoiceListParams = {}
+): string {
+  const query = new URLSearchParams();
+  for (const [key, value] of Object.entries(params)) {
+    if (value !== undefined) {
+      query.set(key, String(value));
+    }
+  }
+  const encoded = query.toString();
+  return encoded ? `?${encoded}` : "";
+}
+
 // Public API
 
-export async function getProducts(): Promise<Product[]> {
-  return fetchApi<Product[]>("/api/products");
+export async function getProducts(
+  params?: ProductListParams
+): Promise<PaginatedResponse<Product>> {
+  return fetchApi<PaginatedResponse<Product>>(
+    `/api/products${toQueryString(params)}`
+  );
+}
+
+export async function searchProducts(
+  q: string,
+  page = 1
+): Promise<PaginatedResponse<ProductSearchResult>> {
+  const query = new URLSearchParams({ q, page: String(page) });
+  return fetchApi<PaginatedResponse<ProductSearchResult>>(
+    `/api/products/search?${query.toString()}`
+  );
 }
 
-export async function getProduct(id: number): Promise<Product> {
-  return fetchApi<Product>(`/api/products/${id}`);
+// Accepts the product slug, a previous slug or the numeric id
+export async function getProduct(slugOrId: string): Promise<ProductLookup> {
+  return fetchApi<ProductLookup>(`/api/products/${encodeURIComponent(slugOrId)}`);
 }
 
-export async function createOrder(order: CreateOrderRequest): Promise<Order> {
-  return fetchApi<Order>("/api/orders", {
+// Retries with the same idempotency key return the first order instead of
+// creating another one
+export async function createOrder(
+  order: CreateOrderRequest,
+  idempotencyKey?: string
+): Promise<CreatedOrder> {
+  return fetchApi<CreatedOrder>("/api/orders", {
     method: "POST",
+    headers: idempotencyKey ? { "Idempotency-Key": idempotencyKey } : {},
     body: JSON.stringify(order),
   });
 }
 
+// Order number as in the emails (or the full id) plus the customer's email
+export async function lookupOrder(
+  orderNumber: string,
+  email: string
+): Promise<OrderTracking> {
+  return fetchApi<OrderTracking>("/api/orders/lookup", {
+    method: "POST",
+    body: JSON.stringify({ order_number: orderNumber, email }),
+  });
+}
+
+// Token from the tracking link in the order emails
+export async function trackOrder(token: string): Promise<OrderTracking> {
+  return fetchApi<OrderTracking>(
+    `/api/orders/track/${encodeURIComponent(token)}`
+  );
+}
+
+// What a discount code takes off the cart; the email checks the
+// per-customer limit
+export async function validatePromotion(
+  code: string,
+  items: OrderItem[],
+  customerEmail?: string
+): Promise<PromotionQuote> {
+  return fetchApi<PromotionQuote>("/api/promotions/validate", {
+    method: "POST",
+    body: JSON.stringify({ code, items, customer_email: customerEmail }),
+  });
+}
+
+export async function getShippingMethods(): Promise<ShippingMethod[]> {
+  return fetchApi<ShippingMethod[]>("/api/shipping/methods");
+}
+
+// Shipping options and prices for the cart; a discount code counts towards
+// free shipping like it does on the order
+export async function quoteShipping(
+  items: OrderItem[],
+  postalCode?: string,
+  promotionCode?: string,
+  customerEmail?: string
+): Promise<ShippingQuote> {
+  return fetchApi<ShippingQuote>("/api/shipping/quote", {
+    method: "POST",
+    body: JSON.stringify({
+      items,
+      postal_code: postalCode,
+      promotion_code: promotionCode,
+      customer_email: customerEmail,
+    }),
+  });
+}
+
+// Offices of a carrier in a city, for office delivery
+export async function getCourierOffices(
+  carrier: Carrier,
+  city: string
+): Promise<CourierOffice[]> {
+  const query = new URLSearchParams({ carrier, city });
+  return fetchApi<CourierOffice[]>(`/api/shipping/offices?${query.toString()}`);
+}
+
+// Payment methods checkout can offer
+export async function getPaymentConfig(): Promise<PaymentConfig> {
+  return fetchApi<PaymentConfig>("/api/payments/config");
+}
+
+// Holds stock for the cart until the reservation expires or is ordered
+export async function createReservation(
+  items: OrderItem[]
+): Promise<StockReservation> {
+  return fetchApi<StockReservation>("/api/reservations", {
+    method: "POST",
+    body: JSON.stringify({ items }),
+  });
+}
+
+export async function getReservation(id: string): Promise<StockReservation> {
+  return fetchApi<StockReservation>(`/api/reservations/${id}`);
+}
+
+// Replaces the held items and restarts the timer
+export async function updateReservation(
+  id: string,
+  items: OrderItem[]
+): Promise<StockReservation> {
+  return fetchApi<StockReservation>(`/api/reservations/${id}`, {
+    method: "PUT",
+    body: JSON.stringify({ items }),
+  });
+}
+
+export async function deleteReservation(id: string): Promise<void> {
+  return fetchApi<void>(`/api/reservations/${id}`, {
+    method: "DELETE",
+  });
+}
+
 // Admin API
 
 export async function adminLogin(
@@ -72,12 +264,18 @@ export async function adminLogin(
   });
 }
 
-export async function getAdminProducts(token: string): Promise<Product[]> {
-  return fetchApi<Product[]>("/api/admin/products", {
-    headers: {
-      Authorization: `Bearer ${token}`,
-    },
-  });
+export async function getAdminProducts(
+  token: string,
+  params?: ProductListParams
+): Promise<PaginatedResponse<Product>> {
+  return fetchApi<PaginatedResponse<Product>>(
+    `/api/admin/products${toQueryString(params)}`,
+    {
+      headers: {
+        Authorization: `Bearer ${token}`,
+      },
+    }
+  );
 }
 
 export async function createProduct(
@@ -141,6 +339,236 @@ export async function uploadProductImage(
   return response.json();
 }
 
+export async function uploadProductImages(
+  token: string,
+  id: number,
+  files: File[],
+  altText?: string
+): Promise<ProductImage[]> {
+  const formData = new FormData();
+  if (altText) {
+    formData.append("alt_text", altText);
+  }
+  for (const file of files) {
+    formData.append("images", file);
+  }
+
+  const url = `${API_URL}/api/admin/products/${id}/images`;
+  const response = await fetch(url, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: formData,
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: "Unknown error" }));
+    throw new ApiError(response.status, error.error || "Upload failed");
+  }
+
+  return response.json();
+}
+
+export async function reorderProductImages(
+  token: string,
+  id: number,
+  imageIds: number[]
+): Promise<ProductImage[]> {
+  return fetchApi<ProductImage[]>(`/api/admin/products/${id}/images/order`, {
+    method: "PUT",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ image_ids: imageIds }),
+  });
+}
+
+export async function updateProductImage(
+  token: string,
+  id: number,
+  imageId: number,
+  altText: string
+): Promise<ProductImage> {
+  return fetchApi<ProductImage>(`/api/admin/products/${id}/images/${imageId}`, {
+    method: "PUT",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ alt_text: altText }),
+  });
+}
+
+export async function setPrimaryProductImage(
+  token: string,
+  id: number,
+  imageId: number
+): Promise<ProductImage[]> {
+  return fetchApi<ProductImage[]>(
+    `/api/admin/products/${id}/images/${imageId}/primary`,
+    {
+      method: "POST",
+      headers: {
+        Authorization: `Bearer ${token}`,
+      },
+    }
+  );
+}
+
+export async function deleteProductImage(
+  token: string,
+  id: number,
+  imageId: number
+): Promise<void> {
+  return fetchApi<void>(`/api/admin/products/${id}/images/${imageId}`, {
+    method: "DELETE",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getProductOptions(
+  token: string,
+  id: number
+): Promise<ProductOption[]> {
+  return fetchApi<ProductOption[]>(`/api/admin/products/${id}/options`, {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function createProductOption(
+  token: string,
+  id: number,
+  option: CreateProductOptionRequest
+): Promise<ProductOption> {
+  return fetchApi<ProductOption>(`/api/admin/products/${id}/options`, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(option),
+  });
+}
+
+export async function updateProductOption(
+  token: string,
+  id: number,
+  optionId: number,
+  option: UpdateProductOptionRequest
+): Promise<ProductOption> {
+  return fetchApi<ProductOption>(
+    `/api/admin/products/${id}/options/${optionId}`,
+    {
+      method: "PUT",
+      headers: {
+        Authorization: `Bearer ${token}`,
+      },
+      body: JSON.stringify(option),
+    }
+  );
+}
+
+export async function deleteProductOption(
+  token: string,
+  id: number,
+  optionId: number
+): Promise<void> {
+  return fetchApi<void>(`/api/admin/products/${id}/options/${optionId}`, {
+    method: "DELETE",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getProductVariants(
+  token: string,
+  id: number
+): Promise<ProductVariant[]> {
+  return fetchApi<ProductVariant[]>(`/api/admin/products/${id}/variants`, {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function createProductVariant(
+  token: string,
+  id: number,
+  variant: CreateProductVariantRequest
+): Promise<ProductVariant> {
+  return fetchApi<ProductVariant>(`/api/admin/products/${id}/variants`, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(variant),
+  });
+}
+
+export async function updateProductVariant(
+  token: string,
+  id: number,
+  variantId: number,
+  variant: UpdateProductVariantRequest
+): Promise<ProductVariant> {
+  return fetchApi<ProductVariant>(
+    `/api/admin/products/${id}/variants/${variantId}`,
+    {
+      method: "PUT",
+      headers: {
+        Authorization: `Bearer ${token}`,
+      },
+      body: JSON.stringify(variant),
+    }
+  );
+}
+
+export async function deleteProductVariant(
+  token: string,
+  id: number,
+  variantId: number
+): Promise<void> {
+  return fetchApi<void>(`/api/admin/products/${id}/variants/${variantId}`, {
+    method: "DELETE",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getInventoryMovements(
+  token: string,
+  id: number,
+  params: InventoryMovementParams = {}
+): Promise<PaginatedResponse<InventoryMovement>> {
+  return fetchApi<PaginatedResponse<InventoryMovement>>(
+    `/api/admin/products/${id}/inventory${toQueryString(params)}`,
+    {
+      headers: {
+        Authorization: `Bearer ${token}`,
+      },
+    }
+  );
+}
+
+export async function createInventoryAdjustment(
+  token: string,
+  id: number,
+  adjustment: CreateInventoryAdjustmentRequest
+): Promise<StockLevel> {
+  return fetchApi<StockLevel>(`/api/admin/products/${id}/inventory`, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(adjustment),
+  });
+}
+
 export async function getAdminOrders(token: string): Promise<Order[]> {
   return fetchApi<Order[]>("/api/admin/orders", {
     headers: {
@@ -152,8 +580,8 @@ export async function getAdminOrders(token: string): Promise<Order[]> {
 export async function getAdminOrder(
   token: string,
   id: string
-): Promise<[Order, OrderItemResponse[]]> {
-  return fetchApi<[Order, OrderItemResponse[]]>(`/api/admin/orders/${id}`, {
+): Promise<OrderDetail> {
+  return fetchApi<OrderDetail>(`/api/admin/orders/${id}`, {
     headers: {
       Authorization: `Bearer ${token}`,
     },
@@ -163,14 +591,494 @@ export async function getAdminOrder(
 export async function updateOrderStatus(
   token: string,
   id: string,
-  status: string
+  status: OrderStatus,
+  comment?: string,
+  trackingNumber?: string
 ): Promise<Order> {
   return fetchApi<Order>(`/api/admin/orders/${id}`, {
     method: "PUT",
     headers: {
       Authorization: `Bearer ${token}`,
     },
-    body: JSON.stringify({ status }),
+    body: JSON.stringify({ status, comment, tracking_number: trackingNumber }),
+  });
+}
+
+// Resolves to null when the order has no waybill yet
+export async function getWaybill(
+  token: string,
+  orderId: string
+): Promise<Shipment | null> {
+  try {
+    return await fetchApi<Shipment>(`/api/admin/orders/${orderId}/waybill`, {
+      headers: {
+        Authorization: `Bearer ${token}`,
+      },
+    });
+  } catch (err) {
+    if (err instanceof ApiError && err.status === 404) {
+      return null;
+    }
+    throw err;
+  }
+}
+
+export async function createWaybill(
+  token: string,
+  orderId: string,
+  request: CreateWaybillRequest = {}
+): Promise<Shipment> {
+  return fetchApi<Shipment>(`/api/admin/orders/${orderId}/waybill`, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(request),
+  });
+}
+
+export async function getOrderPayments(
+  token: string,
+  orderId: string
+): Promise<OrderPayments> {
+  return fetchApi<OrderPayments>(`/api/admin/orders/${orderId}/payments`, {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+// Refunds the card payment through the provider
+export async function createRefund(
+  token: string,
+  orderId: string,
+  request: CreateRefundRequest = {}
+): Promise<Refund> {
+  return fetchApi<Refund>(`/api/admin/orders/${orderId}/refunds`, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(request),
+  });
+}
+
+export async function issueInvoice(
+  token: string,
+  orderId: string,
+  request: IssueInvoiceRequest = {}
+): Promise<InvoiceDetail> {
+  return fetchApi<InvoiceDetail>(`/api/admin/orders/${orderId}/invoice`, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(request),
+  });
+}
+
+export async function getInvoices(
+  token: string,
+  params: InvoiceListParams = {}
+): Promise<PaginatedResponse<Invoice>> {
+  return fetchApi<PaginatedResponse<Invoice>>(
+    `/api/admin/invoices${toQueryString(params)}`,
+    {
+      headers: {
+        Authorization: `Bearer ${token}`,
+      },
+    }
+  );
+}
+
+export async function getInvoice(
+  token: string,
+  id: number
+): Promise<InvoiceDetail> {
+  return fetchApi<InvoiceDetail>(`/api/admin/invoices/${id}`, {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+// Credits a refund, an amount or the rest of the invoice
+export async function createCreditNote(
+  token: string,
+  invoiceId: number,
+  request: CreateCreditNoteRequest = {}
+): Promise<InvoiceDetail> {
+  return fetchApi<InvoiceDetail>(`/api/admin/invoices/${invoiceId}/credit-notes`, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(request),
+  });
+}
+
+// The invoice or credit note PDF, for opening in a new tab
+export async function getInvoicePdf(token: string, id: number): Promise<Blob> {
+  const url = `${API_URL}/api/admin/invoices/${id}/pdf`;
+  const response = await fetch(url, {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: "Unknown error" }));
+    throw new ApiError(response.status, error.error || "Download failed");
+  }
+
+  return response.blob();
+}
+
+// Matches a courier's COD payout CSV against the orders
+export async function importCodRemittance(
+  token: string,
+  file: File,
+  options: ImportCodRemittanceOptions = {}
+): Promise<CodRemittanceDetail> {
+  const formData = new FormData();
+  formData.append("file", file);
+  for (const [name, value] of Object.entries(options)) {
+    if (value) {
+      formData.append(name, value);
+    }
+  }
+
+  const url = `${API_URL}/api/admin/cod-remittances`;
+  const response = await fetch(url, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: formData,
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: "Unknown error" }));
+    throw new ApiError(response.status, error.error || "Import failed");
+  }
+
+  return response.json();
+}
+
+export async function getCodRemittances(token: string): Promise<CodRemittance[]> {
+  return fetchApi<CodRemittance[]>("/api/admin/cod-remittances", {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getCodRemittance(
+  token: string,
+  id: number
+): Promise<CodRemittanceDetail> {
+  return fetchApi<CodRemittanceDetail>(`/api/admin/cod-remittances/${id}`, {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+// Matches a bank statement (CAMT.053 XML or CSV) against transfer orders
+export async function importBankStatement(
+  token: string,
+  file: File
+): Promise<BankStatementDetail> {
+  const formData = new FormData();
+  formData.append("file", file);
+
+  const url = `${API_URL}/api/admin/bank-statements`;
+  const response = await fetch(url, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: formData,
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: "Unknown error" }));
+    throw new ApiError(response.status, error.error || "Import failed");
+  }
+
+  return response.json();
+}
+
+export async function getBankStatements(token: string): Promise<BankStatement[]> {
+  return fetchApi<BankStatement[]>("/api/admin/bank-statements", {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getBankStatement(
+  token: string,
+  id: number
+): Promise<BankStatementDetail> {
+  return fetchApi<BankStatementDetail>(`/api/admin/bank-statements/${id}`, {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+// The label PDF, for opening in a new tab
+export async function getWaybillLabel(
+  token: string,
+  orderId: string
+): Promise<Blob> {
+  const url = `${API_URL}/api/admin/orders/${orderId}/waybill/label`;
+  const response = await fetch(url, {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+
+  if (!response.ok) {
+    const error = await response.json().catch(() => ({ error: "Unknown error" }));
+    throw new ApiError(response.status, error.error || "Download failed");
+  }
+
+  return response.blob();
+}
+
+export async function getPromotions(token: string): Promise<Promotion[]> {
+  return fetchApi<Promotion[]>("/api/admin/promotions", {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getPromotion(
+  token: string,
+  id: number
+): Promise<Promotion> {
+  return fetchApi<Promotion>(`/api/admin/promotions/${id}`, {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function createPromotion(
+  token: string,
+  promotion: CreatePromotionRequest
+): Promise<Promotion> {
+  return fetchApi<Promotion>("/api/admin/promotions", {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(promotion),
+  });
+}
+
+export async function updatePromotion(
+  token: string,
+  id: number,
+  promotion: UpdatePromotionRequest
+): Promise<Promotion> {
+  return fetchApi<Promotion>(`/api/admin/promotions/${id}`, {
+    method: "PUT",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(promotion),
+  });
+}
+
+export async function deletePromotion(token: string, id: number): Promise<void> {
+  return fetchApi<void>(`/api/admin/promotions/${id}`, {
+    method: "DELETE",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getAdminShippingMethods(
+  token: string
+): Promise<ShippingMethodDetail[]> {
+  return fetchApi<ShippingMethodDetail[]>("/api/admin/shipping/methods", {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function createShippingMethod(
+  token: string,
+  method: CreateShippingMethodRequest
+): Promise<ShippingMethodDetail> {
+  return fetchApi<ShippingMethodDetail>("/api/admin/shipping/methods", {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(method),
+  });
+}
+
+export async function updateShippingMethod(
+  token: string,
+  id: number,
+  method: UpdateShippingMethodRequest
+): Promise<ShippingMethodDetail> {
+  return fetchApi<ShippingMethodDetail>(`/api/admin/shipping/methods/${id}`, {
+    method: "PUT",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(method),
+  });
+}
+
+export async function deleteShippingMethod(
+  token: string,
+  id: number
+): Promise<void> {
+  return fetchApi<void>(`/api/admin/shipping/methods/${id}`, {
+    method: "DELETE",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getShippingZones(token: string): Promise<ShippingZone[]> {
+  return fetchApi<ShippingZone[]>("/api/admin/shipping/zones", {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function createShippingZone(
+  token: string,
+  zone: ShippingZoneRequest
+): Promise<ShippingZone> {
+  return fetchApi<ShippingZone>("/api/admin/shipping/zones", {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(zone),
+  });
+}
+
+export async function updateShippingZone(
+  token: string,
+  id: number,
+  zone: ShippingZoneRequest
+): Promise<ShippingZone> {
+  return fetchApi<ShippingZone>(`/api/admin/shipping/zones/${id}`, {
+    method: "PUT",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(zone),
+  });
+}
+
+export async function deleteShippingZone(token: string, id: number): Promise<void> {
+  return fetchApi<void>(`/api/admin/shipping/zones/${id}`, {
+    method: "DELETE",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getTaxClasses(token: string): Promise<TaxClass[]> {
+  return fetchApi<TaxClass[]>("/api/admin/tax-classes", {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function createTaxClass(
+  token: string,
+  taxClass: CreateTaxClassRequest
+): Promise<TaxClass> {
+  return fetchApi<TaxClass>("/api/admin/tax-classes", {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(taxClass),
+  });
+}
+
+export async function updateTaxClass(
+  token: string,
+  code: string,
+  taxClass: UpdateTaxClassRequest
+): Promise<TaxClass> {
+  return fetchApi<TaxClass>(`/api/admin/tax-classes/${code}`, {
+    method: "PUT",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify(taxClass),
+  });
+}
+
+export async function deleteTaxClass(token: string, code: string): Promise<void> {
+  return fetchApi<void>(`/api/admin/tax-classes/${code}`, {
+    method: "DELETE",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getJobs(
+  token: string,
+  params: JobListParams = {}
+): Promise<PaginatedResponse<BackgroundJob>> {
+  return fetchApi<PaginatedResponse<BackgroundJob>>(
+    `/api/admin/jobs${toQueryString(params)}`,
+    {
+      headers: {
+        Authorization: `Bearer ${token}`,
+      },
+    }
+  );
+}
+
+export async function getJob(token: string, id: number): Promise<BackgroundJob> {
+  return fetchApi<BackgroundJob>(`/api/admin/jobs/${id}`, {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function retryJob(
+  token: string,
+  id: number
+): Promise<BackgroundJob> {
+  return fetchApi<BackgroundJob>(`/api/admin/jobs/${id}/retry`, {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+}
+
+export async function getJobSchedules(token: string): Promise<JobSchedule[]> {
+  return fetchApi<JobSchedule[]>("/api/admin/jobs/schedules", {
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
   });
 }
 
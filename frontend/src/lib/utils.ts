@@ -1,6 +1,20 @@
 import { type ClassValue, clsx } from "clsx";
 import { twMerge } from "tailwind-merge";
+import type { Money } from "@/types";
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs));
 }
+
+// Fixed rate of the euro changeover; both amounts are shown during the
+// transition
+export const BGN_PER_EUR = 1.95583;
+
+export function formatMoney(money: Money): string {
+  return `${money.amount} € (${money.bgn} лв)`;
+}
+
+// For amounts added up in the browser, like the cart total
+export function formatEuro(amount: number): string {
+  return `${amount.toFixed(2)} € (${(amount * BGN_PER_EUR).toFixed(2)} лв)`;
+}
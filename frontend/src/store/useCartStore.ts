@@ -65,7 +65,7 @@ export const useCartStore = create<CartStore>()(
       getTotalPrice: () => {
         return get().items.reduce(
           (total, item) =>
-            total + parseFloat(item.product.price) * item.quantity,
+            total + parseFloat(item.product.price.amount) * item.quantity,
           0
         );
       },
@@ -76,6 +76,9 @@ export const useCartStore = create<CartStore>()(
     }),
     {
       name: "cart-storage",
+      // Carts saved before the euro hold prices in leva
+      version: 1,
+      migrate: () => ({ items: [] }) as unknown as CartStore,
     }
   )
 );
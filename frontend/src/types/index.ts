@@ -1,17 +1,203 @@
+// Amount in euro with its lev equivalent, as the law requires during the
+// transition; amounts sent to the API are plain euro strings
+export interface Money {
+  amount: string;
+  currency: "EUR";
+  bgn: string;
+}
+
 export interface Product {
   id: number;
   name: string;
+  slug: string;
   description: string | null;
-  price: string;
+  price: Money;
   image_url: string | null;
   stock_quantity: number;
   is_active: boolean;
   created_at: string;
   updated_at: string;
+  // Packed weight, used to price shipping
+  weight_grams: number;
+  // Code of the tax class; the price includes its VAT
+  tax_class: string;
+  // Rate and parts of the price; null unless the studio charges VAT
+  tax_rate: string | null;
+  net_price: Money | null;
+  vat_amount: Money | null;
+  // Included in listing and detail responses
+  // Stock minus the units held by active reservations
+  available_quantity?: number;
+  images?: ProductImage[];
+  options?: ProductOption[];
+  variants?: ProductVariant[];
+}
+
+export interface ProductImage {
+  id: number;
+  product_id: number;
+  url: string;
+  alt_text: string | null;
+  position: number;
+  is_primary: boolean;
+  // Resized renditions for srcset; empty for images uploaded before processing
+  variants: ImageVariant[];
+  created_at: string;
+}
+
+export interface ImageVariant {
+  size: "thumbnail" | "medium" | "large";
+  format: "webp" | "avif";
+  width: number;
+  height: number;
+  url: string;
+}
+
+export interface ProductOption {
+  id: number;
+  product_id: number;
+  name: string;
+  position: number;
+  created_at: string;
+  values: ProductOptionValue[];
+}
+
+export interface ProductOptionValue {
+  id: number;
+  option_id: number;
+  value: string;
+  position: number;
+}
+
+export interface ProductVariant {
+  id: number;
+  product_id: number;
+  sku: string;
+  // Overrides the product price when set
+  price: Money | null;
+  stock_quantity: number;
+  is_active: boolean;
+  position: number;
+  option_value_ids: number[];
+  // Option values in option order, e.g. "Червен / M"
+  label: string;
+  // Stock minus the units held by active reservations
+  available_quantity: number;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface CreateProductOptionRequest {
+  name: string;
+  position?: number;
+  values: string[];
+}
+
+export interface UpdateProductOptionRequest {
+  name?: string;
+  position?: number;
+  values?: string[];
+}
+
+export interface CreateProductVariantRequest {
+  sku: string;
+  price?: string;
+  stock_quantity: number;
+  is_active?: boolean;
+  position?: number;
+  option_value_ids: number[];
+}
+
+export interface UpdateProductVariantRequest {
+  sku?: string;
+  // null clears the price override
+  price?: string | null;
+  stock_quantity?: number;
+  is_active?: boolean;
+  position?: number;
+  option_value_ids?: number[];
+}
+
+export type InventoryMovementReason =
+  | "sale"
+  | "cancellation_restock"
+  | "return_restock"
+  | "manual_adjustment"
+  | "production_batch"
+  | "damage"
+  | "stock_count";
+
+export interface InventoryMovement {
+  id: number;
+  product_id: number;
+  variant_id: number | null;
+  quantity_change: number;
+  stock_after: number;
+  reason: InventoryMovementReason;
+  order_id: string | null;
+  order_item_id: number | null;
+  admin_id: number | null;
+  admin_email: string | null;
+  note: string | null;
+  created_at: string;
+}
+
+export interface InventoryMovementParams {
+  page?: number;
+  limit?: number;
+  variant_id?: number;
+  reason?: InventoryMovementReason;
+}
+
+export interface CreateInventoryAdjustmentRequest {
+  variant_id?: number;
+  reason: "manual_adjustment" | "production_batch" | "damage" | "stock_count";
+  // Units added or removed (negative); the counted level for stock_count
+  quantity: number;
+  note?: string;
+}
+
+export interface StockLevel {
+  product_id: number;
+  variant_id: number | null;
+  stock_quantity: number;
+}
+
+export interface PaginatedResponse<T> {
+  items: T[];
+  total: number;
+  page: number;
+  limit: number;
+  total_pages: number;
+}
+
+export type ProductSort = "newest" | "price_asc" | "price_desc" | "name";
+
+export interface ProductListParams {
+  page?: number;
+  limit?: number;
+  min_price?: string;
+  max_price?: string;
+  in_stock?: boolean;
+  sort?: ProductSort;
+  is_active?: boolean;
+}
+
+export interface ProductSearchResult extends Product {
+  rank: number;
+  // HTML with matches wrapped in <mark>
+  name_highlight: string;
+  snippet: string | null;
+}
+
+export interface ProductLookup extends Product {
+  moved: boolean;
 }
 
 export interface OrderItem {
   product_id: number;
+  // Required for products that have variants
+  variant_id?: number;
   quantity: number;
 }
 
@@ -23,11 +209,150 @@ export interface Order {
   delivery_address: string;
   delivery_city: string;
   delivery_postal_code: string | null;
-  total_amount: string;
-  status: string;
+  total_amount: Money;
+  status: OrderStatus;
   notes: string | null;
   created_at: string;
   updated_at: string;
+  processing_at: string | null;
+  shipped_at: string | null;
+  delivered_at: string | null;
+  cancelled_at: string | null;
+  returned_at: string | null;
+  tracking_number: string | null;
+  // Items before the discount; total_amount is what the customer pays,
+  // shipping included
+  subtotal_amount: Money;
+  discount_amount: Money;
+  promotion_id: number | null;
+  promotion_code: string | null;
+  shipping_method_id: number | null;
+  shipping_method_name: string | null;
+  shipping_amount: Money;
+  // Carrier office the parcel goes to, for office delivery
+  courier_office_code: string | null;
+  shipping_weight_grams: number;
+  payment_method: PaymentMethod;
+  // Separate from the fulfilment status
+  payment_status: PaymentStatus;
+  paid_at: string | null;
+  refunded_amount: Money;
+  // Charged for collecting cash on delivery, included in total_amount
+  cod_fee: Money;
+  // What was received, once reconciled
+  collected_amount: Money | null;
+  // Quoted with a bank transfer
+  payment_reference: string | null;
+  // Unpaid transfer orders are cancelled after this
+  payment_due_at: string | null;
+  // Parts of total_amount; without VAT the net amount is the total
+  net_amount: Money;
+  vat_amount: Money;
+  // Rate on shipping and the cash on delivery fee, null without VAT
+  shipping_tax_rate: string | null;
+  tax_breakdown: TaxLine[];
+  // Company to invoice, when the customer asked for an invoice
+  invoice_company: string | null;
+  invoice_eik: string | null;
+  invoice_vat_number: string | null;
+  invoice_mol: string | null;
+  invoice_address: string | null;
+}
+
+// Amounts of an order charged at one VAT rate
+export interface TaxLine {
+  // Percent
+  rate: string;
+  net_amount: Money;
+  vat_amount: Money;
+  gross_amount: Money;
+}
+
+export type OrderStatus =
+  | "pending"
+  | "processing"
+  | "shipped"
+  | "delivered"
+  | "cancelled"
+  | "returned";
+
+export interface OrderStatusChange {
+  id: number;
+  order_id: string;
+  // null for the initial status of a new order
+  from_status: OrderStatus | null;
+  to_status: OrderStatus;
+  admin_id: number | null;
+  admin_email: string | null;
+  comment: string | null;
+  created_at: string;
+}
+
+export interface OrderDetail extends Order {
+  items: OrderItemResponse[];
+  history: OrderStatusChange[];
+  allowed_transitions: OrderStatus[];
+}
+
+// Order as shown to a customer following it without an account
+// Order as returned by checkout; card orders carry the payment to confirm,
+// transfer orders where to pay
+export interface CreatedOrder extends Order {
+  payment: CheckoutPayment | null;
+  bank_transfer: BankTransferInstructions | null;
+}
+
+export interface CheckoutPayment {
+  provider: string;
+  client_secret: string;
+  // Stripe.js key, null for the fake provider
+  publishable_key: string | null;
+}
+
+export interface BankTransferInstructions {
+  iban: string;
+  bic: string | null;
+  beneficiary: string;
+  // Goes in the payment details
+  reference: string;
+  amount: Money;
+  currency: string;
+  due_at: string;
+}
+
+export interface OrderTracking {
+  id: string;
+  reference: string;
+  status: OrderStatus;
+  customer_name: string;
+  delivery_city: string;
+  subtotal_amount: Money;
+  discount_amount: Money;
+  promotion_code: string | null;
+  shipping_method_name: string | null;
+  shipping_amount: Money;
+  cod_fee: Money;
+  total_amount: Money;
+  // VAT included in the total, by rate
+  vat_amount: Money;
+  tax_breakdown: TaxLine[];
+  tracking_number: string | null;
+  payment_method: PaymentMethod;
+  payment_status: PaymentStatus;
+  // For paying by bank transfer
+  payment_reference: string | null;
+  payment_due_at: string | null;
+  created_at: string;
+  items: {
+    product_name: string;
+    variant_label: string | null;
+    product_price: Money;
+    quantity: number;
+    subtotal: Money;
+    discount_amount: Money;
+  }[];
+  history: { status: OrderStatus; created_at: string }[];
+  tracking_token: string;
 }
 
 export interface OrderItemResponse {
@@ -35,21 +360,533 @@ export interface OrderItemResponse {
   order_id: string;
   product_id: number | null;
   product_name: string;
-  product_price: string;
+  product_price: Money;
   quantity: number;
-  subtotal: string;
+  subtotal: Money;
   created_at: string;
+  variant_id: number | null;
+  variant_label: string | null;
+  sku: string | null;
+  discount_amount: Money;
+  // Parts of what was paid for the line; the rate is null without VAT
+  tax_rate: string | null;
+  net_amount: Money;
+  vat_amount: Money;
 }
 
 export interface CreateOrderRequest {
   customer_name: string;
   customer_email: string;
   customer_phone: string;
+  // Street address, or the carrier office for office delivery
   delivery_address: string;
   delivery_city: string;
   delivery_postal_code?: string;
   notes?: string;
   items: OrderItem[];
+  // Stock held for this checkout
+  reservation_id?: string;
+  promotion_code?: string;
+  shipping_method_id: number;
+  // Office code from getCourierOffices, for office delivery
+  courier_office_code?: string;
+  // Cash on delivery when omitted
+  payment_method?: PaymentMethod;
+  // Company details, if the customer wants an invoice
+  invoice?: InvoiceBuyerRequest;
+}
+
+// Company an invoice is made out to
+export interface InvoiceBuyerRequest {
+  company: string;
+  // ЕИК (BULSTAT), 9 or 13 digits
+  eik: string;
+  // Only for VAT-registered companies
+  vat_number?: string;
+  // МОЛ, the person legally representing the company
+  mol?: string;
+  address: string;
+}
+
+export interface StockReservation {
+  id: string;
+  expires_at: string;
+  order_id: string | null;
+  converted_at: string | null;
+  created_at: string;
+  updated_at: string;
+  items: StockReservationItem[];
+}
+
+export interface StockReservationItem {
+  id: number;
+  reservation_id: string;
+  product_id: number;
+  variant_id: number | null;
+  quantity: number;
+}
+
+export type PromotionKind = "percentage" | "fixed_amount" | "free_shipping";
+
+export interface Promotion {
+  id: number;
+  code: string;
+  description: string | null;
+  kind: PromotionKind;
+  value: string;
+  starts_at: string | null;
+  ends_at: string | null;
+  min_order_amount: Money | null;
+  usage_limit: number | null;
+  usage_limit_per_customer: number | null;
+  is_active: boolean;
+  created_at: string;
+  updated_at: string;
+  product_ids: number[];
+  category_ids: number[];
+  times_used: number;
+}
+
+export interface CreatePromotionRequest {
+  code: string;
+  description?: string;
+  kind: PromotionKind;
+  // Percent or amount off; ignored for free_shipping
+  value?: string;
+  starts_at?: string;
+  ends_at?: string;
+  min_order_amount?: string;
+  usage_limit?: number;
+  usage_limit_per_customer?: number;
+  is_active?: boolean;
+  // Empty applies the promotion to every product
+  product_ids?: number[];
+  category_ids?: number[];
+}
+
+// null clears an optional field
+export type UpdatePromotionRequest = {
+  [K in keyof CreatePromotionRequest]?: CreatePromotionRequest[K] | null;
+};
+
+export interface PromotionQuote {
+  code: string;
+  description: string | null;
+  kind: PromotionKind;
+  subtotal_amount: Money;
+  discount_amount: Money;
+  total_amount: Money;
+  free_shipping: boolean;
+  items: {
+    product_id: number;
+    variant_id: number | null;
+    quantity: number;
+    subtotal: Money;
+    discount_amount: Money;
+  }[];
+}
+
+export type ShippingKind = "courier_office" | "courier_address" | "pickup";
+
+export type Carrier = "econt" | "speedy";
+
+export interface ShippingMethod {
+  id: number;
+  name: string;
+  description: string | null;
+  kind: ShippingKind;
+  // null for pickup
+  carrier: Carrier | null;
+  // Orders of at least this much after discounts ship for free
+  free_shipping_threshold: Money | null;
+  // Added to orders paid in cash on delivery
+  cod_fee: Money;
+  is_active: boolean;
+  position: number;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface ShippingRate {
+  id: number;
+  method_id: number;
+  // null applies outside the method's zone-specific rates
+  zone_id: number | null;
+  // null for no limit
+  max_weight_grams: number | null;
+  price: Money;
+}
+
+export interface ShippingMethodDetail extends ShippingMethod {
+  rates: ShippingRate[];
+}
+
+export interface ShippingZone {
+  id: number;
+  name: string;
+  postal_code_from: number;
+  postal_code_to: number;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface ShippingRateRequest {
+  zone_id?: number;
+  max_weight_grams?: number;
+  price: string;
+}
+
+export interface CreateShippingMethodRequest {
+  name: string;
+  description?: string;
+  kind: ShippingKind;
+  carrier?: Carrier;
+  free_shipping_threshold?: string;
+  cod_fee?: string;
+  is_active?: boolean;
+  position?: number;
+  rates: ShippingRateRequest[];
+}
+
+// null clears an optional field; rates replaces the whole price table
+export type UpdateShippingMethodRequest = {
+  [K in keyof CreateShippingMethodRequest]?: CreateShippingMethodRequest[K] | null;
+};
+
+export interface ShippingZoneRequest {
+  name: string;
+  postal_code_from: number;
+  postal_code_to: number;
+}
+
+export interface ShippingOption extends ShippingMethod {
+  price: Money;
+  // Still to add to the cart for free shipping
+  free_shipping_remaining: Money | null;
+  total_amount: Money;
+}
+
+export interface ShippingQuote {
+  weight_grams: number;
+  subtotal_amount: Money;
+  discount_amount: Money;
+  options: ShippingOption[];
+}
+
+export interface CourierOffice {
+  code: string;
+  name: string;
+  city: string;
+  address: string;
+}
+
+export type ShipmentStatus = "created" | "in_transit" | "delivered";
+
+// Courier waybill of an order
+export interface Shipment {
+  id: number;
+  order_id: string;
+  carrier: Carrier;
+  tracking_number: string;
+  status: ShipmentStatus;
+  // Latest tracking event as reported by the courier
+  last_event: string | null;
+  last_checked_at: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface CreateWaybillRequest {
+  // Overrides the office chosen at checkout
+  office_code?: string;
+  // Declared contents, the product names by default
+  description?: string;
+}
+
+export type PaymentMethod = "cash_on_delivery" | "bank_transfer" | "card";
+
+export type PaymentStatus =
+  | "pending"
+  | "paid"
+  | "failed"
+  | "partially_refunded"
+  | "refunded";
+
+// What checkout can offer
+export interface PaymentConfig {
+  methods: PaymentMethod[];
+  provider: string | null;
+  publishable_key: string | null;
+}
+
+// Attempt to take a card payment
+export interface Payment {
+  id: number;
+  order_id: string;
+  provider: string;
+  provider_payment_id: string;
+  // As charged; payments from before the euro are in leva
+  amount: string;
+  currency: string;
+  status: "pending" | "succeeded" | "failed";
+  failure_message: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface Refund {
+  id: number;
+  payment_id: number;
+  // Set once the provider has taken the refund
+  provider_refund_id: string | null;
+  // In the currency of the payment
+  amount: string;
+  currency: string;
+  reason: string | null;
+  admin_id: number | null;
+  // Pending until the provider answers; sent again with the next refund
+  status: "pending" | "succeeded" | "failed";
+  failure_message: string | null;
+  created_at: string;
+}
+
+export interface OrderPayments {
+  payment_method: PaymentMethod;
+  payment_status: PaymentStatus;
+  refunded_amount: Money;
+  payments: Payment[];
+  refunds: Refund[];
+}
+
+export interface CreateRefundRequest {
+  // The rest of the payment when omitted
+  amount?: string;
+  reason?: string;
+}
+
+export type InvoiceKind = "invoice" | "credit_note";
+
+// An invoice or credit note as issued; amounts of a credit note are what it
+// credits
+export interface Invoice {
+  id: number;
+  number: number;
+  kind: InvoiceKind;
+  order_id: string;
+  // Invoice a credit note corrects
+  invoice_id: number | null;
+  refund_id: number | null;
+  issue_date: string;
+  tax_event_date: string;
+  seller_name: string;
+  seller_eik: string;
+  seller_vat_number: string | null;
+  seller_mol: string | null;
+  seller_address: string;
+  buyer_name: string;
+  buyer_eik: string | null;
+  buyer_vat_number: string | null;
+  buyer_mol: string | null;
+  buyer_address: string;
+  payment_method: PaymentMethod;
+  net_amount: Money;
+  vat_amount: Money;
+  total_amount: Money;
+  tax_breakdown: TaxLine[];
+  // Legal ground for lines charged no VAT
+  vat_exemption_reason: string | null;
+  // Why a credit note was issued
+  reason: string | null;
+  admin_id: number | null;
+  created_at: string;
+}
+
+export interface InvoiceLine {
+  id: number;
+  invoice_id: number;
+  position: number;
+  description: string;
+  quantity: number;
+  // Without VAT, after any discount
+  unit_price: Money;
+  tax_rate: string | null;
+  net_amount: Money;
+  vat_amount: Money;
+  total_amount: Money;
+}
+
+export interface InvoiceDetail extends Invoice {
+  // Zero-padded to 10 digits, as printed
+  formatted_number: string;
+  lines: InvoiceLine[];
+  credit_notes: Invoice[];
+  corrected_invoice: Invoice | null;
+}
+
+export interface IssueInvoiceRequest {
+  // Replaces the company given at checkout
+  buyer?: InvoiceBuyerRequest;
+  issue_date?: string;
+  tax_event_date?: string;
+}
+
+export interface CreateCreditNoteRequest {
+  // Credits the refund's amount
+  refund_id?: number;
+  // What is left of the invoice when neither is given
+  amount?: string;
+  reason?: string;
+  issue_date?: string;
+}
+
+export interface InvoiceListParams {
+  page?: number;
+  limit?: number;
+  kind?: InvoiceKind;
+  order_id?: string;
+}
+
+export type CodLineStatus =
+  | "matched"
+  | "amount_mismatch"
+  | "unknown_tracking_number"
+  | "not_cash_on_delivery"
+  | "already_collected"
+  | "duplicate"
+  | "invalid_amount";
+
+// A courier's cash on delivery payout report as imported
+export interface CodRemittance {
+  id: number;
+  carrier: Carrier | null;
+  file_name: string | null;
+  total_amount: Money;
+  line_count: number;
+  matched_count: number;
+  admin_id: number | null;
+  created_at: string;
+}
+
+export interface CodRemittanceLine {
+  id: number;
+  remittance_id: number;
+  line_number: number;
+  tracking_number: string;
+  // Null when the report's amount could not be read
+  amount: Money | null;
+  // The amount as written, when it could not be read
+  raw_amount: string | null;
+  order_id: string | null;
+  // What the order expected to collect
+  expected_amount: Money | null;
+  status: CodLineStatus;
+}
+
+// Discrepancies come first
+export interface CodRemittanceDetail extends CodRemittance {
+  lines: CodRemittanceLine[];
+}
+
+export interface ImportCodRemittanceOptions {
+  carrier?: Carrier;
+  // Header names, when they are not recognised
+  tracking_column?: string;
+  amount_column?: string;
+}
+
+export type BankEntryStatus =
+  | "matched"
+  | "amount_mismatch"
+  | "no_reference"
+  | "unknown_reference"
+  | "already_paid"
+  | "order_cancelled"
+  | "duplicate";
+
+// An imported statement of the studio's bank account
+export interface BankStatement {
+  id: number;
+  file_name: string | null;
+  format: "camt053" | "csv";
+  // Sum of the incoming payments
+  total_amount: Money;
+  entry_count: number;
+  matched_count: number;
+  admin_id: number | null;
+  created_at: string;
+}
+
+export interface BankStatementEntry {
+  id: number;
+  statement_id: number;
+  bank_reference: string;
+  booking_date: string | null;
+  amount: string;
+  currency: string;
+  payer_name: string | null;
+  remittance_info: string | null;
+  order_id: string | null;
+  status: BankEntryStatus;
+}
+
+// Discrepancies come first
+export interface BankStatementDetail extends BankStatement {
+  entries: BankStatementEntry[];
+}
+
+export type JobStatus = "queued" | "running" | "succeeded" | "dead";
+
+export interface BackgroundJob {
+  id: number;
+  kind: string;
+  payload: Record<string, unknown>;
+  status: JobStatus;
+  attempts: number;
+  max_attempts: number;
+  run_at: string;
+  locked_at: string | null;
+  last_error: string | null;
+  finished_at: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface JobListParams {
+  page?: number;
+  limit?: number;
+  status?: JobStatus;
+  kind?: string;
+}
+
+export interface JobSchedule {
+  name: string;
+  schedule: string;
+  next_run_at: string;
+  last_run_at: string | null;
+}
+
+export interface TaxClass {
+  code: string;
+  name: string;
+  // Percent
+  rate: string;
+  // Legal ground printed on invoices for lines charged no VAT
+  exemption_reason: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface CreateTaxClassRequest {
+  code: string;
+  name: string;
+  rate: string;
+  exemption_reason?: string;
+}
+
+export interface UpdateTaxClassRequest {
+  name: string;
+  rate: string;
+  exemption_reason?: string | null;
 }
 
 export interface LoginRequest {
@@ -67,20 +904,28 @@ export interface LoginResponse {
 
 export interface CreateProductRequest {
   name: string;
+  slug?: string;
   description?: string;
   price: string;
   stock_quantity: number;
+  weight_grams?: number;
+  // The standard class when omitted
+  tax_class?: string;
 }
 
 export interface UpdateProductRequest {
   name?: string;
+  slug?: string;
   description?: string;
   price?: string;
   stock_quantity?: number;
   is_active?: boolean;
+  weight_grams?: number;
+  tax_class?: string;
 }
 
 export interface CartItem {
   product: Product;
+  variant?: ProductVariant;
   quantity: number;
 }